@@ -0,0 +1,160 @@
+//! Module for achievements, goals beyond finishing levels which the player
+//! unlocks once and for all.
+//!
+//! Achievements are defined in the `ACHIEVEMENTS` table, each with one of
+//! the conditions which the game knows how to check. Their names and
+//! descriptions are translated from the keys `achievement.<id>` and
+//! `achievement.<id>.description`.
+
+use std::collections::HashSet;
+use game::events::GameEvent;
+use lang::tr;
+use level::{try_load_all_level_headers, LEVEL_DIR};
+use level::info::Medal;
+use progress::Progress;
+
+/// The slowest hit which counts as a bounce.
+const MIN_BOUNCE_SPEED: f32 = 0.5;
+
+/// What must be done to earn an achievement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    /// finish a level with the ball no smaller than it started
+    FinishWithoutShrinking,
+    /// collect every gem of every level in the level directory, over any
+    /// number of runs
+    AllGems,
+    /// bounce off things at least this many times in a single run
+    Bounces(u32),
+    /// earn the given medal, or a better one, on any level
+    Medal(Medal),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Achievement {
+    /// the identifier by which the achievement is saved
+    pub id: &'static str,
+    pub condition: Condition,
+}
+
+/// All achievements, in the order in which they are listed.
+pub static ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "airtight",
+        condition: Condition::FinishWithoutShrinking,
+    },
+    Achievement {
+        id: "collector",
+        condition: Condition::AllGems,
+    },
+    Achievement {
+        id: "pinball",
+        condition: Condition::Bounces(100),
+    },
+    Achievement {
+        id: "par",
+        condition: Condition::Medal(Medal::Gold),
+    },
+];
+
+impl Achievement {
+    pub fn name(&self) -> String {
+        tr(&format!("achievement.{}", self.id))
+    }
+
+    pub fn description(&self) -> String {
+        tr(&format!("achievement.{}.description", self.id))
+    }
+}
+
+/// How a finished run went.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunEnd {
+    /// the time taken to finish the level, in seconds
+    pub time: f32,
+    /// the medal earned for the time, if any
+    pub medal: Option<Medal>,
+    /// whether any ball ended smaller than it started
+    pub shrunk: bool,
+    /// whether every gem in the level directory was collected, counting
+    /// this run
+    pub all_gems: bool,
+}
+
+/// Keeps track of what happens in a run of a level, to tell which
+/// achievements it earns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AchievementTracker {
+    /// the achievements unlocked before or during this run
+    unlocked: HashSet<&'static str>,
+    bounces: u32,
+    gems: usize,
+}
+
+impl AchievementTracker {
+    /// Start tracking a run, skipping the achievements which the player
+    /// already has.
+    pub fn new(progress: &Progress) -> Self {
+        AchievementTracker {
+            unlocked: ACHIEVEMENTS
+                .iter()
+                .filter(|a| progress.is_unlocked(a.id))
+                .map(|a| a.id)
+                .collect(),
+            bounces: 0,
+            gems: 0,
+        }
+    }
+
+    pub fn observe(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::Impact { speed, .. } if speed >= MIN_BOUNCE_SPEED => self.bounces += 1,
+            GameEvent::GemPickedUp(_) => self.gems += 1,
+            _ => {}
+        }
+    }
+
+    /// the number of gems picked up in this run
+    pub fn gems(&self) -> usize {
+        self.gems
+    }
+
+    /// Obtain the achievements earned since the last check, given how the
+    /// run ended if it did. Each achievement is only reported once.
+    pub fn check(&mut self, end: Option<&RunEnd>) -> Vec<&'static Achievement> {
+        let earned: Vec<_> = ACHIEVEMENTS
+            .iter()
+            .filter(|a| !self.unlocked.contains(a.id))
+            .filter(|a| self.is_met(a.condition, end))
+            .collect();
+        self.unlocked.extend(earned.iter().map(|a| a.id));
+        earned
+    }
+
+    fn is_met(&self, condition: Condition, end: Option<&RunEnd>) -> bool {
+        match (condition, end) {
+            (Condition::Bounces(n), _) => self.bounces >= n,
+            (Condition::FinishWithoutShrinking, Some(end)) => !end.shrunk,
+            (Condition::AllGems, Some(end)) => end.all_gems,
+            (Condition::Medal(medal), Some(end)) => end.medal.map_or(false, |m| m >= medal),
+            (_, None) => false,
+        }
+    }
+}
+
+/// Check whether the player has collected every gem of every level in the
+/// level directory, in either mode. Levels which cannot be read are not
+/// counted, and an empty directory has nothing to collect.
+pub fn collected_all_gems(progress: &Progress) -> bool {
+    let levels = match try_load_all_level_headers(LEVEL_DIR) {
+        Ok(levels) => levels,
+        Err(_) => return false,
+    };
+    let names: Vec<_> = levels
+        .iter()
+        .filter_map(|&(_, ref header)| header.as_ref().ok())
+        .map(|header| header.name())
+        .collect();
+    let all_gems = |name: &str, hard_mode| progress.level(name, hard_mode).map_or(false, |p| p.all_gems);
+    !names.is_empty() && names.iter().all(|name| all_gems(name, false) || all_gems(name, true))
+}
@@ -1,18 +1,61 @@
 use na::Vector2;
-use util::clamp;
+use consts::{HEIGHT, WIDTH};
+use util::{clamp, default_vector2, XorShiftRng};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Camera {
     pos: Vector2<f32>, // top-left
     width: f32,
     height: f32,
     half_width: f32,
     half_height: f32,
+    /// the current magnification factor
+    zoom: f32,
+    /// the magnification factor which the camera is easing towards
+    target_zoom: f32,
+    shake: Shake,
+    /// the factor applied to the magnitude of every shake
+    shake_intensity: f32,
+}
+
+/// The minimum zoom factor, which shows the most of the map.
+pub const MIN_ZOOM: f32 = 0.8;
+/// The maximum zoom factor.
+pub const MAX_ZOOM: f32 = 1.3;
+/// The fraction of the remaining zoom difference covered in each tick.
+const ZOOM_EASING: f32 = 0.05;
+/// How close to the left and right edges of the view the focus point may
+/// get before the camera follows it, in map units.
+const MARGIN_W: f32 = 120.;
+/// How close to the top and bottom edges of the view the focus point may
+/// get before the camera follows it, in map units.
+const MARGIN_H: f32 = 80.;
+
+/// The state of a screen shake effect.
+#[derive(Debug, Clone)]
+struct Shake {
+    magnitude: f32,
+    duration: f32,
+    remaining: f32,
+    offset: Vector2<f32>,
+    rng: XorShiftRng,
+}
+
+impl Default for Shake {
+    fn default() -> Self {
+        Shake {
+            magnitude: 0.,
+            duration: 0.,
+            remaining: 0.,
+            offset: Vector2::zeros(),
+            rng: XorShiftRng::default(),
+        }
+    }
 }
 
 impl Default for Camera {
     fn default() -> Self {
-        Camera::new([0., 0.].into(), ::WIDTH as f32, ::HEIGHT as f32)
+        Camera::new([0., 0.].into(), WIDTH as f32, HEIGHT as f32)
     }
 }
 
@@ -26,11 +69,102 @@ impl Camera {
             height,
             half_width: width / 2.,
             half_height: height / 2.,
+            zoom: 1.,
+            target_zoom: 1.,
+            shake: Shake::default(),
+            shake_intensity: 1.,
         }
     }
 
+    /// Obtain the position of the camera's top-left corner, including the
+    /// current shake offset.
     pub fn position(&self) -> Vector2<f32> {
-        self.pos
+        self.pos + self.shake.offset
+    }
+
+    /// Obtain the current magnification factor, to be applied as a scale
+    /// transformation before translating by the camera's position.
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Obtain the magnification factor which the camera is easing towards.
+    pub fn target_zoom(&self) -> f32 {
+        self.target_zoom
+    }
+
+    /// Set the magnification factor which the camera eases towards. This is
+    /// capped between `MIN_ZOOM` and `MAX_ZOOM`.
+    pub fn set_target_zoom(&mut self, zoom: f32) {
+        self.target_zoom = clamp(zoom, MIN_ZOOM, MAX_ZOOM);
+    }
+
+    /// Ease the zoom towards the target zoom by the given number of ticks,
+    /// keeping the center of the view in place.
+    pub fn update_zoom(&mut self, ticks: f32) {
+        let center = self.pos + self.half_view();
+        let t = f32::min(1., ZOOM_EASING * ticks);
+        self.zoom += (self.target_zoom - self.zoom) * t;
+        self.pos = center - self.half_view();
+    }
+
+    /// The dimensions of the visible portion of the map, in map units.
+    pub fn view(&self) -> Vector2<f32> {
+        [self.width / self.zoom, self.height / self.zoom].into()
+    }
+
+    /// The region of the map in which the focus point can move without the
+    /// camera following it, as `[x, y, width, height]`.
+    pub fn soft_focus_rect(&self) -> [f32; 4] {
+        let view = self.view();
+        [
+            self.pos[0] + MARGIN_W,
+            self.pos[1] + MARGIN_H,
+            view[0] - MARGIN_W * 2.,
+            view[1] - MARGIN_H * 2.,
+        ]
+    }
+
+    fn half_view(&self) -> Vector2<f32> {
+        [self.half_width / self.zoom, self.half_height / self.zoom].into()
+    }
+
+    /// Scale down the magnitude of all shakes by the given factor, from 0
+    /// (no shaking at all) to 1.
+    pub fn set_shake_intensity(&mut self, intensity: f32) {
+        self.shake_intensity = clamp(intensity, 0., 1.);
+    }
+
+    /// Start shaking the camera by up to `magnitude` pixels, decaying over
+    /// `duration` ticks, scaled by the shake intensity. A weaker shake does
+    /// not override a stronger one already in progress.
+    pub fn shake(&mut self, magnitude: f32, duration: f32) {
+        let magnitude = magnitude * self.shake_intensity;
+        let current = if self.shake.duration > 0. {
+            self.shake.magnitude * self.shake.remaining / self.shake.duration
+        } else {
+            0.
+        };
+        if magnitude > current && duration > 0. {
+            self.shake.magnitude = magnitude;
+            self.shake.duration = duration;
+            self.shake.remaining = duration;
+        }
+    }
+
+    /// Advance the shake effect by the given number of ticks.
+    pub fn update_shake(&mut self, ticks: f32) {
+        let shake = &mut self.shake;
+        shake.remaining = f32::max(0., shake.remaining - ticks);
+        if shake.remaining <= 0. {
+            shake.offset = default_vector2();
+            return;
+        }
+        let m = shake.magnitude * shake.remaining / shake.duration;
+        // round to whole pixels so that the low resolution view does not shimmer
+        let x = shake.rng.range(-m, m).round();
+        let y = shake.rng.range(-m, m).round();
+        shake.offset = [x, y].into();
     }
 
     pub fn round_position(&mut self) {
@@ -42,35 +176,33 @@ impl Camera {
     /// touching the map's boundaries).
     pub fn focus_on(&mut self, focus: Vector2<f32>, map_dim: Vector2<f32>) {
         let point = self.focus_point(focus, map_dim);
-        self.pos = [point[0] - self.half_width, point[1] - self.half_height].into();
+        self.pos = point - self.half_view();
     }
 
     /// Move the camera just enough to have the focus point sufficiently inside
     /// the viewport (still without crossing the map's boundaries).
     pub fn soft_focus_on(&mut self, focus: Vector2<f32>, map_dim: Vector2<f32>) {
         // do this one axis at a time
+        let view = self.view();
 
         // x
-        const MARGIN_W: f32 = 120.;
-
         let rx = focus[0] - self.pos[0] - MARGIN_W;
         if rx < 0. {
             self.pos[0] += rx;
         }
 
-        let rx = focus[0] - (self.pos[0] + self.width - MARGIN_W);
+        let rx = focus[0] - (self.pos[0] + view[0] - MARGIN_W);
         if rx > 0. {
             self.pos[0] += rx;
         }
 
         // y
-        const MARGIN_H: f32 = 80.;
         let ry = focus[1] - self.pos[1] - MARGIN_H;
         if ry < 0. {
             self.pos[1] += ry;
         }
 
-        let ry = focus[1] - (self.pos[1] + self.height - MARGIN_H);
+        let ry = focus[1] - (self.pos[1] + view[1] - MARGIN_H);
         if ry > 0. {
             self.pos[1] += ry;
         }
@@ -85,20 +217,90 @@ impl Camera {
         self.pos += v.into();
     }
 
+    /// Pan the camera, keeping it within the map's boundaries.
+    pub fn pan_within<V>(&mut self, v: V, map_dim: Vector2<f32>)
+    where
+        V: Into<Vector2<f32>>,
+    {
+        self.pan(v);
+        self.clamp_to_bounds(map_dim);
+    }
+
+    /// Keep the camera within the map's boundaries. On an axis where the map
+    /// is smaller than the viewport, the map is centered instead.
     pub fn clamp_to_bounds(&mut self, map_dim: Vector2<f32>) {
-        let (hw, hh) = (self.half_width, self.half_height);
-        self.pos[0] = clamp(self.pos[0], 0., map_dim[0] - hw);
-        self.pos[1] = clamp(self.pos[1], 0., map_dim[1] - hh);
+        let view = self.view();
+        for i in 0..2 {
+            self.pos[i] = clamp_axis(self.pos[i], view[i], map_dim[i]);
+        }
     }
 
     /// Obtain the coordinates that a camera should center on with the given
     /// focus point and display dimensions
     fn focus_point(&self, focus: Vector2<f32>, dim: Vector2<f32>) -> Vector2<f32> {
-        let (hw, hh) = (self.half_width, self.half_height);
-        let (x, y) = (focus[0], focus[1]);
+        let half_view = self.half_view();
+        let mut point = focus;
+        for i in 0..2 {
+            point[i] = clamp_axis(point[i] - half_view[i], half_view[i] * 2., dim[i]) + half_view[i];
+        }
+        point
+    }
+}
+
+/// The direction in which to pan the camera while the given arrow keys are
+/// held, in the order left, right, up and down.
+pub fn pan_direction(keys: [bool; 4]) -> Vector2<f32> {
+    let axis = |neg: bool, pos: bool| (pos as i32 - neg as i32) as f32;
+    Vector2::new(axis(keys[0], keys[1]), axis(keys[2], keys[3]))
+}
+
+/// Clamp the start of a view of the given length so that it stays within
+/// `[0, map_len]`, or center it if the map is smaller than the view.
+fn clamp_axis(start: f32, view_len: f32, map_len: f32) -> f32 {
+    if map_len <= view_len {
+        (map_len - view_len) / 2.
+    } else {
+        clamp(start, 0., map_len - view_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_map_is_centered() {
+        // a map 200 units across in a view 320 units across
+        for &start in &[-500., -60., 0., 500.] {
+            assert_eq!(clamp_axis(start, 320., 200.), -60.);
+        }
+    }
+
+    #[test]
+    fn map_of_the_view_size_fits_exactly() {
+        for &start in &[-10., 0., 10.] {
+            assert_eq!(clamp_axis(start, 320., 320.), 0.);
+            assert_eq!(clamp_axis(start, 200., 200.), 0.);
+        }
+        let mut camera = Camera::new(Vector2::new(5., -5.), 320., 200.);
+        camera.clamp_to_bounds(Vector2::new(320., 200.));
+        assert_eq!(camera.position(), Vector2::new(0., 0.));
+    }
+
+    #[test]
+    fn large_map_keeps_the_view_inside() {
+        assert_eq!(clamp_axis(-1., 320., 2000.), 0.);
+        assert_eq!(clamp_axis(0., 320., 2000.), 0.);
+        assert_eq!(clamp_axis(900., 320., 2000.), 900.);
+        assert_eq!(clamp_axis(1680., 320., 2000.), 1680.);
+        assert_eq!(clamp_axis(1681., 320., 2000.), 1680.);
+        assert_eq!(clamp_axis(5000., 200., 2000.), 1800.);
 
-        let x = clamp(x, hw, dim[0] - hw);
-        let y = clamp(y, hh, dim[1] - hh);
-        [x, y].into()
+        let map_dim = Vector2::new(2000., 2000.);
+        let mut camera = Camera::new(Vector2::new(0., 0.), 320., 200.);
+        camera.focus_on(Vector2::new(1990., 10.), map_dim);
+        assert_eq!(camera.position(), Vector2::new(1680., 0.));
+        camera.focus_on(Vector2::new(1000., 1000.), map_dim);
+        assert_eq!(camera.position(), Vector2::new(840., 900.));
     }
 }
@@ -0,0 +1,213 @@
+//! Module for recording the last few seconds of play into an animated GIF.
+//!
+//! While capturing, every other frame of the low resolution display is kept
+//! in a bounded ring buffer. When the capture stops, the frames are encoded
+//! on a worker thread, so that the game does not hitch.
+
+use std::collections::VecDeque;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use gif::{Encoder, Frame, Repeat, SetParameter};
+use graphics::character::CharacterCache;
+use graphics::{ellipse, Context, DrawState, Graphics, Text, Transformed};
+use consts::{HEIGHT, WIDTH};
+use profile::duration_ms;
+use util::DynResult;
+
+/// The directory which captures are saved to.
+pub const CAPTURE_DIR: &str = "captures";
+/// The number of frames kept, about 10 seconds when taking every other
+/// frame at 60 frames per second.
+const MAX_FRAMES: usize = 300;
+/// How hard the encoder works on picking the colors of each frame, from 1
+/// (slowest, best) to 30.
+const QUANTIZE_SPEED: i32 = 10;
+/// The shortest delay between frames which GIF viewers honor, in
+/// hundredths of a second.
+const MIN_DELAY: u16 = 2;
+const REC_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 1.0];
+
+/// A frame of the low resolution display, in RGBA with the top row first.
+struct CapturedFrame {
+    pixels: Vec<u8>,
+    time: Instant,
+}
+
+/// The state of gameplay capture.
+#[derive(Default)]
+pub struct Recorder {
+    /// the frames kept so far, oldest first, while capturing
+    frames: Option<VecDeque<CapturedFrame>>,
+    /// whether the next frame is to be skipped
+    skip: bool,
+    /// the captures still being encoded
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.frames.is_some()
+    }
+
+    /// Start capturing, or stop and save the capture.
+    pub fn toggle(&mut self) {
+        if self.is_recording() {
+            self.stop();
+        } else {
+            self.frames = Some(VecDeque::with_capacity(MAX_FRAMES));
+            self.skip = false;
+            println!("Capture started");
+        }
+    }
+
+    /// Whether the frame about to be drawn is to be kept, which is every
+    /// other frame while capturing.
+    pub fn wants_frame(&mut self) -> bool {
+        if !self.is_recording() {
+            return false;
+        }
+        self.skip = !self.skip;
+        !self.skip
+    }
+
+    /// Keep a frame read back from the low resolution target, whose rows
+    /// come bottom first.
+    pub fn push_frame(&mut self, pixels: &[u8]) {
+        let frames = match self.frames {
+            Some(ref mut frames) => frames,
+            None => return,
+        };
+        let row = WIDTH as usize * 4;
+        if pixels.len() != row * HEIGHT as usize {
+            eprintln!("Warning: captured frame has the wrong size, skipping it");
+            return;
+        }
+        let mut frame = if frames.len() >= MAX_FRAMES {
+            frames.pop_front().map(|f| f.pixels).unwrap_or_default()
+        } else {
+            Vec::with_capacity(pixels.len())
+        };
+        frame.clear();
+        for line in pixels.chunks(row).rev() {
+            frame.extend_from_slice(line);
+        }
+        frames.push_back(CapturedFrame {
+            pixels: frame,
+            time: Instant::now(),
+        });
+    }
+
+    /// Stop capturing, and save the frames kept on a worker thread.
+    pub fn stop(&mut self) {
+        let frames = match self.frames.take() {
+            Some(frames) => frames,
+            None => return,
+        };
+        if frames.is_empty() {
+            return;
+        }
+        let path = capture_path();
+        let worker = thread::spawn(move || match save_gif(&path, frames) {
+            Ok(()) => println!("Capture saved to {}", path.display()),
+            Err(e) => eprintln!("Warning: could not save the capture to {}: {}", path.display(), e),
+        });
+        self.workers.push(worker);
+    }
+
+    /// Stop capturing, and wait for all captures to be saved, so that none
+    /// is lost when the game closes.
+    pub fn finish(&mut self) {
+        self.stop();
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                eprintln!("Warning: a capture could not be saved");
+            }
+        }
+    }
+
+    /// Draw a small recording indicator in the corner of the screen, while
+    /// capturing.
+    pub fn draw_indicator<C, G>(&self, ctx: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = C::Texture>,
+    {
+        if !self.is_recording() {
+            return;
+        }
+        let width = f64::from(ctx.viewport.map(|v| v.window_size[0]).unwrap_or(0));
+        let c = ctx.trans(width - 56., 8.);
+        ellipse(REC_COLOR, [0., 2., 10., 10.], c.transform, g);
+        let _ = Text::new_color(REC_COLOR, 12).draw(
+            "REC",
+            cache,
+            &DrawState::default(),
+            c.transform.trans(14., 12.),
+            g,
+        );
+    }
+}
+
+/// A new file name for a capture, after the current time.
+fn capture_path() -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap_or_default();
+    Path::new(CAPTURE_DIR).join(format!("capture-{}.gif", secs))
+}
+
+/// Save the frames as an animated GIF.
+fn save_gif(path: &Path, frames: VecDeque<CapturedFrame>) -> DynResult<()> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    encode_gif(BufWriter::new(File::create(path)?), frames)
+}
+
+/// Encode the frames into an animated GIF, timing each frame after the
+/// time taken until the next one.
+fn encode_gif<W: Write>(writer: W, frames: VecDeque<CapturedFrame>) -> DynResult<()> {
+    let mut encoder = Encoder::new(writer, WIDTH, HEIGHT, &[])?;
+    encoder.set(Repeat::Infinite)?;
+    let mut frames = frames.into_iter().peekable();
+    // the last frame lasts as long as the one before it
+    let mut delay = MIN_DELAY;
+    while let Some(mut frame) = frames.next() {
+        if let Some(next) = frames.peek() {
+            let ms = duration_ms(next.time.duration_since(frame.time));
+            delay = ((ms / 10.).round() as u16).max(MIN_DELAY);
+        }
+        let mut gif_frame = Frame::from_rgba_speed(WIDTH, HEIGHT, &mut frame.pixels, QUANTIZE_SPEED);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+    use consts::{HEIGHT, WIDTH};
+    use super::{encode_gif, CapturedFrame};
+
+    #[test]
+    fn encodes_frames_into_a_gif() {
+        let start = Instant::now();
+        let frames: VecDeque<_> = [0u8, 255]
+            .iter()
+            .enumerate()
+            .map(|(i, &shade)| CapturedFrame {
+                pixels: vec![shade; usize::from(WIDTH) * usize::from(HEIGHT) * 4],
+                time: start + Duration::from_millis(33 * i as u64),
+            })
+            .collect();
+        let mut data = Vec::new();
+        encode_gif(&mut data, frames).unwrap();
+        assert!(data.starts_with(b"GIF89a"));
+    }
+}
@@ -0,0 +1,266 @@
+//! Module for the developer console, a drop-down text input for debug
+//! commands in the game and the editor.
+//!
+//! The console is toggled at run time with the backquote key or F10. It is
+//! only built with the `dev-console` feature.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::str::FromStr;
+use graphics::character::CharacterCache;
+use graphics::{rectangle, Context, DrawState, Graphics, Text, Transformed};
+use na::Vector2;
+use piston::input::GenericEvent;
+use util::clamp;
+
+/// The number of output lines kept and shown.
+const MAX_OUTPUT_LINES: usize = 12;
+/// The number of entered lines kept in the history.
+const MAX_HISTORY: usize = 50;
+/// The vertical distance between lines, in hires pixels.
+const LINE_HEIGHT: f64 = 16.;
+const BACK_COLOR: [f32; 4] = [0., 0., 0., 0.8];
+const OUTPUT_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+const INPUT_COLOR: [f32; 4] = [0.6, 1.0, 0.6, 1.0];
+const HELP: &[&str] = &[
+    "give gems N, setsize SIZE, tp X Y, spawn mine|gem|pump|leak X Y",
+    "timescale SCALE, reload, save, clear, help",
+];
+
+/// A kind of object which can be spawned from the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnKind {
+    Mine,
+    Gem,
+    Pump,
+    Leak,
+}
+
+impl FromStr for SpawnKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "mine" => Ok(SpawnKind::Mine),
+            "gem" => Ok(SpawnKind::Gem),
+            "pump" => Ok(SpawnKind::Pump),
+            "leak" => Ok(SpawnKind::Leak),
+            _ => Err(format!("Cannot spawn a {}", s)),
+        }
+    }
+}
+
+/// A command entered in the console, which the controller carries out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// collect the given number of gems
+    GiveGems(u32),
+    /// change the size of the ball
+    SetSize(f32),
+    /// move the ball, or the view in the editor, to the given position
+    Teleport(Vector2<f32>),
+    /// create an object at the given position
+    Spawn(SpawnKind, Vector2<f32>),
+    /// run the game slower or faster by the given factor
+    TimeScale(f64),
+    /// load the level's files again
+    Reload,
+    /// save the level, or the state of the run in the game
+    Save,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<_> = words.collect();
+        match (name, args.len()) {
+            ("give", 2) if args[0] == "gems" => parse_arg(args[1]).map(Command::GiveGems),
+            ("setsize", 1) => {
+                let size = parse_arg(args[0])?;
+                if size > 0. {
+                    Ok(Command::SetSize(size))
+                } else {
+                    Err("The size must be positive".to_string())
+                }
+            }
+            ("tp", 2) => Ok(Command::Teleport(parse_position(&args)?)),
+            ("spawn", 3) => Ok(Command::Spawn(args[0].parse()?, parse_position(&args[1..])?)),
+            ("timescale", 1) => {
+                let scale = parse_arg(args[0])?;
+                if scale >= 0. {
+                    Ok(Command::TimeScale(scale))
+                } else {
+                    Err("The time scale cannot be negative".to_string())
+                }
+            }
+            ("reload", 0) => Ok(Command::Reload),
+            ("save", 0) => Ok(Command::Save),
+            _ => Err(format!("Unknown command: {} (see help)", line)),
+        }
+    }
+}
+
+fn parse_arg<T: FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("Not a number: {}", word))
+}
+
+fn parse_position(args: &[&str]) -> Result<Vector2<f32>, String> {
+    Ok(Vector2::new(parse_arg(args[0])?, parse_arg(args[1])?))
+}
+
+/// What became of an event given to the console.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleInput {
+    /// the console is closed, so the event is left to the controller
+    Ignored,
+    /// the console took the event
+    Consumed,
+    /// the console was opened, so the controller should forget any held
+    /// keys, whose release it will not see
+    Opened,
+    /// the console took the event, which entered a command
+    Command(Command),
+}
+
+/// The state of the developer console.
+#[derive(Debug, Default)]
+pub struct Console {
+    open: bool,
+    /// the line being typed
+    input: String,
+    /// the lines entered before, oldest first
+    history: Vec<String>,
+    /// the index of the history entry being shown, if browsing it
+    browsing: Option<usize>,
+    output: VecDeque<String>,
+}
+
+impl Console {
+    /// Whether the console is shown.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Add a line to the output, such as the outcome of a command.
+    pub fn print<S: Into<String>>(&mut self, line: S) {
+        self.output.push_back(line.into());
+        while self.output.len() > MAX_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+    }
+
+    /// Handle an event, taking every event while the console is open.
+    pub fn event<E: GenericEvent>(&mut self, e: &E) -> ConsoleInput {
+        use piston::input::{Button, ButtonState, Key};
+        let toggled = e.text_args().map_or(false, |text| text == "`")
+            || e.press_args() == Some(Button::Keyboard(Key::F10));
+        if toggled {
+            self.open = !self.open;
+            self.input.clear();
+            self.browsing = None;
+            return if self.open {
+                ConsoleInput::Opened
+            } else {
+                ConsoleInput::Consumed
+            };
+        }
+        if !self.open {
+            return ConsoleInput::Ignored;
+        }
+        if let Some(b) = e.button_args().filter(|b| b.state == ButtonState::Press) {
+            match b.button {
+                Button::Keyboard(Key::Escape) => self.open = false,
+                Button::Keyboard(Key::Backspace) => {
+                    self.input.pop();
+                }
+                Button::Keyboard(Key::Up) => self.browse(-1),
+                Button::Keyboard(Key::Down) => self.browse(1),
+                Button::Keyboard(Key::Return) => return self.submit(),
+                _ => {}
+            }
+        }
+        if let Some(text) = e.text_args() {
+            self.input.extend(text.chars().filter(|c| !c.is_control()));
+        }
+        ConsoleInput::Consumed
+    }
+
+    /// Show an older or newer line of the history, going past the newest
+    /// one back to an empty line.
+    fn browse(&mut self, offset: isize) {
+        let end = self.history.len() as isize;
+        let current = self.browsing.map_or(end, |i| i as isize);
+        let next = clamp(current + offset, 0, end);
+        if next == end {
+            self.browsing = None;
+            self.input.clear();
+        } else {
+            self.browsing = Some(next as usize);
+            self.input = self.history[next as usize].clone();
+        }
+    }
+
+    /// Enter the typed line, handling the commands which concern the
+    /// console itself.
+    fn submit(&mut self) -> ConsoleInput {
+        let line = mem::replace(&mut self.input, String::new()).trim().to_string();
+        self.browsing = None;
+        if line.is_empty() {
+            return ConsoleInput::Consumed;
+        }
+        self.print(format!("> {}", line));
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        match line.as_str() {
+            "help" => {
+                for help in HELP {
+                    self.print(*help);
+                }
+                ConsoleInput::Consumed
+            }
+            "clear" => {
+                self.output.clear();
+                ConsoleInput::Consumed
+            }
+            _ => match Command::parse(&line) {
+                Ok(command) => ConsoleInput::Command(command),
+                Err(e) => {
+                    self.print(e);
+                    ConsoleInput::Consumed
+                }
+            },
+        }
+    }
+
+    /// Draw the console over the top of the screen, if it is open.
+    pub fn draw<C, G>(&self, ctx: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = C::Texture>,
+    {
+        if !self.is_open() {
+            return;
+        }
+        let width = f64::from(ctx.viewport.map(|v| v.window_size[0]).unwrap_or(0));
+        let height = LINE_HEIGHT * (MAX_OUTPUT_LINES + 1) as f64 + 12.;
+        rectangle(BACK_COLOR, [0., 0., width, height], ctx.transform, g);
+        let lines = self.output.iter().map(|line| (OUTPUT_COLOR, line.clone()));
+        let input = (INPUT_COLOR, format!("> {}_", self.input));
+        // the input sits at the bottom, with the output right above it
+        let first = MAX_OUTPUT_LINES - self.output.len();
+        for (i, (color, text)) in lines.chain(Some(input)).enumerate() {
+            let _ = Text::new_color(color, 12).draw(
+                &text,
+                cache,
+                &DrawState::default(),
+                ctx.transform.trans(8., LINE_HEIGHT * (first + i + 1) as f64),
+                g,
+            );
+        }
+    }
+}
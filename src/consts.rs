@@ -0,0 +1,10 @@
+//! Module for global game constants.
+
+/// The width of the logical (low resolution) display, in pixels.
+pub const WIDTH: u16 = 320;
+/// The height of the logical (low resolution) display, in pixels.
+pub const HEIGHT: u16 = 200;
+/// The default width of the game window.
+pub const DEFAULT_PHYSICAL_WIDTH: u16 = WIDTH * 3;
+/// The default height of the game window.
+pub const DEFAULT_PHYSICAL_HEIGHT: u16 = HEIGHT * 3;
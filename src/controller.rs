@@ -1,11 +1,17 @@
 use graphics::{Context, Graphics};
 use graphics::character::CharacterCache;
 use piston::input::{GenericEvent, UpdateArgs};
+use profile::FrameStats;
 use resource::{ResourceManage, SpriteManage};
 
 pub trait Controller {
     type Res: ResourceManage;
     const NEEDS_HI_RES: bool = false;
+    /// the name of the controller, to tell its frame times apart
+    const NAME: &'static str = "controller";
+    /// whether the controllers beneath this one in the stack keep being
+    /// drawn under it
+    const TRANSLUCENT: bool = false;
 
     fn event<E>(&mut self, e: &E) -> Option<ControllerAction>
     where
@@ -25,6 +31,25 @@ pub trait Controller {
         // render nothing by default
     }
 
+    /// Forget the state of held keys and buttons, since their release will
+    /// not be seen while the window is out of focus.
+    /// What the controller is showing, to be put in the window title, such
+    /// as the name of the level being played.
+    fn title(&self) -> Option<String> {
+        // leave the title alone by default
+        None
+    }
+
+    fn focus_lost(&mut self) {
+        // do nothing by default
+    }
+
+    /// Receive the frame times of the recent frames, to show them on the
+    /// screen.
+    fn show_frame_stats(&mut self, _stats: &FrameStats) {
+        // ignore them by default
+    }
+
     fn exit(&mut self) {
         // do nothing by default
     }
@@ -32,10 +57,31 @@ pub trait Controller {
 
 pub type LevelId = u16;
 
+/// A controller to be opened, as asked for by another controller.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameState {
+    Title,
+    Game(LevelId),
+    /// Play back a replay file on the given level.
+    Replay(LevelId, String),
+    Editor(Option<String>),
+    /// The pause menu, shown over the game.
+    Pause,
+    Error { message: String, fatal: bool },
+    Exit,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ControllerAction {
     Exit,
     OpenEditor(Option<String>),
     LoadGame(LevelId),
     LoadTitleScreen,
+    /// Open a controller over the current one, which is kept alive but
+    /// receives no events nor updates until the new one is closed.
+    Push(GameState),
+    /// Close the current controller, going back to the one beneath it.
+    Pop,
+    /// The loading screen is done, and the game it loaded takes its place.
+    Loaded,
 }
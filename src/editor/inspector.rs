@@ -0,0 +1,110 @@
+use lang::tr;
+use super::object::{ObjectInfo, ObjectRef};
+
+/// A numeric property of an object which can be adjusted in the inspector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Property {
+    X,
+    Y,
+    Width,
+    Height,
+    Texture,
+    GemsRequired,
+}
+
+impl Property {
+    /// The properties of the given object which can be adjusted, in the
+    /// order in which they are shown.
+    pub fn all_of(info: &ObjectInfo) -> &'static [Property] {
+        use self::Property::*;
+        match *info {
+            ObjectInfo::Wall(_) => &[X, Y, Width, Height, Texture],
+            ObjectInfo::Spikes(_) | ObjectInfo::Water(_) => &[X, Y, Width, Height],
+            ObjectInfo::Finish(_) => &[X, Y, GemsRequired],
+            _ => &[X, Y],
+        }
+    }
+
+    pub fn label(&self) -> String {
+        use self::Property::*;
+        match *self {
+            X => tr("inspector.x"),
+            Y => tr("inspector.y"),
+            Width => tr("inspector.width"),
+            Height => tr("inspector.height"),
+            Texture => tr("inspector.texture"),
+            GemsRequired => tr("inspector.gems_required"),
+        }
+    }
+
+    /// Whether the property is a position or a size, which is adjusted by
+    /// the grid spacing rather than one unit at a time.
+    pub fn is_spatial(&self) -> bool {
+        use self::Property::*;
+        match *self {
+            X | Y | Width | Height => true,
+            Texture | GemsRequired => false,
+        }
+    }
+
+    /// Obtain the value of this property of the given object, if it has it.
+    pub fn value_of(&self, info: &ObjectInfo) -> Option<i32> {
+        use self::Property::*;
+        match (*self, info) {
+            (X, info) => Some(info.position()[0]),
+            (Y, info) => Some(info.position()[1]),
+            (Width, &ObjectInfo::Wall(ref info)) => Some(info.dim[0]),
+            (Width, &ObjectInfo::Spikes(ref info)) => Some(info.dim[0]),
+            (Width, &ObjectInfo::Water(ref info)) => Some(info.dim[0]),
+            (Height, &ObjectInfo::Wall(ref info)) => Some(info.dim[1]),
+            (Height, &ObjectInfo::Spikes(ref info)) => Some(info.dim[1]),
+            (Height, &ObjectInfo::Water(ref info)) => Some(info.dim[1]),
+            (Texture, &ObjectInfo::Wall(ref info)) => Some(info.texture_id as i32),
+            (GemsRequired, &ObjectInfo::Finish(ref info)) => Some(info.gems_required as i32),
+            _ => None,
+        }
+    }
+
+    /// Write the given value into this property of the object. Objects
+    /// without the property are left unchanged.
+    pub fn set(&self, info: &mut ObjectInfo, value: i32) {
+        use self::Property::*;
+        match (*self, info) {
+            (X, info) => {
+                let mut pos = info.position();
+                pos[0] = value;
+                info.set_position(pos);
+            }
+            (Y, info) => {
+                let mut pos = info.position();
+                pos[1] = value;
+                info.set_position(pos);
+            }
+            (Width, &mut ObjectInfo::Wall(ref mut info)) => info.dim[0] = value,
+            (Width, &mut ObjectInfo::Spikes(ref mut info)) => info.dim[0] = value,
+            (Width, &mut ObjectInfo::Water(ref mut info)) => info.dim[0] = value,
+            (Height, &mut ObjectInfo::Wall(ref mut info)) => info.dim[1] = value,
+            (Height, &mut ObjectInfo::Spikes(ref mut info)) => info.dim[1] = value,
+            (Height, &mut ObjectInfo::Water(ref mut info)) => info.dim[1] = value,
+            (Texture, &mut ObjectInfo::Wall(ref mut info)) => info.texture_id = value as u32,
+            (GemsRequired, &mut ObjectInfo::Finish(ref mut info)) => info.gems_required = value as u32,
+            _ => {}
+        }
+    }
+}
+
+/// The name of the given kind of object, as shown in the inspector.
+pub fn kind_label(obj: ObjectRef) -> String {
+    match obj {
+        ObjectRef::Wall(_) => tr("object.wall"),
+        ObjectRef::Spikes(_) => tr("object.spikes"),
+        ObjectRef::Mine(_) => tr("object.mine"),
+        ObjectRef::Leak(_) => tr("object.leak"),
+        ObjectRef::Pump(_) => tr("object.pump"),
+        ObjectRef::Boost(_) => tr("object.boost"),
+        ObjectRef::Gem(_) => tr("object.gem"),
+        ObjectRef::Finish => tr("object.finish"),
+        ObjectRef::Decoration(_) => tr("object.decoration"),
+        ObjectRef::Water(_) => tr("object.water"),
+    }
+}
@@ -0,0 +1,110 @@
+use lang::tr;
+use level::GameLevel;
+use level::info;
+
+/// A level metadata field which can be edited through text entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataField {
+    Name,
+    Author,
+    Description,
+    Difficulty,
+    ParTimes,
+}
+
+impl MetadataField {
+    /// The field which comes after this one, if any.
+    pub fn next(&self) -> Option<MetadataField> {
+        use self::MetadataField::*;
+        match *self {
+            Name => Some(Author),
+            Author => Some(Description),
+            Description => Some(Difficulty),
+            Difficulty => Some(ParTimes),
+            ParTimes => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        use self::MetadataField::*;
+        match *self {
+            Name => tr("metadata.name"),
+            Author => tr("metadata.author"),
+            Description => tr("metadata.description"),
+            Difficulty => tr("metadata.difficulty"),
+            ParTimes => tr("metadata.par_times"),
+        }
+    }
+
+    /// Obtain the current value of this field in the level, as text.
+    pub fn value_of(&self, level: &GameLevel) -> String {
+        use self::MetadataField::*;
+        match *self {
+            Name => level.name().to_string(),
+            Author => level.author().unwrap_or_default().to_string(),
+            Description => level.description().unwrap_or_default().to_string(),
+            Difficulty => level
+                .difficulty()
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            ParTimes => level
+                .par_times()
+                .map(|p| format!("{} {} {}", p.gold, p.silver, p.bronze))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Write the given text into this field of the level. Empty text
+    /// clears optional fields.
+    pub fn apply(&self, level: &mut GameLevel, text: &str) {
+        use self::MetadataField::*;
+        let text = text.trim();
+        let opt_text = if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
+        };
+        match *self {
+            Name => {
+                if let Some(name) = opt_text {
+                    level.set_name(name);
+                }
+            }
+            Author => level.set_author(opt_text),
+            Description => level.set_description(opt_text),
+            Difficulty => level.set_difficulty(text.parse().ok()),
+            ParTimes => level.set_par_times(parse_par_times(text)),
+        }
+    }
+}
+
+/// Parse the gold, silver and bronze times of a level, in this order,
+/// separated by spaces or commas.
+fn parse_par_times(text: &str) -> Option<info::ParTimes> {
+    let times: Vec<f32> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match times[..] {
+        [gold, silver, bronze] => Some(info::ParTimes { gold, silver, bronze }),
+        _ => None,
+    }
+}
+
+/// The state of an ongoing metadata text entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextEntry {
+    pub field: MetadataField,
+    pub buffer: String,
+}
+
+impl TextEntry {
+    pub fn new(field: MetadataField, level: &GameLevel) -> Self {
+        TextEntry {
+            field,
+            buffer: field.value_of(level),
+        }
+    }
+}
@@ -1,26 +1,113 @@
+use std::cell::Cell;
 use std::path::{Path, PathBuf};
-use na::Vector2;
-use camera::Camera;
+use std::time::SystemTime;
+use na::{norm_squared, Vector2};
+use camera::{pan_direction, Camera};
+#[cfg(feature = "dev-console")]
+use console::{Command, Console, ConsoleInput, SpawnKind};
+use consts::{DEFAULT_PHYSICAL_HEIGHT, DEFAULT_PHYSICAL_WIDTH, HEIGHT, WIDTH};
+use lang::{tr, tr_fmt};
 use level::*;
 use level::info::*;
+use level::stats::LevelStats;
 use game::{entities, wall};
-use game::ball::{Ball, BallController, BALL_DEFAULT_SIZE};
-use graphics::{clear, ellipse, rectangle, Context, Graphics, Transformed};
+use game::background::Background;
+use game::border;
+use game::decoration::Decoration;
+use game::leak::Leak;
+use game::level_entities::LevelEntities;
+use game::spikes::SpikeStrip;
+use game::water::Water;
+use game::minimap::{Minimap, FINISH_COLOR, PUMP_COLOR};
+use game::palette::Palette;
+use game::ball::{Ball, BallController, Thrust, BALL_DEFAULT_SIZE, MIN_BALL_SIZE};
+use graphics::{ellipse, line, rectangle, Context, DrawState, Graphics, Rectangle, Text, Transformed};
 use graphics::character::CharacterCache;
+use graphics::{ImageSize, Viewport};
 use piston::input::{GenericEvent, UpdateArgs};
 use controller::{Controller, ControllerAction};
-use resource::{GameTexture, ResourceManage, Result, SpriteAssetId, SpriteManage};
-use physics::{Collidable, SimpleCollidable};
+use resource::{GameTexture, ResourceManage, Result, SpriteAssetId, SpriteLease, SpriteManage};
+use resource::manifest::BASE_SPRITES;
+use resource::sprite::free_level_textures;
+use settings::Settings;
+use physics::{Collidable, Positioned, SimpleCollidable};
+use util::{clamp, default_vector2, modified_time, snap_to_grid, vec_f32, vec_i32, DynResult, WATCH_INTERVAL};
 
+mod inspector;
+mod metadata;
+mod object;
+mod open;
 mod placeholder;
+mod trajectory;
+use self::inspector::{kind_label, Property};
+use self::metadata::*;
+use self::object::*;
+use self::open::OpenDialog;
 use self::placeholder::*;
+use self::trajectory::{TrajectoryPreview, PREVIEW_TICKS_PER_UPDATE};
 
-const VERSION: &str = "1.0";
+/// The amount by which the map is resized with each key press.
+const MAP_RESIZE_STEP: i32 = 40;
+/// The empty space left around the level's contents when shrinking the map
+/// to fit.
+const MAP_FIT_MARGIN: f32 = 40.;
+/// The available grid spacings, in pixels. A spacing of 1 means free
+/// placement.
+const GRID_SPACINGS: [u32; 4] = [1, 4, 8, 16];
+/// The amount by which the ball's starting size changes with each key press.
+const BALL_SIZE_STEP: f32 = 1.;
+/// The width and height of each entry of the placeholder palette, in
+/// physical pixels.
+const PALETTE_CELL: f64 = 28.;
+/// The size of a water region placed with a click, without dragging.
+const DEFAULT_WATER_SIZE: i32 = 48;
+/// The smallest distance between objects placed along a line or in a grid,
+/// so that a fine grid does not flood the level.
+const MIN_PATTERN_SPACING: f32 = 16.;
+/// The most objects placed along a line or in a grid at once.
+const MAX_PATTERN_OBJECTS: usize = 400;
+/// How far the cursor may move during a Shift-click for it not to draw a
+/// selection rectangle.
+const CLICK_TOLERANCE: f32 = 2.;
+/// The length and depth of a newly placed spike strip.
+const SPIKE_STRIP_SIZE: (i32, i32) = (48, 12);
+/// The amount by which a boost pad's strength changes with each key press.
+const BOOST_STRENGTH_STEP: f32 = 0.5;
+const MAX_BOOST_STRENGTH: f32 = 6.;
+/// The amount by which a leak's radius changes with each key press.
+const LEAK_RADIUS_STEP: f32 = 4.;
+const MIN_LEAK_RADIUS: f32 = 8.;
+const MAX_LEAK_RADIUS: f32 = 96.;
+/// The durations offered for survival goals, in seconds.
+const SURVIVE_DURATIONS: [f32; 3] = [30., 60., 120.];
+/// How long messages stay on the screen, in ticks.
+const NOTICE_DURATION: f32 = 180.;
+/// The number of pixels for each map unit in level pictures.
+const SNAPSHOT_SCALE: u32 = 2;
+/// How far the camera pans per tick while an arrow key is held.
+const KEY_PAN_SPEED: f32 = 4.;
+/// How close to the window's border the cursor scrolls the camera, in
+/// physical pixels.
+const EDGE_SCROLL_MARGIN: f32 = 8.;
+/// How far the camera pans per tick while the cursor is at the border.
+const EDGE_SCROLL_SPEED: f32 = 2.;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum EditState {
     Idle,
     Panning,
+    /// Panning by dragging the left mouse button while holding Space.
+    SpacePanning,
+    /// Drawing a selection rectangle from the given logical position.
+    Selecting(Vector2<f32>),
+    /// Dragging the selected objects, last at the given snapped position.
+    Dragging(Vector2<i32>),
+    /// Drawing a water region or a tiled wall from the given snapped
+    /// position.
+    Sizing(Vector2<f32>),
+    /// Picking up the properties of an object with Alt held, so that
+    /// releasing the button does not place anything.
+    Picking,
 }
 
 impl Default for EditState {
@@ -35,19 +122,94 @@ where
 {
     level: GameLevel,
     res: R,
+    // keeps the sprites shared by all levels loaded
+    _sprites: SpriteLease<R>,
     ball: BallController<R>,
+    background: Background<R>,
     walls: Vec<wall::Wall<R>>,
+    // wall indices grouped by texture, in drawing order
+    wall_order: Vec<usize>,
+    spikes: Vec<SpikeStrip>,
     pumps: Vec<entities::Pump<R>>,
+    boosts: Vec<entities::Boost<R>>,
     mines: Vec<entities::Mine<R>>,
+    leaks: Vec<Leak>,
     gems: Vec<entities::Gem<R>>,
     finish: Option<entities::Finish<R>>,
+    water: Vec<Water>,
+    decorations: Vec<Decoration<R>>,
     camera: Camera,
     // the physical cursor, relative to display
     cursor: Vector2<f32>,
     // the logical cursor, relative to map
     logical_cursor: Vector2<f32>,
+    // the number of window coordinates per map pixel on each axis, as of
+    // the last frame
+    cursor_scale: Cell<Vector2<f32>>,
     state: EditState,
     placeholder: ObjectPlaceholder,
+    // level metadata being typed in, if any
+    text_entry: Option<TextEntry>,
+    minimap: Minimap,
+    // the index of the grid spacing to which objects snap
+    grid: usize,
+    // whether to draw the grid
+    show_grid: bool,
+    // the last copied object, if any
+    clipboard: Option<ObjectInfo>,
+    // whether a Ctrl key is held down
+    ctrl: bool,
+    // whether a Shift key is held down
+    shift: bool,
+    // whether an Alt key is held down
+    alt: bool,
+    // the currently selected objects
+    selection: Vec<ObjectRef>,
+    // the first end of a line or grid of objects being placed, if any
+    pattern_start: Option<Vector2<f32>>,
+    // the list of levels to open, if being shown
+    open_dialog: Option<OpenDialog>,
+    // whether the level has changed since it was loaded or saved
+    modified: bool,
+    // whether to ask before leaving with unsaved changes
+    confirm_exit: bool,
+    // a reason why the ball may not be able to finish the level, when
+    // asking whether to save it anyway
+    confirm_save: Option<String>,
+    // a message for the user, with its remaining ticks on the screen
+    notice: Option<(String, f32)>,
+    // the file which the level was last loaded from or saved to, if any
+    path: Option<PathBuf>,
+    // the modification time of that file, when it was last loaded or saved
+    file_time: Option<SystemTime>,
+    // whether to ask before reloading the level after it changed on disk
+    confirm_reload: bool,
+    // the time since files were last checked for changes, in seconds
+    watch_time: f64,
+    // the arrow keys held down for panning: left, right, up and down
+    pan_keys: [bool; 4],
+    // whether Space is held down, so that left dragging pans
+    space: bool,
+    // whether the cursor at the window's border scrolls the camera
+    edge_scroll: bool,
+    // whether the cursor is inside the window
+    cursor_inside: bool,
+    // the active field of the object inspector, if it is shown
+    inspector: Option<usize>,
+    // whether to show the level's statistics
+    show_stats: bool,
+    // the level's statistics, if they are up to date
+    stats: Option<LevelStats>,
+    // the simulated path of the ball, while Shift + T is held
+    trajectory: Option<TrajectoryPreview<R>>,
+    // the direction in which the ball is thrust in the preview
+    trajectory_thrust: Thrust,
+    // the arrow keys held down while previewing the ball's path
+    trajectory_keys: Thrust,
+    #[cfg(feature = "dev-console")]
+    console: Console,
+    // the colors in which the ball and the level's objects are drawn
+    palette: &'static Palette,
 }
 
 
@@ -58,172 +220,397 @@ where
 {
     pub fn new(resource_manager: R) -> Result<Self> {
         let mut lvl = GameLevel::default();
-        lvl.set_version(VERSION); 
+        lvl.set_version(CURRENT_VERSION); 
         LevelEditorController::with_level(lvl, resource_manager)
     }
 
-    pub fn load<P: AsRef<Path>>(path: P, resource_manager: R) -> Result<Self> {
-        let level = GameLevel::load(path).unwrap();
-        LevelEditorController::with_level(level, resource_manager)
+    pub fn load<P: AsRef<Path>>(path: P, resource_manager: R) -> DynResult<Self> {
+        let level = GameLevel::load(&path)?;
+        let mut editor = LevelEditorController::with_level(level, resource_manager)?;
+        editor.set_path(path.as_ref().to_path_buf());
+        Ok(editor)
     }
 
     fn with_level(level: GameLevel, resource_manager: R) -> Result<Self> {
-        LevelEditorController::load_base_assets(resource_manager)?;
-        let ball = Ball::with_default_size(level.ball_position());
-        let ball = BallController::new(ball, resource_manager)?;
+        LevelEntities::load_assets(&level, resource_manager)?;
+        let sprites = SpriteLease::new(resource_manager, &BASE_SPRITES);
+        let ball = Ball::new(level.ball_position(), level.ball_size().unwrap_or(BALL_DEFAULT_SIZE));
+        let ball = BallController::new(ball, level.ball_params(), resource_manager)?;
         let mut camera = Camera::default();
         camera.focus_on(level.ball_position(), level.map().dimensions_f32());
+        let background = Background::new(&level, resource_manager);
 
-        let walls: Result<Vec<_>> = level
-            .walls()
-            .iter()
-            .map(|info| wall::Wall::new(info.clone(), resource_manager))
-            .collect();
-
-        let pumps: Result<Vec<_>> = level
-            .pumps()
-            .iter()
-            .map(|info| entities::Pump::new(info.clone(), resource_manager))
-            .collect();
-
-        let mines: Result<Vec<_>> = level
-            .mines()
-            .iter()
-            .map(|info| entities::Mine::new(info.clone(), resource_manager))
-            .collect();
-
-        let gems: Result<Vec<_>> = level
-            .gems()
-            .iter()
-            .map(|info| entities::Gem::new(info.clone(), resource_manager))
-            .collect();
-
-        let finish = if let Some(finish_info) = level.finish_flag() {
-            Some(entities::Finish::new(
-                finish_info.clone(),
-                resource_manager,
-            )?)
-        } else {
-            None
-        };
+        let minimap = Minimap::new(&level);
 
-        Ok(LevelEditorController {
+        let mut editor = LevelEditorController {
             level,
             ball,
+            background,
             camera,
             cursor: [0.0, 0.0].into(),
             logical_cursor: [0.0, 0.0].into(),
+            cursor_scale: Cell::new(Vector2::new(
+                f32::from(DEFAULT_PHYSICAL_WIDTH) / f32::from(WIDTH),
+                f32::from(DEFAULT_PHYSICAL_HEIGHT) / f32::from(HEIGHT),
+            )),
             state: Default::default(),
             res: resource_manager,
-            walls: walls?,
-            mines: mines?,
-            pumps: pumps?,
-            gems: gems?,
-            finish,
-            placeholder: ObjectPlaceholder::Wall {
-                dim: [48.0, 48.0].into(),
-                texture_id: 0,
+            _sprites: sprites,
+            walls: Vec::new(),
+            wall_order: Vec::new(),
+            spikes: Vec::new(),
+            mines: Vec::new(),
+            leaks: Vec::new(),
+            pumps: Vec::new(),
+            boosts: Vec::new(),
+            gems: Vec::new(),
+            finish: None,
+            water: Vec::new(),
+            decorations: Vec::new(),
+            placeholder: ObjectPlaceholder::default_wall(),
+            text_entry: None,
+            minimap,
+            grid: 1,
+            show_grid: false,
+            clipboard: None,
+            ctrl: false,
+            shift: false,
+            alt: false,
+            selection: Vec::new(),
+            pattern_start: None,
+            open_dialog: None,
+            modified: false,
+            confirm_exit: false,
+            confirm_save: None,
+            notice: None,
+            path: None,
+            file_time: None,
+            confirm_reload: false,
+            watch_time: 0.,
+            pan_keys: [false; 4],
+            space: false,
+            edge_scroll: false,
+            cursor_inside: false,
+            inspector: None,
+            show_stats: false,
+            stats: None,
+            trajectory: None,
+            trajectory_thrust: Thrust {
+                right: true,
+                ..Thrust::default()
             },
-        })
-    }
-
-    fn load_base_assets(resource_manager: R) -> Result<()> {
-        let mut sprite = resource_manager.sprite();
-        sprite.new_sprite_from_path(SpriteAssetId::Pump, "assets/pump-wheel.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Gem, "assets/gem.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Mine, "assets/mine.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Flag, "assets/flag.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Check, "assets/check.png")?;
-        for i in 0.. {
-            let path = format!("assets/{}.png", i);
-            match sprite.new_sprite_from_path(SpriteAssetId::Other(i), path) {
-                Ok(_) => {}
-                Err(_) => {
-                    return Ok(());
+            trajectory_keys: Thrust::default(),
+            #[cfg(feature = "dev-console")]
+            console: Console::default(),
+            palette: Settings::load_or_default().palette.palette(),
+        };
+        editor.rebuild_entities()?;
+        Ok(editor)
+    }
+
+    /// Record a change to the level, which is then to be saved and have its
+    /// statistics updated.
+    fn mark_modified(&mut self) {
+        self.modified = true;
+        self.stats = None;
+        if self.trajectory.is_some() {
+            self.start_trajectory();
+        }
+    }
+
+    /// Recreate all entities from the level's information, discarding the
+    /// selection.
+    fn rebuild_entities(&mut self) -> Result<()> {
+        let res = self.res;
+        self.walls = self.level
+            .walls()
+            .iter()
+            .map(|info| wall::Wall::new(info.clone(), res))
+            .collect::<Result<_>>()?;
+        self.wall_order = wall::draw_order(self.level.walls());
+        let entities = LevelEntities::new(&self.level, res)?;
+        self.spikes = entities.spikes;
+        self.pumps = entities.pumps;
+        self.boosts = entities.boosts;
+        self.mines = entities.mines;
+        self.leaks = entities.leaks;
+        self.gems = entities.gems;
+        self.finish = entities.finish;
+        self.water = entities.water;
+        self.decorations = entities.decorations;
+        self.ball.set_position(self.level.ball_position());
+        self.selection.clear();
+        self.minimap.rebuild(&self.level);
+        self.stats = None;
+        Ok(())
+    }
+
+    /// Mirror the whole level around the map's center, horizontally or
+    /// vertically.
+    fn mirror_level(&mut self, horizontal: bool) -> Result<()> {
+        self.mark_modified();
+        let axis = if horizontal { 0 } else { 1 };
+        let map_len = if horizontal {
+            self.level.map().width()
+        } else {
+            self.level.map().height()
+        } as i32;
+        let decoration_dims: Vec<_> = self.decorations.iter().map(|d| d.dimensions()).collect();
+        {
+            let level = &mut self.level;
+            for info in level.walls_mut() {
+                info.pos[axis] = map_len - info.pos[axis] - info.dim[axis];
+                info.mirror_texture(axis);
+            }
+            for info in level.spikes_mut() {
+                info.pos[axis] = map_len - info.pos[axis] - info.dim[axis];
+                info.facing = info.facing.mirrored(axis);
+            }
+            for info in level.pumps_mut() {
+                info.pos[axis] = map_len - info.pos[axis];
+            }
+            for info in level.boosts_mut() {
+                info.pos[axis] = map_len - info.pos[axis];
+                info.direction[axis] = -info.direction[axis];
+            }
+            for info in level.mines_mut() {
+                info.pos[axis] = map_len - info.pos[axis];
+            }
+            for info in level.leaks_mut() {
+                info.pos[axis] = map_len - info.pos[axis];
+            }
+            for info in level.gems_mut() {
+                info.pos[axis] = map_len - info.pos[axis];
+            }
+            if let Some(info) = level.finish_flag_mut() {
+                info.pos[axis] = map_len - info.pos[axis];
+            }
+            for info in level.water_mut() {
+                info.pos[axis] = map_len - info.pos[axis] - info.dim[axis];
+            }
+            for (info, dim) in level.decorations_mut().iter_mut().zip(decoration_dims) {
+                info.pos[axis] = map_len - info.pos[axis] - dim[axis] as i32;
+            }
+            {
+                let open = level.map_mut().open_edges_mut();
+                if horizontal {
+                    ::std::mem::swap(&mut open.left, &mut open.right);
+                } else {
+                    ::std::mem::swap(&mut open.up, &mut open.down);
                 }
             }
+            let mut ball_pos = level.ball_position();
+            ball_pos[axis] = map_len as f32 - ball_pos[axis];
+            level.set_ball_position(ball_pos);
+            if let Some(mut pos) = level.second_ball_position() {
+                pos[axis] = map_len as f32 - pos[axis];
+                level.set_second_ball_position(Some(pos));
+            }
         }
-        Ok(())
+        self.rebuild_entities()
+    }
+
+    /// Move all of the level's contents by the given offset, growing the
+    /// map when moving towards the right or the bottom. The contents are
+    /// not moved if any of them would end up outside the map.
+    fn shift_level(&mut self, offset: Vector2<i32>) -> Result<()> {
+        let origin = self.content_origin();
+        if origin[0] + (offset[0] as f32) < 0. || origin[1] + (offset[1] as f32) < 0. {
+            eprintln!("Cannot shift the level by ({}, {}): some objects would be left outside", offset[0], offset[1]);
+            return Ok(());
+        }
+        self.level.translate(offset);
+        self.mark_modified();
+        self.rebuild_entities()
+    }
+
+
+    /// The spacing of the grid to which objects snap, in pixels.
+    fn grid_spacing(&self) -> u32 {
+        GRID_SPACINGS[self.grid]
+    }
+
+    /// Obtain the logical cursor snapped to the active grid.
+    fn snapped_cursor(&self) -> Vector2<f32> {
+        snap_to_grid(self.logical_cursor, self.grid_spacing() as f32)
     }
 
     fn place_current_object(&mut self) -> Result<()> {
-        let pos = self.logical_cursor;
+        let pos = self.snapped_cursor();
+        match self.placeholder_info(pos) {
+            Some(info) => self.add_object(info),
+            None => {
+                // just redefine the ball's position
+                self.ball.set_position(pos);
+                self.level.set_ball_position(pos);
+                self.mark_modified();
+                Ok(())
+            }
+        }
+    }
 
-        match self.placeholder {
-            ObjectPlaceholder::Wall { dim, texture_id } => {
-                // snap position to 4 pixel grid
-                let mut pos = pos;
-                pos /= 4.;
-                pos[0] = pos[0].round();
-                pos[1] = pos[1].round();
-                pos *= 4.;
-                let pos = Vector2::new(pos[0] as i32, pos[1] as i32);
-                let dim = Vector2::new(dim[0] as i32, dim[1] as i32);
-
-                // create info and entity
-                let info = WallInfo {
-                    pos,
-                    dim,
+    /// Create the information of an object like the current placeholder at
+    /// the given position. The ball is not an object, so it has none.
+    fn placeholder_info(&self, pos: Vector2<f32>) -> Option<ObjectInfo> {
+        let ipos = vec_i32(pos);
+        let info = match self.placeholder {
+            ObjectPlaceholder::Wall { dim, texture_id, rotation, flip, tile } => {
+                let dim = rotated_dim(dim, rotation);
+                ObjectInfo::Wall(WallInfo {
+                    pos: ipos,
+                    dim: vec_i32(dim),
                     texture_id,
-                };
-                let wall = wall::Wall::new(info.clone(), self.res)?;
+                    rotation,
+                    flip,
+                    tile,
+                })
+            }
+            ObjectPlaceholder::Mine => ObjectInfo::Mine(MineInfo { pos: ipos }),
+            ObjectPlaceholder::Leak { radius } => ObjectInfo::Leak(LeakInfo { pos: ipos, radius }),
+            ObjectPlaceholder::Pump { charges } => ObjectInfo::Pump(PumpInfo { pos: ipos, charges }),
+            ObjectPlaceholder::Boost { angle, strength } => ObjectInfo::Boost(BoostInfo {
+                pos: ipos,
+                direction: boost_direction(angle),
+                strength,
+            }),
+            ObjectPlaceholder::Gem => ObjectInfo::Gem(GemInfo { pos: ipos }),
+            ObjectPlaceholder::Ball => return None,
+            ObjectPlaceholder::Finish => ObjectInfo::Finish(FinishInfo {
+                pos: ipos,
+                gems_required: 0,
+            }),
+            ObjectPlaceholder::Decoration { texture_id, layer, .. } => {
+                ObjectInfo::Decoration(DecorationInfo {
+                    pos: ipos,
+                    texture_id,
+                    layer,
+                    parallax: DecorationInfo::default_parallax(),
+                })
+            }
+            ObjectPlaceholder::Water => ObjectInfo::Water(WaterInfo {
+                pos: ipos,
+                dim: Vector2::new(DEFAULT_WATER_SIZE, DEFAULT_WATER_SIZE),
+            }),
+            ObjectPlaceholder::Spikes { facing } => ObjectInfo::Spikes(SpikeInfo {
+                pos: ipos,
+                dim: spike_strip_dims(facing),
+                facing,
+            }),
+        };
+        Some(info)
+    }
+
+    /// Place evenly spaced copies of the current placeholder from the given
+    /// position to the snapped cursor, either along the line between them
+    /// or in a grid with them at opposite corners. Copies are at least a
+    /// grid step apart.
+    fn place_pattern(&mut self, start: Vector2<f32>, grid: bool) -> Result<()> {
+        let end = self.snapped_cursor();
+        let spacing = f32::max(self.grid_spacing() as f32, MIN_PATTERN_SPACING);
+        let points: Vec<Vector2<f32>> = if grid {
+            let xs = spread(start[0], end[0], spacing);
+            let ys = spread(start[1], end[1], spacing);
+            ys.iter()
+                .flat_map(|&y| xs.iter().map(move |&x| Vector2::new(x, y)))
+                .collect()
+        } else {
+            let length = norm_squared(&(end - start)).sqrt();
+            spread(0., length, spacing)
+                .into_iter()
+                .map(|d| if length > 0. { start + (end - start) * (d / length) } else { start })
+                .collect()
+        };
+        if points.len() > MAX_PATTERN_OBJECTS {
+            let message = tr_fmt("editor.pattern_too_large", &[&points.len(), &MAX_PATTERN_OBJECTS]);
+            self.notice = Some((message, NOTICE_DURATION));
+            return Ok(());
+        }
+        for &pos in &points {
+            if let Some(info) = self.placeholder_info(pos) {
+                self.add_object(info)?;
+            }
+        }
+        self.notice = Some((tr_fmt("editor.pattern_placed", &[&points.len()]), NOTICE_DURATION));
+        Ok(())
+    }
 
+    /// Place a water region between the given position and the snapped
+    /// cursor. A region without area is placed with the default size.
+    fn place_region(&mut self, start: Vector2<f32>) -> Result<()> {
+        let end = self.snapped_cursor();
+        let pos = Vector2::new(f32::min(start[0], end[0]) as i32, f32::min(start[1], end[1]) as i32);
+        let dim = Vector2::new((start[0] - end[0]).abs() as i32, (start[1] - end[1]).abs() as i32);
+        if dim[0] == 0 || dim[1] == 0 {
+            return self.place_current_object();
+        }
+        let info = match self.placeholder {
+            ObjectPlaceholder::Wall { texture_id, rotation, flip, tile, .. } => ObjectInfo::Wall(WallInfo {
+                pos,
+                dim,
+                texture_id,
+                rotation,
+                flip,
+                tile,
+            }),
+            _ => ObjectInfo::Water(WaterInfo { pos, dim }),
+        };
+        self.add_object(info)
+    }
+
+    /// Add an object to the level. There can only be one finish flag, so
+    /// adding one moves the existing flag instead.
+    fn add_object(&mut self, info: ObjectInfo) -> Result<()> {
+        self.mark_modified();
+        match info {
+            ObjectInfo::Wall(info) => {
+                let wall = wall::Wall::new(info.clone(), self.res)?;
                 // adjust map to fit
                 self.level.map_mut().expand_to_fit(info.pos + info.dim);
-
                 // add to editor
                 self.walls.push(wall);
                 // and add to level
                 self.level.walls_mut().push(info);
-
-                // we're done
-                Ok(())
+                self.wall_order = wall::draw_order(self.level.walls());
+                self.minimap.rebuild(&self.level);
             }
-            ObjectPlaceholder::Mine => {
-                let pos = Vector2::new(pos[0] as i32, pos[1] as i32);
-                let info = MineInfo { pos };
-                // add to map
+            ObjectInfo::Spikes(info) => {
+                self.level.map_mut().expand_to_fit(info.pos + info.dim);
+                self.spikes.push(SpikeStrip::new(info.clone()));
+                self.level.spikes_mut().push(info);
+            }
+            ObjectInfo::Mine(info) => {
                 let mine = entities::Mine::new(info.clone(), self.res)?;
                 self.mines.push(mine);
-                // and add to level
                 self.level.mines_mut().push(info);
-                // we're done
-                Ok(())
             }
-            ObjectPlaceholder::Pump => {
-                let pos = Vector2::new(pos[0] as i32, pos[1] as i32);
-                let info = PumpInfo { pos };
-                // add to map
+            ObjectInfo::Leak(info) => {
+                self.leaks.push(Leak::new(info.clone()));
+                self.level.leaks_mut().push(info);
+            }
+            ObjectInfo::Pump(info) => {
                 let pump = entities::Pump::new(info.clone(), self.res)?;
                 self.pumps.push(pump);
-                // and add to level
                 self.level.pumps_mut().push(info);
-                // we're done
-                Ok(())
             }
-            ObjectPlaceholder::Gem => {
-                let pos = Vector2::new(pos[0] as i32, pos[1] as i32);
-                let info = GemInfo { pos };
-                // add to map
+            ObjectInfo::Boost(info) => {
+                let boost = entities::Boost::new(info.clone(), self.res)?;
+                self.boosts.push(boost);
+                self.level.boosts_mut().push(info);
+            }
+            ObjectInfo::Gem(info) => {
                 let gem = entities::Gem::new(info.clone(), self.res)?;
                 self.gems.push(gem);
-                // add to level
                 self.level.gems_mut().push(info);
-                // update finish flag with gem count
+                // keep requiring all gems, unless the requirement was
+                // changed by hand
+                let num_gems = self.level.gems().len() as u32;
                 if let Some(finish) = self.level.finish_flag_mut() {
-                    finish.gems_required += 1;
+                    if finish.gems_required + 1 == num_gems {
+                        finish.gems_required = num_gems;
+                    }
                 }
-                // we're done
-                Ok(())
             }
-            ObjectPlaceholder::Ball => {
-                // just redefine the position
-                self.ball.set_position(pos);
-                self.level.set_ball_position(pos);
-                Ok(())
-            }
-            ObjectPlaceholder::Finish => {
-                let pos = Vector2::new(pos[0] as i32, pos[1] as i32);
+            ObjectInfo::Finish(FinishInfo { pos, .. }) => {
                 if let Some(f) = self.finish.as_mut() {
                     // redefine position in level
                     let info = self.level.finish_flag_mut().unwrap();
@@ -235,99 +622,1123 @@ where
                 let gems_required = self.level.gems().len() as u32;
                 let info = FinishInfo { pos, gems_required };
                 let finish = entities::Finish::new(info.clone(), self.res)?;
-                // add as entity
                 self.finish = Some(finish);
-                // add to level
                 self.level.set_finish_flag(info);
-                Ok(())
+            }
+            ObjectInfo::Decoration(info) => {
+                // no map expansion needed
+                let decoration = Decoration::new(info.clone(), self.res)?;
+                self.decorations.push(decoration);
+                self.level.decorations_mut().push(info);
+            }
+            ObjectInfo::Water(info) => {
+                self.level.map_mut().expand_to_fit(info.pos + info.dim);
+                self.water.push(Water::new(info.clone()));
+                self.level.water_mut().push(info);
             }
         }
+        Ok(())
     }
 
-    fn remove_at(&mut self, logical_pos: Vector2<f32>) -> bool {
-        // try to remove a wall
+    /// Find the object at the given position, if any.
+    fn object_at(&self, logical_pos: Vector2<f32>) -> Option<ObjectRef> {
         if let Some(i) = self.walls
             .iter()
             .position(|w| w.test_point_collision_simple(logical_pos))
         {
-            // remove entity
-            self.walls.remove(i);
-            // and remove from level
-            self.level.walls_mut().remove(i);
-            return true;
+            return Some(ObjectRef::Wall(i));
+        }
+        if let Some(i) = self.spikes
+            .iter()
+            .position(|o| o.test_point_collision_simple(logical_pos))
+        {
+            return Some(ObjectRef::Spikes(i));
         }
-
-        // try to remove a mine
         if let Some(i) = self.mines
             .iter()
             .position(|o| o.test_point_collision_simple(logical_pos))
         {
-            // remove entity
-            self.mines.remove(i);
-            // and remove from level
-            self.level.mines_mut().remove(i);
-            return true;
+            return Some(ObjectRef::Mine(i));
         }
-
-        // try to remove a pump
+        // pumps are only collidable through mutable references
+        let pump_radius = entities::PUMP_SIZE / 2.;
         if let Some(i) = self.pumps
-            .iter_mut()
-            .position(|o| o.test_point_collision_simple(logical_pos))
+            .iter()
+            .position(|o| norm_squared(&(o.position() - logical_pos)) <= pump_radius * pump_radius)
         {
-            // remove entity
-            self.pumps.remove(i);
-            // and remove from level
-            self.level.pumps_mut().remove(i);
-            return true;
+            return Some(ObjectRef::Pump(i));
+        }
+        let boost_radius = entities::BOOST_SIZE / 2.;
+        if let Some(i) = self.boosts
+            .iter()
+            .position(|o| norm_squared(&(o.position() - logical_pos)) <= boost_radius * boost_radius)
+        {
+            return Some(ObjectRef::Boost(i));
         }
-
-        // try to remove a gem
         if let Some(i) = self.gems
-            .iter_mut()
+            .iter()
             .position(|o| o.test_point_collision_simple(logical_pos))
         {
-            // remove entity
-            self.gems.remove(i);
-            // remove from level
-            self.level.gems_mut().remove(i);
-            // update gem count in finish flag
-            if let Some(finish) = self.level.finish_flag_mut() {
-                finish.gems_required -= 1;
-            }
-            return true;
+            return Some(ObjectRef::Gem(i));
         }
-
-        // try to remove the finish flag
         if self.finish
             .as_ref()
             .map(|o| o.test_point_collision_simple(logical_pos))
             .unwrap_or(false)
         {
-            // remove entity
-            self.finish = None;
-            // and remove from level
-            self.level.clear_finish_flag();
-            return true;
+            return Some(ObjectRef::Finish);
+        }
+        if let Some(i) = self.leaks
+            .iter()
+            .position(|o| o.overlaps(logical_pos, 0.))
+        {
+            return Some(ObjectRef::Leak(i));
+        }
+        // front decorations first
+        let camera_pos = self.camera.position();
+        if let Some(i) = self.decorations
+            .iter()
+            .rposition(|o| o.contains(logical_pos, camera_pos))
+        {
+            return Some(ObjectRef::Decoration(i));
+        }
+        // water last, so that objects in it can still be picked
+        self.water
+            .iter()
+            .position(|o| o.contains(logical_pos))
+            .map(ObjectRef::Water)
+    }
+
+    /// Remove the given object from the editor and from the level.
+    fn remove_object(&mut self, obj: ObjectRef) {
+        self.mark_modified();
+        match obj {
+            ObjectRef::Wall(i) => {
+                self.walls.remove(i);
+                self.level.walls_mut().remove(i);
+                self.wall_order = wall::draw_order(self.level.walls());
+                self.minimap.rebuild(&self.level);
+            }
+            ObjectRef::Spikes(i) => {
+                self.spikes.remove(i);
+                self.level.spikes_mut().remove(i);
+            }
+            ObjectRef::Mine(i) => {
+                self.mines.remove(i);
+                self.level.mines_mut().remove(i);
+            }
+            ObjectRef::Leak(i) => {
+                self.leaks.remove(i);
+                self.level.leaks_mut().remove(i);
+            }
+            ObjectRef::Pump(i) => {
+                self.pumps.remove(i);
+                self.level.pumps_mut().remove(i);
+            }
+            ObjectRef::Boost(i) => {
+                self.boosts.remove(i);
+                self.level.boosts_mut().remove(i);
+            }
+            ObjectRef::Gem(i) => {
+                self.gems.remove(i);
+                self.level.gems_mut().remove(i);
+                // the flag cannot require more gems than there are
+                let num_gems = self.level.gems().len() as u32;
+                if let Some(finish) = self.level.finish_flag_mut() {
+                    finish.gems_required = u32::min(finish.gems_required, num_gems);
+                }
+            }
+            ObjectRef::Finish => {
+                self.finish = None;
+                self.level.clear_finish_flag();
+            }
+            ObjectRef::Decoration(i) => {
+                self.decorations.remove(i);
+                self.level.decorations_mut().remove(i);
+            }
+            ObjectRef::Water(i) => {
+                self.water.remove(i);
+                self.level.water_mut().remove(i);
+            }
+        }
+    }
+
+    fn remove_at(&mut self, logical_pos: Vector2<f32>) -> bool {
+        if let Some(obj) = self.object_at(logical_pos) {
+            // indices of the selection would no longer be valid
+            self.selection.clear();
+            self.remove_object(obj);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replace the information of an existing object, rebuilding its
+    /// entity. Information of a different kind of object is ignored.
+    fn set_object_info(&mut self, obj: ObjectRef, info: ObjectInfo) -> Result<()> {
+        self.mark_modified();
+        match (obj, info) {
+            (ObjectRef::Wall(i), ObjectInfo::Wall(info)) => {
+                self.walls[i] = wall::Wall::new(info.clone(), self.res)?;
+                self.level.map_mut().expand_to_fit(info.pos + info.dim);
+                self.level.walls_mut()[i] = info;
+                self.wall_order = wall::draw_order(self.level.walls());
+            }
+            (ObjectRef::Spikes(i), ObjectInfo::Spikes(info)) => {
+                self.spikes[i] = SpikeStrip::new(info.clone());
+                self.level.map_mut().expand_to_fit(info.pos + info.dim);
+                self.level.spikes_mut()[i] = info;
+            }
+            (ObjectRef::Mine(i), ObjectInfo::Mine(info)) => {
+                self.mines[i] = entities::Mine::new(info.clone(), self.res)?;
+                self.level.mines_mut()[i] = info;
+            }
+            (ObjectRef::Leak(i), ObjectInfo::Leak(info)) => {
+                self.leaks[i] = Leak::new(info.clone());
+                self.level.leaks_mut()[i] = info;
+            }
+            (ObjectRef::Pump(i), ObjectInfo::Pump(info)) => {
+                self.pumps[i] = entities::Pump::new(info.clone(), self.res)?;
+                self.level.pumps_mut()[i] = info;
+            }
+            (ObjectRef::Boost(i), ObjectInfo::Boost(info)) => {
+                self.boosts[i] = entities::Boost::new(info.clone(), self.res)?;
+                self.level.boosts_mut()[i] = info;
+            }
+            (ObjectRef::Gem(i), ObjectInfo::Gem(info)) => {
+                self.gems[i] = entities::Gem::new(info.clone(), self.res)?;
+                self.level.gems_mut()[i] = info;
+            }
+            (ObjectRef::Finish, ObjectInfo::Finish(info)) => {
+                self.finish = Some(entities::Finish::new(info.clone(), self.res)?);
+                self.level.set_finish_flag(info);
+            }
+            (ObjectRef::Decoration(i), ObjectInfo::Decoration(info)) => {
+                self.decorations[i] = Decoration::new(info.clone(), self.res)?;
+                self.level.decorations_mut()[i] = info;
+            }
+            (ObjectRef::Water(i), ObjectInfo::Water(info)) => {
+                self.water[i] = Water::new(info.clone());
+                self.level.map_mut().expand_to_fit(info.pos + info.dim);
+                self.level.water_mut()[i] = info;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Change the ball's starting size, within the range of sizes at which
+    /// the ball is alive.
+    fn adjust_ball_size(&mut self, delta: f32) {
+        let size = self.level.ball_size().unwrap_or(BALL_DEFAULT_SIZE) + delta;
+        let size = clamp(size, MIN_BALL_SIZE, self.level.ball_params().capacity);
+        self.level.set_ball_size(if size == BALL_DEFAULT_SIZE { None } else { Some(size) });
+        let current = self.ball.size();
+        self.ball.add_size(size - current);
+        self.mark_modified();
+    }
+
+    /// The name of the ball physics preset used by the level, or "custom"
+    /// if the level's parameters do not match any preset.
+    fn ball_params_name(&self) -> &'static str {
+        let params = self.level.ball_params();
+        BallParams::presets()
+            .iter()
+            .find(|&&(_, p)| p == params)
+            .map(|&(name, _)| name)
+            .unwrap_or("custom")
+    }
+
+    /// Switch the level's ball physics to the next preset.
+    fn cycle_ball_params(&mut self) -> Result<()> {
+        let params = self.level.ball_params();
+        let presets = BallParams::presets();
+        let next = presets
+            .iter()
+            .position(|&(_, p)| p == params)
+            .map(|i| (i + 1) % presets.len())
+            .unwrap_or(0);
+        let params = presets[next].1;
+        self.level.set_ball_params(if params == BallParams::default() { None } else { Some(params) });
+        // keep the starting size within the new capacity
+        if let Some(size) = self.level.ball_size() {
+            self.level.set_ball_size(Some(f32::min(size, params.capacity)));
+        }
+        let size = self.level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+        let ball = Ball::new(self.level.ball_position(), size);
+        self.ball = BallController::new(ball, params, self.res)?;
+        self.mark_modified();
+        Ok(())
+    }
+
+    /// Change the strength of the boost pad being placed.
+    fn adjust_boost_strength(&mut self, delta: f32) {
+        if let ObjectPlaceholder::Boost { ref mut strength, .. } = self.placeholder {
+            *strength = clamp(*strength + delta, BOOST_STRENGTH_STEP, MAX_BOOST_STRENGTH);
+        }
+    }
+
+    /// Change the number of charges of the pump being placed, where going
+    /// below one charge makes them unlimited.
+    fn adjust_pump_charges(&mut self, delta: i32) {
+        if let ObjectPlaceholder::Pump { ref mut charges } = self.placeholder {
+            let n = charges.map(|n| n as i32).unwrap_or(0) + delta;
+            *charges = if n > 0 { Some(n as u32) } else { None };
+        }
+    }
+
+    /// Change the radius of the leak being placed.
+    fn adjust_leak_radius(&mut self, delta: f32) {
+        if let ObjectPlaceholder::Leak { ref mut radius } = self.placeholder {
+            *radius = clamp(*radius + delta, MIN_LEAK_RADIUS, MAX_LEAK_RADIUS);
+        }
+    }
+
+    /// Switch the level to the next kind of goal. Survival goals are
+    /// offered with a few different durations.
+    fn cycle_goal(&mut self) {
+        let goal = match self.level.goal() {
+            Goal::ReachFinish => Goal::CollectAllGems,
+            Goal::CollectAllGems => Goal::Survive { seconds: SURVIVE_DURATIONS[0] },
+            Goal::Survive { seconds } => {
+                match SURVIVE_DURATIONS.iter().position(|&s| s > seconds) {
+                    Some(i) => Goal::Survive { seconds: SURVIVE_DURATIONS[i] },
+                    None => Goal::ReachFinish,
+                }
+            }
+        };
+        self.level.set_goal(goal);
+        self.mark_modified();
+    }
+
+    /// Whether the finish flag is the target of editing commands, either
+    /// because it is the object being placed or because it is under the
+    /// cursor.
+    fn is_finish_targeted(&self) -> bool {
+        if let ObjectPlaceholder::Finish = self.placeholder {
+            return self.finish.is_some();
+        }
+        self.object_at(self.logical_cursor) == Some(ObjectRef::Finish)
+    }
+
+    /// Change the number of gems required by the finish flag, within the
+    /// number of gems in the level.
+    fn adjust_gems_required(&mut self, delta: i32) {
+        let num_gems = self.level.gems().len() as i32;
+        if let Some(mut info) = self.level.finish_flag().cloned() {
+            let required = info.gems_required as i32 + delta;
+            info.gems_required = clamp(required, 0, num_gems) as u32;
+            self.set_object_info(ObjectRef::Finish, ObjectInfo::Finish(info)).unwrap();
+        }
+    }
+
+    /// The object shown in the inspector: the selected object if there is
+    /// only one, or else the object under the cursor.
+    fn inspected(&self) -> Option<ObjectRef> {
+        if self.selection.len() == 1 {
+            return Some(self.selection[0]);
+        }
+        self.object_at(self.logical_cursor)
+    }
+
+    /// Adjust the active field of the inspected object, by the grid spacing
+    /// for positions and sizes or by one otherwise.
+    fn adjust_inspected(&mut self, sign: i32) -> Result<()> {
+        let (obj, field) = match (self.inspected(), self.inspector) {
+            (Some(obj), Some(field)) => (obj, field),
+            _ => return Ok(()),
+        };
+        let mut info = match ObjectInfo::from_level(&self.level, obj) {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        let properties = Property::all_of(&info);
+        let property = properties[field % properties.len()];
+        let value = match property.value_of(&info) {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        let step = if property.is_spatial() { self.grid_spacing() as i32 } else { 1 };
+        let value = value + sign * step;
+        let value = match property {
+            Property::Width | Property::Height => i32::max(1, value),
+            Property::Texture => {
+                // roll over the available textures
+                let count = u32::max(1, self.res.sprite().max_texture_id()) as i32;
+                (value % count + count) % count
+            }
+            Property::GemsRequired => clamp(value, 0, self.level.gems().len() as i32),
+            Property::X | Property::Y => value,
+        };
+        property.set(&mut info, value);
+        self.set_object_info(obj, info)?;
+        self.minimap.rebuild(&self.level);
+        Ok(())
+    }
+
+    /// Draw the inspector panel with the inspected object's fields, at the
+    /// top-left corner of the screen.
+    /// Draw a strip with every kind of placeholder at the bottom-right
+    /// corner, highlighting the one in hand and showing the number keys
+    /// which select them.
+    fn draw_palette<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let window_size = c.viewport.unwrap().window_size;
+        let kinds = ObjectPlaceholder::all_defaults();
+        let width = PALETTE_CELL * kinds.len() as f64;
+        let x0 = window_size[0] as f64 - width - 8.;
+        let y0 = window_size[1] as f64 - PALETTE_CELL - 8.;
+        rectangle([0., 0., 0., 0.75], [x0 - 2., y0 - 2., width + 4., PALETTE_CELL + 4.], c.transform, g);
+        let sprite = self.res.sprite();
+        for (i, kind) in kinds.iter().enumerate() {
+            let active = self.placeholder.is_same_kind(kind);
+            // the active entry shows the texture in hand
+            let kind = if active { &self.placeholder } else { kind };
+            let cell = [x0 + PALETTE_CELL * i as f64, y0, PALETTE_CELL, PALETTE_CELL];
+            let icon = [cell[0] + 3., cell[1] + 3., cell[2] - 6., cell[3] - 6.];
+            match palette_sprite(kind).and_then(|id| sprite.get_sprite_region(id).ok()) {
+                Some(region) => {
+                    let (w, h) = region.get_size();
+                    let (w, h) = (f64::from(u32::max(w, 1)), f64::from(u32::max(h, 1)));
+                    let scale = f64::min(icon[2] / w, icon[3] / h);
+                    let x = icon[0] + (icon[2] - w * scale) / 2.;
+                    let y = icon[1] + (icon[3] - h * scale) / 2.;
+                    region.draw(c.transform.trans(x, y).scale(scale, scale), g);
+                }
+                None => draw_palette_icon(kind, icon, c, g),
+            }
+            if active {
+                Rectangle::new_border([1.0, 0.85, 0.2, 0.9], 1.).draw(cell, &c.draw_state, c.transform, g);
+            }
+            if let Some(n) = kind.number() {
+                let _ = Text::new_color([1.; 4], 8).draw(
+                    &n.to_string(),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(cell[0] + 2., cell[1] + 9.),
+                    g,
+                );
+            }
+        }
+    }
+
+    fn draw_inspector<C, G>(&self, field: usize, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let inspected = self.inspected()
+            .and_then(|obj| ObjectInfo::from_level(&self.level, obj).map(|info| (obj, info)));
+        let (obj, info) = match inspected {
+            Some(inspected) => inspected,
+            None => {
+                rectangle([0., 0., 0., 0.75], [4., 4., 180., 24.], c.transform, g);
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                    &tr("inspector.nothing"),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(12., 20.),
+                    g,
+                );
+                return;
+            }
+        };
+        let properties = Property::all_of(&info);
+        let active = field % properties.len();
+        let height = 32. + 14. * properties.len() as f64;
+        rectangle([0., 0., 0., 0.75], [4., 4., 180., height], c.transform, g);
+        let _ = Text::new_color([1.; 4], 12).draw(
+            &kind_label(obj),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(12., 22.),
+            g,
+        );
+        for (i, property) in properties.iter().enumerate() {
+            let color = if i == active {
+                [1.0, 1.0, 0.25, 1.0]
+            } else {
+                [0.8, 0.8, 0.8, 1.0]
+            };
+            let value = property.value_of(&info).unwrap_or_default();
+            let _ = Text::new_color(color, 10).draw(
+                &format!("{}: {}", property.label(), value),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 40. + 14. * i as f64),
+                g,
+            );
+        }
+    }
+
+    /// Select all objects within the rectangle between the two given
+    /// logical positions.
+    fn select_within(&mut self, a: Vector2<f32>, b: Vector2<f32>) {
+        let min = Vector2::new(f32::min(a[0], b[0]) as i32, f32::min(a[1], b[1]) as i32);
+        let max = Vector2::new(f32::max(a[0], b[0]) as i32, f32::max(a[1], b[1]) as i32);
+        self.selection = ObjectRef::all_within(&self.level, min, max);
+    }
+
+    /// Move all selected objects by the given offset. If one of them cannot
+    /// be moved, the ones moved before it are put back where they were.
+    fn move_selection(&mut self, delta: Vector2<i32>) -> Result<()> {
+        let mut moved = Vec::new();
+        for obj in self.selection.clone() {
+            if let Some(info) = ObjectInfo::from_level(&self.level, obj) {
+                let mut new_info = info.clone();
+                new_info.set_position(info.position() + delta);
+                if let Err(e) = self.set_object_info(obj, new_info) {
+                    for (obj, info) in moved.into_iter().rev() {
+                        // these were built before, so they build again
+                        let _ = self.set_object_info(obj, info);
+                    }
+                    return Err(e);
+                }
+                moved.push((obj, info));
+            }
+        }
+        self.minimap.rebuild(&self.level);
+        Ok(())
+    }
+
+    /// Remove all selected objects.
+    fn remove_selection(&mut self) {
+        let mut selection = ::std::mem::replace(&mut self.selection, Vec::new());
+        // remove from the last index so that the other indices stay valid
+        selection.sort();
+        for obj in selection.into_iter().rev() {
+            self.remove_object(obj);
+        }
+    }
+
+    /// Obtain the bounding rectangle of an object, in logical coordinates.
+    fn object_bounds(&self, obj: ObjectRef) -> [f32; 4] {
+        let around = |pos: Vector2<f32>, w: f32, h: f32| [pos[0] - w / 2., pos[1] - h / 2., w, h];
+        match obj {
+            ObjectRef::Wall(i) => {
+                let info = &self.level.walls()[i];
+                [info.pos[0] as f32, info.pos[1] as f32, info.dim[0] as f32, info.dim[1] as f32]
+            }
+            ObjectRef::Spikes(i) => {
+                let info = &self.level.spikes()[i];
+                [info.pos[0] as f32, info.pos[1] as f32, info.dim[0] as f32, info.dim[1] as f32]
+            }
+            ObjectRef::Mine(i) => {
+                let size = entities::MINE_SIZE + 4.;
+                around(self.mines[i].position(), size, size)
+            }
+            ObjectRef::Leak(i) => {
+                let size = self.leaks[i].radius() * 2.;
+                around(self.leaks[i].position(), size, size)
+            }
+            ObjectRef::Pump(i) => {
+                around(self.pumps[i].position(), entities::PUMP_SIZE, entities::PUMP_SIZE)
+            }
+            ObjectRef::Boost(i) => {
+                around(self.boosts[i].position(), entities::BOOST_SIZE, entities::BOOST_SIZE)
+            }
+            ObjectRef::Gem(i) => {
+                around(self.gems[i].position(), entities::GEM_SIZE_W, entities::GEM_SIZE_H)
+            }
+            ObjectRef::Finish => {
+                let pos = self.finish.as_ref().map(|f| f.position()).unwrap_or_else(default_vector2);
+                around(pos, entities::FINISH_SIZE, entities::FINISH_SIZE)
+            }
+            ObjectRef::Decoration(i) => {
+                let info = &self.level.decorations()[i];
+                let dim = self.decorations[i].dimensions();
+                [info.pos[0] as f32, info.pos[1] as f32, dim[0], dim[1]]
+            }
+            ObjectRef::Water(i) => {
+                let info = &self.level.water()[i];
+                [info.pos[0] as f32, info.pos[1] as f32, info.dim[0] as f32, info.dim[1] as f32]
+            }
+        }
+    }
+
+    /// Copy the object under the cursor into the clipboard.
+    fn copy_at_cursor(&mut self) {
+        if let Some(obj) = self.object_at(self.logical_cursor) {
+            self.clipboard = ObjectInfo::from_level(&self.level, obj);
+        }
+    }
+
+    /// Add a copy of the clipboard's object at the snapped cursor position.
+    fn paste_at_cursor(&mut self) -> Result<()> {
+        if let Some(mut info) = self.clipboard.clone() {
+            let pos = self.snapped_cursor();
+            info.set_position(vec_i32(pos));
+            self.add_object(info)?;
+        }
+        Ok(())
+    }
+
+    /// Switch to placing objects of the same kind as the given placeholder,
+    /// keeping the current properties if the kind does not change.
+    fn select_placeholder(&mut self, placeholder: ObjectPlaceholder) {
+        if self.placeholder.is_same_kind(&placeholder) {
+            return;
+        }
+        self.placeholder = placeholder;
+        self.fit_placeholder_to_texture();
+    }
+
+    /// Make a textured placeholder as large as its texture, going back to
+    /// the first texture if its own does not exist.
+    fn fit_placeholder_to_texture(&mut self) {
+        if let Some((dim, texture_id)) = self.placeholder.texture_mut() {
+            let tdims = self.res
+                .sprite()
+                .get_sprite_dimensions(SpriteAssetId::Other(*texture_id));
+            if let Some(tex_dim) = tdims {
+                *dim = tex_dim;
+            } else {
+                *texture_id = 0;
+                *dim = [48., 48.].into();
+            }
+        }
+    }
+
+    /// Simulate the ball's path from its starting position again, thrust
+    /// in the chosen direction.
+    fn start_trajectory(&mut self) {
+        match TrajectoryPreview::new(&self.level, self.trajectory_thrust, self.res) {
+            Ok(preview) => self.trajectory = Some(preview),
+            Err(e) => eprintln!("Warning: could not preview the ball's path: {}", e),
+        }
+    }
+
+    /// Configure the placeholder to place objects like the one under the
+    /// cursor, leaving the level as it is.
+    fn pick_at_cursor(&mut self) {
+        let obj = match self.object_at(self.logical_cursor) {
+            Some(obj) => obj,
+            None => return,
+        };
+        let info = match ObjectInfo::from_level(&self.level, obj) {
+            Some(info) => info,
+            None => return,
+        };
+        let mut placeholder = ObjectPlaceholder::from_info(&info);
+        let keeps_dim = match info {
+            ObjectInfo::Wall(ref wall) => !wall.tile,
+            _ => false,
+        };
+        if !keeps_dim {
+            if let Some((dim, texture_id)) = placeholder.texture_mut() {
+                // tiles and decorations are as large as their texture
+                let tdims = self.res
+                    .sprite()
+                    .get_sprite_dimensions(SpriteAssetId::Other(*texture_id));
+                if let Some(tex_dim) = tdims {
+                    *dim = tex_dim;
+                }
+            }
+        }
+        self.placeholder = placeholder;
+        self.notice = Some((tr_fmt("editor.picked", &[&kind_label(obj)]), NOTICE_DURATION));
+    }
+
+    /// Add a copy of the object under the cursor, one grid cell away from it.
+    fn duplicate_at_cursor(&mut self) -> Result<()> {
+        let info = self.object_at(self.logical_cursor)
+            .and_then(|obj| ObjectInfo::from_level(&self.level, obj));
+        if let Some(mut info) = info {
+            let step = self.grid_spacing() as i32;
+            let pos = info.position() + Vector2::new(step, step);
+            info.set_position(pos);
+            self.add_object(info)?;
+        }
+        Ok(())
+    }
+
+    /// Obtain the bottom-right corner of the bounding box of all objects in
+    /// the level.
+    fn content_extent(&self) -> Vector2<f32> {
+        let mut extent: Vector2<f32> = [1., 1.].into();
+        {
+            let mut fit = |pos: Vector2<f32>, hsize: f32| {
+                extent[0] = f32::max(extent[0], pos[0] + hsize);
+                extent[1] = f32::max(extent[1], pos[1] + hsize);
+            };
+            for info in self.level.walls() {
+                let corner = info.pos + info.dim;
+                fit(vec_f32(corner), 0.);
+            }
+            for info in self.level.spikes() {
+                let corner = info.pos + info.dim;
+                fit(vec_f32(corner), 0.);
+            }
+            for info in self.level.water() {
+                let corner = info.pos + info.dim;
+                fit(vec_f32(corner), 0.);
+            }
+            for pump in &self.pumps {
+                fit(pump.position(), entities::PUMP_SIZE / 2.);
+            }
+            for boost in &self.boosts {
+                fit(boost.position(), entities::BOOST_SIZE / 2.);
+            }
+            for mine in &self.mines {
+                fit(mine.position(), entities::MINE_SIZE / 2.);
+            }
+            for leak in &self.leaks {
+                fit(leak.position(), leak.radius());
+            }
+            for gem in &self.gems {
+                fit(gem.position(), entities::GEM_SIZE_H / 2.);
+            }
+            if let Some(finish) = self.finish.as_ref() {
+                fit(finish.position(), entities::FINISH_SIZE / 2.);
+            }
+            fit(self.ball.position(), BALL_DEFAULT_SIZE / 2.);
+        }
+        extent
+    }
+
+    /// Obtain the top-left corner of the bounding box of all objects in the
+    /// level.
+    fn content_origin(&self) -> Vector2<f32> {
+        let mut origin = self.level.map().dimensions_f32();
+        {
+            let mut fit = |pos: Vector2<f32>| {
+                origin[0] = f32::min(origin[0], pos[0]);
+                origin[1] = f32::min(origin[1], pos[1]);
+            };
+            for info in self.level.walls() {
+                fit(vec_f32(info.pos));
+            }
+            for o in &self.pumps {
+                fit(o.position());
+            }
+            for o in &self.boosts {
+                fit(o.position());
+            }
+            for o in &self.mines {
+                fit(o.position());
+            }
+            for o in &self.leaks {
+                fit(o.position());
+            }
+            for o in &self.gems {
+                fit(o.position());
+            }
+            if let Some(o) = self.finish.as_ref() {
+                fit(o.position());
+            }
+            for info in self.level.decorations() {
+                fit(vec_f32(info.pos));
+            }
+            for info in self.level.water() {
+                fit(vec_f32(info.pos));
+            }
+            for info in self.level.spikes() {
+                fit(vec_f32(info.pos));
+            }
+            fit(self.ball.position());
+        }
+        origin
+    }
+
+    /// Resize the map by the given amounts, refusing to shrink it past the
+    /// level's contents.
+    fn resize_map(&mut self, dw: i32, dh: i32) {
+        let map = self.level.map();
+        let w = i64::from(map.width()) + i64::from(dw);
+        let h = i64::from(map.height()) + i64::from(dh);
+        let extent = self.content_extent();
+        if w < extent[0].ceil() as i64 || h < extent[1].ceil() as i64 {
+            eprintln!("Cannot shrink the map to {}x{}: some objects would be left outside", w, h);
+            return;
+        }
+        self.set_map_size(w as u32, h as u32);
+    }
+
+    /// Shrink (or grow) the map to fit the level's contents plus a margin.
+    fn shrink_map_to_fit(&mut self) {
+        let extent = self.content_extent();
+        let w = (extent[0] + MAP_FIT_MARGIN).ceil() as u32;
+        let h = (extent[1] + MAP_FIT_MARGIN).ceil() as u32;
+        self.set_map_size(w, h);
+    }
+
+    /// Map the physical cursor onto the level, after either of them moved.
+    fn update_logical_cursor(&mut self) {
+        self.logical_cursor = cursor_to_level(self.cursor, self.cursor_scale.get(), self.camera.position());
+    }
+
+    /// Center the camera on the given point of the level, as far as the
+    /// map's boundaries allow.
+    fn jump_to(&mut self, pos: Vector2<f32>) {
+        self.camera.focus_on(pos, self.level.map().dimensions_f32());
+        self.camera.round_position();
+        self.update_logical_cursor();
+    }
+
+    /// Pan the camera with the held arrow keys.
+    fn pan_with_keys(&mut self, ticks: f32) {
+        let direction = pan_direction(self.pan_keys);
+        if direction == Vector2::zeros() {
+            return;
+        }
+        self.camera.pan_within(direction * KEY_PAN_SPEED * ticks, self.level.map().dimensions_f32());
+        self.update_logical_cursor();
+    }
+
+    /// Pan the camera while the cursor is at the window's border, if edge
+    /// scrolling is enabled.
+    fn scroll_at_edges(&mut self, ticks: f32) {
+        if !self.edge_scroll || !self.cursor_inside || self.state != EditState::Idle {
+            return;
+        }
+        let scale = self.cursor_scale.get();
+        let screen = Vector2::new(WIDTH as f32 * scale[0], HEIGHT as f32 * scale[1]);
+        let axis = |pos: f32, size: f32| {
+            if pos < EDGE_SCROLL_MARGIN {
+                -1.
+            } else if pos >= size - EDGE_SCROLL_MARGIN {
+                1.
+            } else {
+                0.
+            }
+        };
+        let direction = Vector2::new(axis(self.cursor[0], screen[0]), axis(self.cursor[1], screen[1]));
+        if direction == Vector2::zeros() {
+            return;
+        }
+        self.camera.pan_within(direction * EDGE_SCROLL_SPEED * ticks, self.level.map().dimensions_f32());
+        self.update_logical_cursor();
+    }
+
+    fn set_map_size(&mut self, width: u32, height: u32) {
+        self.mark_modified();
+        self.level.map_mut().resize(width, height);
+        self.camera.clamp_to_bounds(self.level.map().dimensions_f32());
+        self.minimap.rebuild(&self.level);
+    }
+
+    /// Draw the placement grid over the visible part of the map, in the
+    /// high resolution pass.
+    fn draw_grid<G: Graphics>(&self, c: Context, pixel_scale: Vector2<f32>, g: &mut G) {
+        let spacing = self.grid_spacing() as f32;
+        // skip grids too dense to be useful
+        if spacing * pixel_scale[0] < 4. {
+            return;
+        }
+        let color = [1., 1., 1., 0.15];
+        let camera_pos = self.camera.position();
+        let view = self.camera.view();
+        let (w, h) = (view[0] * pixel_scale[0], view[1] * pixel_scale[1]);
+
+        let mut x = (camera_pos[0] / spacing).ceil() * spacing;
+        while x < camera_pos[0] + view[0] {
+            let sx = ((x - camera_pos[0]) * pixel_scale[0]) as f64;
+            line(color, 0.5, [sx, 0., sx, h as f64], c.transform, g);
+            x += spacing;
+        }
+        let mut y = (camera_pos[1] / spacing).ceil() * spacing;
+        while y < camera_pos[1] + view[1] {
+            let sy = ((y - camera_pos[1]) * pixel_scale[1]) as f64;
+            line(color, 0.5, [0., sy, w as f64, sy], c.transform, g);
+            y += spacing;
+        }
+    }
+
+    fn save(&mut self) {
+        self.level.set_version(CURRENT_VERSION);
+        // levels in the current version must be free of issues
+        let issues = self.level.validate();
+        if let Some(issue) = issues.first() {
+            for issue in &issues {
+                eprintln!("Cannot save the level: {}", issue);
+            }
+            self.notice = Some((tr_fmt("editor.cannot_save", &[issue]), NOTICE_DURATION));
+            return;
+        }
+        // a level which may not be solvable can still be saved, if confirmed
+        let warnings = self.level.check_reachability().issues(&self.level);
+        if let Some(warning) = warnings.first() {
+            for warning in &warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            self.confirm_save = Some(warning.to_string());
+            return;
+        }
+        self.write_level();
+    }
+
+    /// Save the level into a new file, without checking it.
+    fn write_level(&mut self) {
+        let mut filepath: PathBuf = Default::default();
+        let mut s = Default::default();
+        for i in 0_u16.. {
+            s = format!("levels/{}.json", i);
+            let path = Path::new(&s).to_path_buf();
+            if !path.exists() {
+                filepath = path;
+                break;
+            }
+        }
+        if self.level.name() == DEFAULT_NAME {
+            self.level.set_name(&*s);
+        }
+        if let Err(e) = self.level.save(&filepath) {
+            eprintln!("Could not save the level: {}", e);
+            self.notice = Some((tr_fmt("editor.save_failed", &[&e]), NOTICE_DURATION));
+            return;
+        }
+        self.set_path(filepath);
+        self.modified = false;
+        println!("Saved level to {}", s);
+    }
+
+    /// Draw a picture of the level next to its file, or in the levels
+    /// directory if it was never saved.
+    fn save_snapshot(&mut self) {
+        let path = self.path
+            .as_ref()
+            .map(|p| p.with_extension("png"))
+            .unwrap_or_else(|| PathBuf::from("levels/snapshot.png"));
+        let message = match self.level.render_to_image(SNAPSHOT_SCALE).save(&path) {
+            Ok(()) => tr_fmt("editor.snapshot_saved", &[&path.display()]),
+            Err(e) => tr_fmt("editor.snapshot_failed", &[&e]),
+        };
+        println!("{}", message);
+        self.notice = Some((message, NOTICE_DURATION));
+    }
+
+    /// Replace the level being edited with the level in the given file.
+    /// Unsaved changes are discarded.
+    fn open_level(&mut self, path: &Path) -> DynResult<()> {
+        let level = GameLevel::load(path)?;
+        // free the current level's assets
+        self.exit();
+        match LevelEditorController::with_level(level, self.res) {
+            Ok(mut editor) => {
+                #[cfg(feature = "dev-console")]
+                {
+                    editor.console = ::std::mem::replace(&mut self.console, Console::default());
+                }
+                *self = editor;
+                self.set_path(path.to_path_buf());
+                Ok(())
+            }
+            Err(e) => {
+                // keep editing the current level
+                LevelEntities::load_assets(&self.level, self.res)?;
+                self.background = Background::new(&self.level, self.res);
+                Err(e.into())
+            }
+        }
+    }
+
+    #[cfg(feature = "dev-console")]
+    /// Carry out a command from the developer console, telling how it went
+    /// on the console. Commands which only make sense in the game are
+    /// turned down.
+    fn run_command(&mut self, command: Command) {
+        let outcome = match command {
+            Command::Spawn(kind, pos) => {
+                let ipos = vec_i32(pos);
+                let info = match kind {
+                    SpawnKind::Mine => ObjectInfo::Mine(MineInfo { pos: ipos }),
+                    SpawnKind::Gem => ObjectInfo::Gem(GemInfo { pos: ipos }),
+                    SpawnKind::Pump => ObjectInfo::Pump(PumpInfo { pos: ipos, charges: None }),
+                    SpawnKind::Leak => ObjectInfo::Leak(LeakInfo {
+                        pos: ipos,
+                        radius: LeakInfo::default_radius(),
+                    }),
+                };
+                self.add_object(info)
+                    .map(|()| format!("Spawned a {:?} at {}, {}", kind, ipos[0], ipos[1]))
+                    .map_err(|e| e.to_string())
+            }
+            Command::Teleport(pos) => {
+                self.jump_to(pos);
+                Ok(format!("View moved to {}, {}", pos[0], pos[1]))
+            }
+            Command::Reload => match self.path.clone() {
+                Some(path) => self.open_level(&path)
+                    .map(|()| format!("Reloaded {}", path.display()))
+                    .map_err(|e| e.to_string()),
+                None => Err("The level has not been saved to a file".to_string()),
+            },
+            Command::Save => {
+                self.save();
+                Ok("Saving the level".to_string())
+            }
+            _ => Err("Not available in the editor".to_string()),
+        };
+        match outcome {
+            Ok(message) => self.console.print(message),
+            Err(e) => self.console.print(format!("Error: {}", e)),
+        }
+    }
+
+    /// Handle events while choosing a level to open.
+    fn open_dialog_event<E: GenericEvent>(&mut self, e: &E) {
+        use piston::input::{Button, ButtonState, Key};
+        if let Some(b) = e.button_args() {
+            match (b.button, b.state) {
+                (Button::Keyboard(Key::Escape), ButtonState::Press) => {
+                    self.open_dialog = None;
+                }
+                (Button::Keyboard(Key::Up), ButtonState::Press) => {
+                    if let Some(dialog) = self.open_dialog.as_mut() {
+                        dialog.select_previous();
+                    }
+                }
+                (Button::Keyboard(Key::Down), ButtonState::Press) => {
+                    if let Some(dialog) = self.open_dialog.as_mut() {
+                        dialog.select_next();
+                    }
+                }
+                (Button::Keyboard(Key::Return), ButtonState::Press) => {
+                    let path = self.open_dialog
+                        .take()
+                        .and_then(|d| d.selected().map(Path::to_path_buf));
+                    if let Some(path) = path {
+                        if let Err(e) = self.open_level(&path) {
+                            eprintln!("Could not open level {}: {}", path.display(), e);
+                            self.notice = Some((tr_fmt("editor.open_failed", &[&e]), NOTICE_DURATION));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Remember the file of the level, so that changes to it on disk can be
+    /// noticed.
+    fn set_path(&mut self, path: PathBuf) {
+        self.file_time = modified_time(&path);
+        self.path = Some(path);
+    }
+
+    /// Reload the sprites which changed on disk, and ask whether to reload
+    /// the level if its file changed.
+    fn check_files(&mut self) {
+        let reloaded = self.res.sprite().reload_changed();
+        if !reloaded.is_empty() {
+            self.background = Background::new(&self.level, self.res);
+            match self.rebuild_entities() {
+                Ok(()) => {
+                    let message = tr_fmt("game.reloaded", &[&reloaded.len()]);
+                    self.notice = Some((message, NOTICE_DURATION));
+                }
+                Err(e) => eprintln!("Warning: could not recreate the level's objects: {}", e),
+            }
+        }
+        let file_time = self.path.as_ref().and_then(modified_time);
+        if file_time.is_some() && file_time != self.file_time {
+            self.file_time = file_time;
+            self.confirm_reload = true;
+        }
+    }
+
+    /// Handle events while asking whether to reload the level from disk.
+    fn confirm_reload_event<E: GenericEvent>(&mut self, e: &E) {
+        use piston::input::{Button, ButtonState, Key};
+        if let Some(b) = e.button_args() {
+            match (b.button, b.state) {
+                (Button::Keyboard(Key::Y), ButtonState::Press) => {
+                    self.confirm_reload = false;
+                    if let Some(path) = self.path.clone() {
+                        if let Err(e) = self.open_level(&path) {
+                            eprintln!("Could not reload {}: {}", path.display(), e);
+                            self.notice = Some((tr_fmt("editor.reload_failed", &[&e]), NOTICE_DURATION));
+                        }
+                    }
+                }
+                (Button::Keyboard(Key::N), ButtonState::Press)
+                | (Button::Keyboard(Key::Escape), ButtonState::Press) => {
+                    self.confirm_reload = false;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Handle events while asking whether to discard unsaved changes.
+    fn confirm_exit_event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
+        use piston::input::{Button, ButtonState, Key};
+        if let Some(b) = e.button_args() {
+            match (b.button, b.state) {
+                (Button::Keyboard(Key::Y), ButtonState::Press) => {
+                    self.confirm_exit = false;
+                    return Some(ControllerAction::LoadTitleScreen);
+                }
+                (Button::Keyboard(Key::N), ButtonState::Press)
+                | (Button::Keyboard(Key::Escape), ButtonState::Press) => {
+                    self.confirm_exit = false;
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn confirm_save_event<E: GenericEvent>(&mut self, e: &E) {
+        use piston::input::{Button, ButtonState, Key};
+        if let Some(b) = e.button_args() {
+            match (b.button, b.state) {
+                (Button::Keyboard(Key::Y), ButtonState::Press) => {
+                    self.confirm_save = None;
+                    self.write_level();
+                }
+                (Button::Keyboard(Key::N), ButtonState::Press)
+                | (Button::Keyboard(Key::Escape), ButtonState::Press) => {
+                    self.confirm_save = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Handle events while typing in level metadata.
+    fn text_entry_event<E: GenericEvent>(&mut self, e: &E) {
+        use piston::input::{Button, ButtonState, Key};
+        if let Some(b) = e.button_args() {
+            match (b.button, b.state) {
+                (Button::Keyboard(Key::Escape), ButtonState::Press) => {
+                    // cancel without changes
+                    self.text_entry = None;
+                }
+                (Button::Keyboard(Key::Return), ButtonState::Press) => {
+                    if let Some(entry) = self.text_entry.take() {
+                        entry.field.apply(&mut self.level, &entry.buffer);
+                        self.mark_modified();
+                    }
+                }
+                (Button::Keyboard(Key::Tab), ButtonState::Press) => {
+                    // commit and move on to the next field
+                    if let Some(entry) = self.text_entry.take() {
+                        entry.field.apply(&mut self.level, &entry.buffer);
+                        self.mark_modified();
+                        self.text_entry = entry
+                            .field
+                            .next()
+                            .map(|f| TextEntry::new(f, &self.level));
+                    }
+                }
+                (Button::Keyboard(Key::Backspace), ButtonState::Press) => {
+                    if let Some(entry) = self.text_entry.as_mut() {
+                        entry.buffer.pop();
+                    }
+                }
+                _ => {}
+            }
         }
-
-        false
-    }
-
-    fn save(&mut self) {
-        let mut filepath: PathBuf = Default::default();
-        let mut s = Default::default();
-        self.level.set_version(VERSION);
-        for i in 0_u16.. {
-            s = format!("levels/{}.json", i);
-            let path = Path::new(&s).to_path_buf();
-            if !path.exists() {
-                filepath = path;
-                break;
+        if let Some(text) = e.text_args() {
+            if let Some(entry) = self.text_entry.as_mut() {
+                entry.buffer.extend(text.chars().filter(|c| !c.is_control()));
             }
         }
-        self.level.set_name(&*s);
-        self.level.save(filepath).unwrap();
-        println!("Saved level to {}", s);
     }
 }
 
@@ -337,9 +1748,42 @@ where
 {
     type Res = R;
     const NEEDS_HI_RES: bool = true;
+    const NAME: &'static str = "editor";
 
     fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
         use piston::input::{Button, ButtonState, Key, MouseButton};
+        #[cfg(feature = "dev-console")]
+        match self.console.event(e) {
+            ConsoleInput::Ignored => {}
+            ConsoleInput::Consumed => return None,
+            ConsoleInput::Opened => {
+                self.focus_lost();
+                return None;
+            }
+            ConsoleInput::Command(command) => {
+                self.run_command(command);
+                return None;
+            }
+        }
+        if self.text_entry.is_some() {
+            self.text_entry_event(e);
+            return None;
+        }
+        if self.open_dialog.is_some() {
+            self.open_dialog_event(e);
+            return None;
+        }
+        if self.confirm_reload {
+            self.confirm_reload_event(e);
+            return None;
+        }
+        if self.confirm_exit {
+            return self.confirm_exit_event(e);
+        }
+        if self.confirm_save.is_some() {
+            self.confirm_save_event(e);
+            return None;
+        }
         self.ball.event(e);
         if let Some(b) = e.button_args() {
             // Set cell value.
@@ -357,9 +1801,74 @@ where
                         _ => {}
                     }
                 }
+                (Button::Mouse(MouseButton::Left), ButtonState::Press, _) => {
+                    if self.space {
+                        self.state = EditState::SpacePanning;
+                    } else if self.alt {
+                        self.pick_at_cursor();
+                        self.state = EditState::Picking;
+                    } else if self.shift {
+                        // start a selection rectangle
+                        self.state = EditState::Selecting(self.logical_cursor);
+                    } else if self.object_at(self.logical_cursor)
+                        .map(|o| self.selection.contains(&o))
+                        .unwrap_or(false)
+                    {
+                        // start dragging the selected objects
+                        let pos = self.snapped_cursor();
+                        self.state = EditState::Dragging(vec_i32(pos));
+                    } else if self.placeholder.is_region() {
+                        // start drawing a water region or a tiled wall
+                        self.state = EditState::Sizing(self.snapped_cursor());
+                    }
+                }
                 (Button::Mouse(MouseButton::Left), ButtonState::Release, _) => {
-                    // place new object
-                    self.place_current_object().unwrap();
+                    match self.state {
+                        EditState::Selecting(start) => {
+                            let end = self.logical_cursor;
+                            self.state = EditState::Idle;
+                            let is_click = norm_squared(&(end - start)) <= CLICK_TOLERANCE * CLICK_TOLERANCE;
+                            if is_click && self.placeholder.is_repeatable() {
+                                // Shift-clicks mark both ends of a line of
+                                // objects, or a grid if Ctrl is held too
+                                match self.pattern_start.take() {
+                                    Some(first) => {
+                                        let grid = self.ctrl;
+                                        if let Err(e) = self.place_pattern(first, grid) {
+                                            eprintln!("Could not place the objects: {}", e);
+                                            let message = tr_fmt("editor.pattern_failed", &[&e]);
+                                            self.notice = Some((message, NOTICE_DURATION));
+                                        }
+                                    }
+                                    None => {
+                                        self.pattern_start = Some(self.snapped_cursor());
+                                        self.notice = Some((tr("editor.pattern_start"), NOTICE_DURATION));
+                                    }
+                                }
+                            } else {
+                                self.select_within(start, end);
+                            }
+                        }
+                        EditState::Dragging(_) | EditState::Picking => {
+                            self.state = EditState::Idle;
+                        }
+                        EditState::SpacePanning => {
+                            // releasing a pan never places an object
+                            self.state = EditState::Idle;
+                            self.camera.round_position();
+                        }
+                        EditState::Sizing(start) => {
+                            self.state = EditState::Idle;
+                            if let Err(e) = self.place_region(start) {
+                                eprintln!("Could not place the region: {}", e);
+                                self.notice = Some((tr_fmt("editor.region_failed", &[&e]), NOTICE_DURATION));
+                            }
+                        }
+                        _ => {
+                            // place new object
+                            self.place_current_object().unwrap();
+                        }
+                    }
                 }
                 (Button::Mouse(MouseButton::Right), ButtonState::Press, _) => {
                     // attempt to remove an object at the cursor's position
@@ -367,14 +1876,254 @@ where
                     self.remove_at(pos);
                 }
                 (Button::Keyboard(Key::Escape), ButtonState::Press, _) => {
-                    return Some(ControllerAction::LoadTitleScreen);
+                    if self.pattern_start.take().is_some() {
+                        return None;
+                    }
+                    if self.selection.is_empty() {
+                        if !self.modified {
+                            return Some(ControllerAction::LoadTitleScreen);
+                        }
+                        self.confirm_exit = true;
+                        self.state = EditState::Idle;
+                        return None;
+                    }
+                    self.selection.clear();
                 }
-                (Button::Keyboard(Key::Comma), ButtonState::Press, _) => {
-                    if let ObjectPlaceholder::Wall {
-                        ref mut dim,
-                        ref mut texture_id,
-                    } = self.placeholder
+                (Button::Keyboard(Key::LShift), state, _) | (Button::Keyboard(Key::RShift), state, _) => {
+                    self.shift = state == ButtonState::Press;
+                }
+                (Button::Keyboard(Key::Space), state, _) => {
+                    self.space = state == ButtonState::Press;
+                }
+                (Button::Keyboard(Key::T), ButtonState::Press, _) if self.shift && self.trajectory.is_none() => {
+                    // the arrow keys aim the preview instead of panning
+                    self.pan_keys = [false; 4];
+                    self.trajectory_keys = Thrust::default();
+                    self.start_trajectory();
+                    self.notice = Some((tr("editor.trajectory"), NOTICE_DURATION));
+                }
+                (Button::Keyboard(Key::T), ButtonState::Release, _) => {
+                    self.trajectory = None;
+                }
+                (Button::Keyboard(Key::LAlt), state, _) | (Button::Keyboard(Key::RAlt), state, _) => {
+                    self.alt = state == ButtonState::Press;
+                }
+                (Button::Keyboard(Key::E), ButtonState::Press, _) if !self.shift => {
+                    self.edge_scroll = !self.edge_scroll;
+                    let message = if self.edge_scroll {
+                        tr("editor.edge_scroll_on")
+                    } else {
+                        tr("editor.edge_scroll_off")
+                    };
+                    self.notice = Some((message, NOTICE_DURATION));
+                }
+                (Button::Keyboard(Key::F2), ButtonState::Press, _) => {
+                    self.show_stats = !self.show_stats;
+                }
+                (Button::Keyboard(Key::F10), ButtonState::Press, _) => {
+                    self.save_snapshot();
+                }
+                (Button::Keyboard(Key::F12), ButtonState::Press, _) => {
+                    println!("{}", self.res.sprite().sprite_report());
+                }
+                (Button::Keyboard(Key::Delete), ButtonState::Press, _) => {
+                    self.remove_selection();
+                }
+                (Button::Keyboard(key), state, _) if self.trajectory.is_some() && is_arrow(key) => {
+                    // aim the preview with the held arrow keys
+                    let pressed = state == ButtonState::Press;
+                    match key {
+                        Key::Left => self.trajectory_keys.left = pressed,
+                        Key::Right => self.trajectory_keys.right = pressed,
+                        Key::Up => self.trajectory_keys.up = pressed,
+                        _ => self.trajectory_keys.down = pressed,
+                    }
+                    if pressed {
+                        self.trajectory_thrust = self.trajectory_keys;
+                        self.start_trajectory();
+                    }
+                }
+                (Button::Keyboard(key), ButtonState::Press, _) if self.ctrl && is_arrow(key) => {
+                    // open or close the map's edge on that side
                     {
+                        let open = self.level.map_mut().open_edges_mut();
+                        let edge = match key {
+                            Key::Left => &mut open.left,
+                            Key::Right => &mut open.right,
+                            Key::Up => &mut open.up,
+                            _ => &mut open.down,
+                        };
+                        *edge = !*edge;
+                    }
+                    self.mark_modified();
+                }
+                (Button::Keyboard(key), ButtonState::Press, _) if self.shift && self.selection.is_empty() && is_arrow(key) => {
+                    // shift the whole level
+                    let step = MAP_RESIZE_STEP;
+                    let offset = match key {
+                        Key::Left => Vector2::new(-step, 0),
+                        Key::Right => Vector2::new(step, 0),
+                        Key::Up => Vector2::new(0, -step),
+                        _ => Vector2::new(0, step),
+                    };
+                    if let Err(e) = self.shift_level(offset) {
+                        eprintln!("Could not shift the level: {}", e);
+                        self.notice = Some((tr_fmt("editor.shift_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(key), ButtonState::Press, _) if !self.selection.is_empty() && is_arrow(key) => {
+                    // nudge the selected objects
+                    let step = self.grid_spacing() as i32;
+                    let delta = match key {
+                        Key::Left => Vector2::new(-step, 0),
+                        Key::Right => Vector2::new(step, 0),
+                        Key::Up => Vector2::new(0, -step),
+                        _ => Vector2::new(0, step),
+                    };
+                    if let Err(e) = self.move_selection(delta) {
+                        eprintln!("Could not move the selection: {}", e);
+                        self.notice = Some((tr_fmt("editor.move_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(key), state, _) if is_arrow(key) => {
+                    // pan the camera for as long as the key is held
+                    let i = match key {
+                        Key::Left => 0,
+                        Key::Right => 1,
+                        Key::Up => 2,
+                        _ => 3,
+                    };
+                    self.pan_keys[i] = state == ButtonState::Press;
+                }
+                (Button::Keyboard(key), ButtonState::Press, _) if number_key(key).is_some() => {
+                    if let Some(placeholder) = number_key(key).and_then(ObjectPlaceholder::from_number) {
+                        self.select_placeholder(placeholder);
+                    }
+                }
+                (Button::Keyboard(Key::Home), ButtonState::Press, _) => {
+                    let pos = self.level.ball_position();
+                    self.jump_to(pos);
+                }
+                (Button::Keyboard(Key::End), ButtonState::Press, _) => {
+                    if let Some(pos) = self.finish.as_ref().map(|f| f.position()) {
+                        self.jump_to(pos);
+                    }
+                }
+                (Button::Keyboard(Key::LCtrl), state, _) | (Button::Keyboard(Key::RCtrl), state, _) => {
+                    self.ctrl = state == ButtonState::Press;
+                }
+                (Button::Keyboard(Key::C), ButtonState::Press, _) if self.ctrl => {
+                    self.copy_at_cursor();
+                }
+                (Button::Keyboard(Key::V), ButtonState::Press, _) if self.ctrl => {
+                    if let Err(e) = self.paste_at_cursor() {
+                        eprintln!("Could not paste the object: {}", e);
+                        self.notice = Some((tr_fmt("editor.paste_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(Key::D), ButtonState::Press, _) if self.ctrl => {
+                    if let Err(e) = self.duplicate_at_cursor() {
+                        eprintln!("Could not duplicate the object: {}", e);
+                        self.notice = Some((tr_fmt("editor.duplicate_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(Key::G), ButtonState::Press, _) if self.ctrl => {
+                    self.cycle_goal();
+                }
+                (Button::Keyboard(Key::I), ButtonState::Press, _) if !self.ctrl => {
+                    self.inspector = match self.inspector {
+                        Some(_) => None,
+                        None => Some(0),
+                    };
+                }
+                (Button::Keyboard(Key::Tab), ButtonState::Press, _) if self.inspector.is_some() && self.inspected().is_some() => {
+                    // move on to the inspector's next field
+                    self.inspector = self.inspector.map(|field| field + 1);
+                }
+                (Button::Keyboard(Key::Minus), ButtonState::Press, _) if self.inspector.is_some() && self.inspected().is_some() => {
+                    if let Err(e) = self.adjust_inspected(-1) {
+                        eprintln!("Could not change the object: {}", e);
+                        self.notice = Some((tr_fmt("editor.inspect_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(Key::Equals), ButtonState::Press, _) if self.inspector.is_some() && self.inspected().is_some() => {
+                    if let Err(e) = self.adjust_inspected(1) {
+                        eprintln!("Could not change the object: {}", e);
+                        self.notice = Some((tr_fmt("editor.inspect_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(Key::Tab), ButtonState::Press, _) => {
+                    // start editing the level's metadata
+                    self.text_entry = Some(TextEntry::new(MetadataField::Name, &self.level));
+                }
+                (Button::Keyboard(Key::LeftBracket), ButtonState::Press, _) => {
+                    self.resize_map(-MAP_RESIZE_STEP, 0);
+                }
+                (Button::Keyboard(Key::RightBracket), ButtonState::Press, _) => {
+                    self.resize_map(MAP_RESIZE_STEP, 0);
+                }
+                (Button::Keyboard(Key::Minus), ButtonState::Press, _) => {
+                    if let ObjectPlaceholder::Boost { .. } = self.placeholder {
+                        self.adjust_boost_strength(-BOOST_STRENGTH_STEP);
+                    } else if let ObjectPlaceholder::Leak { .. } = self.placeholder {
+                        self.adjust_leak_radius(-LEAK_RADIUS_STEP);
+                    } else if let ObjectPlaceholder::Pump { .. } = self.placeholder {
+                        self.adjust_pump_charges(-1);
+                    } else if self.is_finish_targeted() {
+                        self.adjust_gems_required(-1);
+                    } else {
+                        self.resize_map(0, -MAP_RESIZE_STEP);
+                    }
+                }
+                (Button::Keyboard(Key::Equals), ButtonState::Press, _) => {
+                    if let ObjectPlaceholder::Boost { .. } = self.placeholder {
+                        self.adjust_boost_strength(BOOST_STRENGTH_STEP);
+                    } else if let ObjectPlaceholder::Leak { .. } = self.placeholder {
+                        self.adjust_leak_radius(LEAK_RADIUS_STEP);
+                    } else if let ObjectPlaceholder::Pump { .. } = self.placeholder {
+                        self.adjust_pump_charges(1);
+                    } else if self.is_finish_targeted() {
+                        self.adjust_gems_required(1);
+                    } else {
+                        self.resize_map(0, MAP_RESIZE_STEP);
+                    }
+                }
+                (Button::Keyboard(Key::P), ButtonState::Press, _) => {
+                    if let Err(e) = self.cycle_ball_params() {
+                        eprintln!("Could not change the ball's physics: {}", e);
+                        self.notice = Some((tr_fmt("editor.ball_params_failed", &[&e]), NOTICE_DURATION));
+                    }
+                }
+                (Button::Keyboard(Key::R), ButtonState::Press, _) => {
+                    // rotate the spike strip's facing, the boost pad's direction
+                    // or the wall's texture
+                    match self.placeholder {
+                        ObjectPlaceholder::Spikes { ref mut facing } => {
+                            *facing = facing.rotated();
+                        }
+                        ObjectPlaceholder::Boost { ref mut angle, .. } => {
+                            *angle = (*angle + 1) % 8;
+                        }
+                        ObjectPlaceholder::Wall { ref mut rotation, .. } => {
+                            *rotation = (*rotation + 1) % 4;
+                        }
+                        _ => {}
+                    }
+                }
+                (Button::Keyboard(Key::L), ButtonState::Press, _) => {
+                    // switch the decoration's layer
+                    if let ObjectPlaceholder::Decoration { ref mut layer, .. } = self.placeholder {
+                        *layer = match *layer {
+                            DecorationLayer::Back => DecorationLayer::Front,
+                            DecorationLayer::Front => DecorationLayer::Back,
+                        };
+                    }
+                }
+                (Button::Keyboard(Key::Comma), ButtonState::Press, _) => {
+                    if let ObjectPlaceholder::Ball = self.placeholder {
+                        self.adjust_ball_size(-BALL_SIZE_STEP);
+                    }
+                    if let Some((dim, texture_id)) = self.placeholder.texture_mut() {
                         // roll wall texture
                         if *texture_id == 0 {
                             *texture_id = self.res.sprite().max_texture_id().saturating_sub(1);
@@ -394,11 +2143,10 @@ where
                     }
                 }
                 (Button::Keyboard(Key::Period), ButtonState::Press, _) => {
-                    if let ObjectPlaceholder::Wall {
-                        ref mut dim,
-                        ref mut texture_id,
-                    } = self.placeholder
-                    {
+                    if let ObjectPlaceholder::Ball = self.placeholder {
+                        self.adjust_ball_size(BALL_SIZE_STEP);
+                    }
+                    if let Some((dim, texture_id)) = self.placeholder.texture_mut() {
                         *texture_id += 1;
                         // roll wall texture
                         if *texture_id == self.res.sprite().max_texture_id() {
@@ -421,20 +2169,31 @@ where
         }
         if let Some(m) = e.mouse_cursor_args() {
             let newcursor: Vector2<f32> = [m[0] as f32, m[1] as f32].into();
-            let pixel_scale = 2.;
-            if self.state == EditState::Panning {
+            if self.state == EditState::Panning || self.state == EditState::SpacePanning {
                 let mut delta = self.cursor - newcursor;
-                delta /= pixel_scale;
-                self.camera.pan(delta);
-                self.camera.clamp_to_bounds(self.level.map().dimensions_f32());
+                delta.component_div_assign(&self.cursor_scale.get());
+                self.camera.pan_within(delta, self.level.map().dimensions_f32());
             }
 
             self.cursor = newcursor;
-            self.logical_cursor = self.camera.position() + self.cursor / pixel_scale;
+            self.update_logical_cursor();
+
+            if let EditState::Dragging(last) = self.state {
+                let pos = vec_i32(self.snapped_cursor());
+                if pos != last {
+                    match self.move_selection(pos - last) {
+                        Ok(()) => self.state = EditState::Dragging(pos),
+                        Err(e) => {
+                            eprintln!("Could not move the selection: {}", e);
+                            self.notice = Some((tr_fmt("editor.move_failed", &[&e]), NOTICE_DURATION));
+                        }
+                    }
+                }
+            }
         }
 
-        if let Some(_m) = e.cursor_args() {
-            //println!("{:?}", m);
+        if let Some(inside) = e.cursor_args() {
+            self.cursor_inside = inside;
         }
 
         if let Some(m) = e.mouse_scroll_args() {
@@ -447,22 +2206,7 @@ where
                 self.placeholder = self.placeholder.previous();
             }
             if y_scroll != 0. {
-                if let ObjectPlaceholder::Wall {
-                    ref mut dim,
-                    ref mut texture_id,
-                } = self.placeholder
-                {
-                    // update wall dimensions from texture id
-                    let tdims = self.res
-                        .sprite()
-                        .get_sprite_dimensions(SpriteAssetId::Other(*texture_id));
-                    if let Some(tex_dim) = tdims {
-                        *dim = tex_dim;
-                    } else {
-                        *texture_id = 0;
-                        *dim = [48., 48.].into();
-                    }
-                }
+                self.fit_placeholder_to_texture();
             }
         }
 
@@ -471,12 +2215,80 @@ where
                 // save here
                 self.save();
             }
+            if k == "M" || k == "m" {
+                self.minimap.toggle();
+            }
+            if k == "g" && !self.ctrl {
+                self.show_grid = !self.show_grid;
+            }
+            if k == "G" && !self.ctrl {
+                // cycle through grid spacings
+                self.grid = (self.grid + 1) % GRID_SPACINGS.len();
+            }
+            if k == "F" || k == "f" {
+                if let ObjectPlaceholder::Wall { ref mut flip, .. } = self.placeholder {
+                    *flip = !*flip;
+                } else {
+                    self.shrink_map_to_fit();
+                }
+            }
+            if k == "t" {
+                if let ObjectPlaceholder::Wall { ref mut tile, .. } = self.placeholder {
+                    *tile = !*tile;
+                }
+            }
+            if k == "O" || k == "o" {
+                match OpenDialog::new("levels/") {
+                    Ok(dialog) => self.open_dialog = Some(dialog),
+                    Err(e) => eprintln!("Could not list levels: {}", e),
+                }
+            }
+            if k == "K" || k == "k" {
+                self.pick_at_cursor();
+            }
+            if k == "H" || k == "V" {
+                if let Err(e) = self.mirror_level(k == "H") {
+                    eprintln!("Could not mirror the level: {}", e);
+                    self.notice = Some((tr_fmt("editor.mirror_failed", &[&e]), NOTICE_DURATION));
+                }
+            }
         }
 
         None
     }
 
-    fn update(&mut self, _u: UpdateArgs) -> Option<ControllerAction> {
+    fn update(&mut self, u: UpdateArgs) -> Option<ControllerAction> {
+        let ticks = (60. * u.dt) as f32;
+        if let Some((_, t)) = self.notice.as_mut() {
+            *t -= ticks;
+        }
+        if self.notice.as_ref().map(|&(_, t)| t <= 0.).unwrap_or(false) {
+            self.notice = None;
+        }
+        if self.text_entry.is_some()
+            || self.open_dialog.is_some()
+            || self.confirm_reload
+            || self.confirm_exit
+            || self.confirm_save.is_some()
+        {
+            // key releases go to the dialog, so forget which keys were held
+            self.pan_keys = [false; 4];
+            self.space = false;
+        } else {
+            self.pan_with_keys(ticks);
+            self.scroll_at_edges(ticks);
+        }
+        if let Some(preview) = self.trajectory.as_mut() {
+            preview.advance(&self.level, PREVIEW_TICKS_PER_UPDATE);
+        }
+        if self.show_stats && self.stats.is_none() {
+            self.stats = Some(LevelStats::of(&self.level));
+        }
+        self.watch_time += u.dt;
+        if self.watch_time >= WATCH_INTERVAL {
+            self.watch_time = 0.;
+            self.check_files();
+        }
         None
     }
 
@@ -485,49 +2297,340 @@ where
         C: CharacterCache<Texture = GameTexture<R>>,
         G: Graphics<Texture = GameTexture<R>>,
     {
-        clear([0.6, 0.6, 0.6, 1.0], g);
         // use camera focus to define a position
         let camera_pos = self.camera.position();
+        self.background
+            .draw(c, camera_pos, self.level.map().dimensions_f32(), g);
+        for decoration in self.decorations.iter().filter(|d| d.layer() == DecorationLayer::Back) {
+            decoration.draw(c, camera_pos, g);
+        }
+        let c0 = c;
         let c = c.trans((-camera_pos[0]).into(), (-camera_pos[1]).into());
 
-        for wall in &self.walls {
-            wall.draw(c, g);
+        for &i in &self.wall_order {
+            self.walls[i].draw(c, g);
+        }
+        border::draw_borders(self.level.map(), c, g);
+        border::draw_open_edges(self.level.map(), c, g);
+        for leak in &self.leaks {
+            leak.draw(c, self.palette, g);
+        }
+        for spikes in &self.spikes {
+            spikes.draw(c, self.palette, g);
         }
         for mine in &self.mines {
-            mine.draw(c, g);
+            mine.draw(c, self.palette, g);
         }
         for gem in &self.gems {
-            gem.draw(c, g);
+            gem.draw(c, self.palette, g);
+        }
+        self.ball.draw(c, self.palette, g);
+        if let Some(preview) = self.trajectory.as_ref() {
+            preview.draw(c, g);
+        }
+        for water in &self.water {
+            water.draw(c, g);
+        }
+        for boost in &self.boosts {
+            boost.draw(c, g);
         }
-        self.ball.draw(c, g);
         for pump in &self.pumps {
             pump.draw(c, g);
         }
 
         if let Some(finish) = self.finish.as_ref() {
-            finish.draw(c, g);
+            finish.draw(c, 1., g);
+        }
+        for decoration in self.decorations.iter().filter(|d| d.layer() == DecorationLayer::Front) {
+            decoration.draw(c0, camera_pos, g);
         }
     }
 
-    fn render_hires<C, G>(&self, c: Context, _cache: &mut C, g: &mut G)
+    fn render_hires<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
     where
         C: CharacterCache<Texture = GameTexture<R>>,
         G: Graphics<Texture = GameTexture<R>>,
     {
-        let mut point = self.logical_cursor - self.camera.position();
+        if let Some(entry) = self.text_entry.as_ref() {
+            let window_size = c.viewport.unwrap().window_size;
+            rectangle([0., 0., 0., 0.75], [0., 0., window_size[0] as f64, 56.], c.transform, g);
+            let _ = Text::new_color([1.; 4], 16).draw(
+                &format!("{}: {}_", entry.field.label(), entry.buffer),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 24.),
+                g,
+            );
+            let _ = Text::new_color([1.0, 1.0, 0.25, 1.0], 10).draw(
+                &tr("editor.text_entry_hint"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 46.),
+                g,
+            );
+            return;
+        }
+
+        if self.confirm_reload {
+            let window_size = c.viewport.unwrap().window_size;
+            rectangle([0., 0., 0., 0.75], [0., 0., window_size[0] as f64, 56.], c.transform, g);
+            let message = if self.modified {
+                tr("editor.reload_modified")
+            } else {
+                tr("editor.reload")
+            };
+            let _ = Text::new_color([1.; 4], 16).draw(
+                &message,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 24.),
+                g,
+            );
+            return;
+        }
+
+        if self.confirm_exit {
+            let window_size = c.viewport.unwrap().window_size;
+            rectangle([0., 0., 0., 0.75], [0., 0., window_size[0] as f64, 56.], c.transform, g);
+            let _ = Text::new_color([1.; 4], 16).draw(
+                &tr("editor.discard"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 24.),
+                g,
+            );
+            return;
+        }
+
+        if let Some(warning) = self.confirm_save.as_ref() {
+            let window_size = c.viewport.unwrap().window_size;
+            rectangle([0., 0., 0., 0.75], [0., 0., window_size[0] as f64, 56.], c.transform, g);
+            let _ = Text::new_color([1.0, 0.6, 0.5, 1.0], 12).draw(
+                warning,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 20.),
+                g,
+            );
+            let _ = Text::new_color([1.; 4], 16).draw(
+                &tr("editor.save_anyway"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 44.),
+                g,
+            );
+            return;
+        }
+
+        if let Some(dialog) = self.open_dialog.as_ref() {
+            let window_size = c.viewport.unwrap().window_size;
+            rectangle(
+                [0., 0., 0., 0.85],
+                [0., 0., window_size[0] as f64, window_size[1] as f64],
+                c.transform,
+                g,
+            );
+            let _ = Text::new_color([1.; 4], 16).draw(
+                &tr("editor.open"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 24.),
+                g,
+            );
+            if dialog.is_empty() {
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 12).draw(
+                    &tr("editor.no_levels"),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(12., 48.),
+                    g,
+                );
+            }
+            for (i, label) in dialog.labels().enumerate() {
+                let color = if i == dialog.selected_index() {
+                    [1.0, 1.0, 0.25, 1.0]
+                } else {
+                    [0.8, 0.8, 0.8, 1.0]
+                };
+                let _ = Text::new_color(color, 12).draw(
+                    label,
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(12., 48. + 16. * i as f64),
+                    g,
+                );
+            }
+            let hint = if self.modified {
+                tr("editor.open_hint_modified")
+            } else {
+                tr("editor.open_hint")
+            };
+            let _ = Text::new_color([1.0, 1.0, 0.25, 1.0], 10).draw(
+                &hint,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., window_size[1] as f64 - 12.),
+                g,
+            );
+            return;
+        }
+
         let viewport = c.viewport.unwrap();
-        let pixel_scale_w = viewport.window_size[0] as f32 / ::WIDTH as f32;
-        let pixel_scale_h = viewport.window_size[1] as f32 / ::HEIGHT as f32;
-        let pixel_scale = Vector2::from([pixel_scale_w, pixel_scale_h]);
+        let pixel_scale = cursor_scale(&viewport);
+        let (pixel_scale_w, pixel_scale_h) = (pixel_scale[0], pixel_scale[1]);
+        self.cursor_scale.set(pixel_scale);
+        if self.show_grid {
+            self.draw_grid(c, pixel_scale, g);
+        }
+
+        let markers = self.gems
+            .iter()
+            .map(|gem| (gem.position(), self.palette.gem_marker))
+            .chain(self.mines.iter().map(|mine| (mine.position(), self.palette.mine_marker)))
+            .chain(self.pumps.iter().map(|pump| (pump.position(), PUMP_COLOR)))
+            .chain(self.finish.iter().map(|finish| (finish.position(), FINISH_COLOR)));
+        let view = (self.camera.position(), self.camera.view());
+        self.minimap.draw(c, markers, self.ball.position(), view, g);
+
+        // show the level's goal
+        let _ = Text::new_color([1.; 4], 10).draw(
+            &tr_fmt("editor.goal", &[&self.level.goal().description()]),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(8., viewport.window_size[1] as f64 - 8.),
+            g,
+        );
+        if let Some((text, _)) = self.notice.as_ref() {
+            let _ = Text::new_color([1.0, 0.6, 0.5, 1.0], 12).draw(
+                text,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(8., viewport.window_size[1] as f64 - 24.),
+                g,
+            );
+        }
+
+        if let Some(field) = self.inspector {
+            self.draw_inspector(field, c, cache, g);
+        }
+        if let Some(stats) = self.stats.as_ref().filter(|_| self.show_stats) {
+            // above the goal and notices, at the bottom-left corner
+            let lines = stats.lines();
+            let height = 28. + 14. * lines.len() as f64;
+            let top = viewport.window_size[1] as f64 - 40. - height;
+            rectangle([0., 0., 0., 0.75], [4., top, 260., height], c.transform, g);
+            let _ = Text::new_color([1.; 4], 12).draw(
+                &tr("stats.title"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., top + 18.),
+                g,
+            );
+            for (i, line) in lines.iter().enumerate() {
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                    line,
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(12., top + 34. + 14. * i as f64),
+                    g,
+                );
+            }
+        }
+
+        self.draw_palette(c, cache, g);
+
+        // highlight the selection
+        let camera_pos = self.camera.position();
+        let to_screen = |r: [f32; 4]| [
+            ((r[0] - camera_pos[0]) * pixel_scale_w) as f64,
+            ((r[1] - camera_pos[1]) * pixel_scale_h) as f64,
+            (r[2] * pixel_scale_w) as f64,
+            (r[3] * pixel_scale_h) as f64,
+        ];
+        let highlight = Rectangle::new_border([1.0, 0.85, 0.2, 0.9], 1.);
+        for obj in &self.selection {
+            let r = to_screen(self.object_bounds(*obj));
+            highlight.draw(r, &c.draw_state, c.transform, g);
+        }
+        // show the flag's gem requirement
+        if let Some(info) = self.level.finish_flag() {
+            let x = (info.pos[0] as f32 - camera_pos[0] + entities::FINISH_SIZE / 2.) * pixel_scale_w;
+            let y = (info.pos[1] as f32 - camera_pos[1]) * pixel_scale_h;
+            let _ = Text::new_color([1.; 4], 10).draw(
+                &format!("{}/{}", info.gems_required, self.level.gems().len()),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(x as f64 + 4., y as f64),
+                g,
+            );
+        }
+        // show the ball's physics preset
+        if let ObjectPlaceholder::Ball = self.placeholder {
+            let pos = self.level.ball_position();
+            let x = (pos[0] - camera_pos[0] + self.ball.size() / 2.) * pixel_scale_w;
+            let y = (pos[1] - camera_pos[1]) * pixel_scale_h;
+            let _ = Text::new_color([1.; 4], 10).draw(
+                self.ball_params_name(),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(x as f64 + 4., y as f64),
+                g,
+            );
+        }
+        // preview the line or grid of objects being placed
+        if let Some(start) = self.pattern_start {
+            let end = self.snapped_cursor();
+            let color = [1.0, 0.85, 0.2, 0.9];
+            if self.ctrl {
+                let r = [
+                    f32::min(start[0], end[0]),
+                    f32::min(start[1], end[1]),
+                    (start[0] - end[0]).abs(),
+                    (start[1] - end[1]).abs(),
+                ];
+                Rectangle::new_border(color, 0.5).draw(to_screen(r), &c.draw_state, c.transform, g);
+            } else {
+                let a = to_screen([start[0], start[1], 0., 0.]);
+                let b = to_screen([end[0], end[1], 0., 0.]);
+                line(color, 0.5, [a[0], a[1], b[0], b[1]], c.transform, g);
+            }
+        }
+        if let EditState::Selecting(start) = self.state {
+            let end = self.logical_cursor;
+            let r = [
+                f32::min(start[0], end[0]),
+                f32::min(start[1], end[1]),
+                (start[0] - end[0]).abs(),
+                (start[1] - end[1]).abs(),
+            ];
+            Rectangle::new_border([1., 1., 1., 0.8], 0.5).draw(to_screen(r), &c.draw_state, c.transform, g);
+            return;
+        }
+        if let EditState::Sizing(start) = self.state {
+            let end = self.snapped_cursor();
+            let r = [
+                f32::min(start[0], end[0]),
+                f32::min(start[1], end[1]),
+                (start[0] - end[0]).abs(),
+                (start[1] - end[1]).abs(),
+            ];
+            if let ObjectPlaceholder::Wall { dim, rotation, .. } = self.placeholder {
+                let r = to_screen(r);
+                rectangle([0.25, 0.265, 0.3, 0.75], r, c.transform, g);
+                let tile = rotated_dim(dim, rotation);
+                let tile = [(tile[0] * pixel_scale_w) as f64, (tile[1] * pixel_scale_h) as f64];
+                draw_tile_grid(r, tile, c, g);
+            } else {
+                rectangle([0.2, 0.45, 0.9, 0.5], to_screen(r), c.transform, g);
+            }
+            return;
+        }
+
+        let point = self.snapped_cursor() - camera_pos;
         match self.placeholder {
-            ObjectPlaceholder::Wall { dim, .. } => {
+            ObjectPlaceholder::Wall { dim, rotation, flip, tile, .. } => {
                 let color = [0.25, 0.265, 0.3, 0.75];
-                // snap point to 4 pixel grid
-                point /= 4.;
-                point[0] = point[0].round();
-                point[1] = point[1].round();
-                point *= 4.;
-
+                let dim = rotated_dim(dim, rotation);
                 let point = [point[0] * pixel_scale_w, point[1] * pixel_scale_h];
                 let (x, y) = (point[0] as f64, point[1] as f64);
                 let r = [
@@ -537,6 +2640,15 @@ where
                     (dim[1] * pixel_scale_h) as f64,
                 ];
                 rectangle(color, r, c.transform, g);
+                // mark the corner where the texture's top-left corner ends up
+                let (cx, cy) = texture_corner(rotation, flip);
+                let mark = 6.;
+                let mx = x + cx * (r[2] - mark);
+                let my = y + cy * (r[3] - mark);
+                rectangle([0.9, 0.9, 1., 0.9], [mx, my, mark, mark], c.transform, g);
+                if tile {
+                    draw_tile_grid(r, [r[2], r[3]], c, g);
+                }
             }
             ObjectPlaceholder::Mine => {
                 let color = [0.5, 0.3, 0.3, 0.75];
@@ -547,10 +2659,43 @@ where
                 let r = [(x - hsize[0]), (y - hsize[1]), size[0], size[1]];
                 ellipse(color, r, c.transform, g);
             }
-            ObjectPlaceholder::Pump => {
+            ObjectPlaceholder::Leak { radius } => {
+                let color = [1.0, 0.2, 0.15, 0.4];
+                let r = point_to_rect(point, [radius * 2., radius * 2.], pixel_scale);
+                ellipse(color, r, c.transform, g);
+            }
+            ObjectPlaceholder::Pump { charges } => {
                 let color = [1., 1., 0.25, 0.75];
                 let r = point_to_rect(point, [entities::PUMP_SIZE, entities::PUMP_SIZE], pixel_scale);
                 ellipse(color, r, c.transform, g);
+                let label = match charges {
+                    Some(n) => format!("{} charges", n),
+                    None => "unlimited".to_string(),
+                };
+                let _ = Text::new_color([1.; 4], 10).draw(
+                    &label,
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(r[0] + r[2] + 4., r[1] + r[3] / 2.),
+                    g,
+                );
+            }
+            ObjectPlaceholder::Boost { angle, strength } => {
+                let color = [1.0, 0.9, 0.4, 0.75];
+                let r = point_to_rect(point, [entities::BOOST_SIZE, entities::BOOST_SIZE], pixel_scale);
+                ellipse(color, r, c.transform, g);
+                // show the direction, longer for stronger pads
+                let (x, y) = ((point[0] * pixel_scale_w) as f64, (point[1] * pixel_scale_h) as f64);
+                let tip = boost_direction(angle) * (strength * 4.);
+                let tip = [x + (tip[0] * pixel_scale_w) as f64, y + (tip[1] * pixel_scale_h) as f64];
+                line([1., 0.5, 0.1, 1.], 1., [x, y, tip[0], tip[1]], c.transform, g);
+                let _ = Text::new_color([1.; 4], 10).draw(
+                    &format!("{}", strength),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(r[0] + r[2] + 4., y),
+                    g,
+                );
             }
             ObjectPlaceholder::Gem => {
                 let color = [0.8, 0.2, 0.7, 0.75];
@@ -559,7 +2704,8 @@ where
             }
             ObjectPlaceholder::Ball => {
                 let color = [0.5, 0.86, 1.0, 0.75];
-                let r = point_to_rect(point, [BALL_DEFAULT_SIZE, BALL_DEFAULT_SIZE], pixel_scale);
+                let size = self.level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+                let r = point_to_rect(point, [size, size], pixel_scale);
                 ellipse(color, r, c.transform, g);
             }
             ObjectPlaceholder::Finish => {
@@ -567,10 +2713,96 @@ where
                 let r = point_to_rect(point, [8., 24.], pixel_scale);
                 ellipse(color, r, c.transform, g);
             }
+            ObjectPlaceholder::Decoration { dim, layer, .. } => {
+                let color = match layer {
+                    DecorationLayer::Back => [0.3, 0.5, 0.3, 0.5],
+                    DecorationLayer::Front => [0.4, 0.8, 0.4, 0.75],
+                };
+                let (x, y) = ((point[0] * pixel_scale_w) as f64, (point[1] * pixel_scale_h) as f64);
+                let r = [
+                    x,
+                    y,
+                    (dim[0] * pixel_scale_w) as f64,
+                    (dim[1] * pixel_scale_h) as f64,
+                ];
+                rectangle(color, r, c.transform, g);
+            }
+            ObjectPlaceholder::Water => {
+                let color = [0.2, 0.45, 0.9, 0.5];
+                let (x, y) = ((point[0] * pixel_scale_w) as f64, (point[1] * pixel_scale_h) as f64);
+                let size = DEFAULT_WATER_SIZE as f32;
+                let r = [x, y, (size * pixel_scale_w) as f64, (size * pixel_scale_h) as f64];
+                rectangle(color, r, c.transform, g);
+            }
+            ObjectPlaceholder::Spikes { facing } => {
+                let color = [0.75, 0.75, 0.8, 0.75];
+                let dim = spike_strip_dims(facing);
+                let (x, y) = ((point[0] * pixel_scale_w) as f64, (point[1] * pixel_scale_h) as f64);
+                let r = [
+                    x,
+                    y,
+                    (dim[0] as f32 * pixel_scale_w) as f64,
+                    (dim[1] as f32 * pixel_scale_h) as f64,
+                ];
+                rectangle(color, r, c.transform, g);
+                // mark the spiked face
+                let (w, h) = (r[2], r[3]);
+                let edge = match facing {
+                    Direction::Up => [x, y, x + w, y],
+                    Direction::Down => [x, y + h, x + w, y + h],
+                    Direction::Left => [x, y, x, y + h],
+                    Direction::Right => [x + w, y, x + w, y + h],
+                };
+                line([1., 0.3, 0.3, 1.], 1., edge, c.transform, g);
+            }
         }
+
+        #[cfg(feature = "dev-console")]
+        self.console.draw(c, cache, g);
+    }
+
+    fn focus_lost(&mut self) {
+        self.ball.release_all_thrust();
+        self.ctrl = false;
+        self.shift = false;
+        self.alt = false;
+        self.trajectory = None;
+        self.pan_keys = [false; 4];
+        self.space = false;
+        self.cursor_inside = false;
+    }
+
+    fn title(&self) -> Option<String> {
+        let file = match self.path.as_ref().and_then(|path| path.file_name()) {
+            Some(file) => file.to_string_lossy().into_owned(),
+            None => tr("window.untitled"),
+        };
+        let marker = if self.modified { " *" } else { "" };
+        Some(format!("{}{}", tr_fmt("window.editor", &[&file]), marker))
+    }
+
+    fn exit(&mut self) {
+        let mut sprite = self.res.sprite();
+        let _ = sprite.free_sprite(SpriteAssetId::LevelBackground);
+        free_level_textures(&mut sprite);
     }
+}
+
+/// The number of window coordinates per map pixel on each axis, with the
+/// hires pass drawn in the given viewport. The cursor is in window
+/// coordinates too, so the framebuffer's pixels per window coordinate on
+/// HiDPI displays do not change it.
+fn cursor_scale(viewport: &Viewport) -> Vector2<f32> {
+    Vector2::new(
+        viewport.window_size[0] as f32 / WIDTH as f32,
+        viewport.window_size[1] as f32 / HEIGHT as f32,
+    )
+}
 
-    fn exit(&mut self) {}
+/// Map the cursor, in window coordinates, onto the level seen by a camera
+/// with its top-left corner at `camera_pos`, given the `cursor_scale`.
+fn cursor_to_level(cursor: Vector2<f32>, scale: Vector2<f32>, camera_pos: Vector2<f32>) -> Vector2<f32> {
+    camera_pos + cursor.component_div(&scale)
 }
 
 fn point_to_rect(point: Vector2<f32>, item_dims: [f32; 2], pixel_scale: Vector2<f32>) -> [f64; 4] {
@@ -581,3 +2813,149 @@ fn point_to_rect(point: Vector2<f32>, item_dims: [f32; 2], pixel_scale: Vector2<
     let hsize_h = (size_h * 0.5) as f64;
     [(x - hsize_w as f64), (y - hsize_h as f64), size_w as f64, size_h as f64]
 }
+
+/// The sprite which stands for the given kind of placeholder in the
+/// palette, if it has one.
+fn palette_sprite(placeholder: &ObjectPlaceholder) -> Option<SpriteAssetId> {
+    match *placeholder {
+        ObjectPlaceholder::Wall { texture_id, .. } | ObjectPlaceholder::Decoration { texture_id, .. } => {
+            Some(SpriteAssetId::Other(texture_id))
+        }
+        ObjectPlaceholder::Mine => Some(SpriteAssetId::Mine),
+        ObjectPlaceholder::Pump { .. } => Some(SpriteAssetId::Pump),
+        ObjectPlaceholder::Boost { .. } => Some(SpriteAssetId::Boost),
+        ObjectPlaceholder::Gem => Some(SpriteAssetId::Gem),
+        ObjectPlaceholder::Finish => Some(SpriteAssetId::Flag),
+        _ => None,
+    }
+}
+
+/// Draw a plain shape for a kind of placeholder in the palette, over the
+/// given screen rectangle, where it has no sprite.
+fn draw_palette_icon<G: Graphics>(placeholder: &ObjectPlaceholder, r: [f64; 4], c: Context, g: &mut G) {
+    match *placeholder {
+        ObjectPlaceholder::Wall { .. } => rectangle([0.25, 0.265, 0.3, 1.], r, c.transform, g),
+        ObjectPlaceholder::Decoration { .. } => rectangle([0.4, 0.8, 0.4, 1.], r, c.transform, g),
+        ObjectPlaceholder::Water => rectangle([0.2, 0.45, 0.9, 0.8], r, c.transform, g),
+        ObjectPlaceholder::Spikes { .. } => {
+            let r = [r[0], r[1] + r[3] / 2., r[2], r[3] / 2.];
+            rectangle([0.75, 0.75, 0.8, 1.], r, c.transform, g);
+            line([1., 0.3, 0.3, 1.], 1., [r[0], r[1], r[0] + r[2], r[1]], c.transform, g);
+        }
+        ObjectPlaceholder::Mine => ellipse([0.5, 0.3, 0.3, 1.], r, c.transform, g),
+        ObjectPlaceholder::Leak { .. } => ellipse([1.0, 0.2, 0.15, 0.6], r, c.transform, g),
+        ObjectPlaceholder::Pump { .. } => ellipse([1., 1., 0.25, 1.], r, c.transform, g),
+        ObjectPlaceholder::Boost { .. } => ellipse([1.0, 0.9, 0.4, 1.], r, c.transform, g),
+        ObjectPlaceholder::Gem => ellipse([0.8, 0.2, 0.7, 1.], r, c.transform, g),
+        ObjectPlaceholder::Ball => ellipse([0.5, 0.86, 1.0, 1.], r, c.transform, g),
+        ObjectPlaceholder::Finish => {
+            let r = [r[0] + r[2] / 3., r[1], r[2] / 3., r[3]];
+            ellipse([1.; 4], r, c.transform, g);
+        }
+    }
+}
+
+/// Outline the tiles of a tiled wall over the given screen rectangle, with
+/// tiles of the given screen size. Tiles grow as they do in game when there
+/// would be too many of them.
+fn draw_tile_grid<G: Graphics>(r: [f64; 4], tile: [f64; 2], c: Context, g: &mut G) {
+    let color = [0.9, 0.9, 1., 0.4];
+    Rectangle::new_border(color, 0.5).draw(r, &c.draw_state, c.transform, g);
+    if tile[0] <= 0. || tile[1] <= 0. {
+        return;
+    }
+    let scale = wall::tile_scale((tile[0], tile[1]), (r[2], r[3]));
+    let (tile_w, tile_h) = (tile[0] * scale, tile[1] * scale);
+    let mut x = r[0] + tile_w;
+    while x < r[0] + r[2] {
+        line(color, 0.5, [x, r[1], x, r[1] + r[3]], c.transform, g);
+        x += tile_w;
+    }
+    let mut y = r[1] + tile_h;
+    while y < r[1] + r[3] {
+        line(color, 0.5, [r[0], y, r[0] + r[2], y], c.transform, g);
+        y += tile_h;
+    }
+}
+
+/// The direction of a boost pad, from its angle in steps of 45 degrees
+/// clockwise from the right.
+fn boost_direction(angle: u32) -> Vector2<f32> {
+    let a = angle as f32 * ::std::f32::consts::FRAC_PI_4;
+    Vector2::new(a.cos(), a.sin())
+}
+
+/// The dimensions of a newly placed spike strip with the given facing.
+fn spike_strip_dims(facing: Direction) -> Vector2<i32> {
+    let (length, depth) = SPIKE_STRIP_SIZE;
+    if facing.is_horizontal() {
+        Vector2::new(depth, length)
+    } else {
+        Vector2::new(length, depth)
+    }
+}
+
+/// Spread values evenly from `from` to `to`, both included, at least
+/// `spacing` apart. If the ends are closer than that, only `from` is kept.
+fn spread(from: f32, to: f32, spacing: f32) -> Vec<f32> {
+    let steps = ((to - from).abs() / spacing).floor() as usize;
+    if steps == 0 {
+        return vec![from];
+    }
+    (0..=steps)
+        .map(|i| from + (to - from) * i as f32 / steps as f32)
+        .collect()
+}
+
+/// The number of a key which selects a kind of placeholder, on the main
+/// keyboard or the keypad, if it is one.
+fn number_key(key: ::piston::input::Key) -> Option<u32> {
+    use piston::input::Key;
+    match key {
+        Key::D1 | Key::NumPad1 => Some(1),
+        Key::D2 | Key::NumPad2 => Some(2),
+        Key::D3 | Key::NumPad3 => Some(3),
+        Key::D4 | Key::NumPad4 => Some(4),
+        Key::D5 | Key::NumPad5 => Some(5),
+        Key::D6 | Key::NumPad6 => Some(6),
+        _ => None,
+    }
+}
+
+fn is_arrow(key: ::piston::input::Key) -> bool {
+    use piston::input::Key;
+    match key {
+        Key::Left | Key::Right | Key::Up | Key::Down => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The viewport of the default 3x window, on a display with the given
+    /// framebuffer pixels per window coordinate.
+    fn viewport(dpi: f64) -> Viewport {
+        let (w, h) = (u32::from(WIDTH) * 3, u32::from(HEIGHT) * 3);
+        let draw_size = [(f64::from(w) * dpi) as u32, (f64::from(h) * dpi) as u32];
+        Viewport {
+            rect: [0, 0, draw_size[0] as i32, draw_size[1] as i32],
+            draw_size,
+            window_size: [w, h],
+        }
+    }
+
+    #[test]
+    fn cursor_maps_onto_the_level_at_any_dpi() {
+        let camera_pos = Vector2::new(100., 40.);
+        for &dpi in &[1.0, 2.0] {
+            let scale = cursor_scale(&viewport(dpi));
+            assert_eq!(scale, Vector2::new(3., 3.), "at DPI {}", dpi);
+            let to_level = |x: f32, y: f32| cursor_to_level(Vector2::new(x, y), scale, camera_pos);
+            assert_eq!(to_level(0., 0.), camera_pos);
+            assert_eq!(to_level(480., 300.), Vector2::new(260., 140.));
+            assert_eq!(to_level(960., 600.), Vector2::new(420., 240.));
+        }
+    }
+}
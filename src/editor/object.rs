@@ -0,0 +1,115 @@
+use na::Vector2;
+use level::GameLevel;
+use level::info::*;
+
+/// A reference to an object placed in the level, by its kind and its index
+/// in the level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ObjectRef {
+    Wall(usize),
+    Spikes(usize),
+    Mine(usize),
+    Leak(usize),
+    Pump(usize),
+    Boost(usize),
+    Gem(usize),
+    Finish,
+    Decoration(usize),
+    Water(usize),
+}
+
+/// A copy of an object's information, which can be added to the level.
+#[derive(Debug, Clone)]
+pub enum ObjectInfo {
+    Wall(WallInfo),
+    Spikes(SpikeInfo),
+    Mine(MineInfo),
+    Leak(LeakInfo),
+    Pump(PumpInfo),
+    Boost(BoostInfo),
+    Gem(GemInfo),
+    Finish(FinishInfo),
+    Decoration(DecorationInfo),
+    Water(WaterInfo),
+}
+
+impl ObjectRef {
+    /// Obtain references to all objects in the level whose position lies
+    /// within the given rectangle (inclusive).
+    pub fn all_within(level: &GameLevel, min: Vector2<i32>, max: Vector2<i32>) -> Vec<ObjectRef> {
+        let inside = |pos: Vector2<i32>| {
+            pos[0] >= min[0] && pos[1] >= min[1] && pos[0] <= max[0] && pos[1] <= max[1]
+        };
+        let mut objects = Vec::new();
+        objects.extend(level.walls().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Wall(i)));
+        objects.extend(level.spikes().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Spikes(i)));
+        objects.extend(level.mines().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Mine(i)));
+        objects.extend(level.leaks().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Leak(i)));
+        objects.extend(level.pumps().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Pump(i)));
+        objects.extend(level.boosts().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Boost(i)));
+        objects.extend(level.gems().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Gem(i)));
+        if level.finish_flag().map(|o| inside(o.pos)).unwrap_or(false) {
+            objects.push(ObjectRef::Finish);
+        }
+        objects.extend(level.decorations().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Decoration(i)));
+        objects.extend(level.water().iter().enumerate()
+            .filter(|&(_, o)| inside(o.pos)).map(|(i, _)| ObjectRef::Water(i)));
+        objects
+    }
+}
+
+impl ObjectInfo {
+    /// Copy the information of the given object in the level.
+    pub fn from_level(level: &GameLevel, obj: ObjectRef) -> Option<ObjectInfo> {
+        match obj {
+            ObjectRef::Wall(i) => level.walls().get(i).cloned().map(ObjectInfo::Wall),
+            ObjectRef::Spikes(i) => level.spikes().get(i).cloned().map(ObjectInfo::Spikes),
+            ObjectRef::Mine(i) => level.mines().get(i).cloned().map(ObjectInfo::Mine),
+            ObjectRef::Leak(i) => level.leaks().get(i).cloned().map(ObjectInfo::Leak),
+            ObjectRef::Pump(i) => level.pumps().get(i).cloned().map(ObjectInfo::Pump),
+            ObjectRef::Boost(i) => level.boosts().get(i).cloned().map(ObjectInfo::Boost),
+            ObjectRef::Gem(i) => level.gems().get(i).cloned().map(ObjectInfo::Gem),
+            ObjectRef::Finish => level.finish_flag().cloned().map(ObjectInfo::Finish),
+            ObjectRef::Decoration(i) => level.decorations().get(i).cloned().map(ObjectInfo::Decoration),
+            ObjectRef::Water(i) => level.water().get(i).cloned().map(ObjectInfo::Water),
+        }
+    }
+
+    pub fn position(&self) -> Vector2<i32> {
+        match *self {
+            ObjectInfo::Wall(ref info) => info.pos,
+            ObjectInfo::Spikes(ref info) => info.pos,
+            ObjectInfo::Mine(ref info) => info.pos,
+            ObjectInfo::Leak(ref info) => info.pos,
+            ObjectInfo::Pump(ref info) => info.pos,
+            ObjectInfo::Boost(ref info) => info.pos,
+            ObjectInfo::Gem(ref info) => info.pos,
+            ObjectInfo::Finish(ref info) => info.pos,
+            ObjectInfo::Decoration(ref info) => info.pos,
+            ObjectInfo::Water(ref info) => info.pos,
+        }
+    }
+
+    pub fn set_position(&mut self, pos: Vector2<i32>) {
+        match *self {
+            ObjectInfo::Wall(ref mut info) => info.pos = pos,
+            ObjectInfo::Spikes(ref mut info) => info.pos = pos,
+            ObjectInfo::Mine(ref mut info) => info.pos = pos,
+            ObjectInfo::Leak(ref mut info) => info.pos = pos,
+            ObjectInfo::Pump(ref mut info) => info.pos = pos,
+            ObjectInfo::Boost(ref mut info) => info.pos = pos,
+            ObjectInfo::Gem(ref mut info) => info.pos = pos,
+            ObjectInfo::Finish(ref mut info) => info.pos = pos,
+            ObjectInfo::Decoration(ref mut info) => info.pos = pos,
+            ObjectInfo::Water(ref mut info) => info.pos = pos,
+        }
+    }
+}
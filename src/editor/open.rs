@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+use level::{try_load_all_level_headers, LevelResult};
+
+/// A list of level files to choose from, for opening in the editor.
+#[derive(Debug, Clone)]
+pub struct OpenDialog {
+    /// the path and the display label of each level
+    entries: Vec<(PathBuf, String)>,
+    selected: usize,
+}
+
+impl OpenDialog {
+    /// List the levels in the given directory. Levels which cannot be read
+    /// are still listed, so that the problem can be seen when opening them.
+    pub fn new<P: AsRef<Path>>(dir: P) -> LevelResult<Self> {
+        let entries = try_load_all_level_headers(dir)?
+            .into_iter()
+            .map(|(path, header)| {
+                let label = match header {
+                    Ok(header) => format!("{} ({})", header.name(), path.display()),
+                    Err(e) => format!("{} ({})", path.display(), e.reason()),
+                };
+                (path, label)
+            })
+            .collect();
+        Ok(OpenDialog {
+            entries,
+            selected: 0,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|&(_, ref label)| label.as_str())
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    /// The path of the selected level, if any.
+    pub fn selected(&self) -> Option<&Path> {
+        self.entries.get(self.selected).map(|&(ref path, _)| path.as_path())
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+}
@@ -1,41 +1,222 @@
+use std::mem;
 use na::Vector2;
+use level::info::{BoostInfo, DecorationLayer, Direction, LeakInfo};
+use util::vec_f32;
+use super::object::ObjectInfo;
+
+/// Obtain the dimensions of a wall after rotating its texture by the given
+/// number of quarter turns.
+pub fn rotated_dim(dim: Vector2<f32>, rotation: u8) -> Vector2<f32> {
+    if rotation % 2 == 1 {
+        Vector2::new(dim[1], dim[0])
+    } else {
+        dim
+    }
+}
+
+/// Find where the top-left corner of a wall texture ends up after flipping
+/// and rotating it, as a fraction of the wall's dimensions.
+pub fn texture_corner(rotation: u8, flip: bool) -> (f64, f64) {
+    let corner = if flip { (1., 0.) } else { (0., 0.) };
+    // each quarter turn clockwise moves the corner to the next one
+    (0..rotation % 4).fold(corner, |(x, y), _| (1. - y, x))
+}
 
 pub enum ObjectPlaceholder
 {
     Wall {
         dim: Vector2<f32>,
         texture_id: u32, 
+        /// quarter turns clockwise, from 0 to 3
+        rotation: u8,
+        flip: bool,
+        /// whether the wall is tiled, and drawn by dragging
+        tile: bool,
     },
     Mine,
-    Pump,
+    Leak {
+        radius: f32,
+    },
+    Pump {
+        /// the number of heals, unlimited if `None`
+        charges: Option<u32>,
+    },
+    Boost {
+        /// the direction, in steps of 45 degrees clockwise from the right
+        angle: u32,
+        strength: f32,
+    },
     Gem,
     Ball,
     Finish,
+    Decoration {
+        dim: Vector2<f32>,
+        texture_id: u32,
+        layer: DecorationLayer,
+    },
+    Water,
+    Spikes {
+        facing: Direction,
+    },
 }
 
 impl ObjectPlaceholder {
 
+    /// Create a placeholder which places objects like the given one. The
+    /// dimensions of decorations and tiled walls are those of a single
+    /// tile, which the object does not know, so they are left at their
+    /// defaults.
+    pub fn from_info(info: &ObjectInfo) -> ObjectPlaceholder {
+        match *info {
+            ObjectInfo::Wall(ref info) => {
+                let dim = if info.tile {
+                    [48., 48.].into()
+                } else {
+                    // the placeholder's dimensions are before rotating
+                    rotated_dim(vec_f32(info.dim), info.rotation)
+                };
+                ObjectPlaceholder::Wall {
+                    dim,
+                    texture_id: info.texture_id,
+                    rotation: info.rotation,
+                    flip: info.flip,
+                    tile: info.tile,
+                }
+            }
+            ObjectInfo::Spikes(ref info) => ObjectPlaceholder::Spikes { facing: info.facing },
+            ObjectInfo::Mine(_) => ObjectPlaceholder::Mine,
+            ObjectInfo::Leak(ref info) => ObjectPlaceholder::Leak { radius: info.radius },
+            ObjectInfo::Pump(ref info) => ObjectPlaceholder::Pump { charges: info.charges },
+            ObjectInfo::Boost(ref info) => ObjectPlaceholder::Boost {
+                angle: boost_angle(info.direction),
+                strength: info.strength,
+            },
+            ObjectInfo::Gem(_) => ObjectPlaceholder::Gem,
+            ObjectInfo::Finish(_) => ObjectPlaceholder::Finish,
+            ObjectInfo::Decoration(ref info) => ObjectPlaceholder::Decoration {
+                dim: [48., 48.].into(),
+                texture_id: info.texture_id,
+                layer: info.layer,
+            },
+            ObjectInfo::Water(_) => ObjectPlaceholder::Water,
+        }
+    }
+
+    /// Obtain one placeholder of each kind, with its default properties, in
+    /// the order in which `next` goes through them.
+    pub fn all_defaults() -> Vec<ObjectPlaceholder> {
+        vec![
+            ObjectPlaceholder::default_wall(),
+            ObjectPlaceholder::default_mine(),
+            ObjectPlaceholder::default_leak(),
+            ObjectPlaceholder::default_pump(),
+            ObjectPlaceholder::default_boost(),
+            ObjectPlaceholder::default_gem(),
+            ObjectPlaceholder::default_ball(),
+            ObjectPlaceholder::default_finish(),
+            ObjectPlaceholder::default_decoration(),
+            ObjectPlaceholder::default_water(),
+            ObjectPlaceholder::default_spikes(),
+        ]
+    }
+
+    /// Obtain a placeholder of the kind selected with the given number key,
+    /// from 1 to 6.
+    pub fn from_number(n: u32) -> Option<ObjectPlaceholder> {
+        match n {
+            1 => Some(ObjectPlaceholder::default_wall()),
+            2 => Some(ObjectPlaceholder::default_mine()),
+            3 => Some(ObjectPlaceholder::default_pump()),
+            4 => Some(ObjectPlaceholder::default_gem()),
+            5 => Some(ObjectPlaceholder::default_ball()),
+            6 => Some(ObjectPlaceholder::default_finish()),
+            _ => None,
+        }
+    }
+
+    /// the number key which selects this kind of placeholder, if any
+    pub fn number(&self) -> Option<u32> {
+        (1..7).find(|&n| ObjectPlaceholder::from_number(n).map_or(false, |p| p.is_same_kind(self)))
+    }
+
+    /// Check whether both placeholders place the same kind of object,
+    /// regardless of their properties.
+    pub fn is_same_kind(&self, other: &ObjectPlaceholder) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
     pub fn next(&self) -> ObjectPlaceholder {
         use self::ObjectPlaceholder::*;
         match *self {
             Wall {..} => ObjectPlaceholder::default_mine(),
-            Mine => ObjectPlaceholder::default_pump(),
-            Pump => ObjectPlaceholder::default_gem(),
+            Mine => ObjectPlaceholder::default_leak(),
+            Leak {..} => ObjectPlaceholder::default_pump(),
+            Pump {..} => ObjectPlaceholder::default_boost(),
+            Boost {..} => ObjectPlaceholder::default_gem(),
             Gem => ObjectPlaceholder::default_ball(),
             Ball => ObjectPlaceholder::default_finish(),
-            Finish => ObjectPlaceholder::default_wall(),
+            Finish => ObjectPlaceholder::default_decoration(),
+            Decoration {..} => ObjectPlaceholder::default_water(),
+            Water => ObjectPlaceholder::default_spikes(),
+            Spikes {..} => ObjectPlaceholder::default_wall(),
         }
     }
 
     pub fn previous(&self) -> ObjectPlaceholder {
         use self::ObjectPlaceholder::*;
         match *self {
-            Wall {..} => ObjectPlaceholder::default_finish(),
+            Wall {..} => ObjectPlaceholder::default_spikes(),
+            Spikes {..} => ObjectPlaceholder::default_water(),
             Mine => ObjectPlaceholder::default_wall(),
-            Pump => ObjectPlaceholder::default_mine(),
-            Gem => ObjectPlaceholder::default_pump(),
+            Leak {..} => ObjectPlaceholder::default_mine(),
+            Pump {..} => ObjectPlaceholder::default_leak(),
+            Boost {..} => ObjectPlaceholder::default_pump(),
+            Gem => ObjectPlaceholder::default_boost(),
             Ball => ObjectPlaceholder::default_gem(),
             Finish => ObjectPlaceholder::default_ball(),
+            Decoration {..} => ObjectPlaceholder::default_finish(),
+            Water => ObjectPlaceholder::default_decoration(),
+        }
+    }
+
+    /// Check whether many copies of this placeholder can be placed at once,
+    /// along a line or in a grid.
+    pub fn is_repeatable(&self) -> bool {
+        match *self {
+            ObjectPlaceholder::Mine |
+            ObjectPlaceholder::Gem |
+            ObjectPlaceholder::Pump { .. } |
+            ObjectPlaceholder::Boost { .. } |
+            ObjectPlaceholder::Leak { .. } |
+            ObjectPlaceholder::Decoration { .. } |
+            ObjectPlaceholder::Spikes { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Check whether this placeholder is placed by dragging out a region.
+    pub fn is_region(&self) -> bool {
+        match *self {
+            ObjectPlaceholder::Water | ObjectPlaceholder::Wall { tile: true, .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Obtain mutable access to the dimensions and texture ID of this
+    /// placeholder, if it is a textured object.
+    pub fn texture_mut(&mut self) -> Option<(&mut Vector2<f32>, &mut u32)> {
+        match *self {
+            ObjectPlaceholder::Wall {
+                ref mut dim,
+                ref mut texture_id,
+                ..
+            }
+            | ObjectPlaceholder::Decoration {
+                ref mut dim,
+                ref mut texture_id,
+                ..
+            } => Some((dim, texture_id)),
+            _ => None,
         }
     }
 
@@ -46,13 +227,27 @@ impl ObjectPlaceholder {
         ObjectPlaceholder::Wall {
             dim: [48., 48.].into(),
             texture_id: 0,
+            rotation: 0,
+            flip: false,
+            tile: false,
         }
     }
     pub fn default_mine() -> ObjectPlaceholder {
         ObjectPlaceholder::Mine
     }
+    pub fn default_leak() -> ObjectPlaceholder {
+        ObjectPlaceholder::Leak {
+            radius: LeakInfo::default_radius(),
+        }
+    }
     pub fn default_pump() -> ObjectPlaceholder {
-        ObjectPlaceholder::Pump
+        ObjectPlaceholder::Pump { charges: None }
+    }
+    pub fn default_boost() -> ObjectPlaceholder {
+        ObjectPlaceholder::Boost {
+            angle: 0,
+            strength: BoostInfo::default_strength(),
+        }
     }
     pub fn default_gem() -> ObjectPlaceholder {
         ObjectPlaceholder::Gem
@@ -60,5 +255,26 @@ impl ObjectPlaceholder {
     pub fn default_finish() -> ObjectPlaceholder {
         ObjectPlaceholder::Finish
     }
+    pub fn default_decoration() -> ObjectPlaceholder {
+        ObjectPlaceholder::Decoration {
+            dim: [48., 48.].into(),
+            texture_id: 0,
+            layer: DecorationLayer::Back,
+        }
+    }
+    pub fn default_water() -> ObjectPlaceholder {
+        ObjectPlaceholder::Water
+    }
+    pub fn default_spikes() -> ObjectPlaceholder {
+        ObjectPlaceholder::Spikes {
+            facing: Direction::Up,
+        }
+    }
 }
 
+/// The direction of a boost pad, in steps of 45 degrees clockwise from the
+/// right, rounded to the nearest step.
+fn boost_angle(direction: Vector2<f32>) -> u32 {
+    let steps = (direction[1].atan2(direction[0]) / ::std::f32::consts::FRAC_PI_4).round() as i32;
+    ((steps % 8 + 8) % 8) as u32
+}
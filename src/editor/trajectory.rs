@@ -0,0 +1,139 @@
+//! Module for previewing the path of the ball in the editor, by simulating
+//! it from its starting position with a steady thrust.
+
+use graphics::{rectangle, Context, Graphics};
+use na::Vector2;
+use game::ball::{Ball, BallController, Thrust, BALL_DEFAULT_SIZE};
+use level::GameLevel;
+use physics::{centered_box, rect_circle_collision, AnimatedObject, Bounded, Collidable, CollisionInfo};
+use resource::{ResourceManage, Result};
+use util::{rect_intersects, vec_f32};
+
+/// The number of ticks simulated for a preview.
+pub const PREVIEW_TICKS: usize = 300;
+/// The most ticks simulated in a single update, so that a preview does not
+/// hold up the editor.
+pub const PREVIEW_TICKS_PER_UPDATE: usize = 60;
+/// The number of ticks between the dots of the drawn path.
+const DOT_SPACING: usize = 4;
+const DOT_SIZE: f64 = 1.5;
+const DOT_COLOR: [f32; 4] = [0.5, 0.86, 1.0, 0.9];
+
+/// The shape of a wall, which the ball bounces off like it does off the
+/// walls of the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WallShape {
+    pos: Vector2<f32>,
+    dim: Vector2<f32>,
+}
+
+impl Bounded for WallShape {
+    fn bounding_box(&self) -> [f32; 4] {
+        [self.pos[0], self.pos[1], self.dim[0], self.dim[1]]
+    }
+}
+
+impl Collidable for WallShape {
+    fn test_circle_collision(&self, position: Vector2<f32>, radius: f32) -> CollisionInfo {
+        rect_circle_collision(self.pos, self.dim, position, radius)
+    }
+
+    fn on_collision<A>(&mut self, ball: &mut A, overlap: Vector2<f32>)
+    where
+        A: AnimatedObject,
+    {
+        ball.issue_bounce(overlap, [0., 0.].into())
+    }
+}
+
+/// A simulation of the ball on its own copy of the level's walls, which
+/// runs a few ticks at a time.
+#[derive(Debug)]
+pub struct TrajectoryPreview<R> {
+    ball: BallController<R>,
+    walls: Vec<WallShape>,
+    /// the positions of the ball so far, one per tick
+    path: Vec<Vector2<f32>>,
+    finished: bool,
+}
+
+impl<R> TrajectoryPreview<R>
+where
+    R: ResourceManage,
+{
+    /// Prepare a simulation of the level's ball, thrust steadily in the
+    /// given direction from its starting position.
+    pub fn new(level: &GameLevel, thrust: Thrust, resource_manager: R) -> Result<Self> {
+        let size = level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+        let ball = Ball::new(level.ball_position(), size);
+        let mut ball = BallController::new(ball, level.ball_params(), resource_manager)?;
+        ball.set_thrust(thrust);
+        let walls = level
+            .walls()
+            .iter()
+            .map(|info| WallShape {
+                pos: vec_f32(info.pos),
+                dim: vec_f32(info.dim),
+            })
+            .collect();
+        Ok(TrajectoryPreview {
+            path: vec![ball.position()],
+            ball,
+            walls,
+            finished: false,
+        })
+    }
+
+    /// Simulate up to the given number of ticks, stopping for good once the
+    /// preview is long enough or the ball pops or leaves the map.
+    pub fn advance(&mut self, level: &GameLevel, ticks: usize) {
+        let map = level.map();
+        let open = map.open_edges();
+        for _ in 0..ticks {
+            if self.finished {
+                return;
+            }
+            let ball = &mut self.ball;
+            if !open.left {
+                ball.handle_collision_with(map.left_border());
+            }
+            if !open.right {
+                ball.handle_collision_with(map.right_border());
+            }
+            if !open.up {
+                ball.handle_collision_with(map.up_border());
+            }
+            if !open.down {
+                ball.handle_collision_with(map.down_border());
+            }
+            let reach = centered_box(ball.position(), ball.size() + 2.);
+            for wall in self.walls.iter_mut().filter(|w| rect_intersects(w.bounding_box(), reach)) {
+                ball.handle_collision_with(wall);
+            }
+            ball.update(1.);
+            self.path.push(ball.position());
+            self.finished = self.path.len() > PREVIEW_TICKS
+                || ball.is_dead()
+                || map.is_outside(ball.position(), ball.size() / 2.);
+        }
+    }
+
+    /// Draw the path simulated so far as a dotted line, in map coordinates.
+    pub fn draw<G: Graphics>(&self, c: Context, g: &mut G) {
+        for pos in self.path.iter().step_by(DOT_SPACING) {
+            let r = [
+                f64::from(pos[0]) - DOT_SIZE / 2.,
+                f64::from(pos[1]) - DOT_SIZE / 2.,
+                DOT_SIZE,
+                DOT_SIZE,
+            ];
+            rectangle(DOT_COLOR, r, c.transform, g);
+        }
+        // mark where the simulation ended
+        if let Some(pos) = self.path.last().filter(|_| self.finished) {
+            let size = DOT_SIZE * 3.;
+            let r = [f64::from(pos[0]) - size / 2., f64::from(pos[1]) - size / 2., size, size];
+            rectangle(DOT_COLOR, r, c.transform, g);
+        }
+    }
+}
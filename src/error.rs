@@ -0,0 +1,100 @@
+//! Module for the error screen, which is shown when another controller
+//! fails to load.
+
+use std::marker::PhantomData;
+use graphics::character::CharacterCache;
+use graphics::{clear, Context, DrawState, Graphics, Text, Transformed};
+use piston::input::{GenericEvent, UpdateArgs};
+use controller::{Controller, ControllerAction};
+use lang::tr;
+use resource::{GameTexture, ResourceManage};
+use util::wrap_text;
+
+/// Maximum number of characters per line of the error message.
+const LINE_WIDTH: usize = 56;
+
+pub struct ErrorController<R>
+where
+    R: ResourceManage,
+{
+    lines: Vec<String>,
+    next: ControllerAction,
+    phantom: PhantomData<R>,
+}
+
+impl<R> ErrorController<R>
+where
+    R: ResourceManage,
+{
+    /// Create an error screen with the given message. Once the player
+    /// presses a button, the controller yields the `next` action.
+    pub fn new<M: AsRef<str>>(message: M, next: ControllerAction, _res: R) -> Self {
+        ErrorController {
+            lines: wrap_text(message.as_ref(), LINE_WIDTH),
+            next,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<R> Controller for ErrorController<R>
+where
+    R: ResourceManage,
+{
+    type Res = R;
+    const NEEDS_HI_RES: bool = true;
+    const NAME: &'static str = "error";
+
+    fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
+        use piston::input::ButtonState;
+        if let Some(b) = e.button_args() {
+            if b.state == ButtonState::Press {
+                return Some(self.next.clone());
+            }
+        }
+        None
+    }
+
+    fn update(&mut self, _u: UpdateArgs) -> Option<ControllerAction> {
+        None
+    }
+
+    fn render<C, G>(&self, _c: Context, _cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        clear([0.2, 0.05, 0.05, 1.0], g);
+    }
+
+    fn render_hires<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let c = c.trans(24., 60.);
+        let _ = Text::new_color([1.0, 0.4, 0.4, 1.0], 24).draw(
+            &tr("error.title"),
+            cache,
+            &DrawState::default(),
+            c.transform,
+            g,
+        );
+        for (i, line) in self.lines.iter().enumerate() {
+            let _ = Text::new_color([1.; 4], 16).draw(
+                line,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(0., 50. + 24. * i as f64),
+                g,
+            );
+        }
+        let _ = Text::new_color([1.0, 1.0, 0.25, 1.0], 12).draw(
+            &tr("error.continue"),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(0., 480.),
+            g,
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Module for animated sprites, made of a sequence of frames.
+
+use graphics::ImageSize;
+use graphics::math::Matrix2d;
+use graphics::Graphics;
+use resource::{Result, SpriteAssetId, SpriteManage, SpriteRegion};
+
+/// A looping sequence of sprite frames, each shown for the same amount of
+/// time.
+#[derive(Debug, Clone)]
+pub struct AnimatedSprite<T> {
+    frames: Vec<SpriteRegion<T>>,
+    /// the duration of each frame, in ticks
+    frame_duration: f32,
+    /// the time elapsed since the first frame, in ticks
+    elapsed: f32,
+}
+
+impl<T> AnimatedSprite<T>
+where
+    T: ImageSize,
+{
+    /// Create an animation from the given frames, each lasting
+    /// `frame_duration` ticks.
+    pub fn new(frames: Vec<SpriteRegion<T>>, frame_duration: f32) -> Self {
+        assert!(!frames.is_empty());
+        assert!(frame_duration > 0.);
+        AnimatedSprite {
+            frames,
+            frame_duration,
+            elapsed: 0.,
+        }
+    }
+
+    /// Create an animation from named frames of a loaded atlas.
+    pub fn from_atlas<S, N>(sprite: &S, atlas_id: SpriteAssetId, names: &[N], frame_duration: f32) -> Result<Self>
+    where
+        S: SpriteManage<Texture = T>,
+        N: AsRef<str>,
+    {
+        let frames: Result<Vec<_>> = names
+            .iter()
+            .map(|name| sprite.get_region(atlas_id, name.as_ref()))
+            .collect();
+        Ok(AnimatedSprite::new(frames?, frame_duration))
+    }
+
+    /// Create an animation from separately loaded sprites.
+    pub fn from_sprites<S>(sprite: &S, ids: &[SpriteAssetId], frame_duration: f32) -> Result<Self>
+    where
+        S: SpriteManage<Texture = T>,
+    {
+        let frames: Result<Vec<_>> = ids.iter().map(|id| sprite.get_sprite_region(*id)).collect();
+        Ok(AnimatedSprite::new(frames?, frame_duration))
+    }
+
+    /// Advance the animation by the given number of ticks.
+    pub fn update(&mut self, ticks: f32) {
+        let total = self.frame_duration * self.frames.len() as f32;
+        self.elapsed = (self.elapsed + ticks) % total;
+    }
+
+    /// Obtain the frame to show at this time.
+    pub fn current_frame(&self) -> &SpriteRegion<T> {
+        let i = (self.elapsed / self.frame_duration) as usize;
+        &self.frames[usize::min(i, self.frames.len() - 1)]
+    }
+
+    #[inline]
+    pub fn draw<G>(&self, transform: Matrix2d, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        self.current_frame().draw(transform, g)
+    }
+
+    /// Draw the current frame, multiplying its colors by the given color.
+    #[inline]
+    pub fn draw_tinted<G>(&self, color: [f32; 4], transform: Matrix2d, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        self.current_frame().draw_tinted(color, transform, g)
+    }
+}
+
+impl<T> ImageSize for AnimatedSprite<T>
+where
+    T: ImageSize,
+{
+    fn get_size(&self) -> (u32, u32) {
+        self.current_frame().get_size()
+    }
+}
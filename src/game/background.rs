@@ -0,0 +1,67 @@
+//! Module for the level background, which is drawn behind everything else.
+
+use std::rc::Rc;
+use na::Vector2;
+use graphics::{clear, Context, DrawState, Graphics, Image, ImageSize, Transformed};
+use consts::{HEIGHT, WIDTH};
+use level::GameLevel;
+use resource::{GameTexture, ResourceManage, SpriteAssetId, SpriteManage};
+
+/// How much the background image moves relative to the camera.
+pub const BACKGROUND_PARALLAX: f32 = 0.5;
+
+pub struct Background<R>
+where
+    R: ResourceManage,
+{
+    color: [f32; 4],
+    gfx_img: Option<Rc<GameTexture<R>>>,
+}
+
+impl<R> Background<R>
+where
+    R: ResourceManage,
+{
+    /// Create the background of the given level. If the level's background
+    /// image cannot be loaded, a warning is logged and only the plain color
+    /// is used.
+    pub fn new(level: &GameLevel, resource_manager: R) -> Self {
+        let gfx_img = level.background_texture().and_then(|path| {
+            let mut sprite = resource_manager.sprite();
+            sprite
+                .new_sprite_from_path(SpriteAssetId::LevelBackground, path)
+                .and_then(|_| sprite.get_sprite(SpriteAssetId::LevelBackground))
+                .map_err(|e| {
+                    eprintln!("Warning: could not load background image {}: {}", path, e);
+                })
+                .ok()
+        });
+        Background {
+            color: level.background_color(),
+            gfx_img,
+        }
+    }
+
+    /// Draw the background, given the camera position and the map
+    /// dimensions. The image is stretched so that it covers the whole map
+    /// as the camera scrolls with the parallax effect.
+    pub fn draw<G>(&self, ctx: Context, camera_pos: Vector2<f32>, map_dim: Vector2<f32>, g: &mut G)
+    where
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        clear(self.color, g);
+        if let Some(img) = self.gfx_img.as_ref() {
+            let (view_w, view_h) = (WIDTH as f32, HEIGHT as f32);
+            let size_w = view_w + f32::max(0., map_dim[0] - view_w) * BACKGROUND_PARALLAX;
+            let size_h = view_h + f32::max(0., map_dim[1] - view_h) * BACKGROUND_PARALLAX;
+            let (w, h) = img.get_size();
+            let w_scale = size_w / w as f32;
+            let h_scale = size_h / h as f32;
+            let offset = -camera_pos * BACKGROUND_PARALLAX;
+            let ctx = ctx
+                .trans(offset[0].into(), offset[1].into())
+                .scale(w_scale.into(), h_scale.into());
+            Image::new().draw(&**img, &DrawState::default(), ctx.transform, g);
+        }
+    }
+}
@@ -1,17 +1,35 @@
 use piston::input::GenericEvent;
-use graphics::{ellipse, Context, Graphics};
+use std::collections::VecDeque;
+use graphics::{ellipse, Context, Ellipse, Graphics, Transformed};
 use na::{norm_squared, Vector2};
-use physics::{rigid_bounce, AnimatedObject, Collidable, CollisionInfo, SimpleCollidable, Positioned};
-use util::default_vector2;
+use physics::{rigid_bounce, AnimatedObject, Collidable, CollisionInfo, Impact, SimpleCollidable, Positioned};
+use util::{clamp, default_vector2};
+use level::info::BallParams;
+use super::events::EventQueue;
+use super::palette::Palette;
+use super::water::{WATER_BUOYANCY, WATER_DRAG, WATER_THRUST_FACTOR};
 use resource::{ResourceManage, Result};
 
 pub const BALL_DEFAULT_SIZE: f32 = 28.;
-const BALL_CAPACITY: f32 = 34.;
-const DECREASE_FACTOR: f32 = -0.011;
-const THRUST_FORCE: f32 = 0.04;
+/// The largest size a ball can have without bursting, unless the level
+/// specifies otherwise.
+pub const BALL_CAPACITY: f32 = 34.;
+/// The smallest size a ball can have without deflating completely.
+pub const MIN_BALL_SIZE: f32 = 4.;
+/// How much the ball shrinks per tick in hard mode, whether thrusting or
+/// not.
+pub const HARD_MODE_DEFLATE_RATE: f32 = 0.004;
 const COLLISION_DAMPENING: f32 = 0.01;
-const TOO_MUCH_SPEED_SQR: f32 = 22.;
 const TOO_MUCH_SPEED_RESISTANCE: f32 = 0.005;
+/// How much the ball stretches per unit of speed.
+const STRETCH_FACTOR: f32 = 0.04;
+const MAX_STRETCH: f32 = 0.25;
+/// How much the ball is squashed right after a bounce.
+const SQUASH_AMOUNT: f32 = 0.3;
+/// How long the ball stays squashed after a bounce, in ticks.
+const SQUASH_DURATION: f32 = 6.;
+/// The number of past positions kept for drawing the trail.
+const TRAIL_LENGTH: usize = 8;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Ball {
@@ -20,6 +38,9 @@ pub struct Ball {
     #[serde(default = "default_vector2")]
     vel: Vector2<f32>,
     #[serde(default = "Ball::default_size")] size: f32,
+    #[serde(skip)]
+    #[serde(default = "Ball::default_capacity")]
+    capacity: f32,
 }
 
 impl Ball {
@@ -27,6 +48,10 @@ impl Ball {
         BALL_DEFAULT_SIZE
     }
 
+    pub fn default_capacity() -> f32 {
+        BALL_CAPACITY
+    }
+
     pub fn new<P>(position: P, size: f32) -> Ball
     where
         P: Into<Vector2<f32>>,
@@ -35,6 +60,7 @@ impl Ball {
             pos: position.into(),
             vel: default_vector2(),
             size,
+            capacity: BALL_CAPACITY,
         }
     }
 
@@ -79,7 +105,11 @@ impl Ball {
 
     #[inline]
     pub fn capacity(&self) -> f32 {
-        BALL_CAPACITY
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: f32) {
+        self.capacity = capacity;
     }
 
     pub fn thrust<V>(&mut self, thrust: V)
@@ -120,7 +150,7 @@ impl Ball {
     }
 
     pub fn is_dead(&self) -> bool {
-        self.size < 4. || self.size > BALL_CAPACITY + 2.
+        self.size < MIN_BALL_SIZE || self.size > self.capacity + 2.
     }
 
     pub fn update_position(&mut self, factor: f32) {
@@ -128,35 +158,145 @@ impl Ball {
     }
 
     pub fn maximize_size(&mut self) {
-        self.size = BALL_CAPACITY;
+        self.size = self.capacity;
     }
 
-    pub fn draw<G: Graphics>(&self, ctx: Context, gfx: &mut G) {
+    /// The color which warns that the ball is about to deflate completely
+    /// or to burst, if it is.
+    fn warning_color(&self, palette: &Palette) -> Option<[f32; 4]> {
+        if self.size < 5.5 {
+            Some(palette.ball_small)
+        } else if self.size > self.capacity - 2.5 {
+            Some(palette.ball_full)
+        } else {
+            None
+        }
+    }
+
+    pub fn draw<G: Graphics>(&self, ctx: Context, palette: &Palette, gfx: &mut G) {
+        self.draw_deformed(ctx, 0., 0., palette, 1., gfx)
+    }
+
+    /// Draw the ball stretched by `amount` along the axis at the given
+    /// angle, and squashed along the perpendicular axis. A negative amount
+    /// squashes the ball along the axis instead. This does not affect the
+    /// ball's collision radius.
+    ///
+    /// The ball changes color when it is about to deflate or burst. With a
+    /// `flash` intensity below 1, the change is toned down and shown by a
+    /// steady outline instead, as the color may flicker.
+    pub fn draw_deformed<G: Graphics>(
+        &self,
+        ctx: Context,
+        angle: f32,
+        amount: f32,
+        palette: &Palette,
+        flash: f32,
+        gfx: &mut G,
+    ) {
         if self.is_dead() {
             return;
         }
 
-        let color = if self.size < 5.5 {
-            [0.7, 0.5, 0.9, 1.0]
-        } else if self.size > BALL_CAPACITY - 2.5 {
-            [0.7, 0.88, 1.0, 0.8]
-        } else {
-            [0.5, 0.86, 1.0, 1.0]
-        };
         let (x, y) = (self.pos[0] as f64, self.pos[1] as f64);
         let hsize = (self.size / 2.) as f64;
         let draw_size = (self.size + 4.) as f64;
         let r = [
-            x - hsize - 2.,
-            y - hsize - 2.,
+            -hsize - 2.,
+            -hsize - 2.,
             draw_size,
             draw_size,
         ];
-
+        let scale = f64::from(1. + amount);
+        let ctx = ctx
+            .trans(x, y)
+            .rot_rad(angle.into())
+            .scale(scale, 1. / scale);
+
+        let warning = self.warning_color(palette);
+        let color = warning.map_or(palette.ball, |warning| mix_colors(palette.ball, warning, flash));
         ellipse(color, r, ctx.transform, gfx);
+        if let Some(outline) = palette.ball_outline {
+            Ellipse::new_border(outline, 0.75).draw(r, &ctx.draw_state, ctx.transform, gfx);
+        }
+        if let Some(mut warning) = warning.filter(|_| flash < 1.) {
+            warning[3] *= 1. - flash;
+            Ellipse::new_border(warning, 1.).draw(r, &ctx.draw_state, ctx.transform, gfx);
+        }
+    }
+}
+
+fn full_intensity() -> f32 {
+    1.
+}
+
+/// Mix two colors, going from `a` to `b` as `t` goes from 0 to 1.
+fn mix_colors(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let mut mixed = a;
+    for (m, b) in mixed.iter_mut().zip(&b) {
+        *m += (b - *m) * t;
+    }
+    mixed
+}
+
+/// The directions in which the ball is being thrust.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Thrust {
+    pub right: bool,
+    pub left: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+impl Thrust {
+    /// Encode the thrust as bit flags (right, left, up, down from the
+    /// lowest bit).
+    pub fn to_bits(self) -> u8 {
+        self.right as u8 | (self.left as u8) << 1 | (self.up as u8) << 2 | (self.down as u8) << 3
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Thrust {
+            right: bits & 1 != 0,
+            left: bits & 2 != 0,
+            up: bits & 4 != 0,
+            down: bits & 8 != 0,
+        }
+    }
+}
+
+/// The set of inputs which control a ball.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Controls {
+    /// The arrow keys, the keypad and the first gamepad.
+    Primary,
+    /// The W, A, S and D keys and the second gamepad.
+    Secondary,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Controls::Primary
+    }
+}
+
+impl Controls {
+    /// The ID of the gamepad bound to these controls.
+    fn gamepad_id(self) -> i32 {
+        match self {
+            Controls::Primary => 0,
+            Controls::Secondary => 1,
+        }
     }
 }
 
+/// The state of a ball which can be saved and restored in practice mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BallState {
+    ball: Ball,
+    num_gems: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BallController<R> {
     ball: Ball,
@@ -169,6 +309,26 @@ pub struct BallController<R> {
     acc_overlaps: Vector2<f32>,
     num_overlaps: usize,
     num_gems: u32,
+    /// the hardest impact since the last update
+    #[serde(skip)] pending_impact: Option<Impact>,
+    /// the hardest impact resolved in the last update
+    #[serde(skip)] impact: Option<Impact>,
+    /// remaining squash time, in ticks
+    #[serde(skip)] squash_time: f32,
+    /// angle of the normal of the last bounce
+    #[serde(skip)] squash_angle: f32,
+    #[serde(skip)] trail: VecDeque<Vector2<f32>>,
+    #[serde(default)] params: BallParams,
+    #[serde(skip)] controls: Controls,
+    /// how deep the ball is in water, from 0 to 1
+    #[serde(skip)] submersion: f32,
+    /// how much the ball shrinks per tick regardless of thrust
+    #[serde(skip)] constant_deflation: f32,
+    /// how strongly the ball's color changes warn about its size, from 0
+    /// to 1
+    #[serde(skip)]
+    #[serde(default = "full_intensity")]
+    flash_intensity: f32,
     resource_manager: R,
 }
 
@@ -177,7 +337,8 @@ where
     R: ResourceManage,
 {
     /// Creates a new gameboard controller.
-    pub fn new(ball: Ball, resource_manager: R) -> Result<Self> {
+    pub fn new(mut ball: Ball, params: BallParams, resource_manager: R) -> Result<Self> {
+        ball.set_capacity(params.capacity);
         Ok(BallController {
             ball,
             thrust_right: false,
@@ -187,34 +348,84 @@ where
             acc_overlaps: default_vector2(),
             num_overlaps: 0,
             num_gems: 0,
+            pending_impact: None,
+            impact: None,
+            squash_time: 0.,
+            squash_angle: 0.,
+            trail: VecDeque::with_capacity(TRAIL_LENGTH),
+            params,
+            controls: Controls::Primary,
+            submersion: 0.,
+            constant_deflation: 0.,
+            flash_intensity: 1.,
             resource_manager,
         })
     }
 
+    /// Choose the inputs which control the ball.
+    pub fn set_controls(&mut self, controls: Controls) {
+        self.controls = controls;
+    }
+
+    /// Make the ball shrink by the given amount on every tick, on top of
+    /// what thrusting costs.
+    pub fn set_constant_deflation(&mut self, rate: f32) {
+        self.constant_deflation = f32::max(0., rate);
+    }
+
+    /// Tone down the ball's changes of color by the given factor, from 0
+    /// (a steady outline instead) to 1.
+    pub fn set_flash_intensity(&mut self, intensity: f32) {
+        self.flash_intensity = clamp(intensity, 0., 1.);
+    }
+
+    /// Define how deep the ball is in water, from 0 (out of the water) to 1
+    /// (fully submerged). Water slows the ball down, weakens its thrust and
+    /// pushes it upwards, in proportion to this amount.
+    pub fn set_submersion(&mut self, submersion: f32) {
+        self.submersion = clamp(submersion, 0., 1.);
+    }
+
     /// Handles events.
     pub fn event<E: GenericEvent>(&mut self, e: &E) {
         use piston::input::{ButtonState, ControllerAxisArgs, Key};
         use piston::input::Button::Keyboard;
+        let primary = self.controls == Controls::Primary;
         if let Some(b) = e.button_args() {
             // Set cell value.
             match b.button {
-                Keyboard(Key::Right) | Keyboard(Key::NumPad6) => {
+                Keyboard(Key::Right) | Keyboard(Key::NumPad6) if primary => {
                     self.thrust_right = b.state == ButtonState::Press;
                 }
-                Keyboard(Key::Left) | Keyboard(Key::NumPad4) => {
+                Keyboard(Key::Left) | Keyboard(Key::NumPad4) if primary => {
                     self.thrust_left = b.state == ButtonState::Press;
                 }
-                Keyboard(Key::Up) | Keyboard(Key::NumPad8) => {
+                Keyboard(Key::Up) | Keyboard(Key::NumPad8) if primary => {
                     self.thrust_up = b.state == ButtonState::Press;
                 }
-                Keyboard(Key::Down) | Keyboard(Key::NumPad2) => {
+                Keyboard(Key::Down) | Keyboard(Key::NumPad2) if primary => {
+                    self.thrust_down = b.state == ButtonState::Press;
+                }
+                Keyboard(Key::D) if !primary => {
+                    self.thrust_right = b.state == ButtonState::Press;
+                }
+                Keyboard(Key::A) if !primary => {
+                    self.thrust_left = b.state == ButtonState::Press;
+                }
+                Keyboard(Key::W) if !primary => {
+                    self.thrust_up = b.state == ButtonState::Press;
+                }
+                Keyboard(Key::S) if !primary => {
                     self.thrust_down = b.state == ButtonState::Press;
                 }
                 _ => {
                     // do nothing
                 }
             }
-        } else if let Some(ControllerAxisArgs { id: 0, axis, position}) = e.controller_axis_args() {
+        } else if let Some(ControllerAxisArgs { id, axis, position }) = e.controller_axis_args() {
+            if id != self.controls.gamepad_id() {
+                return;
+            }
             match axis {
                 0 =>  {
                     // horizontal axis
@@ -244,6 +455,7 @@ where
     }
 
     pub fn update(&mut self, factor: f32) {
+        self.impact = self.pending_impact.take();
         if self.is_dead() {
             return;
         }
@@ -252,6 +464,10 @@ where
         if self.num_overlaps > 0 {
             // average the collision vectors
             let overlap = self.acc_overlaps / self.num_overlaps as f32;
+            if norm_squared(&overlap) > 0. {
+                self.squash_time = SQUASH_DURATION;
+                self.squash_angle = overlap[1].atan2(overlap[0]);
+            }
             self.correct_and_rigid_bounce(overlap);
             // dampen velocity a little bit
             self.ball.decay_velocity(COLLISION_DAMPENING);
@@ -259,7 +475,8 @@ where
             self.num_overlaps = 0;
         }
 
-        let thrust_force: f32 = THRUST_FORCE * factor;
+        let thrust_scale = 1. - (1. - WATER_THRUST_FACTOR) * self.submersion;
+        let thrust_force: f32 = self.params.thrust * thrust_scale * factor;
         let mut total_effort = 0;
         if self.thrust_right {
             self.ball.thrust([thrust_force, 0.]);
@@ -278,12 +495,111 @@ where
             total_effort += 1;
         }
 
-        if self.ball.speed_sqr() > TOO_MUCH_SPEED_SQR {
+        if self.params.drag > 0. {
+            self.ball.decay_velocity(f32::min(1., self.params.drag * factor));
+        }
+        if self.submersion > 0. {
+            self.ball.decay_velocity(f32::min(1., WATER_DRAG * self.submersion * factor));
+            self.ball.thrust([0., -WATER_BUOYANCY * self.submersion * factor]);
+        }
+        let max_speed = self.params.max_speed;
+        if self.ball.speed_sqr() > max_speed * max_speed {
             self.ball.decay_velocity(TOO_MUCH_SPEED_RESISTANCE);
         }
 
+        if self.trail.len() == TRAIL_LENGTH {
+            self.trail.pop_back();
+        }
+        self.trail.push_front(self.ball.position());
+        self.squash_time = f32::max(0., self.squash_time - factor);
+
         self.ball.update_position(factor);
-        self.ball.add_size(total_effort as f32 * DECREASE_FACTOR * factor);
+        let deflation = total_effort as f32 * self.params.deflate_rate + self.constant_deflation;
+        self.ball.add_size(-deflation * factor);
+    }
+
+    /// Obtain the thrust currently applied to the ball.
+    pub fn thrust(&self) -> Thrust {
+        Thrust {
+            right: self.thrust_right,
+            left: self.thrust_left,
+            up: self.thrust_up,
+            down: self.thrust_down,
+        }
+    }
+
+    /// Apply the given thrust to the ball, regardless of input events.
+    pub fn set_thrust(&mut self, thrust: Thrust) {
+        self.thrust_right = thrust.right;
+        self.thrust_left = thrust.left;
+        self.thrust_up = thrust.up;
+        self.thrust_down = thrust.down;
+    }
+
+    /// Stop all thrust, as if every control was released.
+    pub fn release_all_thrust(&mut self) {
+        self.set_thrust(Thrust::default());
+    }
+
+    /// Save the ball's position, velocity, size and gems.
+    pub fn state(&self) -> BallState {
+        BallState {
+            ball: self.ball.clone(),
+            num_gems: self.num_gems,
+        }
+    }
+
+    /// Bring the ball back to a saved state. Thrust, collisions and bounce
+    /// effects from before are dropped, so that the ball starts afresh.
+    pub fn restore_state(&mut self, state: &BallState) {
+        self.ball = state.ball.clone();
+        self.num_gems = state.num_gems;
+        self.release_all_thrust();
+        self.acc_overlaps = default_vector2();
+        self.num_overlaps = 0;
+        self.pending_impact = None;
+        self.impact = None;
+        self.squash_time = 0.;
+        self.trail.clear();
+        self.submersion = 0.;
+    }
+
+    /// Obtain the direction of the thrust currently applied to the ball.
+    /// This is a zero vector if no thrust is applied.
+    pub fn thrust_direction(&self) -> Vector2<f32> {
+        let mut dir: Vector2<f32> = default_vector2();
+        if self.thrust_right {
+            dir[0] += 1.;
+        }
+        if self.thrust_left {
+            dir[0] -= 1.;
+        }
+        if self.thrust_up {
+            dir[1] -= 1.;
+        }
+        if self.thrust_down {
+            dir[1] += 1.;
+        }
+        dir
+    }
+
+    /// Obtain the hardest hit taken by the ball in the last update, if it
+    /// bounced off anything.
+    #[inline]
+    pub fn last_impact(&self) -> Option<Impact> {
+        self.impact
+    }
+
+    /// Keep the given impact if it is the hardest since the last update.
+    fn record_impact(&mut self, impact: Option<Impact>) {
+        let harder = match (impact, self.pending_impact) {
+            (Some(new), Some(old)) => new.speed > old.speed,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if harder {
+            self.pending_impact = impact;
+        }
     }
 
     #[inline]
@@ -321,9 +637,44 @@ where
         self.ball.add_size(extra_size)
     }
 
-    #[inline]
-    pub fn draw<G: Graphics>(&self, ctx: Context, gfx: &mut G) {
-        self.ball.draw(ctx, gfx)
+    /// Lose the ball, after it fell out of the map through an open edge.
+    pub fn fall_out(&mut self) {
+        let size = self.ball.size();
+        self.ball.add_size(-size);
+    }
+
+    /// Draw the ball, deformed according to its motion, along with its
+    /// trail.
+    pub fn draw<G: Graphics>(&self, ctx: Context, palette: &Palette, gfx: &mut G) {
+        if self.is_dead() {
+            return;
+        }
+
+        // the trail, from the oldest position to the newest
+        let n = self.trail.len();
+        for (i, p) in self.trail.iter().enumerate().rev() {
+            let t = 1. - (i + 1) as f32 / (n + 1) as f32;
+            let size = (self.ball.size() * 0.6 * t) as f64;
+            let r = [
+                p[0] as f64 - size / 2.,
+                p[1] as f64 - size / 2.,
+                size,
+                size,
+            ];
+            let mut color = palette.trail;
+            color[3] *= t;
+            ellipse(color, r, ctx.transform, gfx);
+        }
+
+        if self.squash_time > 0. {
+            let amount = -SQUASH_AMOUNT * self.squash_time / SQUASH_DURATION;
+            self.ball.draw_deformed(ctx, self.squash_angle, amount, palette, self.flash_intensity, gfx);
+        } else {
+            let vel = self.ball.velocity();
+            let stretch = f32::min(MAX_STRETCH, self.ball.speed_sqr().sqrt() * STRETCH_FACTOR);
+            let angle = vel[1].atan2(vel[0]);
+            self.ball.draw_deformed(ctx, angle, stretch, palette, self.flash_intensity, gfx);
+        }
     }
 
     #[inline]
@@ -331,6 +682,44 @@ where
         self.ball.is_dead()
     }
 
+    /// Draw the ball popping after its death, where `progress` goes from 0
+    /// to 1 over the course of the animation.
+    pub fn draw_pop<G: Graphics>(&self, ctx: Context, progress: f32, palette: &Palette, gfx: &mut G) {
+        let progress = clamp(progress, 0., 1.);
+        let pos = self.ball.position();
+        let size = (clamp(self.ball.size(), MIN_BALL_SIZE, self.ball.capacity()) * (1. + progress)) as f64;
+        let r = [
+            pos[0] as f64 - size / 2.,
+            pos[1] as f64 - size / 2.,
+            size,
+            size,
+        ];
+        let mut color = palette.ball;
+        color[3] *= 1. - progress;
+        Ellipse::new_border(color, 1.).draw(r, &ctx.draw_state, ctx.transform, gfx);
+    }
+
+    /// Draw the ball being absorbed into the given target point, where
+    /// `progress` goes from 0 to 1 over the course of the animation.
+    pub fn draw_absorbed<G: Graphics>(
+        &self,
+        ctx: Context,
+        target: Vector2<f32>,
+        progress: f32,
+        palette: &Palette,
+        gfx: &mut G,
+    ) {
+        let progress = clamp(progress, 0., 1.);
+        let pos = self.ball.position();
+        let p = pos + (target - pos) * progress;
+        let scale = f64::from(1. - progress);
+        let ctx = ctx
+            .trans(p[0].into(), p[1].into())
+            .scale(scale, scale)
+            .trans((-pos[0]).into(), (-pos[1]).into());
+        self.ball.draw_deformed(ctx, 0., 0., palette, self.flash_intensity, gfx)
+    }
+
     fn correct_and_rigid_bounce(&mut self, overlap: Vector2<f32>) {
         // correct position to not overlap
         self.ball.add_position(overlap);
@@ -339,22 +728,33 @@ where
         self.ball.set_velocity(rigid_bounce(vel, overlap));
     }
 
-    pub fn handle_collision_with<T>(&mut self, mut object: T)
+    /// Test and handle a collision with the given object, returning whether
+    /// a collision happened.
+    pub fn handle_collision_with<T>(&mut self, mut object: T) -> bool
     where
         T: Collidable,
     {
         let collision = object.test_circle_collision(self.ball.position(), self.ball.size() / 2.);
         if let CollisionInfo::Yes(overlap) = collision {
             object.on_collision(self, overlap);
+            true
+        } else {
+            false
         }
     }
 
-    pub fn handle_simple_collision_with<T>(&mut self, mut object: T)
+    /// Test and handle a simple collision with the given object, returning
+    /// whether a collision happened. What comes of it is reported to
+    /// `events`.
+    pub fn handle_simple_collision_with<T>(&mut self, mut object: T, events: &mut EventQueue) -> bool
     where
         T: SimpleCollidable,
     {
         if object.test_circle_collision_simple(self.ball.position(), self.ball.size() / 2.) {
-            object.on_collision_simple(self);
+            object.on_collision_simple(self, events);
+            true
+        } else {
+            false
         }
     }
 }
@@ -370,12 +770,20 @@ impl<R> AnimatedObject for BallController<R>
 where
     R: ResourceManage,
 {
-    fn issue_bounce(&mut self, overlap: Vector2<f32>) {
+    fn velocity(&self) -> Vector2<f32> {
+        self.ball.velocity()
+    }
+
+    fn issue_bounce(&mut self, overlap: Vector2<f32>, surface_velocity: Vector2<f32>) {
+        let relative_velocity = self.ball.velocity() - surface_velocity;
+        self.record_impact(Impact::new(overlap, relative_velocity));
         self.acc_overlaps += overlap;
         self.num_overlaps += 1;
     }
 
     fn correct_and_flip_x(&mut self, overlap: f32) {
+        let impact = Impact::new([overlap, 0.].into(), self.ball.velocity());
+        self.record_impact(impact);
         // correct position to not overlap
         self.ball.add_position([overlap, 0.]);
         // and bounce horizontally
@@ -383,6 +791,8 @@ where
     }
 
     fn correct_and_flip_y(&mut self, overlap: f32) {
+        let impact = Impact::new([0., overlap].into(), self.ball.velocity());
+        self.record_impact(impact);
         // correct position to not overlap
         self.ball.add_position([0., overlap]);
         // and bounce vertically
@@ -413,3 +823,52 @@ where
         self.num_gems
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::wall::Wall;
+    use level::info::WallInfo;
+    use resource::{NullAudioManager, NullResourceManager, NullSpriteManager, SpriteAssetId};
+
+    #[test]
+    fn ball_dropped_onto_a_wall_reports_its_approach_speed() {
+        let mut sprite = NullSpriteManager::new(()).unwrap();
+        sprite.insert_sprite(SpriteAssetId::Other(0), 16, 16);
+        let res = NullResourceManager::new(sprite, NullAudioManager::new(()).unwrap());
+        let floor = WallInfo {
+            pos: [0, 100].into(),
+            dim: [320, 20].into(),
+            texture_id: 0,
+            rotation: 0,
+            flip: false,
+            tile: false,
+        };
+        let mut wall = Wall::new(floor, &res).unwrap();
+        // half a unit off, so that the ball sinks into the wall instead of
+        // landing right on its surface
+        let ball = Ball::new([160., 50.5], 28.);
+        let mut ball = BallController::new(ball, BallParams::default(), &res).unwrap();
+        ball.set_velocity(Vector2::new(0.5, 2.));
+
+        let mut approach = None;
+        for _ in 0..60 {
+            let vel = ball.velocity();
+            if ball.handle_collision_with(&mut wall) {
+                approach = Some(vel);
+                ball.update(1.);
+                break;
+            }
+            ball.update(1.);
+            assert_eq!(ball.last_impact(), None);
+        }
+        let approach = approach.expect("the ball never reached the wall");
+        let impact = ball.last_impact().expect("the ball did not report the impact");
+        assert_eq!(impact.normal, Vector2::new(0., -1.));
+        assert!((impact.speed - approach[1]).abs() < 1e-6, "{:?} from {:?}", impact, approach);
+        // the ball bounced back up, and the impact only lasts one update
+        assert!(ball.velocity()[1] < 0.);
+        ball.update(1.);
+        assert_eq!(ball.last_impact(), None);
+    }
+}
@@ -0,0 +1,63 @@
+//! Module for drawing the edges of the map, so that the ball does not seem
+//! to bounce off nothing.
+
+use graphics::{rectangle, Context, Graphics};
+use level::map::{Map, OpenEdges};
+
+/// The color of the band along the closed edges of the map.
+const BAND_COLOR: [f32; 4] = [0.05, 0.05, 0.1, 0.6];
+/// How wide the band along the closed edges is, in map units.
+const BAND_WIDTH: f64 = 3.;
+/// The colors of the stripes marking open edges in the editor.
+const STRIPE_COLORS: [[f32; 4]; 2] = [[0.95, 0.75, 0.1, 0.8], [0.1, 0.1, 0.1, 0.8]];
+/// How long each stripe marking an open edge is, in map units.
+const STRIPE_LENGTH: f64 = 8.;
+
+/// The rectangle along each edge of the map, just inside it, as
+/// `(left, right, up, down)`.
+fn edge_rects(map: &Map, width: f64) -> [[f64; 4]; 4] {
+    let (w, h) = (f64::from(map.width()), f64::from(map.height()));
+    [
+        [0., 0., width, h],
+        [w - width, 0., width, h],
+        [0., 0., w, width],
+        [0., h - width, w, width],
+    ]
+}
+
+fn edge_flags(open: OpenEdges) -> [bool; 4] {
+    [open.left, open.right, open.up, open.down]
+}
+
+/// Draw a dark band just inside the closed edges of the map.
+pub fn draw_borders<G: Graphics>(map: &Map, c: Context, g: &mut G) {
+    let flags = edge_flags(map.open_edges());
+    for (r, &open) in edge_rects(map, BAND_WIDTH).iter().zip(&flags) {
+        if !open {
+            rectangle(BAND_COLOR, *r, c.transform, g);
+        }
+    }
+}
+
+/// Mark the open edges of the map with hazard stripes.
+pub fn draw_open_edges<G: Graphics>(map: &Map, c: Context, g: &mut G) {
+    let flags = edge_flags(map.open_edges());
+    for (r, &open) in edge_rects(map, BAND_WIDTH).iter().zip(&flags) {
+        if !open {
+            continue;
+        }
+        // split the band along its length
+        let along = if r[2] > r[3] { 0 } else { 1 };
+        let length = r[2 + along];
+        let mut offset = 0.;
+        let mut i = 0;
+        while offset < length {
+            let mut stripe = *r;
+            stripe[along] += offset;
+            stripe[2 + along] = f64::min(STRIPE_LENGTH, length - offset);
+            rectangle(STRIPE_COLORS[i % 2], stripe, c.transform, g);
+            offset += STRIPE_LENGTH;
+            i += 1;
+        }
+    }
+}
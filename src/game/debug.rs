@@ -0,0 +1,136 @@
+//! Module for the debug overlay, which shows the collision shapes and other
+//! internals of a game.
+//!
+//! The overlay is toggled at run time, but it can be left out of the game
+//! entirely by building without the `debug-overlay` feature.
+
+use std::cell::Cell;
+use graphics::character::CharacterCache;
+use graphics::{line, Context, DrawState, Ellipse, Graphics, Rectangle, Text, Transformed};
+use na::Vector2;
+use profile::FrameStats;
+
+pub const WALL_COLOR: [f32; 4] = [0.3, 1.0, 0.3, 0.8];
+pub const ENTITY_COLOR: [f32; 4] = [1.0, 1.0, 0.3, 0.8];
+pub const HAZARD_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 0.8];
+pub const WATER_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 0.8];
+pub const BALL_COLOR: [f32; 4] = [0.3, 1.0, 1.0, 0.9];
+pub const BORDER_COLOR: [f32; 4] = [1.0, 0.5, 0.0, 0.9];
+pub const CAMERA_COLOR: [f32; 4] = [1.0, 0.3, 1.0, 0.6];
+const TEXT_COLOR: [f32; 4] = [0.6, 1.0, 0.6, 1.0];
+/// How long the frame and update rates are measured before showing them,
+/// in seconds.
+const RATE_INTERVAL: f64 = 0.5;
+/// How long the ball's velocity vector is drawn, per unit of velocity.
+pub const VELOCITY_SCALE: f32 = 10.;
+
+/// The state of the debug overlay.
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+    visible: bool,
+    /// frames rendered since the rates were last measured
+    frames: Cell<u32>,
+    /// updates since the rates were last measured
+    updates: u32,
+    /// the time since the rates were last measured, in seconds
+    elapsed: f64,
+    /// the measured frames and updates per second
+    rates: (f64, f64),
+    /// the frame times measured by the game loop
+    frame_stats: FrameStats,
+}
+
+impl DebugOverlay {
+    /// Whether the overlay is shown. This is always false without the
+    /// `debug-overlay` feature, so that drawing it can be optimized away.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        cfg!(feature = "debug-overlay") && self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.frames.set(0);
+        self.updates = 0;
+        self.elapsed = 0.;
+    }
+
+    /// Count an update of the given duration, measuring the rates once
+    /// enough time has passed.
+    pub fn update(&mut self, dt: f64) {
+        if !self.is_visible() {
+            return;
+        }
+        self.updates += 1;
+        self.elapsed += dt;
+        if self.elapsed >= RATE_INTERVAL {
+            self.rates = (
+                f64::from(self.frames.get()) / self.elapsed,
+                f64::from(self.updates) / self.elapsed,
+            );
+            self.frames.set(0);
+            self.updates = 0;
+            self.elapsed = 0.;
+        }
+    }
+
+    pub fn set_frame_stats(&mut self, stats: FrameStats) {
+        self.frame_stats = stats;
+    }
+
+    /// Count a rendered frame.
+    pub fn count_frame(&self) {
+        self.frames.set(self.frames.get() + 1);
+    }
+
+    /// Draw the outline of a rectangle, given as `[x, y, width, height]`.
+    pub fn draw_box<G: Graphics>(&self, rect: [f32; 4], color: [f32; 4], ctx: Context, g: &mut G) {
+        let rect = [
+            f64::from(rect[0]),
+            f64::from(rect[1]),
+            f64::from(rect[2]),
+            f64::from(rect[3]),
+        ];
+        Rectangle::new_border(color, 0.5).draw(rect, &ctx.draw_state, ctx.transform, g);
+    }
+
+    /// Draw the outline of a circle.
+    pub fn draw_circle<G: Graphics>(&self, center: Vector2<f32>, radius: f32, color: [f32; 4], ctx: Context, g: &mut G) {
+        let r = f64::from(radius);
+        let rect = [f64::from(center[0]) - r, f64::from(center[1]) - r, r * 2., r * 2.];
+        Ellipse::new_border(color, 0.5).draw(rect, &ctx.draw_state, ctx.transform, g);
+    }
+
+    /// Draw a vector starting at the given point.
+    pub fn draw_vector<G: Graphics>(&self, from: Vector2<f32>, v: Vector2<f32>, color: [f32; 4], ctx: Context, g: &mut G) {
+        let to = from + v;
+        let coords = [
+            f64::from(from[0]),
+            f64::from(from[1]),
+            f64::from(to[0]),
+            f64::from(to[1]),
+        ];
+        line(color, 0.5, coords, ctx.transform, g);
+    }
+
+    /// Draw the measured rates and frame times, followed by the given
+    /// lines, at the top-right corner of the screen.
+    pub fn draw_stats<C, G>(&self, lines: &[String], ctx: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = C::Texture>,
+    {
+        let x = f64::from(ctx.viewport.map(|v| v.window_size[0]).unwrap_or(0)) - 200.;
+        let rates = format!("FPS {:.0}  UPS {:.0}", self.rates.0, self.rates.1);
+        let times = self.frame_stats.lines();
+        for (i, text) in Some(&rates).into_iter().chain(&times).chain(lines).enumerate() {
+            let _ = Text::new_color(TEXT_COLOR, 10).draw(
+                text,
+                cache,
+                &DrawState::default(),
+                ctx.transform.trans(x, 200. + 14. * i as f64),
+                g,
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Module for decorations: scenery which the ball does not interact with.
+
+use graphics::{Context, Graphics, ImageSize, Transformed};
+use na::Vector2;
+use level::info::{DecorationInfo, DecorationLayer};
+use resource::{GameSprite, GameTexture, ResourceManage, Result};
+use resource::sprite::{AssetId, SpriteManage};
+use util::{rect_contains, vec_f32};
+
+pub struct Decoration<R>
+where
+    R: ResourceManage,
+{
+    pos: Vector2<f32>,
+    layer: DecorationLayer,
+    parallax: f32,
+    gfx_tex: GameSprite<R>,
+}
+
+impl<R> Decoration<R>
+where
+    R: ResourceManage,
+{
+    pub fn new(info: DecorationInfo, res: R) -> Result<Self> {
+        let gfx_tex = res.sprite().get_sprite_region(AssetId::Other(info.texture_id))?;
+        Ok(Decoration {
+            pos: vec_f32(info.pos),
+            layer: info.layer,
+            parallax: info.parallax,
+            gfx_tex,
+        })
+    }
+
+    pub fn layer(&self) -> DecorationLayer {
+        self.layer
+    }
+
+    /// The dimensions of the decoration's sprite.
+    pub fn dimensions(&self) -> Vector2<f32> {
+        let (w, h) = self.gfx_tex.get_size();
+        Vector2::new(w as f32, h as f32)
+    }
+
+    /// Obtain the position of the decoration's top-left corner on the
+    /// screen, given the camera's position.
+    fn screen_position(&self, camera_pos: Vector2<f32>) -> Vector2<f32> {
+        self.pos - camera_pos * self.parallax
+    }
+
+    /// Check whether a point in map coordinates lies on top of this
+    /// decoration, as seen from the given camera position.
+    pub fn contains(&self, point: Vector2<f32>, camera_pos: Vector2<f32>) -> bool {
+        let tl = self.screen_position(camera_pos);
+        let dim = self.dimensions();
+        rect_contains([tl[0], tl[1], dim[0], dim[1]], point - camera_pos)
+    }
+
+    /// Draw the decoration. Unlike other entities, the given context must
+    /// not be translated by the camera, as the camera offset is scaled by
+    /// the decoration's parallax factor.
+    pub fn draw<G>(&self, ctx: Context, camera_pos: Vector2<f32>, g: &mut G)
+    where
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let pos = self.screen_position(camera_pos);
+        let ctx = ctx.trans(pos[0].into(), pos[1].into());
+        self.gfx_tex.draw(ctx.transform, g);
+    }
+}
@@ -1,21 +1,38 @@
 use std::marker::PhantomData;
 use na::{norm_squared, Vector2};
-use physics::{AnimatedObject, SimpleCollidable, Positioned};
-use graphics::{ellipse, Context, DrawState, Graphics, Image, Transformed, ImageSize};
-use resource::{GameTexture, ResourceManage, Result, SpriteManage};
+use physics::{centered_box, AnimatedObject, Bounded, SimpleCollidable, Positioned};
+use graphics::{ellipse, Context, Ellipse, Graphics, Transformed, ImageSize};
+use resource::{GameSprite, GameTexture, ResourceManage, Result, SpriteManage};
 use resource::sprite::AssetId;
-use level::info::{PumpInfo, MineInfo, GemInfo, FinishInfo};
+use level::info::{PumpInfo, BoostInfo, MineInfo, GemInfo, FinishInfo};
+use super::events::{EventQueue, GameEvent};
+use util::vec_f32;
+use super::animation::AnimatedSprite;
+use super::palette::{draw_circle_stripes, Palette};
 
 pub const PUMP_SIZE: f32 = 34.0;
+/// The time between heals of a pump, in ticks.
+const PUMP_COOLDOWN: f32 = 22.;
+/// The tint of a pump with no charges left.
+const EXHAUSTED_PUMP_TINT: [f32; 4] = [0.45, 0.45, 0.45, 0.8];
+
+/// The state of a pump which can be saved and restored in practice mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PumpState {
+    time_to_pump: f32,
+    charges: Option<u32>,
+}
 
 pub struct Pump<R>
 where
     R: ResourceManage,
 {
     pos: Vector2<f32>,
-    gfx_img: GameTexture<R>,
+    gfx_img: GameSprite<R>,
     time_to_pump: f32,
     rot: f32,
+    /// the remaining heals, unlimited if `None`
+    charges: Option<u32>,
     phantom: PhantomData<R>,
 }
 
@@ -24,17 +41,48 @@ where
     R: ResourceManage
 {
     pub fn new(info: PumpInfo, resource_manager: R) -> Result<Self> {
-        let gfx_img = resource_manager.sprite().get_sprite(AssetId::Pump)?;
+        let gfx_img = resource_manager.sprite().get_sprite_region(AssetId::Pump)?;
         Ok(Pump {
-            pos: Vector2::new(info.pos[0] as f32, info.pos[1] as f32),
+            pos: vec_f32(info.pos),
             gfx_img,
             time_to_pump: 0.,
             rot: 0.,
+            charges: info.charges,
             phantom: PhantomData,
         })
     }
 
+    /// The radius of the circle in which the ball touches the pump.
+    pub fn collision_radius(&self) -> f32 {
+        PUMP_SIZE / 2. - 2.
+    }
+
+    /// The number of heals left, or `None` if the pump never runs out.
+    pub fn charges(&self) -> Option<u32> {
+        self.charges
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.charges == Some(0)
+    }
+
+    /// Save the pump's cooldown and charges.
+    pub fn state(&self) -> PumpState {
+        PumpState {
+            time_to_pump: self.time_to_pump,
+            charges: self.charges,
+        }
+    }
+
+    pub fn restore_state(&mut self, state: PumpState) {
+        self.time_to_pump = state.time_to_pump;
+        self.charges = state.charges;
+    }
+
     pub fn update(&mut self, factor: f32) {
+        if self.is_exhausted() {
+            return;
+        }
         self.rot += 0.025 * factor;
         const TWO_PI: f32 = 2. * 3.14159265358979;
         if self.rot > TWO_PI {
@@ -57,22 +105,28 @@ where
         let w_scale = PUMP_SIZE / w;
         let h_scale = PUMP_SIZE / h;
 
-        ellipse(
-            [1.0, 1.0, 0.8, 0.25],
-            [
-                x - size / 2. + 2., y - size / 2. + 2.,
-                size - 4., size - 4.
-            ],
-            ctx.transform,
-            g,
-        );
+        // the glow grows back as the pump recharges
+        if !self.is_exhausted() {
+            let ready = 1. - f64::from(f32::max(0., self.time_to_pump) / PUMP_COOLDOWN);
+            let glow = (size - 4.) * ready;
+            ellipse(
+                [1.0, 1.0, 0.8, 0.25],
+                [x - glow / 2., y - glow / 2., glow, glow],
+                ctx.transform,
+                g,
+            );
+        }
 
         let ctx = ctx
             .trans(x as f64, y as f64)
             .scale(w_scale.into(), h_scale.into())
             .rot_rad(self.rot.into())
             .trans(-hw as f64, -hh as f64);
-        Image::new().draw(&self.gfx_img, &DrawState::default(), ctx.transform, g);
+        if self.is_exhausted() {
+            self.gfx_img.draw_tinted(EXHAUSTED_PUMP_TINT, ctx.transform, g);
+        } else {
+            self.gfx_img.draw(ctx.transform, g);
+        }
     }
 }
 
@@ -85,30 +139,170 @@ where
     }
 }
 
+impl<R> Bounded for Pump<R>
+where
+    R: ResourceManage,
+{
+    fn bounding_box(&self) -> [f32; 4] {
+        centered_box(self.pos, PUMP_SIZE)
+    }
+}
+
 impl<'a, R> SimpleCollidable for &'a mut Pump<R>
 where
     R: ResourceManage,
 {
     fn test_circle_collision_simple(&self, position: Vector2<f32>, radius: f32) -> bool {
-        let d = PUMP_SIZE / 2. + radius - 2.;
+        let d = self.collision_radius() + radius;
         norm_squared(&(self.pos - position)) <= d * d
     }
 
-    fn on_collision_simple<A>(&mut self, ball: &mut A)
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue)
     where
         A: AnimatedObject,
     {
-        // TODO when doing sounds, reproduce something here
-
-        if self.time_to_pump <= 0. {
+        if self.time_to_pump <= 0. && !self.is_exhausted() {
             // pump!
             ball.heal(1.0);
-            self.time_to_pump += 22.;
+            self.time_to_pump += PUMP_COOLDOWN;
+            if let Some(charges) = self.charges.as_mut() {
+                *charges -= 1;
+            }
+            events.push(GameEvent::PumpUsed(self.pos));
         }
     }
 }
 
 
+pub const BOOST_SIZE: f32 = 24.0;
+/// The time before a boost pad can fling the ball again, in ticks.
+const BOOST_COOLDOWN: f32 = 30.;
+/// The duration of the pulse shown when a boost pad is triggered, in ticks.
+const BOOST_PULSE_DURATION: f32 = 12.;
+
+pub struct Boost<R>
+where
+    R: ResourceManage,
+{
+    pos: Vector2<f32>,
+    direction: Vector2<f32>,
+    strength: f32,
+    gfx_img: GameSprite<R>,
+    time_to_boost: f32,
+    pulse: f32,
+}
+
+impl<R> Boost<R>
+where
+    R: ResourceManage
+{
+    pub fn new(info: BoostInfo, resource_manager: R) -> Result<Self> {
+        let gfx_img = resource_manager.sprite().get_sprite_region(AssetId::Boost)?;
+        let norm_sqr = norm_squared(&info.direction);
+        let direction = if norm_sqr > 0. {
+            info.direction / norm_sqr.sqrt()
+        } else {
+            BoostInfo::default_direction()
+        };
+        Ok(Boost {
+            pos: vec_f32(info.pos),
+            direction,
+            strength: info.strength,
+            gfx_img,
+            time_to_boost: 0.,
+            pulse: 0.,
+        })
+    }
+
+    /// The radius of the circle in which the ball touches the boost.
+    pub fn collision_radius(&self) -> f32 {
+        BOOST_SIZE / 2. - 2.
+    }
+
+    /// The ticks left until the boost pad can fling the ball again.
+    pub fn cooldown(&self) -> f32 {
+        self.time_to_boost
+    }
+
+    pub fn set_cooldown(&mut self, cooldown: f32) {
+        self.time_to_boost = cooldown;
+        self.pulse = 0.;
+    }
+
+    pub fn update(&mut self, factor: f32) {
+        if self.time_to_boost > 0. {
+            self.time_to_boost -= factor;
+        }
+        self.pulse = f32::max(0., self.pulse - factor);
+    }
+
+    pub fn draw<G>(&self, ctx: Context, g: &mut G)
+    where
+        G: Graphics<Texture=GameTexture<R>>
+    {
+        let size = BOOST_SIZE as f64;
+        let (x, y) = (self.pos[0] as f64, self.pos[1] as f64);
+        let pulse = (self.pulse / BOOST_PULSE_DURATION) as f64;
+
+        ellipse(
+            [1.0, 0.9, 0.4, 0.2 + 0.4 * pulse as f32],
+            [x - size / 2., y - size / 2., size, size],
+            ctx.transform,
+            g,
+        );
+
+        let (w, h) = self.gfx_img.get_size();
+        let (hw, hh) = (w as f64 / 2., h as f64 / 2.);
+        let scale = size / w as f64 * 0.75 * (1. + 0.3 * pulse);
+        let angle = self.direction[1].atan2(self.direction[0]);
+        let ctx = ctx
+            .trans(x, y)
+            .rot_rad(angle.into())
+            .scale(scale, scale)
+            .trans(-hw, -hh);
+        self.gfx_img.draw(ctx.transform, g);
+    }
+}
+
+impl<R> Positioned for Boost<R>
+where
+    R: ResourceManage,
+{
+    fn position(&self) -> Vector2<f32> {
+        self.pos
+    }
+}
+
+impl<R> Bounded for Boost<R>
+where
+    R: ResourceManage,
+{
+    fn bounding_box(&self) -> [f32; 4] {
+        centered_box(self.pos, BOOST_SIZE)
+    }
+}
+
+impl<'a, R> SimpleCollidable for &'a mut Boost<R>
+where
+    R: ResourceManage,
+{
+    fn test_circle_collision_simple(&self, position: Vector2<f32>, radius: f32) -> bool {
+        let d = self.collision_radius() + radius;
+        norm_squared(&(self.pos - position)) <= d * d
+    }
+
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue)
+    where
+        A: AnimatedObject,
+    {
+        if self.time_to_boost <= 0. {
+            ball.add_velocity(self.direction * self.strength);
+            self.time_to_boost = BOOST_COOLDOWN;
+            self.pulse = BOOST_PULSE_DURATION;
+            events.push(GameEvent::BoostUsed(self.pos));
+        }
+    }
+}
 
 pub const MINE_SIZE: f32 = 6.0;
 
@@ -117,7 +311,7 @@ where
     R: ResourceManage,
 {
     pos: Vector2<f32>,
-    gfx_img: GameTexture<R>,
+    gfx_img: GameSprite<R>,
     res: R,
 }
 
@@ -130,31 +324,49 @@ where
     }
 }
 
+impl<R> Bounded for Mine<R>
+where
+    R: ResourceManage,
+{
+    fn bounding_box(&self) -> [f32; 4] {
+        centered_box(self.pos, MINE_SIZE)
+    }
+}
+
 impl<R> Mine<R>
 where
     R: ResourceManage,
 {
     pub fn new(info: MineInfo, resource_manager: R) -> Result<Self> {
-        let gfx_img = resource_manager.sprite().get_sprite(AssetId::Mine)?;
+        let gfx_img = resource_manager.sprite().get_sprite_region(AssetId::Mine)?;
         Ok(Mine {
-            pos: Vector2::new(info.pos[0] as f32, info.pos[1] as f32),
+            pos: vec_f32(info.pos),
             gfx_img,
             res: resource_manager,
         })
     }
 
-    pub fn draw<G: Graphics>(&self, ctx: Context, gfx: &mut G)
+    /// The radius of the circle in which the ball touches the mine.
+    pub fn collision_radius(&self) -> f32 {
+        MINE_SIZE / 2. + 1.
+    }
+
+    pub fn draw<G: Graphics>(&self, ctx: Context, palette: &Palette, gfx: &mut G)
     where
         G: Graphics<Texture=GameTexture<R>>
     {
         let (x, y) = (self.pos[0] as f64, self.pos[1] as f64);
         let hsize = (MINE_SIZE / 2.) as f64;
-        Image::new().draw(
-            &self.gfx_img,
-            &DrawState::default(),
-            ctx.transform.trans(x - hsize - 2., y - hsize - 2.),
-            gfx
-        );
+        self.gfx_img
+            .draw_tinted(palette.mine_tint, ctx.transform.trans(x - hsize - 2., y - hsize - 2.), gfx);
+        if let Some(color) = palette.hazard_stripes {
+            draw_circle_stripes(color, self.pos, MINE_SIZE / 2., ctx.transform, gfx);
+        }
+        if let Some(color) = palette.hazard_outline {
+            let radius = f64::from(self.collision_radius());
+            let r = [x - radius, y - radius, radius * 2., radius * 2.];
+            Ellipse::new_border(color, 0.5).draw(r, &ctx.draw_state, ctx.transform, gfx);
+        }
     }
 }
 
@@ -163,29 +375,30 @@ where
     R: ResourceManage,
 {
     fn test_circle_collision_simple(&self, position: Vector2<f32>, radius: f32) -> bool {
-        let d = MINE_SIZE / 2. + radius + 1.;
+        let d = self.collision_radius() + radius;
         norm_squared(&(self.pos - position)) <= d * d
     }
 
-    fn on_collision_simple<A>(&mut self, ball: &mut A)
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue)
     where
         A: AnimatedObject,
     {
-        // TODO when doing sounds, reproduce something here
-        // TODO particles
         ball.damage(2.5);
+        events.push(GameEvent::MineHit(self.pos));
     }
 }
 
 pub const GEM_SIZE_W: f32 = 24.;
 pub const GEM_SIZE_H: f32 = 28.;
+const GEM_FRAMES: [&str; 4] = ["gem0", "gem1", "gem2", "gem3"];
+const GEM_FRAME_DURATION: f32 = 9.;
 
 pub struct Gem<R>
 where
     R: ResourceManage,
 {
     pos: Vector2<f32>,
-    gfx_img: GameTexture<R>,
+    gfx_img: AnimatedSprite<GameTexture<R>>,
     picked_up: bool,
 }
 
@@ -198,20 +411,51 @@ where
     }
 }
 
+impl<R> Bounded for Gem<R>
+where
+    R: ResourceManage,
+{
+    fn bounding_box(&self) -> [f32; 4] {
+        centered_box(self.pos, f32::max(GEM_SIZE_W, GEM_SIZE_H))
+    }
+}
+
 impl<R> Gem<R>
 where
     R: ResourceManage
 {
     pub fn new(info: GemInfo, resource_manager: R) -> Result<Self> {
-        let gfx_img = resource_manager.sprite().get_sprite(AssetId::Gem)?;
+        let gfx_img = AnimatedSprite::from_atlas(
+            &resource_manager.sprite(),
+            AssetId::Gem,
+            &GEM_FRAMES,
+            GEM_FRAME_DURATION,
+        )?;
         Ok(Gem {
-            pos: Vector2::new(info.pos[0] as f32, info.pos[1] as f32),
+            pos: vec_f32(info.pos),
             gfx_img,
             picked_up: false,
         })
     }
 
-    pub fn draw<G>(&self, ctx: Context, g: &mut G)
+    /// The radius of the circle in which the ball touches the gem.
+    pub fn collision_radius(&self) -> f32 {
+        GEM_SIZE_W / 2. + 1.
+    }
+
+    pub fn is_picked_up(&self) -> bool {
+        self.picked_up
+    }
+
+    pub fn set_picked_up(&mut self, picked_up: bool) {
+        self.picked_up = picked_up;
+    }
+
+    pub fn update(&mut self, factor: f32) {
+        self.gfx_img.update(factor);
+    }
+
+    pub fn draw<G>(&self, ctx: Context, palette: &Palette, g: &mut G)
     where
         G: Graphics<Texture=GameTexture<R>>
     {
@@ -231,7 +475,7 @@ where
         let ctx = ctx
             .trans(x_pos, y_pos)
             .scale(w_scale.into(), h_scale.into());
-        Image::new().draw(&self.gfx_img, &DrawState::default(), ctx.transform, g);
+        self.gfx_img.draw_tinted(palette.gem_tint, ctx.transform, g);
     }
 }
 
@@ -242,11 +486,11 @@ where
     fn test_circle_collision_simple(&self, position: Vector2<f32>, radius: f32) -> bool {
         if self.picked_up { return false; }
 
-        let d = GEM_SIZE_W / 2. + radius + 1.;
+        let d = self.collision_radius() + radius;
         norm_squared(&(self.pos - position)) <= d * d
     }
 
-    fn on_collision_simple<A>(&mut self, ball: &mut A)
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue)
     where
         A: AnimatedObject
     {
@@ -255,44 +499,129 @@ where
         let item = ();
         ball.pick_up(item);
         self.picked_up = true;
+        events.push(GameEvent::GemPickedUp(self.pos));
     }
 }
 
 pub const FINISH_SIZE: f32 = 24.;
+const FLAG_FRAMES: [&str; 4] = ["flag0", "flag1", "flag2", "flag3"];
+const FLAG_FRAME_DURATION: f32 = 8.;
+/// How long the flag flashes after refusing the ball, in ticks.
+const FLAG_FLASH_DURATION: f32 = 20.;
+/// How long the ball must stay away from the flag before it is refused
+/// again, in ticks.
+const REJECT_COOLDOWN: f32 = 60.;
 
 pub struct Finish<R>
 where
     R: ResourceManage,
 {
     pos: Vector2<f32>,
-    gfx_img: GameTexture<R>,
-    gfx_img_check: GameTexture<R>,
+    gfx_img: AnimatedSprite<GameTexture<R>>,
+    gfx_img_check: GameSprite<R>,
     picked_up: bool,
     gems_required: u32,
+    /// remaining ticks of the refusal flash
+    flash: f32,
+    /// ticks until the flag can refuse the ball again
+    reject_cooldown: f32,
 }
 
+impl<R> Positioned for Finish<R>
+where
+    R: ResourceManage,
+{
+    fn position(&self) -> Vector2<f32> {
+        self.pos
+    }
+}
+
+impl<R> Bounded for Finish<R>
+where
+    R: ResourceManage,
+{
+    fn bounding_box(&self) -> [f32; 4] {
+        centered_box(self.pos, FINISH_SIZE)
+    }
+}
 
 impl<R> Finish<R>
 where
     R: ResourceManage
 {
     pub fn new(info: FinishInfo, resource_manager: R) -> Result<Self> {
-        let gfx_img = resource_manager.sprite().get_sprite(AssetId::Flag)?;
-        let gfx_img_check = resource_manager.sprite().get_sprite(AssetId::Check)?;
+        let gfx_img = AnimatedSprite::from_atlas(
+            &resource_manager.sprite(),
+            AssetId::Flag,
+            &FLAG_FRAMES,
+            FLAG_FRAME_DURATION,
+        )?;
+        let gfx_img_check = resource_manager.sprite().get_sprite_region(AssetId::Check)?;
         Ok(Finish {
-            pos: Vector2::new(info.pos[0] as f32, info.pos[1] as f32),
+            pos: vec_f32(info.pos),
             gfx_img,
             gfx_img_check,
             picked_up: false,
             gems_required: info.gems_required,
+            flash: 0.,
+            reject_cooldown: 0.,
         })
     }
 
+    /// The radius of the circle in which the ball touches the flag.
+    pub fn collision_radius(&self) -> f32 {
+        FINISH_SIZE / 2. + 1.
+    }
+
     pub fn is_picked_up(&self) -> bool {
         self.picked_up
     }
 
-    pub fn draw<G>(&self, ctx: Context, g: &mut G)
+    /// Whether the flag can be reached while holding the given number of
+    /// gems. Gems beyond the required amount do not get in the way.
+    pub fn accepts(&self, items: u32) -> bool {
+        items >= self.gems_required
+    }
+
+    /// Mark the flag as reached, regardless of the gems held.
+    pub fn complete(&mut self) {
+        self.picked_up = true;
+    }
+
+    /// Bring the flag back to its initial state, not reached and not
+    /// refusing the ball.
+    pub fn reset(&mut self) {
+        self.picked_up = false;
+        self.flash = 0.;
+        self.reject_cooldown = 0.;
+    }
+
+    pub fn gems_required(&self) -> u32 {
+        self.gems_required
+    }
+
+    /// Refuse a ball touching the flag without enough gems, returning
+    /// whether the flag flashed. The flag only flashes again once the ball
+    /// has stayed away for a while.
+    pub fn reject(&mut self) -> bool {
+        let flashed = self.reject_cooldown <= 0.;
+        if flashed {
+            self.flash = FLAG_FLASH_DURATION;
+        }
+        self.reject_cooldown = REJECT_COOLDOWN;
+        flashed
+    }
+
+    pub fn update(&mut self, factor: f32) {
+        self.gfx_img.update(factor);
+        self.flash = f32::max(0., self.flash - factor);
+        self.reject_cooldown = f32::max(0., self.reject_cooldown - factor);
+    }
+
+    /// Draw the flag, blinking red while it refuses the ball. With a
+    /// `flash` intensity below 1 the blinking is toned down, and without
+    /// any the flag is outlined instead.
+    pub fn draw<G>(&self, ctx: Context, flash: f32, g: &mut G)
     where
         G: Graphics<Texture=GameTexture<R>>
     {
@@ -301,14 +630,25 @@ where
         let (w, h) = (w as f32, h as f32);
         let (hw, hh) = (w / 2., h / 2.);
 
+        if self.flash > 0. {
+            let size = FINISH_SIZE as f64 + 8.;
+            let r = [x - size / 2., y - size / 2., size, size];
+            if flash > 0. {
+                // blink red while refusing the ball
+                let alpha = if (self.flash / 4.) as u32 % 2 == 0 { 0.6 } else { 0.2 };
+                ellipse([1.0, 0.25, 0.2, alpha * flash], r, ctx.transform, g);
+            } else {
+                Ellipse::new_border([1.0, 0.25, 0.2, 0.8], 1.).draw(r, &ctx.draw_state, ctx.transform, g);
+            }
+        }
+
         let ctx = ctx
             .trans(x - hw as f64, y - hh as f64);
-        let img = if self.picked_up {
-            &self.gfx_img_check
+        if self.picked_up {
+            self.gfx_img_check.draw(ctx.transform, g);
         } else {
-            &self.gfx_img
-        };
-        Image::new().draw(img, &DrawState::default(), ctx.transform, g);
+            self.gfx_img.draw(ctx.transform, g);
+        }
     }
 }
 
@@ -318,16 +658,108 @@ where
 {
     fn test_circle_collision_simple(&self, position: Vector2<f32>, radius: f32) -> bool {
         if self.picked_up { return false; }
-        let d = FINISH_SIZE / 2. + radius + 1.;
+        let d = self.collision_radius() + radius;
         norm_squared(&(self.pos - position)) <= d * d
     }
 
-    fn on_collision_simple<A>(&mut self, ball: &mut A)
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue)
     where
         A: AnimatedObject
     {
-        if !self.picked_up && ball.items() == self.gems_required {
+        if self.picked_up {
+            return;
+        }
+        if self.accepts(ball.items()) {
             self.picked_up = true;
+            events.push(GameEvent::FinishReached);
+        } else if self.reject() {
+            events.push(GameEvent::FinishRejected);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use resource::{NullAudioManager, NullResourceManager, NullSpriteManager};
+
+    /// A ball which only holds a number of items.
+    struct Holder {
+        items: u32,
+    }
+
+    impl AnimatedObject for Holder {
+        fn velocity(&self) -> Vector2<f32> {
+            Vector2::zeros()
+        }
+        fn issue_bounce(&mut self, _: Vector2<f32>, _: Vector2<f32>) {}
+        fn correct_and_flip_x(&mut self, _: f32) {}
+        fn correct_and_flip_y(&mut self, _: f32) {}
+        fn add_velocity(&mut self, _: Vector2<f32>) {}
+        fn add_position(&mut self, _: Vector2<f32>) {}
+        fn damage(&mut self, _: f32) {}
+        fn heal(&mut self, _: f32) {}
+        fn pick_up(&mut self, _: ()) {
+            self.items += 1;
+        }
+        fn items(&self) -> u32 {
+            self.items
         }
     }
+
+    /// Null resources with the sprites of the finish flag loaded.
+    fn null_resources() -> NullResourceManager {
+        let mut sprite = NullSpriteManager::new(()).unwrap();
+        sprite
+            .new_atlas_from_path(AssetId::Flag, "assets/flag-sheet.png", "assets/flag-sheet.json")
+            .unwrap();
+        sprite.new_sprite_from_path(AssetId::Check, "assets/check.png").unwrap();
+        NullResourceManager::new(sprite, NullAudioManager::new(()).unwrap())
+    }
+
+    fn finish(res: &NullResourceManager, gems_required: u32) -> Finish<&NullResourceManager> {
+        let info = FinishInfo {
+            pos: Vector2::new(100, 50),
+            gems_required,
+        };
+        Finish::new(info, res).unwrap()
+    }
+
+    #[test]
+    fn finish_accepts_enough_gems() {
+        let res = null_resources();
+        let flag = finish(&res, 3);
+        assert!(!flag.accepts(0));
+        assert!(!flag.accepts(2));
+        assert!(flag.accepts(3));
+        assert!(flag.accepts(4));
+        assert!(flag.accepts(u32::max_value()));
+    }
+
+    #[test]
+    fn finish_without_gems_accepts_anyone() {
+        let res = null_resources();
+        let flag = finish(&res, 0);
+        assert!(flag.accepts(0));
+        assert!(flag.accepts(5));
+    }
+
+    #[test]
+    fn finish_refuses_the_ball_until_it_holds_the_gems() {
+        let res = null_resources();
+        let mut flag = finish(&res, 2);
+        let mut ball = Holder { items: 1 };
+        let mut events = EventQueue::new();
+        flag.on_collision_simple(&mut ball, &mut events);
+        // touching again right away does not flash the flag once more
+        flag.on_collision_simple(&mut ball, &mut events);
+        assert_eq!(events, vec![GameEvent::FinishRejected]);
+        assert!(!flag.is_picked_up());
+
+        ball.pick_up(());
+        events.clear();
+        flag.on_collision_simple(&mut ball, &mut events);
+        assert_eq!(events, vec![GameEvent::FinishReached]);
+        assert!(flag.is_picked_up());
+    }
 }
@@ -0,0 +1,99 @@
+//! Module for gameplay events, which entities report as things happen to
+//! the ball so that the controller can react to them in one place, after
+//! the physics step.
+
+use na::Vector2;
+
+/// Something that happened during a physics step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    /// a gem at the given position was picked up
+    GemPickedUp(Vector2<f32>),
+    /// the ball touched the mine at the given position
+    MineHit(Vector2<f32>),
+    /// the pump at the given position inflated the ball
+    PumpUsed(Vector2<f32>),
+    /// the boost pad at the given position flung the ball
+    BoostUsed(Vector2<f32>),
+    /// the finish flag accepted the ball
+    FinishReached,
+    /// the finish flag refused the ball for not holding enough gems
+    FinishRejected,
+    /// the ball hit something at the given position, at the given speed
+    /// along the collision normal
+    Impact { pos: Vector2<f32>, speed: f32 },
+}
+
+/// The events of a step, in the order in which they happened.
+pub type EventQueue = Vec<GameEvent>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{GameController, GameOptions, Thrust};
+    use level::info::{FinishInfo, GemInfo, WallInfo};
+    use level::GameLevel;
+    use resource::{NullAudioManager, NullResourceManager, NullSpriteManager};
+
+    /// A corridor with the finish flag, a gem past it and a wall at the
+    /// end, all on the ball's way to the right.
+    fn corridor_level() -> GameLevel {
+        let mut level = GameLevel::default();
+        level.set_ball_position(Vector2::new(36., 100.));
+        level.set_finish_flag(FinishInfo {
+            pos: [100, 100].into(),
+            gems_required: 1,
+        });
+        level.gems_mut().push(GemInfo { pos: [180, 100].into() });
+        level.walls_mut().push(WallInfo {
+            pos: [250, 0].into(),
+            dim: [20, 200].into(),
+            texture_id: 0,
+            rotation: 0,
+            flip: false,
+            tile: false,
+        });
+        level
+    }
+
+    #[test]
+    fn scripted_run_reports_events_in_order() {
+        let sprite = NullSpriteManager::new(()).unwrap();
+        let res = NullResourceManager::new(sprite, NullAudioManager::new(()).unwrap());
+        let mut game = GameController::new(corridor_level(), &res, GameOptions::default()).unwrap();
+        let mut log = EventQueue::new();
+        for tick in 0..600 {
+            // push right for a while, then let the ball roll to the wall
+            // and back
+            game.ball.set_thrust(Thrust {
+                right: tick < 60,
+                ..Thrust::default()
+            });
+            game.simulate(1.);
+            log.extend(game.events.drain(..));
+            if log.last() == Some(&GameEvent::FinishReached) {
+                break;
+            }
+        }
+
+        let kinds: Vec<_> = log
+            .iter()
+            .map(|e| match *e {
+                GameEvent::Impact { .. } => "impact",
+                GameEvent::FinishRejected => "rejected",
+                GameEvent::GemPickedUp(_) => "gem",
+                GameEvent::FinishReached => "reached",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, vec!["rejected", "gem", "impact", "reached"]);
+        assert_eq!(log[1], GameEvent::GemPickedUp(Vector2::new(180., 100.)));
+        match log[2] {
+            GameEvent::Impact { pos, speed } => {
+                assert!(pos[0] < 250. && (pos[1] - 100.).abs() < 1., "{:?}", pos);
+                assert!(speed > 0.);
+            }
+            ref e => panic!("expected an impact, got {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Module for leaks: areas which slowly deflate the ball.
+
+use graphics::{ellipse, Context, Ellipse, Graphics};
+use na::{norm_squared, Vector2};
+use level::info::LeakInfo;
+use physics::{centered_box, AnimatedObject, Bounded, Positioned};
+use util::vec_f32;
+use super::palette::{draw_circle_stripes, Palette};
+
+/// The size lost by the ball in each tick while in a leak.
+pub const LEAK_RATE: f32 = 0.02;
+/// How fast the leak pulses, in radians per tick.
+const PULSE_SPEED: f32 = 0.1;
+/// How much the leak's opacity changes as it pulses.
+const PULSE_AMOUNT: f32 = 0.5;
+
+pub struct Leak {
+    pos: Vector2<f32>,
+    radius: f32,
+    /// the phase of the pulse animation
+    phase: f32,
+}
+
+impl Leak {
+    pub fn new(info: LeakInfo) -> Self {
+        Leak {
+            pos: vec_f32(info.pos),
+            radius: info.radius,
+            phase: 0.,
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// Check whether a circle overlaps the leak.
+    pub fn overlaps(&self, position: Vector2<f32>, radius: f32) -> bool {
+        let d = self.radius + radius;
+        norm_squared(&(self.pos - position)) <= d * d
+    }
+
+    /// Deflate the ball for the given number of ticks.
+    pub fn drain<A: AnimatedObject>(&self, ball: &mut A, ticks: f32) {
+        ball.damage(LEAK_RATE * ticks);
+    }
+
+    pub fn update(&mut self, ticks: f32) {
+        self.phase += PULSE_SPEED * ticks;
+    }
+
+    pub fn draw<G: Graphics>(&self, ctx: Context, palette: &Palette, g: &mut G) {
+        let pulse = 1. + self.phase.sin() * PULSE_AMOUNT;
+        let mut color = palette.leak;
+        color[3] *= pulse;
+        let size = f64::from(self.radius) * 2.;
+        let r = [
+            f64::from(self.pos[0]) - size / 2.,
+            f64::from(self.pos[1]) - size / 2.,
+            size,
+            size,
+        ];
+        ellipse(color, r, ctx.transform, g);
+        if let Some(color) = palette.hazard_stripes {
+            draw_circle_stripes(color, self.pos, self.radius, ctx.transform, g);
+        }
+        let border = palette.hazard_outline.unwrap_or(palette.leak_border);
+        Ellipse::new_border(border, 0.5).draw(r, &ctx.draw_state, ctx.transform, g);
+    }
+}
+
+impl Positioned for Leak {
+    fn position(&self) -> Vector2<f32> {
+        self.pos
+    }
+}
+
+impl Bounded for Leak {
+    fn bounding_box(&self) -> [f32; 4] {
+        centered_box(self.pos, self.radius * 2.)
+    }
+}
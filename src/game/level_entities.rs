@@ -0,0 +1,206 @@
+//! Module for turning the information of a level into its entities, as
+//! needed by both the game and the level editor.
+
+use level::GameLevel;
+use resource::{ResourceManage, Result};
+use resource::sprite::{load_base_sprites, load_level_textures};
+use super::decoration::Decoration;
+use super::entities::{Boost, Finish, Gem, Mine, Pump};
+use super::leak::Leak;
+use super::spikes::SpikeStrip;
+use super::water::Water;
+
+/// The entities of a level, in the same order as the level's objects.
+///
+/// Walls are not included: the game only creates those around the camera,
+/// while the editor creates them all at once.
+pub struct LevelEntities<R>
+where
+    R: ResourceManage,
+{
+    pub spikes: Vec<SpikeStrip>,
+    pub pumps: Vec<Pump<R>>,
+    pub boosts: Vec<Boost<R>>,
+    pub leaks: Vec<Leak>,
+    pub mines: Vec<Mine<R>>,
+    pub gems: Vec<Gem<R>>,
+    pub finish: Option<Finish<R>>,
+    pub water: Vec<Water>,
+    pub decorations: Vec<Decoration<R>>,
+}
+
+impl<R> LevelEntities<R>
+where
+    R: ResourceManage + Copy,
+{
+    /// Create the entities of the given level. The level's assets must have
+    /// been loaded beforehand, see `load_assets`.
+    pub fn new(level: &GameLevel, resource_manager: R) -> Result<Self> {
+        let mut entities = LevelEntities::empty();
+        while entities.create_next(level, resource_manager)? {}
+        Ok(entities)
+    }
+
+    /// Create the next entity of the given level which is still missing,
+    /// so that a big level can be created a few entities at a time. Returns
+    /// whether there was one left to create.
+    pub fn create_next(&mut self, level: &GameLevel, resource_manager: R) -> Result<bool> {
+        if let Some(info) = level.spikes().get(self.spikes.len()) {
+            self.spikes.push(SpikeStrip::new(info.clone()));
+        } else if let Some(info) = level.pumps().get(self.pumps.len()) {
+            self.pumps.push(Pump::new(info.clone(), resource_manager)?);
+        } else if let Some(info) = level.boosts().get(self.boosts.len()) {
+            self.boosts.push(Boost::new(info.clone(), resource_manager)?);
+        } else if let Some(info) = level.leaks().get(self.leaks.len()) {
+            self.leaks.push(Leak::new(info.clone()));
+        } else if let Some(info) = level.mines().get(self.mines.len()) {
+            self.mines.push(Mine::new(info.clone(), resource_manager)?);
+        } else if let Some(info) = level.gems().get(self.gems.len()) {
+            self.gems.push(Gem::new(info.clone(), resource_manager)?);
+        } else if let (Some(info), None) = (level.finish_flag(), self.finish.as_ref()) {
+            self.finish = Some(Finish::new(info.clone(), resource_manager)?);
+        } else if let Some(info) = level.water().get(self.water.len()) {
+            self.water.push(Water::new(info.clone()));
+        } else if let Some(info) = level.decorations().get(self.decorations.len()) {
+            self.decorations.push(Decoration::new(info.clone(), resource_manager)?);
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+impl<R> LevelEntities<R>
+where
+    R: ResourceManage,
+{
+    /// A collection without any entities, to be filled with `create_next`.
+    pub fn empty() -> Self {
+        LevelEntities {
+            spikes: Vec::new(),
+            pumps: Vec::new(),
+            boosts: Vec::new(),
+            leaks: Vec::new(),
+            mines: Vec::new(),
+            gems: Vec::new(),
+            finish: None,
+            water: Vec::new(),
+            decorations: Vec::new(),
+        }
+    }
+
+    /// The number of entities which the given level has, walls aside.
+    pub fn count(level: &GameLevel) -> usize {
+        level.spikes().len()
+            + level.pumps().len()
+            + level.boosts().len()
+            + level.leaks().len()
+            + level.mines().len()
+            + level.gems().len()
+            + level.finish_flag().map_or(0, |_| 1)
+            + level.water().len()
+            + level.decorations().len()
+    }
+
+    /// The number of entities created so far.
+    pub fn created(&self) -> usize {
+        self.spikes.len()
+            + self.pumps.len()
+            + self.boosts.len()
+            + self.leaks.len()
+            + self.mines.len()
+            + self.gems.len()
+            + self.finish.as_ref().map_or(0, |_| 1)
+            + self.water.len()
+            + self.decorations.len()
+    }
+
+    /// Load the sprites shared by all levels and the textures of the given
+    /// level.
+    pub fn load_assets(level: &GameLevel, resource_manager: R) -> Result<()> {
+        let mut sprite = resource_manager.sprite();
+        load_base_sprites(&mut sprite)?;
+        load_level_textures(&mut sprite, level.textures())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::Vector2;
+    use physics::Positioned;
+    use resource::{NullAudioManager, NullResourceManager, NullSpriteManager};
+    use serde_json;
+
+    /// A level with every kind of entity.
+    const FIXTURE: &str = r#"{
+        "name": "Fixture",
+        "version": "1.1",
+        "map": {"width": 640, "height": 400},
+        "ball_pos": [36, 36],
+        "walls": [{"pos": [0, 380], "dim": [640, 20]}],
+        "spikes": [{"pos": [200, 370], "dim": [60, 10], "facing": "up"}],
+        "pumps": [{"pos": [100, 100]}, {"pos": [140, 100], "charges": 2}],
+        "boosts": [{"pos": [300, 200], "direction": [0, -1]}],
+        "leaks": [{"pos": [400, 100]}],
+        "mines": [{"pos": [500, 300]}],
+        "gems": [{"pos": [120, 300]}, {"pos": [320, 300]}, {"pos": [520, 60]}],
+        "finish": {"pos": [600, 340], "gems_required": 2},
+        "water": [{"pos": [0, 300], "dim": [100, 80]}],
+        "decorations": [{"pos": [50, 50], "texture_id": 1, "layer": "front"}]
+    }"#;
+
+    fn null_resources() -> NullResourceManager {
+        NullResourceManager::new(NullSpriteManager::new(()).unwrap(), NullAudioManager::new(()).unwrap())
+    }
+
+    #[test]
+    fn entities_follow_the_level() {
+        let level: GameLevel = serde_json::from_str(FIXTURE).unwrap();
+        let res = null_resources();
+        LevelEntities::load_assets(&level, &res).unwrap();
+        let entities = LevelEntities::new(&level, &res).unwrap();
+
+        assert_eq!(LevelEntities::<&NullResourceManager>::count(&level), 12);
+        assert_eq!(entities.created(), 12);
+        assert_eq!(entities.spikes.len(), 1);
+        assert_eq!(entities.boosts.len(), 1);
+        assert_eq!(entities.leaks.len(), 1);
+        assert_eq!(entities.mines.len(), 1);
+        assert_eq!(entities.water.len(), 1);
+        assert_eq!(entities.decorations.len(), 1);
+        // in the same order as the level's objects
+        let pumps: Vec<_> = entities.pumps.iter().map(|p| p.position()).collect();
+        assert_eq!(pumps, vec![Vector2::new(100., 100.), Vector2::new(140., 100.)]);
+        let gems: Vec<_> = entities.gems.iter().map(|g| g.position()).collect();
+        assert_eq!(
+            gems,
+            vec![Vector2::new(120., 300.), Vector2::new(320., 300.), Vector2::new(520., 60.)]
+        );
+        let finish = entities.finish.as_ref().unwrap();
+        assert_eq!(finish.position(), Vector2::new(600., 340.));
+        assert_eq!(finish.gems_required(), 2);
+    }
+
+    #[test]
+    fn entities_can_be_created_a_few_at_a_time() {
+        let level: GameLevel = serde_json::from_str(FIXTURE).unwrap();
+        let res = null_resources();
+        LevelEntities::load_assets(&level, &res).unwrap();
+        let mut entities = LevelEntities::empty();
+        let mut created = 0;
+        while entities.create_next(&level, &res).unwrap() {
+            created += 1;
+            assert_eq!(entities.created(), created);
+        }
+        assert_eq!(created, 12);
+        assert!(!entities.create_next(&level, &res).unwrap());
+    }
+
+    #[test]
+    fn entities_need_their_assets() {
+        let level: GameLevel = serde_json::from_str(FIXTURE).unwrap();
+        let res = null_resources();
+        assert!(LevelEntities::new(&level, &res).is_err());
+    }
+}
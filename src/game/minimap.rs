@@ -0,0 +1,118 @@
+//! Module for the minimap overlay, which shows the whole map at a glance.
+
+use graphics::{ellipse, rectangle, Context, Graphics, Rectangle, Transformed};
+use na::Vector2;
+use level::GameLevel;
+
+/// The length of the minimap's longest side on the screen, in pixels.
+pub const MINIMAP_SIZE: f64 = 160.;
+/// The distance between the minimap and the corner of the screen, in pixels.
+const MINIMAP_MARGIN: f64 = 8.;
+/// The size of the dots representing objects, in pixels.
+const DOT_SIZE: f64 = 4.;
+
+pub const GEM_COLOR: [f32; 4] = [0.9, 0.3, 0.8, 1.0];
+pub const MINE_COLOR: [f32; 4] = [1.0, 0.3, 0.2, 1.0];
+pub const PUMP_COLOR: [f32; 4] = [1.0, 1.0, 0.4, 1.0];
+pub const EXHAUSTED_PUMP_COLOR: [f32; 4] = [0.5, 0.5, 0.4, 0.6];
+pub const FINISH_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// A minimap of a level. The static geometry is scaled once on creation,
+/// and needs to be rebuilt whenever the level's walls or dimensions change.
+#[derive(Debug, Clone)]
+pub struct Minimap {
+    /// the scale from map units to screen pixels
+    scale: f64,
+    /// the dimensions of the minimap on the screen
+    dim: [f64; 2],
+    /// the walls, already scaled to the minimap
+    walls: Vec<[f64; 4]>,
+    visible: bool,
+}
+
+impl Minimap {
+    pub fn new(level: &GameLevel) -> Self {
+        let mut minimap = Minimap {
+            scale: 1.,
+            dim: [0., 0.],
+            walls: Vec::new(),
+            visible: false,
+        };
+        minimap.rebuild(level);
+        minimap
+    }
+
+    /// Recompute the minimap's static geometry from the given level.
+    pub fn rebuild(&mut self, level: &GameLevel) {
+        let map_dim = level.map().dimensions_f32();
+        let (w, h) = (f64::from(map_dim[0]), f64::from(map_dim[1]));
+        self.scale = MINIMAP_SIZE / f64::max(1., f64::max(w, h));
+        self.dim = [w * self.scale, h * self.scale];
+        let scale = self.scale;
+        self.walls = level
+            .walls()
+            .iter()
+            .map(|w| {
+                [
+                    f64::from(w.pos[0]) * scale,
+                    f64::from(w.pos[1]) * scale,
+                    f64::from(w.dim[0]) * scale,
+                    f64::from(w.dim[1]) * scale,
+                ]
+            })
+            .collect();
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Draw the minimap at the top-right corner of the screen, if visible.
+    /// `markers` are the dynamic objects to show as colored dots, and the
+    /// view is the camera's position and dimensions in map units.
+    pub fn draw<G, I>(&self, ctx: Context, markers: I, ball_pos: Vector2<f32>, view: (Vector2<f32>, Vector2<f32>), g: &mut G)
+    where
+        G: Graphics,
+        I: IntoIterator<Item = (Vector2<f32>, [f32; 4])>,
+    {
+        if !self.visible {
+            return;
+        }
+
+        let window_size = ctx.viewport.map(|v| v.window_size).unwrap_or([0, 0]);
+        let x = f64::from(window_size[0]) - self.dim[0] - MINIMAP_MARGIN;
+        let ctx = ctx.trans(x, MINIMAP_MARGIN);
+
+        rectangle([0., 0., 0., 0.6], [0., 0., self.dim[0], self.dim[1]], ctx.transform, g);
+        for r in &self.walls {
+            rectangle([0.15, 0.16, 0.2, 0.9], *r, ctx.transform, g);
+        }
+        for (pos, color) in markers {
+            self.dot(ctx, pos, color, g);
+        }
+        self.dot(ctx, ball_pos, [0.6, 0.95, 1.0, 1.0], g);
+
+        let (view_pos, view_dim) = view;
+        let r = [
+            f64::from(view_pos[0]) * self.scale,
+            f64::from(view_pos[1]) * self.scale,
+            f64::from(view_dim[0]) * self.scale,
+            f64::from(view_dim[1]) * self.scale,
+        ];
+        Rectangle::new_border([1., 1., 1., 0.8], 0.5).draw(r, &ctx.draw_state, ctx.transform, g);
+    }
+
+    fn dot<G: Graphics>(&self, ctx: Context, pos: Vector2<f32>, color: [f32; 4], g: &mut G) {
+        let r = [
+            f64::from(pos[0]) * self.scale - DOT_SIZE / 2.,
+            f64::from(pos[1]) * self.scale - DOT_SIZE / 2.,
+            DOT_SIZE,
+            DOT_SIZE,
+        ];
+        ellipse(color, r, ctx.transform, g);
+    }
+}
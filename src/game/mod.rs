@@ -1,21 +1,168 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::path::PathBuf;
 use graphics::character::CharacterCache;
-use graphics::{clear, Context, Graphics, Transformed};
+use graphics::{rectangle, Context, DrawState, Ellipse, Graphics, Text, Transformed};
+use na::{norm, Vector2};
 use piston::input::{GenericEvent, UpdateArgs};
 
+pub mod animation;
+pub mod background;
 pub mod ball;
+pub mod border;
+pub mod debug;
+pub mod decoration;
 pub mod entities;
+pub mod events;
 pub mod items;
+pub mod leak;
+pub mod level_entities;
+pub mod minimap;
+pub mod palette;
+pub mod particles;
+pub mod photo;
+pub mod practice;
+pub mod replay;
 pub mod scene;
+pub mod sectors;
+pub mod spikes;
+pub mod timer;
 pub mod wall;
+pub mod water;
 
+use self::background::Background;
 use self::ball::*;
+use self::debug::DebugOverlay;
+use self::decoration::Decoration;
 use self::entities::*;
+use self::events::{EventQueue, GameEvent};
+use self::leak::Leak;
+use self::level_entities::LevelEntities;
+use self::minimap::{Minimap, EXHAUSTED_PUMP_COLOR, FINISH_COLOR, PUMP_COLOR};
+use self::palette::Palette;
+use self::particles::ParticleSystem;
+use self::photo::PhotoMode;
+use self::practice::{SaveSlots, SaveState};
+use self::replay::{Replay, ReplayPlayer};
 use self::scene::Scene;
+use self::sectors::SectorScene;
+use self::spikes::SpikeStrip;
+use self::timer::{draw_medal, format_time, SpeedrunTimer};
 use self::wall::Wall;
+use self::water::Water;
+use achievements::{collected_all_gems, Achievement, AchievementTracker, RunEnd};
 use camera::*;
-use controller::{Controller, ControllerAction};
+#[cfg(feature = "dev-console")]
+use console::{Command, Console, ConsoleInput, SpawnKind};
+use controller::{Controller, ControllerAction, GameState, LevelId};
+use consts::{HEIGHT, WIDTH};
+use physics::{
+    centered_box, resolve_circle_circle, AnimatedObject, Bounded, Circle, Positioned,
+    SimpleCollidable,
+};
+use profile::FrameStats;
+use progress::Progress;
+use lang::{tr, tr_fmt};
 use level::GameLevel;
-use resource::{GameTexture, ResourceManage, Result, SpriteAssetId, SpriteManage};
+use level::info::{DecorationLayer, Goal, WallInfo};
+#[cfg(feature = "dev-console")]
+use level::info::{GemInfo, LeakInfo, MineInfo, PumpInfo};
+use resource::{GameTexture, ResourceManage, Result, SpriteAssetId, SpriteLease, SpriteManage};
+use resource::manifest::BASE_SPRITES;
+use resource::sprite::free_level_textures;
+use settings::{EffectsIntensity, Settings};
+use util::{default_vector2, rect_intersects, WATCH_INTERVAL};
+#[cfg(feature = "dev-console")]
+use util::vec_i32;
+
+/// The minimum wall impact speed which shakes the screen.
+const IMPACT_SHAKE_THRESHOLD: f32 = 2.;
+/// The maximum screen shake magnitude, in pixels.
+const MAX_SHAKE: f32 = 6.;
+/// Duration of the ball's death animation, in ticks.
+const DEATH_DURATION: f32 = 30.;
+/// Duration of the ball being absorbed by the finish flag, in ticks.
+const FINISH_DURATION: f32 = 30.;
+/// How much the camera zooms out per unit of ball speed.
+const SPEED_ZOOM_OUT: f32 = 0.05;
+/// How far ahead of the ball the camera looks, per unit of ball velocity.
+pub const LOOK_AHEAD: f32 = 10.;
+/// The fraction of the remaining look-ahead difference covered in each
+/// tick.
+const LOOK_AHEAD_EASING: f32 = 0.08;
+/// The longest time between both players touching the finish flag for the
+/// level to be completed in co-op, in seconds.
+const COOP_FINISH_WINDOW: f32 = 1.;
+/// The space kept around both players when framing them in co-op.
+const COOP_FRAME_MARGIN: f32 = 160.;
+/// The color marking the second player's ball.
+const PLAYER2_COLOR: [f32; 4] = [1.0, 0.8, 0.3, 0.9];
+/// How long messages stay on the screen, in ticks.
+const NOTICE_DURATION: f32 = 120.;
+/// How long each unlocked achievement is announced, in ticks.
+const TOAST_DURATION: f32 = 240.;
+/// The duration of a single simulation step, in seconds.
+const PHYSICS_STEP: f64 = 1. / 120.;
+/// The longest time simulated in a single update, in seconds. Longer
+/// hitches slow the game down instead of producing huge steps.
+const MAX_FRAME_TIME: f64 = 0.05;
+/// How far beyond the camera's view objects are still drawn, to account for
+/// sprites which are slightly larger than their bounding boxes.
+const CULL_MARGIN: f32 = 16.;
+/// How far around the camera's view and the balls walls are created, so
+/// that they exist by the time they are seen or touched.
+const ACTIVATION_MARGIN: f32 = 128.;
+
+/// One of the players, in co-op mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Player {
+    One,
+    Two,
+}
+
+/// The gameplay modifiers chosen for the session, which apply to every
+/// level played.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct GameOptions {
+    /// whether the ball keeps shrinking over time, with its records kept
+    /// apart from the normal ones
+    pub hard_mode: bool,
+    /// whether the state of the run can be saved and restored, in which
+    /// case nothing is recorded in the player's progress
+    pub practice: bool,
+}
+
+impl GameOptions {
+    /// How much the ball shrinks per tick regardless of thrust.
+    fn constant_deflation(&self) -> f32 {
+        if self.hard_mode {
+            HARD_MODE_DEFLATE_RATE
+        } else {
+            0.
+        }
+    }
+}
+
+/// The phase of a game, which determines what is updated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamePhase {
+    Playing,
+    /// The ball died, with the remaining ticks of its death animation.
+    Dying(f32),
+    /// The level was completed, with the remaining ticks of the finish
+    /// animation.
+    Finished(f32),
+}
+
+impl GamePhase {
+    /// Whether the game is over and its closing animation is complete.
+    pub fn is_over(&self) -> bool {
+        match *self {
+            GamePhase::Playing => false,
+            GamePhase::Dying(t) | GamePhase::Finished(t) => t <= 0.,
+        }
+    }
+}
 
 pub struct GameController<R>
 where
@@ -23,13 +170,83 @@ where
 {
     level: GameLevel,
     ball: BallController<R>,
+    /// the second player's ball, in co-op mode
+    ball2: Option<BallController<R>>,
+    /// when each player last touched the finish flag in co-op mode
+    finish_touches: [Option<f32>; 2],
+    background: Background<R>,
     camera: Camera,
     res: R,
-    walls: Scene<Wall<R>>,
+    /// keeps the sprites shared by all levels loaded
+    _sprites: SpriteLease<R>,
+    walls: SectorScene<WallInfo, Wall<R>>,
+    spikes: Scene<SpikeStrip>,
     pumps: Vec<Pump<R>>,
+    boosts: Vec<Boost<R>>,
+    leaks: Vec<Leak>,
     mines: Scene<Mine<R>>,
     gems: Scene<Gem<R>>,
     finish: Option<Finish<R>>,
+    water: Vec<Water>,
+    decorations: Vec<Decoration<R>>,
+    particles: ParticleSystem,
+    /// what happened in the current step, handled once the physics are done
+    events: EventQueue,
+    phase: GamePhase,
+    /// the smoothed offset from the ball to the camera's focus point
+    look_ahead: Vector2<f32>,
+    minimap: Minimap,
+    /// the file which the level was loaded from, if any
+    path: Option<PathBuf>,
+    /// simulation time not yet consumed by a step, in seconds
+    time_accumulator: f64,
+    /// how much faster than real time the simulation runs
+    time_scale: f64,
+    /// the replay being recorded, if any
+    recording: Option<Replay>,
+    /// the replay being played back, if any
+    playback: Option<ReplayPlayer>,
+    /// the last replay recorded or played
+    last_replay: Option<Replay>,
+    /// the current run, which may become the best run, unless it is a
+    /// replay being played back
+    run: Option<Replay>,
+    /// the number of simulation steps played so far
+    step_count: u32,
+    /// the best run on this level, shown as a ghost ball
+    ghost: Option<Replay>,
+    show_ghost: bool,
+    timer: SpeedrunTimer,
+    /// a message for the player, with its remaining ticks on the screen
+    notice: Option<(String, f32)>,
+    /// what was done towards the achievements in this run
+    achievements: AchievementTracker,
+    /// the achievements unlocked and not yet announced, with the remaining
+    /// ticks of the first one on the screen
+    toasts: VecDeque<(&'static Achievement, f32)>,
+    /// the level which follows this one, if any
+    next_level: Option<LevelId>,
+    options: GameOptions,
+    /// the saved states of the run, in practice mode
+    save_slots: SaveSlots,
+    /// whether either shift key is held
+    shift: bool,
+    /// the time since sprite files were last checked for changes, in
+    /// seconds
+    watch_time: f64,
+    debug: DebugOverlay,
+    #[cfg(feature = "dev-console")]
+    console: Console,
+    /// the colors in which the ball and the level's objects are drawn
+    palette: &'static Palette,
+    /// how strongly screen shake, flashes and particles are shown
+    effects: EffectsIntensity,
+    /// the state of photo mode, while the game stands still and the camera
+    /// moves freely
+    photo: Option<PhotoMode>,
+    /// the number of window pixels per pixel of the display, as of the last
+    /// frame
+    hires_scale: Cell<f32>,
 }
 
 /// Game level controller.
@@ -37,103 +254,1041 @@ impl<R> GameController<R>
 where
     R: ResourceManage + Copy,
 {
-    pub fn new(level: GameLevel, resource_manager: R) -> Result<Self> {
-        GameController::load_base_assets(resource_manager)?;
+    pub fn new(level: GameLevel, resource_manager: R, options: GameOptions) -> Result<Self> {
+        LevelEntities::load_assets(&level, resource_manager)?;
+        let entities = LevelEntities::new(&level, resource_manager)?;
+        GameController::with_entities(level, entities, resource_manager, options)
+    }
+
+    /// Create a game from the level's entities, which were created
+    /// separately after loading the level's assets.
+    pub fn with_entities(
+        level: GameLevel,
+        entities: LevelEntities<R>,
+        resource_manager: R,
+        options: GameOptions,
+    ) -> Result<Self> {
+        let sprites = SpriteLease::new(resource_manager, &BASE_SPRITES);
 
-        let ball = Ball::with_default_size(level.ball_position());
-        let ball = BallController::new(ball, resource_manager)?;
+        let ball = Ball::new(level.ball_position(), level.ball_size().unwrap_or(BALL_DEFAULT_SIZE));
+        let mut ball = BallController::new(ball, level.ball_params(), resource_manager)?;
+        ball.set_constant_deflation(options.constant_deflation());
         let mut camera = Camera::default();
         camera.focus_on(level.ball_position(), level.map().dimensions_f32());
+        let background = Background::new(&level, resource_manager);
 
-        let walls: Result<Vec<_>> = level
-            .walls()
-            .iter()
-            .map(|info| Wall::new(info.clone(), resource_manager))
-            .collect();
+        // group walls by texture to minimize texture switches when drawing,
+        // only creating those around the camera and the balls
+        let walls = wall_scene(&level);
 
-        let pumps: Result<Vec<_>> = level
-            .pumps()
-            .iter()
-            .map(|info| Pump::new(info.clone(), resource_manager))
-            .collect();
+        let minimap = Minimap::new(&level);
+        let level_name = level.name().to_string();
+        let timer = SpeedrunTimer::new(Progress::load_or_default().level(&level_name, options.hard_mode));
+        let settings = Settings::load_or_default();
 
-        let mines: Result<Vec<_>> = level
-            .mines()
+        let mut game = GameController {
+            level,
+            ball,
+            ball2: None,
+            finish_touches: [None, None],
+            background,
+            camera,
+            res: resource_manager,
+            _sprites: sprites,
+            walls,
+            spikes: Scene::from_objects(entities.spikes),
+            mines: Scene::from_objects(entities.mines),
+            pumps: entities.pumps,
+            boosts: entities.boosts,
+            leaks: entities.leaks,
+            gems: Scene::from_objects(entities.gems),
+            finish: entities.finish,
+            water: entities.water,
+            decorations: entities.decorations,
+            particles: ParticleSystem::new(),
+            events: EventQueue::new(),
+            phase: GamePhase::Playing,
+            look_ahead: default_vector2(),
+            minimap,
+            path: None,
+            time_accumulator: 0.,
+            time_scale: 1.,
+            recording: None,
+            playback: None,
+            last_replay: None,
+            // practice runs do not count towards the best run
+            run: if options.practice {
+                None
+            } else {
+                Some(Replay::new(level_name, options.hard_mode))
+            },
+            step_count: 0,
+            ghost: None,
+            show_ghost: true,
+            timer,
+            notice: None,
+            achievements: AchievementTracker::new(&Progress::load_or_default()),
+            toasts: VecDeque::new(),
+            next_level: None,
+            options,
+            save_slots: SaveSlots::default(),
+            shift: false,
+            watch_time: 0.,
+            debug: DebugOverlay::default(),
+            #[cfg(feature = "dev-console")]
+            console: Console::default(),
+            palette: settings.palette.palette(),
+            effects: settings.effects,
+            photo: None,
+            hires_scale: Cell::new(1.),
+        };
+        game.camera.set_shake_intensity(game.effects.shake_factor());
+        game.particles.set_density(game.effects.particle_factor());
+        game.ball.set_flash_intensity(game.effects.flash_factor());
+        game.activate_walls()?;
+        Ok(game)
+    }
+
+    /// Record the file which the level was loaded from, so that it can be
+    /// opened in the editor.
+    pub fn with_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        let path = path.into();
+        self.ghost = Replay::load(Replay::best_path_for_level(&path, self.options.hard_mode)).ok();
+        self.path = Some(path);
+        self
+    }
+
+    /// Set the level to continue to once this one is completed.
+    pub fn with_next_level(mut self, next_level: Option<LevelId>) -> Self {
+        self.next_level = next_level;
+        self
+    }
+
+    /// Create the walls around the camera's view and the balls, dropping
+    /// those which have been far from them for a while.
+    fn activate_walls(&mut self) -> Result<()> {
+        let margin = ACTIVATION_MARGIN;
+        let pos = self.camera.position();
+        let view = self.camera.view();
+        let mut rects = vec![[pos[0] - margin, pos[1] - margin, view[0] + margin * 2., view[1] + margin * 2.]];
+        for ball in Some(&self.ball).into_iter().chain(self.ball2.as_ref()) {
+            let p = ball.position();
+            rects.push([p[0] - margin, p[1] - margin, margin * 2., margin * 2.]);
+        }
+        let res = self.res;
+        self.walls.activate(&rects, |info| Wall::new(info.clone(), res))
+    }
+
+    /// Reload the sprites which changed on disk, recreating the walls,
+    /// decorations and background to show them. Other objects keep their
+    /// sprites until the level is restarted.
+    fn reload_sprites(&mut self) {
+        let reloaded = self.res.sprite().reload_changed();
+        if reloaded.is_empty() {
+            return;
+        }
+        let res = self.res;
+        self.walls.deactivate_all();
+        let walls = self.activate_walls();
+        let decorations: Result<Vec<_>> = self.level
+            .decorations()
             .iter()
-            .map(|info| Mine::new(info.clone(), resource_manager))
+            .map(|info| Decoration::new(info.clone(), res))
             .collect();
+        match (walls, decorations) {
+            (Ok(()), Ok(decorations)) => {
+                self.decorations = decorations;
+                self.background = Background::new(&self.level, res);
+                let message = tr_fmt("game.reloaded", &[&reloaded.len()]);
+                self.notice = Some((message, NOTICE_DURATION));
+            }
+            (Err(e), _) | (_, Err(e)) => eprintln!("Warning: could not recreate the level's objects: {}", e),
+        }
+    }
+
+    /// Start the level over, keeping the last replay and the overlay
+    /// settings.
+    fn restart(&mut self) -> Result<()> {
+        let mut game = GameController::new(self.level.clone(), self.res, self.options)?;
+        game.path = self.path.take();
+        game.next_level = self.next_level;
+        game.last_replay = self.last_replay.take();
+        game.ghost = self.ghost.take();
+        game.show_ghost = self.show_ghost;
+        game.timer.set_visible(self.timer.is_visible());
+        game.toasts = ::std::mem::replace(&mut self.toasts, VecDeque::new());
+        game.save_slots = ::std::mem::replace(&mut self.save_slots, SaveSlots::default());
+        #[cfg(feature = "dev-console")]
+        {
+            game.console = ::std::mem::replace(&mut self.console, Console::default());
+        }
+        game.time_scale = self.time_scale;
+        if self.minimap.is_visible() {
+            game.minimap.toggle();
+        }
+        if self.debug.is_visible() {
+            game.debug.toggle();
+        }
+        *self = game;
+        Ok(())
+    }
+
+    /// Enter or leave photo mode, bringing the camera back to where it was
+    /// when leaving. Photo mode is available in builds with the developer
+    /// console, and otherwise only on levels which the player has finished.
+    fn toggle_photo_mode(&mut self) {
+        if let Some(photo) = self.photo.take() {
+            self.camera = photo.into_camera();
+            return;
+        }
+        if !self.photo_mode_available() {
+            self.notice = Some((tr("game.photo_unavailable"), NOTICE_DURATION));
+            return;
+        }
+        self.focus_lost();
+        self.photo = Some(PhotoMode::new(&self.camera));
+    }
 
-        let gems: Result<Vec<_>> = level
-            .gems()
+    fn photo_mode_available(&self) -> bool {
+        if cfg!(feature = "dev-console") {
+            return true;
+        }
+        if let GamePhase::Finished(_) = self.phase {
+            return true;
+        }
+        let progress = Progress::load_or_default();
+        let name = self.level.name();
+        [false, true]
             .iter()
-            .map(|info| Gem::new(info.clone(), resource_manager))
-            .collect();
+            .any(|&hard_mode| progress.level(name, hard_mode).map_or(false, |l| l.best_time.is_some()))
+    }
+
+    #[cfg(feature = "dev-console")]
+    /// Carry out a command from the developer console, telling how it went
+    /// on the console. Commands which change the run keep it from being
+    /// recorded as the best run.
+    fn run_command(&mut self, command: Command) {
+        let res = self.res;
+        let outcome = match command {
+            Command::GiveGems(count) => {
+                let mut given = 0;
+                for gem in (&mut self.gems).into_iter().filter(|gem| !gem.is_picked_up()) {
+                    if given == count {
+                        break;
+                    }
+                    gem.set_picked_up(true);
+                    self.ball.pick_up(());
+                    given += 1;
+                }
+                Ok(format!("Collected {} gem(s)", given))
+            }
+            Command::SetSize(size) => {
+                let extra_size = size - self.ball.size();
+                self.ball.add_size(extra_size);
+                Ok(format!("Ball size set to {}", size))
+            }
+            Command::Teleport(pos) => {
+                self.ball.set_position(pos);
+                self.ball.set_velocity(default_vector2());
+                Ok(format!("Ball moved to {}, {}", pos[0], pos[1]))
+            }
+            Command::Spawn(kind, pos) => {
+                let pos = vec_i32(pos);
+                let spawned = match kind {
+                    SpawnKind::Mine => Mine::new(MineInfo { pos }, res).map(|mine| self.mines.push(mine)),
+                    SpawnKind::Gem => Gem::new(GemInfo { pos }, res).map(|gem| self.gems.push(gem)),
+                    SpawnKind::Pump => {
+                        Pump::new(PumpInfo { pos, charges: None }, res).map(|pump| self.pumps.push(pump))
+                    }
+                    SpawnKind::Leak => {
+                        let radius = LeakInfo::default_radius();
+                        self.leaks.push(Leak::new(LeakInfo { pos, radius }));
+                        Ok(())
+                    }
+                };
+                spawned
+                    .map(|()| format!("Spawned a {:?} at {}, {}", kind, pos[0], pos[1]))
+                    .map_err(|e| e.to_string())
+            }
+            Command::TimeScale(scale) => {
+                self.time_scale = scale;
+                Ok(format!("Time scale set to {}", scale))
+            }
+            Command::Reload => match self.path.clone() {
+                Some(path) => {
+                    let reloaded = GameLevel::load(&path).map_err(|e| e.to_string()).and_then(|level| {
+                        self.level = level;
+                        self.restart().map_err(|e| e.to_string())
+                    });
+                    reloaded.map(|()| format!("Reloaded {}", path.display()))
+                }
+                None => Err("The level was not loaded from a file".to_string()),
+            },
+            Command::Save => {
+                self.save_state(0);
+                Ok("Saved the run to slot 1".to_string())
+            }
+        };
+        match command {
+            Command::Reload | Command::Save => {}
+            _ => self.run = None,
+        }
+        match outcome {
+            Ok(message) => self.console.print(message),
+            Err(e) => self.console.print(format!("Error: {}", e)),
+        }
+    }
+
+    /// Start the level over, with or without a second player. The second
+    /// player's ball starts at the level's second starting position, or
+    /// next to the first ball.
+    pub fn set_second_player(&mut self, enabled: bool) -> Result<()> {
+        self.restart()?;
+        if enabled {
+            let size = self.level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+            let pos = self.level
+                .second_ball_position()
+                .unwrap_or_else(|| self.level.ball_position() + Vector2::new(size + 4., 0.));
+            let mut ball2 = BallController::new(Ball::new(pos, size), self.level.ball_params(), self.res)?;
+            ball2.set_controls(Controls::Secondary);
+            ball2.set_constant_deflation(self.options.constant_deflation());
+            ball2.set_flash_intensity(self.effects.flash_factor());
+            self.ball2 = Some(ball2);
+            // co-op runs do not count towards the best run
+            self.run = None;
+        }
+        Ok(())
+    }
+
+    /// Start the level over while recording the player's input.
+    pub fn start_recording(&mut self) -> Result<()> {
+        self.restart()?;
+        self.recording = Some(Replay::new(self.level.name(), self.options.hard_mode));
+        Ok(())
+    }
+
+    /// Start the level over and play back the given replay, ignoring the
+    /// player's input until the replay ends.
+    pub fn play_replay(&mut self, replay: Replay) -> Result<()> {
+        self.restart()?;
+        self.run = None;
+        // the replay only plays back the same way under its own modifiers
+        let options = GameOptions {
+            hard_mode: replay.is_hard_mode(),
+            ..GameOptions::default()
+        };
+        self.ball.set_constant_deflation(options.constant_deflation());
+        self.playback = Some(replay.play());
+        Ok(())
+    }
+
+    /// Play back the last replay, which is either the one recorded or
+    /// played in this session, or the one saved next to the level file.
+    fn play_last_replay(&mut self) -> Result<()> {
+        let replay = self.last_replay.clone().or_else(|| {
+            self.path.as_ref().and_then(|path| Replay::load(Replay::path_for_level(path)).ok())
+        });
+        match replay {
+            Some(replay) => self.play_replay(replay),
+            None => {
+                eprintln!("No replay to play");
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<R> GameController<R>
+where
+    R: ResourceManage,
+{
+    /// Stop recording, saving the replay next to the level file if the
+    /// level was loaded from one.
+    pub fn stop_recording(&mut self) {
+        if let Some(replay) = self.recording.take() {
+            if let Some(path) = self.path.as_ref() {
+                let replay_path = Replay::path_for_level(path);
+                match replay.save(&replay_path) {
+                    Ok(()) => println!("Replay saved to {}", replay_path.display()),
+                    Err(e) => eprintln!("Failed to save replay: {}", e),
+                }
+            }
+            self.last_replay = Some(replay);
+        }
+    }
+
+    /// Keep the current run as the best run if it was faster than the
+    /// previous best, saving it next to the level file.
+    fn save_best_run(&mut self) {
+        let run = match self.run.take() {
+            Some(run) => run,
+            None => return,
+        };
+        let is_best = self.ghost.as_ref().map(|best| run.len() < best.len()).unwrap_or(true);
+        if !is_best {
+            return;
+        }
+        if let Some(path) = self.path.as_ref() {
+            if let Err(e) = run.save(Replay::best_path_for_level(path, self.options.hard_mode)) {
+                eprintln!("Failed to save the best run: {}", e);
+            }
+        }
+        self.ghost = Some(run);
+    }
+
+    /// The simulation time elapsed since the level started, in seconds.
+    fn elapsed_time(&self) -> f32 {
+        (f64::from(self.step_count) * PHYSICS_STEP) as f32
+    }
+
+    /// Stop the speedrun timer and record the finished run in the player's
+    /// progress, along with the achievements it earned, unless the run is a
+    /// replay.
+    fn save_best_time(&mut self) {
+        let now = self.elapsed_time();
+        let time = self.timer.finish(now);
+        if self.run.is_none() {
+            return;
+        }
+        let mut progress = Progress::load_or_default();
+        let name = self.level.name();
+        let hard_mode = self.options.hard_mode;
+        let mut changed = progress.record_time(name, hard_mode, time, self.timer.splits());
+        let level_progress = progress.level_mut(name, hard_mode);
+        if self.achievements.gems() >= self.level.gems().len() && !level_progress.all_gems {
+            level_progress.all_gems = true;
+            changed = true;
+        }
+
+        let start_size = self.level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+        let shrunk = self.ball.size() < start_size
+            || self.ball2.as_ref().map_or(false, |b| b.size() < start_size);
+        let medal = self.level.par_times().and_then(|p| p.grade(time));
+        if let Some(medal) = medal {
+            changed |= progress.record_medal(name, hard_mode, medal);
+        }
+        let end = RunEnd {
+            time,
+            medal,
+            shrunk,
+            all_gems: collected_all_gems(&progress),
+        };
+        let earned = self.achievements.check(Some(&end));
+        changed |= !earned.is_empty();
+        self.announce(&earned, &mut progress);
+        if changed {
+            progress.save_or_warn();
+        }
+    }
 
-        let finish = if let Some(finish_info) = level.finish_flag() {
-            Some(Finish::new(finish_info.clone(), resource_manager)?)
+    /// Unlock the given achievements in the progress, and queue them to be
+    /// announced.
+    fn announce(&mut self, earned: &[&'static Achievement], progress: &mut Progress) {
+        for &achievement in earned {
+            progress.unlock(achievement.id);
+            self.toasts.push_back((achievement, TOAST_DURATION));
+        }
+    }
+
+    /// Whether the level's goal has been achieved.
+    fn is_goal_met(&self) -> bool {
+        match self.level.goal() {
+            Goal::ReachFinish => self.finish.as_ref().map(|f| f.is_picked_up()).unwrap_or(false),
+            Goal::CollectAllGems => (&self.gems).into_iter().all(|gem| gem.is_picked_up()),
+            Goal::Survive { seconds } => self.elapsed_time() >= seconds,
+        }
+    }
+
+    /// Advance the game by the given number of ticks.
+    fn step(&mut self, ticks: f32) {
+        // update entities
+        for pump in &mut self.pumps {
+            pump.update(ticks);
+        }
+        for boost in &mut self.boosts {
+            boost.update(ticks);
+        }
+        for leak in &mut self.leaks {
+            leak.update(ticks);
+        }
+        for gem in &mut self.gems {
+            gem.update(ticks);
+        }
+        if let Some(finish) = self.finish.as_mut() {
+            finish.update(ticks);
+        }
+        for water in &mut self.water {
+            water.update(ticks);
+        }
+
+        match self.phase {
+            GamePhase::Playing => self.update_playing(ticks),
+            GamePhase::Dying(t) => self.phase = GamePhase::Dying(t - ticks),
+            GamePhase::Finished(t) => self.phase = GamePhase::Finished(t - ticks),
+        }
+        self.particles.update(ticks);
+        if let Some((_, t)) = self.notice.as_mut() {
+            *t -= ticks;
+        }
+        if self.notice.as_ref().map(|&(_, t)| t <= 0.).unwrap_or(false) {
+            self.notice = None;
+        }
+        if let Some(&mut (_, ref mut t)) = self.toasts.front_mut() {
+            *t -= ticks;
+        }
+        if self.toasts.front().map(|&(_, t)| t <= 0.).unwrap_or(false) {
+            self.toasts.pop_front();
+        }
+
+        // update the camera's zoom and position, framing both players in
+        // co-op by focusing on their midpoint and zooming out as they part
+        let map_dim = self.level.map().dimensions_f32();
+        let (focus, velocity, fit_zoom) = match self.ball2.as_ref() {
+            None => (self.ball.position(), self.ball.velocity(), MAX_ZOOM),
+            Some(ball2) => {
+                let (a, b) = (self.ball.position(), ball2.position());
+                let d = b - a;
+                let fit_zoom = f32::min(
+                    WIDTH as f32 / (d[0].abs() + COOP_FRAME_MARGIN),
+                    HEIGHT as f32 / (d[1].abs() + COOP_FRAME_MARGIN),
+                );
+                ((a + b) * 0.5, (self.ball.velocity() + ball2.velocity()) * 0.5, fit_zoom)
+            }
+        };
+        let speed = if self.phase == GamePhase::Playing {
+            norm(&velocity)
+        } else {
+            0.
+        };
+        self.camera.set_target_zoom(f32::min(fit_zoom, 1. - speed * SPEED_ZOOM_OUT));
+        self.camera.update_zoom(ticks);
+        let target_look_ahead = if self.phase == GamePhase::Playing {
+            velocity * LOOK_AHEAD
         } else {
-            None
+            default_vector2()
         };
+        let t = f32::min(1., LOOK_AHEAD_EASING * ticks);
+        self.look_ahead += (target_look_ahead - self.look_ahead) * t;
+        self.camera.soft_focus_on(focus + self.look_ahead, map_dim);
+        self.camera.update_shake(ticks);
+    }
 
-        Ok(GameController {
-            level,
-            ball,
-            camera,
-            res: resource_manager,
-            walls: Scene::from_objects(walls?),
-            mines: Scene::from_objects(mines?),
-            pumps: pumps?,
-            gems: Scene::from_objects(gems?),
-            finish,
-        })
-    }
-
-    fn load_base_assets(resource_manager: R) -> Result<()> {
-        let mut sprite = resource_manager.sprite();
-        sprite.new_sprite_from_path(SpriteAssetId::Pump, "assets/pump-wheel.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Gem, "assets/gem.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Mine, "assets/mine.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Flag, "assets/flag.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Check, "assets/check.png")?;
-        for i in 0.. {
-            let path = format!("assets/{}.png", i);
-            match sprite.new_sprite_from_path(SpriteAssetId::Other(i), path) {
-                Ok(_) => {}
-                Err(_) => {
-                    return Ok(());
+    /// Update the physics and the entities' interactions with the ball,
+    /// while the game is being played.
+    fn update_playing(&mut self, ticks: f32) {
+        self.simulate(ticks);
+        self.handle_events();
+
+        // start the closing animations
+        let dead_ball = if self.ball.is_dead() {
+            Some(self.ball.position())
+        } else {
+            self.ball2.as_ref().filter(|b| b.is_dead()).map(|b| b.position())
+        };
+        if let Some(pos) = dead_ball {
+            self.particles.burst(pos, [0.5, 0.86, 1.0, 1.0], 30);
+            self.phase = GamePhase::Dying(DEATH_DURATION);
+            self.stop_recording();
+        } else if self.is_goal_met() {
+            self.phase = GamePhase::Finished(FINISH_DURATION);
+            self.stop_recording();
+            self.save_best_time();
+            self.save_best_run();
+        }
+    }
+
+    /// Step the physics and the entities' interactions with the ball,
+    /// queueing the events of the step without reacting to them.
+    fn simulate(&mut self, ticks: f32) {
+        // take the ball's input from the replay, or record it
+        let mut playback_over = false;
+        if let Some(player) = self.playback.as_mut() {
+            let thrust = player.next().unwrap_or_default();
+            self.ball.set_thrust(thrust);
+            playback_over = player.is_finished();
+        }
+        if playback_over {
+            self.last_replay = self.playback.take().map(ReplayPlayer::into_replay);
+        }
+        let (thrust, position) = (self.ball.thrust(), self.ball.position());
+        if let Some(replay) = self.recording.as_mut() {
+            replay.record(thrust, position);
+        }
+        if let Some(run) = self.run.as_mut() {
+            run.record(thrust, position);
+        }
+        self.step_count += 1;
+        let thrust2 = self.ball2.as_ref().map(|b| b.thrust()).unwrap_or_default();
+        if thrust != Thrust::default() || thrust2 != Thrust::default() {
+            let now = self.elapsed_time();
+            self.timer.start(now);
+        }
+
+        // let the balls bounce off each other
+        if let Some(ball2) = self.ball2.as_mut() {
+            let circle1 = Circle::new(self.ball.position(), self.ball.size() / 2.);
+            let circle2 = Circle::new(ball2.position(), ball2.size() / 2.);
+            resolve_circle_circle(&mut self.ball, circle1, ball2, circle2);
+        }
+
+        self.collide_ball(Player::One, ticks);
+        if self.ball2.is_some() {
+            self.collide_ball(Player::Two, ticks);
+        }
+
+        // handle contact with finish flag, which only matters if the goal
+        // is to reach it
+        let now = self.elapsed_time();
+        let reach_finish = self.level.goal() == Goal::ReachFinish;
+        let finish = self.finish.as_mut().filter(|_| reach_finish);
+        if let Some(finish) = finish {
+            match self.ball2.as_ref() {
+                None => {
+                    self.ball.handle_simple_collision_with(finish, &mut self.events);
+                }
+                Some(ball2) => {
+                    // both players need to reach the flag at about the same time
+                    let touch1 = finish.test_circle_collision_simple(self.ball.position(), self.ball.size() / 2.);
+                    let touch2 = finish.test_circle_collision_simple(ball2.position(), ball2.size() / 2.);
+                    if touch1 {
+                        self.finish_touches[0] = Some(now);
+                    }
+                    if touch2 {
+                        self.finish_touches[1] = Some(now);
+                    }
+                    let together = match self.finish_touches {
+                        [Some(a), Some(b)] => (a - b).abs() <= COOP_FINISH_WINDOW,
+                        _ => false,
+                    };
+                    if !finish.accepts(self.ball.items() + ball2.items()) {
+                        if (touch1 || touch2) && finish.reject() {
+                            self.events.push(GameEvent::FinishRejected);
+                        }
+                    } else if together {
+                        finish.complete();
+                        self.events.push(GameEvent::FinishReached);
+                    }
                 }
             }
         }
-        Ok(())
+
+        self.move_ball(Player::One, ticks);
+        if self.ball2.is_some() {
+            self.move_ball(Player::Two, ticks);
+        }
+    }
+
+    /// Handle the collisions of a player's ball with the map and the
+    /// entities, over the given number of ticks.
+    fn collide_ball(&mut self, player: Player, ticks: f32) {
+        let ball = match player {
+            Player::One => &mut self.ball,
+            Player::Two => self.ball2.as_mut().expect("no second player"),
+        };
+
+        // handle map boundary collision, except on open edges
+        let map = self.level.map();
+        let open = map.open_edges();
+        if !open.left {
+            ball.handle_collision_with(map.left_border());
+        }
+        if !open.right {
+            ball.handle_collision_with(map.right_border());
+        }
+        if !open.up {
+            ball.handle_collision_with(map.up_border());
+        }
+        if !open.down {
+            ball.handle_collision_with(map.down_border());
+        }
+        // a ball which left through an open edge falls out of the level
+        if map.is_outside(ball.position(), ball.size() / 2.) {
+            ball.fall_out();
+        }
+
+        // handle collisions with scene, leaving some room for walls which
+        // the ball barely touches
+        let reach = centered_box(ball.position(), ball.size() + 2.);
+        for wall in self.walls.in_rect_mut(reach) {
+            ball.handle_collision_with(wall);
+        }
+        for spikes in self.spikes.at_mut(ball.position()) {
+            ball.handle_collision_with(spikes);
+        }
+        // handle contact with pumps
+        for pump in &mut self.pumps {
+            ball.handle_simple_collision_with(pump, &mut self.events);
+        }
+        // handle contact with boost pads
+        for boost in &mut self.boosts {
+            ball.handle_simple_collision_with(boost, &mut self.events);
+        }
+        // handle leaks, which deflate the ball over time
+        for leak in &self.leaks {
+            if leak.overlaps(ball.position(), ball.size() / 2.) {
+                leak.drain(ball, ticks);
+            }
+        }
+        // handle contact with mines
+        for mine in self.mines.at(ball.position()) {
+            ball.handle_simple_collision_with(mine, &mut self.events);
+        }
+        // handle water, by the deepest region the ball's center is in
+        let submersion = self.water
+            .iter()
+            .map(|w| w.submersion(ball.position(), ball.size()))
+            .fold(0., f32::max);
+        ball.set_submersion(submersion);
+
+        // handle contact with gems
+        for gem in self.gems.at_mut(ball.position()) {
+            ball.handle_simple_collision_with(gem, &mut self.events);
+        }
+    }
+
+    /// React to the events of the current step, in the order in which they
+    /// happened.
+    fn handle_events(&mut self) {
+        let now = self.elapsed_time();
+        let items = self.ball.items() + self.ball2.as_ref().map(|b| b.items()).unwrap_or(0);
+        for event in self.events.drain(..) {
+            self.achievements.observe(&event);
+            match event {
+                GameEvent::GemPickedUp(pos) => {
+                    self.particles.sparkle(pos);
+                    self.timer.split(now);
+                }
+                GameEvent::MineHit(pos) => {
+                    self.particles.burst(pos, [1.0, 0.55, 0.2, 1.0], 40);
+                    self.camera.shake(3., 10.);
+                }
+                GameEvent::FinishRejected => {
+                    // tell the player why the flag refused the ball
+                    let required = self.finish.as_ref().map(|f| f.gems_required()).unwrap_or(0);
+                    let missing = required.saturating_sub(items);
+                    let text = if missing == 1 {
+                        tr("game.need_gem")
+                    } else {
+                        tr_fmt("game.need_gems", &[&missing])
+                    };
+                    self.notice = Some((text, NOTICE_DURATION));
+                }
+                GameEvent::Impact { speed, .. } => {
+                    if speed > IMPACT_SHAKE_THRESHOLD {
+                        let magnitude = f32::min(MAX_SHAKE, (speed - IMPACT_SHAKE_THRESHOLD) * 2. + 1.);
+                        self.camera.shake(magnitude, 12.);
+                    }
+                }
+                GameEvent::PumpUsed(_) | GameEvent::BoostUsed(_) | GameEvent::FinishReached => {}
+            }
+        }
+
+        // achievements which do not need the level to be finished
+        if self.run.is_some() {
+            let earned = self.achievements.check(None);
+            if !earned.is_empty() {
+                let mut progress = Progress::load_or_default();
+                self.announce(&earned, &mut progress);
+                progress.save_or_warn();
+            }
+        }
+    }
+
+    /// Draw the time of the finished run, graded against the level's par
+    /// times if it has any.
+    fn draw_results<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        // the speedrun timer's clock, which stopped at the finish
+        let time = self.timer.elapsed(self.elapsed_time());
+        let window_size = c.viewport.unwrap().window_size;
+        let x = f64::from(window_size[0]) / 2. - 120.;
+        let y = f64::from(window_size[1]) / 2. - 50.;
+        rectangle([0., 0., 0., 0.75], [x, y, 240., 100.], c.transform, g);
+        let _ = Text::new_color([1.; 4], 16).draw(
+            &tr("game.level_complete"),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(x + 12., y + 24.),
+            g,
+        );
+        let _ = Text::new_color([1.; 4], 14).draw(
+            &tr_fmt("game.time", &[&format_time(time)]),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(x + 12., y + 46.),
+            g,
+        );
+        if let Some(par_times) = self.level.par_times() {
+            let text = match par_times.grade(time) {
+                Some(medal) => {
+                    draw_medal(medal, [x + 19., y + 63.], 7., c.transform, g);
+                    medal.name()
+                }
+                None => tr_fmt("game.no_medal", &[&format_time(par_times.bronze)]),
+            };
+            let _ = Text::new_color([1.; 4], 12).draw(
+                &text,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(x + 32., y + 68.),
+                g,
+            );
+        }
+        if self.phase.is_over() {
+            let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                &tr("game.continue"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(x + 12., y + 90.),
+                g,
+            );
+        }
+    }
+
+    /// Draw the collision shapes of the objects within the given rectangle,
+    /// along with the map's borders, the balls' velocities and the region
+    /// in which the camera does not follow the ball.
+    fn draw_debug<G: Graphics>(&self, c: Context, view_rect: [f32; 4], g: &mut G) {
+        let overlay = &self.debug;
+        let map_dim = self.level.map().dimensions_f32();
+        overlay.draw_box([0., 0., map_dim[0], map_dim[1]], debug::BORDER_COLOR, c, g);
+        for wall in self.walls.in_rect(view_rect) {
+            overlay.draw_box(wall.bounding_box(), debug::WALL_COLOR, c, g);
+        }
+        for spikes in self.spikes.in_rect(view_rect) {
+            overlay.draw_box(spikes.bounding_box(), debug::HAZARD_COLOR, c, g);
+        }
+        for water in &self.water {
+            overlay.draw_box(water.bounding_box(), debug::WATER_COLOR, c, g);
+        }
+        for leak in &self.leaks {
+            overlay.draw_circle(leak.position(), leak.radius(), debug::HAZARD_COLOR, c, g);
+        }
+        for mine in self.mines.in_rect(view_rect) {
+            overlay.draw_circle(mine.position(), mine.collision_radius(), debug::HAZARD_COLOR, c, g);
+        }
+        for gem in self.gems.in_rect(view_rect).filter(|gem| !gem.is_picked_up()) {
+            overlay.draw_circle(gem.position(), gem.collision_radius(), debug::ENTITY_COLOR, c, g);
+        }
+        for pump in &self.pumps {
+            overlay.draw_circle(pump.position(), pump.collision_radius(), debug::ENTITY_COLOR, c, g);
+        }
+        for boost in &self.boosts {
+            overlay.draw_circle(boost.position(), boost.collision_radius(), debug::ENTITY_COLOR, c, g);
+        }
+        if let Some(finish) = self.finish.as_ref() {
+            overlay.draw_circle(finish.position(), finish.collision_radius(), debug::ENTITY_COLOR, c, g);
+        }
+        for ball in Some(&self.ball).into_iter().chain(self.ball2.as_ref()) {
+            overlay.draw_circle(ball.position(), ball.size() / 2., debug::BALL_COLOR, c, g);
+            let velocity = ball.velocity() * debug::VELOCITY_SCALE;
+            overlay.draw_vector(ball.position(), velocity, debug::BALL_COLOR, c, g);
+        }
+        overlay.draw_box(self.camera.soft_focus_rect(), debug::CAMERA_COLOR, c, g);
+    }
+
+    /// Describe how many objects of each kind the level has, for the debug
+    /// overlay.
+    fn debug_counts(&self) -> Vec<String> {
+        let gems_left = (&self.gems).into_iter().filter(|gem| !gem.is_picked_up()).count();
+        vec![
+            format!("walls {}/{}  spikes {}", self.walls.active_len(), self.walls.len(), self.spikes.len()),
+            format!("sectors {}", self.walls.active_sectors()),
+            format!("gems {}/{}  mines {}", gems_left, self.gems.len(), self.mines.len()),
+            format!("pumps {}  boosts {}", self.pumps.len(), self.boosts.len()),
+            format!("leaks {}  water {}", self.leaks.len(), self.water.len()),
+            format!("particles {}", self.particles.len()),
+            format!("zoom {:.2}", self.camera.zoom()),
+        ]
+    }
+
+    /// Save the state of the run to the given slot, in practice mode.
+    fn save_state(&mut self, slot: usize) {
+        if self.phase != GamePhase::Playing {
+            return;
+        }
+        self.save_slots[slot] = Some(SaveState {
+            ball: self.ball.state(),
+            gems: (&self.gems).into_iter().map(|gem| gem.is_picked_up()).collect(),
+            pumps: self.pumps.iter().map(|pump| pump.state()).collect(),
+            boosts: self.boosts.iter().map(|boost| boost.cooldown()).collect(),
+            step_count: self.step_count,
+            timer: self.timer.clone(),
+        });
+        self.notice = Some((tr_fmt("game.state_saved", &[&(slot + 1)]), NOTICE_DURATION));
+    }
+
+    /// Bring the run back to the state saved in the given slot, in practice
+    /// mode. This also brings the ball back to life.
+    fn load_state(&mut self, slot: usize) {
+        let state = match self.save_slots[slot].clone() {
+            Some(state) => state,
+            None => {
+                self.notice = Some((tr_fmt("game.state_empty", &[&(slot + 1)]), NOTICE_DURATION));
+                return;
+            }
+        };
+        self.ball.restore_state(&state.ball);
+        for (gem, &picked_up) in (&mut self.gems).into_iter().zip(&state.gems) {
+            gem.set_picked_up(picked_up);
+        }
+        for (pump, &pump_state) in self.pumps.iter_mut().zip(&state.pumps) {
+            pump.restore_state(pump_state);
+        }
+        for (boost, &cooldown) in self.boosts.iter_mut().zip(&state.boosts) {
+            boost.set_cooldown(cooldown);
+        }
+        if let Some(finish) = self.finish.as_mut() {
+            finish.reset();
+        }
+        self.step_count = state.step_count;
+        let visible = self.timer.is_visible();
+        self.timer = state.timer;
+        self.timer.set_visible(visible);
+
+        // a recording cannot play back a jump in time
+        self.recording = None;
+        self.events.clear();
+        self.time_accumulator = 0.;
+        self.phase = GamePhase::Playing;
+        self.look_ahead = default_vector2();
+        self.camera.focus_on(self.ball.position(), self.level.map().dimensions_f32());
+        self.notice = Some((tr_fmt("game.state_loaded", &[&(slot + 1)]), NOTICE_DURATION));
+    }
+
+    /// Move a player's ball, along with the effects of its motion.
+    fn move_ball(&mut self, player: Player, ticks: f32) {
+        let ball = match player {
+            Player::One => &mut self.ball,
+            Player::Two => self.ball2.as_mut().expect("no second player"),
+        };
+
+        ball.update(ticks);
+        if let Some(impact) = ball.last_impact().filter(|i| i.speed > 0.) {
+            self.events.push(GameEvent::Impact {
+                pos: ball.position(),
+                speed: impact.speed,
+            });
+        }
+
+        // emit exhaust while thrusting
+        let thrust_dir = ball.thrust_direction();
+        if !ball.is_dead() && thrust_dir != default_vector2() {
+            self.particles.exhaust(
+                ball.position(),
+                ball.size() / 2.,
+                thrust_dir,
+                ball.velocity(),
+                ticks,
+            );
+        }
     }
 }
 
 impl<R> Controller for GameController<R>
 where
-    R: ResourceManage,
+    R: ResourceManage + Copy,
 {
     type Res = R;
+    const NEEDS_HI_RES: bool = true;
+    const NAME: &'static str = "game";
 
     fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
         use piston::input::Button::{Controller, Keyboard};
         use piston::input::{ButtonState, ControllerButton, Key};
-        self.ball.event(e);
+        #[cfg(feature = "dev-console")]
+        match self.console.event(e) {
+            ConsoleInput::Ignored => {}
+            ConsoleInput::Consumed => return None,
+            ConsoleInput::Opened => {
+                self.focus_lost();
+                return None;
+            }
+            ConsoleInput::Command(command) => {
+                self.run_command(command);
+                return None;
+            }
+        }
+        if e.press_args() == Some(Keyboard(Key::F8)) {
+            self.toggle_photo_mode();
+            return None;
+        }
+        if self.photo.is_some() {
+            if e.press_args() == Some(Keyboard(Key::Escape)) {
+                self.toggle_photo_mode();
+            } else if let Some(photo) = self.photo.as_mut() {
+                let map_dim = self.level.map().dimensions_f32();
+                photo.event(e, &mut self.camera, map_dim, self.hires_scale.get());
+            }
+            return None;
+        }
+        if self.playback.is_none() {
+            self.ball.event(e);
+        }
+        if let Some(ball2) = self.ball2.as_mut() {
+            ball2.event(e);
+        }
         if let Some(b) = e.button_args() {
+            // in practice mode, the function keys restore the save states,
+            // or save them while shift is held
+            if self.options.practice && self.playback.is_none() && b.state == ButtonState::Press {
+                let slot = [Key::F1, Key::F2, Key::F3, Key::F4]
+                    .iter()
+                    .position(|&key| b.button == Keyboard(key));
+                if let Some(slot) = slot {
+                    if self.shift {
+                        self.save_state(slot);
+                    } else {
+                        self.load_state(slot);
+                    }
+                    return None;
+                }
+            }
+
             // Set cell value.
             match (b.button, b.state) {
                 (Keyboard(Key::Escape), ButtonState::Press) => {
-                    return Some(ControllerAction::LoadTitleScreen);
+                    return Some(ControllerAction::Push(GameState::Pause));
+                }
+                (Keyboard(Key::LShift), state) | (Keyboard(Key::RShift), state) => {
+                    self.shift = state == ButtonState::Press;
+                }
+                (Keyboard(Key::F5), ButtonState::Press) => {
+                    if self.recording.is_some() {
+                        self.stop_recording();
+                    } else if let Err(e) = self.start_recording() {
+                        eprintln!("Failed to restart the level: {}", e);
+                    }
+                }
+                (Keyboard(Key::F2), ButtonState::Press) => {
+                    let enabled = self.ball2.is_none();
+                    if let Err(e) = self.set_second_player(enabled) {
+                        eprintln!("Failed to restart the level: {}", e);
+                    }
+                }
+                (Keyboard(Key::F3), ButtonState::Press) => {
+                    self.debug.toggle();
+                }
+                (Keyboard(Key::F12), ButtonState::Press) => {
+                    println!("{}", self.res.sprite().sprite_report());
+                }
+                (Keyboard(Key::F6), ButtonState::Press) => {
+                    self.stop_recording();
+                    if let Err(e) = self.play_last_replay() {
+                        eprintln!("Failed to restart the level: {}", e);
+                    }
                 }
                 (Keyboard(Key::Return), ButtonState::Press)
                 | (Keyboard(Key::Space), ButtonState::Press)
                 | (Controller(ControllerButton { id: 0, button: 0 }), ButtonState::Press)
                 | (Controller(ControllerButton { id: 0, button: 1 }), ButtonState::Press) => {
-                    if self.ball.is_dead() || self
-                        .finish
-                        .as_ref()
-                        .map(|f| f.is_picked_up())
-                        .unwrap_or(false)
-                    {
+                    if self.phase.is_over() {
+                        if let (GamePhase::Finished(_), Some(next)) = (self.phase, self.next_level) {
+                            return Some(ControllerAction::LoadGame(next));
+                        }
                         return Some(ControllerAction::LoadTitleScreen);
                     }
                 }
@@ -145,7 +1300,17 @@ where
 
         if let Some(k) = e.text_args() {
             if k == "E" {
-                return Some(ControllerAction::OpenEditor(None));
+                let path = self.path.as_ref().map(|p| p.display().to_string());
+                return Some(ControllerAction::OpenEditor(path));
+            }
+            if k == "M" || k == "m" {
+                self.minimap.toggle();
+            }
+            if k == "G" || k == "g" {
+                self.show_ghost = !self.show_ghost;
+            }
+            if k == "T" || k == "t" {
+                self.timer.toggle();
             }
         }
 
@@ -153,51 +1318,31 @@ where
     }
 
     fn update(&mut self, u: UpdateArgs) -> Option<ControllerAction> {
-        let ticks = 60. * u.dt as f32;
-
-        // update entities
-        for pump in &mut self.pumps {
-            pump.update(ticks);
+        // the margin around the balls covers the steps of a single update
+        if let Err(e) = self.activate_walls() {
+            eprintln!("Warning: could not create the level's walls: {}", e);
         }
 
-        // handle map boundary collision
-        self.ball
-            .handle_collision_with(self.level.map().left_border());
-        self.ball
-            .handle_collision_with(self.level.map().right_border());
-        self.ball
-            .handle_collision_with(self.level.map().up_border());
-        self.ball
-            .handle_collision_with(self.level.map().down_border());
-
-        // handle collisions with scene
-        for wall in self.walls.at_mut(self.ball.position()) {
-            self.ball.handle_collision_with(wall);
-        }
-        // handle contact with pumps
-        for pump in &mut self.pumps {
-            self.ball.handle_simple_collision_with(pump);
-        }
-        // handle contact with mines
-        for mine in self.mines.at(self.ball.position()) {
-            self.ball.handle_simple_collision_with(mine);
-        }
-        // handle contact with gems
-        for gem in self.gems.at_mut(self.ball.position()) {
-            self.ball.handle_simple_collision_with(gem);
-        }
-        // handle contact with finish flag
-        if let Some(finish) = self.finish.as_mut() {
-            self.ball.handle_simple_collision_with(finish);
+        if let Some(photo) = self.photo.as_mut() {
+            // the game stands still while the camera moves freely
+            let map_dim = self.level.map().dimensions_f32();
+            photo.update(60. * u.dt as f32, &mut self.camera, map_dim);
+        } else {
+            // run the simulation in fixed steps, dropping the time of long
+            // hitches
+            self.time_accumulator += f64::min(u.dt * self.time_scale, MAX_FRAME_TIME);
+            while self.time_accumulator >= PHYSICS_STEP {
+                self.time_accumulator -= PHYSICS_STEP;
+                self.step(60. * PHYSICS_STEP as f32);
+            }
         }
+        self.debug.update(u.dt);
 
-        // update the ball
-        self.ball.update(ticks);
-
-        // update the camera's position
-        let map_dim = self.level.map().dimensions_f32();
-        self.camera.soft_focus_on(self.ball.position(), map_dim);
-
+        self.watch_time += u.dt;
+        if self.watch_time >= WATCH_INTERVAL {
+            self.watch_time = 0.;
+            self.reload_sprites();
+        }
         None
     }
 
@@ -206,26 +1351,455 @@ where
         C: CharacterCache<Texture = GameTexture<R>>,
         G: Graphics<Texture = GameTexture<R>>,
     {
-        clear([0.4, 0.6, 0.7, 1.0], g);
+        self.debug.count_frame();
         // use camera focus to define a position
         let camera_pos = self.camera.position();
-        let c = c.trans((-camera_pos[0]).into(), (-camera_pos[1]).into());
+        self.background
+            .draw(c, camera_pos, self.level.map().dimensions_f32(), g);
+        let zoom = f64::from(self.camera.zoom());
+        let c0 = c.scale(zoom, zoom);
+        for decoration in self.decorations.iter().filter(|d| d.layer() == DecorationLayer::Back) {
+            decoration.draw(c0, camera_pos, g);
+        }
+        let c = c0.trans((-camera_pos[0]).into(), (-camera_pos[1]).into());
 
-        for wall in &self.walls {
+        // only draw what is within the camera's view
+        let view = self.camera.view();
+        let view_rect = [
+            camera_pos[0] - CULL_MARGIN,
+            camera_pos[1] - CULL_MARGIN,
+            view[0] + CULL_MARGIN * 2.,
+            view[1] + CULL_MARGIN * 2.,
+        ];
+        for wall in self.walls.in_rect(view_rect) {
             wall.draw(c, g);
         }
-        for mine in &self.mines {
-            mine.draw(c, g);
+        border::draw_borders(self.level.map(), c, g);
+        for spikes in self.spikes.in_rect(view_rect) {
+            spikes.draw(c, self.palette, g);
+        }
+        for leak in self.leaks.iter().filter(|l| rect_intersects(l.bounding_box(), view_rect)) {
+            leak.draw(c, self.palette, g);
+        }
+        for boost in self.boosts.iter().filter(|b| rect_intersects(b.bounding_box(), view_rect)) {
+            boost.draw(c, g);
         }
-        for gem in &self.gems {
-            gem.draw(c, g);
+        for mine in self.mines.in_rect(view_rect) {
+            mine.draw(c, self.palette, g);
+        }
+        for gem in self.gems.in_rect(view_rect) {
+            gem.draw(c, self.palette, g);
         }
         if let Some(finish) = self.finish.as_ref() {
-            finish.draw(c, g);
+            finish.draw(c, self.effects.flash_factor(), g);
         }
-        self.ball.draw(c, g);
-        for pump in &self.pumps {
+        self.particles.draw(c, g);
+        if self.show_ghost {
+            let ghost_pos = self.ghost.as_ref().and_then(|ghost| ghost.position_at(self.step_count));
+            if let Some(pos) = ghost_pos {
+                let size = self.level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+                replay::draw_ghost(c, pos, size, g);
+            }
+        }
+        for ball in Some(&self.ball).into_iter().chain(self.ball2.as_ref()) {
+            match self.phase {
+                GamePhase::Playing => ball.draw(c, self.palette, g),
+                GamePhase::Dying(t) if ball.is_dead() => {
+                    ball.draw_pop(c, 1. - t / DEATH_DURATION, self.palette, g)
+                }
+                GamePhase::Dying(_) => ball.draw(c, self.palette, g),
+                GamePhase::Finished(t) => {
+                    let target = match self.level.goal() {
+                        Goal::ReachFinish => self.finish.as_ref().map(|f| f.position()),
+                        _ => None,
+                    }.unwrap_or_else(|| ball.position());
+                    ball.draw_absorbed(c, target, 1. - t / FINISH_DURATION, self.palette, g);
+                }
+            }
+        }
+        // mark the second player's ball
+        if let Some(ball2) = self.ball2.as_ref() {
+            if self.phase == GamePhase::Playing {
+                let size = f64::from(ball2.size()) + 6.;
+                let pos = ball2.position();
+                let r = [f64::from(pos[0]) - size / 2., f64::from(pos[1]) - size / 2., size, size];
+                Ellipse::new_border(PLAYER2_COLOR, 0.5).draw(r, &c.draw_state, c.transform, g);
+            }
+        }
+        // water goes over the ball, so that it looks submerged
+        for water in self.water.iter().filter(|w| rect_intersects(w.bounding_box(), view_rect)) {
+            water.draw(c, g);
+        }
+        for pump in self.pumps.iter().filter(|p| rect_intersects(p.bounding_box(), view_rect)) {
             pump.draw(c, g);
         }
+        for decoration in self.decorations.iter().filter(|d| d.layer() == DecorationLayer::Front) {
+            decoration.draw(c0, camera_pos, g);
+        }
+        if self.debug.is_visible() {
+            self.draw_debug(c, view_rect, g);
+        }
+    }
+
+    fn render_hires<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let window_size = c.viewport.unwrap().window_size;
+        self.hires_scale.set(window_size[0] as f32 / f32::from(WIDTH));
+        // photo mode leaves the view clear of the interface
+        if let Some(photo) = self.photo.as_ref() {
+            if photo.show_hint() {
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                    &tr("game.photo_hint"),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(12., f64::from(window_size[1]) - 12.),
+                    g,
+                );
+            }
+            #[cfg(feature = "dev-console")]
+            self.console.draw(c, cache, g);
+            return;
+        }
+
+        let markers = (&self.gems)
+            .into_iter()
+            .filter(|gem| !gem.is_picked_up())
+            .map(|gem| (gem.position(), self.palette.gem_marker))
+            .chain((&self.mines).into_iter().map(|mine| (mine.position(), self.palette.mine_marker)))
+            .chain(self.pumps.iter().map(|pump| {
+                let color = if pump.is_exhausted() { EXHAUSTED_PUMP_COLOR } else { PUMP_COLOR };
+                (pump.position(), color)
+            }))
+            .chain(self.finish.iter().map(|finish| (finish.position(), FINISH_COLOR)))
+            .chain(self.ball2.iter().map(|ball2| (ball2.position(), PLAYER2_COLOR)));
+        let view = (self.camera.position(), self.camera.view());
+        self.minimap.draw(c, markers, self.ball.position(), view, g);
+        self.timer.draw(c, self.elapsed_time(), cache, g);
+        if self.debug.is_visible() {
+            self.debug.draw_stats(&self.debug_counts(), c, cache, g);
+        }
+
+        if let Some((text, _)) = self.notice.as_ref() {
+            let window_size = c.viewport.unwrap().window_size;
+            let _ = Text::new_color([1.0, 0.6, 0.5, 1.0], 14).draw(
+                text,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(f64::from(window_size[0]) / 2. - 60., f64::from(window_size[1]) - 40.),
+                g,
+            );
+        }
+
+        // announce the latest achievement
+        if let Some(&(achievement, _)) = self.toasts.front() {
+            let window_size = c.viewport.unwrap().window_size;
+            let x = f64::from(window_size[0]) / 2. - 160.;
+            rectangle([0., 0., 0., 0.75], [x, 40., 320., 44.], c.transform, g);
+            let _ = Text::new_color([1.0, 0.85, 0.3, 1.0], 14).draw(
+                &tr_fmt("game.achievement", &[&achievement.name()]),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(x + 10., 58.),
+                g,
+            );
+            let _ = Text::new_color([1.; 4], 10).draw(
+                &achievement.description(),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(x + 10., 76.),
+                g,
+            );
+        }
+
+        if let GamePhase::Finished(_) = self.phase {
+            self.draw_results(c, cache, g);
+        }
+
+        if self.options.practice {
+            let window_size = c.viewport.unwrap().window_size;
+            let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                &tr("game.practice"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., f64::from(window_size[1]) - 12.),
+                g,
+            );
+        }
+
+        // show the time left to survive
+        if let Goal::Survive { seconds } = self.level.goal() {
+            if self.phase == GamePhase::Playing {
+                let window_size = c.viewport.unwrap().window_size;
+                let _ = Text::new_color([1.0, 0.9, 0.5, 1.0], 14).draw(
+                    &tr_fmt("game.survive", &[&format_time(seconds - self.elapsed_time())]),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(f64::from(window_size[0]) / 2. - 60., 24.),
+                    g,
+                );
+            }
+        }
+
+        #[cfg(feature = "dev-console")]
+        self.console.draw(c, cache, g);
+    }
+
+    fn focus_lost(&mut self) {
+        self.shift = false;
+        if let Some(photo) = self.photo.as_mut() {
+            photo.release_all();
+        }
+        self.ball.release_all_thrust();
+        if let Some(ball2) = self.ball2.as_mut() {
+            ball2.release_all_thrust();
+        }
+    }
+
+    fn show_frame_stats(&mut self, stats: &FrameStats) {
+        self.debug.set_frame_stats(*stats);
+    }
+
+    fn title(&self) -> Option<String> {
+        Some(self.level.name().to_string())
+    }
+
+    fn exit(&mut self) {
+        // level textures and backgrounds are not shared with other levels
+        let mut sprite = self.res.sprite();
+        let _ = sprite.free_sprite(SpriteAssetId::LevelBackground);
+        free_level_textures(&mut sprite);
+    }
+}
+
+/// Group the level's walls into sectors, in drawing order.
+fn wall_scene<R: ResourceManage>(level: &GameLevel) -> SectorScene<WallInfo, Wall<R>> {
+    let infos = wall::draw_order(level.walls())
+        .into_iter()
+        .map(|i| level.walls()[i].clone());
+    SectorScene::new(infos, |info| {
+        [info.pos[0] as f32, info.pos[1] as f32, info.dim[0] as f32, info.dim[1] as f32]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::character::Character;
+    use graphics::DrawState;
+    use level::generate::{generate_level, GeneratorParams};
+    use level::info::{GemInfo, WallInfo};
+    use level::Map;
+    use physics::AnimatedObject;
+    use resource::null::NullTexture;
+    use resource::{NullAudioManager, NullResourceManager, NullSpriteManager};
+    use std::time::{Duration, Instant};
+    use self::sectors::SPARE_SECTORS;
+
+    /// The time which one frame may take at 60 frames per second.
+    const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+
+    fn null_resources() -> NullResourceManager {
+        NullResourceManager::new(NullSpriteManager::new(()).unwrap(), NullAudioManager::new(()).unwrap())
+    }
+
+    /// A graphics backend which draws nothing, but counts the textured
+    /// draw calls.
+    #[derive(Default)]
+    struct CountingGraphics {
+        textured: usize,
+    }
+
+    impl Graphics for CountingGraphics {
+        type Texture = NullTexture;
+
+        fn clear_color(&mut self, _color: [f32; 4]) {}
+
+        fn clear_stencil(&mut self, _value: u8) {}
+
+        fn tri_list<F>(&mut self, _draw_state: &DrawState, _color: &[f32; 4], mut f: F)
+        where
+            F: FnMut(&mut FnMut(&[[f32; 2]])),
+        {
+            f(&mut |_| {});
+        }
+
+        fn tri_list_uv<F>(&mut self, _draw_state: &DrawState, _color: &[f32; 4], _texture: &NullTexture, mut f: F)
+        where
+            F: FnMut(&mut FnMut(&[[f32; 2]], &[[f32; 2]])),
+        {
+            self.textured += 1;
+            f(&mut |_, _| {});
+        }
+    }
+
+    /// A glyph cache in which every character is a blank square.
+    #[derive(Default)]
+    struct NullGlyphCache {
+        texture: NullTexture,
+    }
+
+    impl CharacterCache for NullGlyphCache {
+        type Texture = NullTexture;
+        type Error = ();
+
+        fn character(&mut self, font_size: u32, _ch: char) -> ::std::result::Result<Character<NullTexture>, ()> {
+            Ok(Character {
+                offset: [0., 0.],
+                size: [f64::from(font_size); 2],
+                texture: &self.texture,
+            })
+        }
+    }
+
+    #[test]
+    fn game_runs_without_a_window() {
+        let res = null_resources();
+        let mut level = GameLevel::default();
+        level.set_ball_position([40., 100.].into());
+        // right in the way of the ball, which rolls to the right
+        level.gems_mut().push(GemInfo { pos: [160, 100].into() });
+        let mut game = GameController::new(level, &res, GameOptions::default()).unwrap();
+        game.ball.set_velocity([3., 0.].into());
+
+        let map_dim = game.level.map().dimensions_f32();
+        let mut bounced = false;
+        for _ in 0..300 {
+            assert_eq!(game.update(UpdateArgs { dt: 1. / 60. }), None);
+            let pos = game.ball.position();
+            assert!(pos[0] > 0. && pos[0] < map_dim[0], "{:?} outside of the map", pos);
+            assert!(pos[1] > 0. && pos[1] < map_dim[1], "{:?} outside of the map", pos);
+            bounced |= game.ball.velocity()[0] < 0.;
+        }
+        assert!(bounced, "the ball never bounced off the right border");
+        assert_eq!(game.ball.items(), 1);
+        assert!(!game.ball.is_dead());
+    }
+
+    #[test]
+    fn replay_ends_where_the_recorded_run_did() {
+        let res = null_resources();
+        let mut game = GameController::new(GameLevel::default(), &res, GameOptions::default()).unwrap();
+        game.start_recording().unwrap();
+        for i in 0..90 {
+            game.ball.set_thrust(Thrust {
+                right: i < 50,
+                down: i > 30,
+                ..Thrust::default()
+            });
+            game.update(UpdateArgs { dt: 1. / 60. });
+        }
+        game.stop_recording();
+        let recorded = game.ball.position();
+        let replay = game.last_replay.take().unwrap();
+        assert_eq!(replay.len(), 180);
+
+        game.play_replay(replay).unwrap();
+        for _ in 0..90 {
+            // the player's input does not get in the way of the replay
+            game.ball.set_thrust(Thrust {
+                left: true,
+                ..Thrust::default()
+            });
+            game.update(UpdateArgs { dt: 1. / 60. });
+        }
+        assert!(game.playback.is_none());
+        assert_eq!(game.ball.position(), recorded);
+    }
+
+    #[test]
+    fn walls_exist_only_around_the_camera() {
+        let params = GeneratorParams {
+            width: 8000,
+            height: 8000,
+            difficulty: 3,
+            seed: 7,
+        };
+        let level = generate_level(&params).unwrap();
+        let res = null_resources();
+        let mut game = GameController::new(level, &res, GameOptions::default()).unwrap();
+        let map_dim = game.level.map().dimensions_f32();
+        let total = game.walls.len();
+
+        // sweep the camera and the ball across the map, diagonally
+        let mut most = 0;
+        for i in 0..=100 {
+            let pos = map_dim * (i as f32 / 100.);
+            game.ball.set_position(pos);
+            game.camera.focus_on(pos, map_dim);
+            game.activate_walls().unwrap();
+            // the view and the margin around it span at most 3x2 sectors,
+            // and walls reach into at most one more on each side
+            assert!(game.walls.active_sectors() <= 4 * 3 + SPARE_SECTORS);
+            most = usize::max(most, game.walls.active_len());
+        }
+        assert!(most > 0 && most < total / 4, "{} of {} walls", most, total);
+    }
+
+    #[test]
+    fn culled_drawing_of_many_walls_fits_in_a_frame() {
+        let res = null_resources();
+        let mut level = GameLevel::default();
+        *level.map_mut() = Map::new(3200, 1664);
+        level.set_ball_position([24., 24.].into());
+        // a grid of 100x50 walls, with gaps between them
+        for i in 0..100 {
+            for j in 0..50 {
+                level.walls_mut().push(WallInfo {
+                    pos: [i * 32, 64 + j * 32].into(),
+                    dim: [16, 16].into(),
+                    texture_id: 0,
+                    rotation: 0,
+                    flip: false,
+                    tile: false,
+                });
+            }
+        }
+        assert_eq!(level.walls().len(), 5000);
+        let game = GameController::new(level, &res, GameOptions::default()).unwrap();
+
+        let c = Context::new_abs(320., 200.);
+        let mut cache = NullGlyphCache::default();
+        let mut g = CountingGraphics::default();
+        let frames = 60;
+        let start = Instant::now();
+        for _ in 0..frames {
+            game.render(c, &mut cache, &mut g);
+        }
+        let per_frame = start.elapsed() / frames;
+        // about 11x5 walls are in view, the rest is not drawn at all
+        let per_frame_draws = g.textured / frames as usize;
+        assert!(per_frame_draws < 200, "{} draws per frame", per_frame_draws);
+        assert!(per_frame < FRAME_BUDGET, "{:?} per frame", per_frame);
+    }
+
+    /// Play two seconds at the given number of updates per second, pushing
+    /// right for the first second and up for the next, and obtain the
+    /// ball's final position.
+    fn scripted_run(ups: u32) -> Vector2<f32> {
+        let res = null_resources();
+        let mut game = GameController::new(GameLevel::default(), &res, GameOptions::default()).unwrap();
+        let dt = 1. / f64::from(ups);
+        for i in 0..2 * ups {
+            let pushing_right = i < ups;
+            game.ball.set_thrust(Thrust {
+                right: pushing_right,
+                up: !pushing_right,
+                ..Thrust::default()
+            });
+            game.update(UpdateArgs { dt });
+        }
+        assert_eq!(game.step_count, 240);
+        game.ball.position()
+    }
+
+    #[test]
+    fn update_rate_does_not_change_the_outcome() {
+        let at_60 = scripted_run(60);
+        let at_240 = scripted_run(240);
+        assert!((at_60 - at_240).norm() < 1e-4, "{:?} != {:?}", at_60, at_240);
+        assert!(at_60 != GameLevel::default().ball_position());
     }
 }
@@ -0,0 +1,172 @@
+//! Module for the palettes in which the ball and the level's objects are
+//! drawn, so that players who have trouble telling the default colors apart
+//! can pick a palette which suits them.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+use graphics::{line, Graphics};
+use graphics::math::Matrix2d;
+use na::Vector2;
+use lang::tr;
+use super::minimap::{GEM_COLOR, MINE_COLOR};
+
+/// The distance between hazard stripes, in pixels.
+pub const STRIPE_SPACING: f32 = 4.;
+/// The width of hazard stripes, in pixels.
+pub const STRIPE_WIDTH: f64 = 0.5;
+
+/// The colors and patterns used to draw the ball and the level's objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// the ball's color when it is close to deflating completely
+    pub ball_small: [f32; 4],
+    /// the ball's color at a safe size
+    pub ball: [f32; 4],
+    /// the ball's color when it is close to bursting
+    pub ball_full: [f32; 4],
+    /// the color of the ball's trail, which fades out along it
+    pub trail: [f32; 4],
+    /// the color of the outline around the ball, if any
+    pub ball_outline: Option<[f32; 4]>,
+    /// the color of the outline around hazards, if any
+    pub hazard_outline: Option<[f32; 4]>,
+    /// the color of the stripes over hazards, if any
+    pub hazard_stripes: Option<[f32; 4]>,
+    /// the color multiplied into the gem sprites
+    pub gem_tint: [f32; 4],
+    /// the color multiplied into the mine sprites
+    pub mine_tint: [f32; 4],
+    /// the color of gems on the minimap
+    pub gem_marker: [f32; 4],
+    /// the color of mines on the minimap
+    pub mine_marker: [f32; 4],
+    pub leak: [f32; 4],
+    pub leak_border: [f32; 4],
+    pub spike_base: [f32; 4],
+    pub spike: [f32; 4],
+}
+
+pub const DEFAULT_PALETTE: Palette = Palette {
+    ball_small: [0.7, 0.5, 0.9, 1.0],
+    ball: [0.5, 0.86, 1.0, 1.0],
+    ball_full: [0.7, 0.88, 1.0, 0.8],
+    trail: [0.5, 0.86, 1.0, 0.35],
+    ball_outline: None,
+    hazard_outline: None,
+    hazard_stripes: None,
+    gem_tint: [1.0, 1.0, 1.0, 1.0],
+    mine_tint: [1.0, 1.0, 1.0, 1.0],
+    gem_marker: GEM_COLOR,
+    mine_marker: MINE_COLOR,
+    leak: [1.0, 0.2, 0.15, 0.12],
+    leak_border: [1.0, 0.3, 0.2, 0.4],
+    spike_base: [0.3, 0.3, 0.35, 1.0],
+    spike: [0.75, 0.75, 0.8, 1.0],
+};
+
+/// Strong colors for the ball's health, with the ball and hazards outlined
+/// and hazards striped.
+pub const HIGH_CONTRAST_PALETTE: Palette = Palette {
+    ball_small: [1.0, 0.4, 0.1, 1.0],
+    ball: [0.3, 0.9, 1.0, 1.0],
+    ball_full: [1.0, 1.0, 1.0, 1.0],
+    trail: [0.3, 0.9, 1.0, 0.5],
+    ball_outline: Some([0.0, 0.0, 0.0, 1.0]),
+    hazard_outline: Some([1.0, 1.0, 0.0, 1.0]),
+    hazard_stripes: Some([0.0, 0.0, 0.0, 0.7]),
+    gem_tint: [1.0, 1.0, 1.0, 1.0],
+    mine_tint: [1.0, 1.0, 1.0, 1.0],
+    gem_marker: GEM_COLOR,
+    mine_marker: MINE_COLOR,
+    leak: [1.0, 0.2, 0.15, 0.3],
+    leak_border: [1.0, 1.0, 0.0, 1.0],
+    spike_base: [0.1, 0.1, 0.1, 1.0],
+    spike: [1.0, 1.0, 1.0, 1.0],
+};
+
+/// Gems in blue and mines in orange, which remain apart for players with
+/// red-green color blindness, with hazards striped.
+pub const DEUTERANOPIA_PALETTE: Palette = Palette {
+    ball_small: [1.0, 0.7, 0.2, 1.0],
+    ball: [0.5, 0.86, 1.0, 1.0],
+    ball_full: [0.9, 0.95, 1.0, 0.8],
+    trail: [0.5, 0.86, 1.0, 0.35],
+    ball_outline: None,
+    hazard_outline: None,
+    hazard_stripes: Some([0.0, 0.0, 0.0, 0.6]),
+    gem_tint: [0.45, 0.75, 1.0, 1.0],
+    mine_tint: [1.0, 0.8, 0.35, 1.0],
+    gem_marker: [0.3, 0.6, 1.0, 1.0],
+    mine_marker: [1.0, 0.65, 0.1, 1.0],
+    leak: [1.0, 0.6, 0.1, 0.15],
+    leak_border: [1.0, 0.65, 0.1, 0.6],
+    spike_base: [0.3, 0.3, 0.35, 1.0],
+    spike: [0.75, 0.75, 0.8, 1.0],
+};
+
+/// The palettes which the player can choose from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteKind {
+    Default,
+    HighContrast,
+    Deuteranopia,
+}
+
+impl Default for PaletteKind {
+    fn default() -> Self {
+        PaletteKind::Default
+    }
+}
+
+impl PaletteKind {
+    pub fn palette(self) -> &'static Palette {
+        match self {
+            PaletteKind::Default => &DEFAULT_PALETTE,
+            PaletteKind::HighContrast => &HIGH_CONTRAST_PALETTE,
+            PaletteKind::Deuteranopia => &DEUTERANOPIA_PALETTE,
+        }
+    }
+
+    /// The palette which follows this one when cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            PaletteKind::Default => PaletteKind::HighContrast,
+            PaletteKind::HighContrast => PaletteKind::Deuteranopia,
+            PaletteKind::Deuteranopia => PaletteKind::Default,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            PaletteKind::Default => tr("palette.default"),
+            PaletteKind::HighContrast => tr("palette.high_contrast"),
+            PaletteKind::Deuteranopia => tr("palette.deuteranopia"),
+        }
+    }
+}
+
+/// Draw diagonal stripes across a circle, so that hazards can be told apart
+/// by more than their color.
+pub fn draw_circle_stripes<G: Graphics>(
+    color: [f32; 4],
+    center: Vector2<f32>,
+    radius: f32,
+    transform: Matrix2d,
+    g: &mut G,
+) {
+    // each stripe runs along (1, 1), at an offset along (1, -1)
+    let mut offset = -radius + STRIPE_SPACING / 2.;
+    while offset < radius {
+        let half_length = (radius * radius - offset * offset).sqrt() * FRAC_1_SQRT_2;
+        let x = center[0] + offset * FRAC_1_SQRT_2;
+        let y = center[1] - offset * FRAC_1_SQRT_2;
+        let stripe = [
+            f64::from(x - half_length),
+            f64::from(y - half_length),
+            f64::from(x + half_length),
+            f64::from(y + half_length),
+        ];
+        line(color, STRIPE_WIDTH, stripe, transform, g);
+        offset += STRIPE_SPACING;
+    }
+}
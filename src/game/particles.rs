@@ -0,0 +1,186 @@
+//! Module for the particle system, which provides purely visual effects
+//! such as explosions, sparkles and the ball's thrust exhaust.
+
+use std::f32::consts::PI;
+use graphics::{rectangle, Context, Graphics};
+use na::Vector2;
+use util::{clamp, XorShiftRng};
+
+/// The maximum number of live particles. Further particles are not spawned
+/// until others die out.
+pub const MAX_PARTICLES: usize = 2000;
+
+/// Number of exhaust particles emitted per tick while thrusting.
+const EXHAUST_RATE: f32 = 0.75;
+/// Velocity decay applied to all particles, per tick.
+const PARTICLE_DRAG: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub pos: Vector2<f32>,
+    pub vel: Vector2<f32>,
+    /// remaining life, in ticks
+    pub life: f32,
+    /// initial life, in ticks
+    pub max_life: f32,
+    pub color: [f32; 4],
+    pub size: f32,
+}
+
+/// A pool of particles. Live particles are kept at the start of the pool,
+/// and dead particles are recycled when spawning new ones.
+#[derive(Debug)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    live: usize,
+    rng: XorShiftRng,
+    exhaust_acc: f32,
+    /// the fraction of particles which are actually emitted
+    density: f32,
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        ParticleSystem::new()
+    }
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: Vec::with_capacity(MAX_PARTICLES),
+            live: 0,
+            rng: XorShiftRng::default(),
+            exhaust_acc: 0.,
+            density: 1.,
+        }
+    }
+
+    /// The number of live particles.
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live == 0
+    }
+
+    /// Emit only the given fraction of particles, from 0 (none at all) to
+    /// 1.
+    pub fn set_density(&mut self, density: f32) {
+        self.density = clamp(density, 0., 1.);
+    }
+
+    /// The number of particles to emit out of the given count, according
+    /// to the density.
+    fn thinned(&self, count: usize) -> usize {
+        (count as f32 * self.density).round() as usize
+    }
+
+    /// Add a particle to the system, unless the system is full.
+    pub fn spawn(&mut self, particle: Particle) {
+        if self.live < self.particles.len() {
+            self.particles[self.live] = particle;
+        } else if self.particles.len() < MAX_PARTICLES {
+            self.particles.push(particle);
+        } else {
+            return;
+        }
+        self.live += 1;
+    }
+
+    /// Emit an explosion of particles in all directions.
+    pub fn burst(&mut self, pos: Vector2<f32>, color: [f32; 4], count: usize) {
+        for _ in 0..self.thinned(count) {
+            let angle = self.rng.range(0., 2. * PI);
+            let speed = self.rng.range(0.5, 2.5);
+            let life = self.rng.range(20., 40.);
+            let size = self.rng.range(1., 3.);
+            let vel = Vector2::new(angle.cos(), angle.sin()) * speed;
+            self.spawn(Particle {
+                pos,
+                vel,
+                life,
+                max_life: life,
+                color,
+                size,
+            });
+        }
+    }
+
+    /// Emit a gentle sparkle of slowly rising particles.
+    pub fn sparkle(&mut self, pos: Vector2<f32>) {
+        for _ in 0..self.thinned(16) {
+            let offset = Vector2::new(self.rng.range(-8., 8.), self.rng.range(-8., 8.));
+            let vel = Vector2::new(self.rng.range(-0.2, 0.2), self.rng.range(-0.6, -0.1));
+            let life = self.rng.range(30., 50.);
+            let brightness = self.rng.range(0.7, 1.0);
+            self.spawn(Particle {
+                pos: pos + offset,
+                vel,
+                life,
+                max_life: life,
+                color: [1.0, 1.0, brightness, 1.0],
+                size: 1.,
+            });
+        }
+    }
+
+    /// Continuously emit exhaust particles from a thrusting object, for the
+    /// given number of ticks. `dir` is the direction of the thrust, and
+    /// particles are emitted from the opposite side of the object.
+    pub fn exhaust(&mut self, pos: Vector2<f32>, radius: f32, dir: Vector2<f32>, base_vel: Vector2<f32>, ticks: f32) {
+        self.exhaust_acc += EXHAUST_RATE * self.density * ticks;
+        while self.exhaust_acc >= 1. {
+            self.exhaust_acc -= 1.;
+            let jitter = Vector2::new(self.rng.range(-0.3, 0.3), self.rng.range(-0.3, 0.3));
+            let life = self.rng.range(10., 20.);
+            let speed = self.rng.range(0.8, 1.5);
+            self.spawn(Particle {
+                pos: pos - dir * radius,
+                vel: base_vel - dir * speed + jitter,
+                life,
+                max_life: life,
+                color: [0.85, 0.95, 1.0, 0.8],
+                size: 2.,
+            });
+        }
+    }
+
+    /// Advance all particles by the given number of ticks, recycling the
+    /// dead ones.
+    pub fn update(&mut self, ticks: f32) {
+        let mut i = 0;
+        while i < self.live {
+            let dead = {
+                let p = &mut self.particles[i];
+                p.life -= ticks;
+                p.pos += p.vel * ticks;
+                p.vel -= p.vel * (PARTICLE_DRAG * ticks);
+                p.life <= 0.
+            };
+            if dead {
+                // move the last live particle into this slot
+                self.live -= 1;
+                self.particles.swap(i, self.live);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    pub fn draw<G: Graphics>(&self, ctx: Context, g: &mut G) {
+        for p in &self.particles[..self.live] {
+            let mut color = p.color;
+            color[3] *= p.life / p.max_life;
+            let hsize = p.size / 2.;
+            let r = [
+                (p.pos[0] - hsize) as f64,
+                (p.pos[1] - hsize) as f64,
+                p.size as f64,
+                p.size as f64,
+            ];
+            rectangle(color, r, ctx.transform, g);
+        }
+    }
+}
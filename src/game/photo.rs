@@ -0,0 +1,110 @@
+//! Module for photo mode, in which the game stands still while the camera
+//! moves freely, for taking screenshots or looking at far away parts of the
+//! level.
+
+use na::Vector2;
+use piston::input::GenericEvent;
+use camera::{pan_direction, Camera};
+
+/// How far the camera pans per tick while an arrow key is held, in pixels
+/// of the display.
+const KEY_PAN_SPEED: f32 = 4.;
+/// How much the zoom changes per step of the mouse wheel.
+const ZOOM_STEP: f32 = 0.1;
+
+/// The state of photo mode.
+#[derive(Debug)]
+pub struct PhotoMode {
+    /// the camera as it was when photo mode started, to be restored when
+    /// it ends
+    saved_camera: Camera,
+    /// the arrow keys held down: left, right, up and down
+    pan_keys: [bool; 4],
+    /// whether the left mouse button is held down, so that moving the
+    /// cursor drags the view
+    dragging: bool,
+    /// the last position of the cursor, in window pixels
+    cursor: Vector2<f32>,
+    /// whether the controls are still to be shown, until the camera first
+    /// moves
+    show_hint: bool,
+}
+
+impl PhotoMode {
+    /// Start photo mode, remembering the given camera.
+    pub fn new(camera: &Camera) -> Self {
+        PhotoMode {
+            saved_camera: camera.clone(),
+            pan_keys: [false; 4],
+            dragging: false,
+            cursor: Vector2::zeros(),
+            show_hint: true,
+        }
+    }
+
+    pub fn show_hint(&self) -> bool {
+        self.show_hint
+    }
+
+    /// Handle an input event, moving the camera around the map.
+    /// `pixel_scale` is the number of window pixels per pixel of the
+    /// display.
+    pub fn event<E>(&mut self, e: &E, camera: &mut Camera, map_dim: Vector2<f32>, pixel_scale: f32)
+    where
+        E: GenericEvent,
+    {
+        use piston::input::{Button, ButtonState, Key, MouseButton};
+        if let Some(b) = e.button_args() {
+            let pressed = b.state == ButtonState::Press;
+            match b.button {
+                Button::Keyboard(Key::Left) => self.pan_keys[0] = pressed,
+                Button::Keyboard(Key::Right) => self.pan_keys[1] = pressed,
+                Button::Keyboard(Key::Up) => self.pan_keys[2] = pressed,
+                Button::Keyboard(Key::Down) => self.pan_keys[3] = pressed,
+                Button::Mouse(MouseButton::Left) => self.dragging = pressed,
+                _ => {}
+            }
+        }
+        if let Some(m) = e.mouse_cursor_args() {
+            let cursor = Vector2::new(m[0] as f32, m[1] as f32);
+            if self.dragging {
+                let delta = (self.cursor - cursor) / (pixel_scale * camera.zoom());
+                camera.pan_within(delta, map_dim);
+                self.show_hint = false;
+            }
+            self.cursor = cursor;
+        }
+        if let Some(m) = e.mouse_scroll_args() {
+            if m[1] != 0. {
+                let step = if m[1] > 0. { ZOOM_STEP } else { -ZOOM_STEP };
+                let zoom = camera.target_zoom() + step;
+                camera.set_target_zoom(zoom);
+                self.show_hint = false;
+            }
+        }
+    }
+
+    /// Pan the camera with the held arrow keys and ease its zoom, for the
+    /// given number of ticks.
+    pub fn update(&mut self, ticks: f32, camera: &mut Camera, map_dim: Vector2<f32>) {
+        let direction = pan_direction(self.pan_keys);
+        if direction != Vector2::zeros() {
+            camera.pan(direction * KEY_PAN_SPEED / camera.zoom() * ticks);
+            self.show_hint = false;
+        }
+        camera.update_zoom(ticks);
+        camera.update_shake(ticks);
+        camera.clamp_to_bounds(map_dim);
+    }
+
+    /// Forget the held keys and buttons, whose release will not be seen.
+    pub fn release_all(&mut self) {
+        self.pan_keys = [false; 4];
+        self.dragging = false;
+    }
+
+    /// End photo mode, giving back the camera as it was when it started.
+    pub fn into_camera(self) -> Camera {
+        self.saved_camera
+    }
+}
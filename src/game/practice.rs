@@ -0,0 +1,27 @@
+//! Module for practice mode, in which the state of a run can be saved to
+//! a few slots and brought back at any time, to go over a hard section
+//! again and again. Practice runs do not count towards any records.
+
+use super::ball::BallState;
+use super::entities::PumpState;
+use super::timer::SpeedrunTimer;
+
+/// The number of save state slots.
+pub const SAVE_SLOTS: usize = 4;
+
+/// A snapshot of everything in a run which changes while playing.
+#[derive(Debug, Clone)]
+pub struct SaveState {
+    pub ball: BallState,
+    /// whether each gem was picked up, in the order of the level's gems
+    pub gems: Vec<bool>,
+    pub pumps: Vec<PumpState>,
+    /// the cooldown of each boost pad, in ticks
+    pub boosts: Vec<f32>,
+    /// the number of simulation steps played so far
+    pub step_count: u32,
+    pub timer: SpeedrunTimer,
+}
+
+/// The save state slots of a practice session.
+pub type SaveSlots = [Option<SaveState>; SAVE_SLOTS];
@@ -0,0 +1,210 @@
+//! Module for replays, which record the player's input on each simulation
+//! step so that a run can be played back exactly.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use graphics::{ellipse, Context, Graphics};
+use na::Vector2;
+use serde_json::{from_reader, to_writer};
+use game::ball::Thrust;
+use util::DynResult;
+
+/// The number of simulation steps between recorded ball positions.
+const TRACK_INTERVAL: u32 = 4;
+/// The color of the ghost ball.
+const GHOST_COLOR: [f32; 4] = [1.0, 0.7, 0.35, 0.4];
+
+/// A recording of the thrust applied on each simulation step of a run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    /// the name of the level which was played
+    #[serde(default)] level: String,
+    /// runs of steps with the same thrust, as pairs of the number of steps
+    /// and the thrust's bit flags
+    #[serde(default)] steps: Vec<(u32, u8)>,
+    /// the ball's position every `TRACK_INTERVAL` steps
+    #[serde(default)] track: Vec<[f32; 2]>,
+    /// whether the run was played in hard mode
+    #[serde(default)] hard_mode: bool,
+}
+
+impl Replay {
+    pub fn new<T: Into<String>>(level: T, hard_mode: bool) -> Self {
+        Replay {
+            level: level.into(),
+            steps: Vec::new(),
+            track: Vec::new(),
+            hard_mode,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+        let file = File::open(path)?;
+        from_reader(file).map_err(From::from)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> DynResult<()> {
+        let file = File::create(path)?;
+        to_writer(file, self).map_err(From::from)
+    }
+
+    /// The path of the replay file kept next to the given level file. The
+    /// extension is not `json`, so that replays are not mistaken for levels.
+    pub fn path_for_level<P: AsRef<Path>>(level_path: P) -> PathBuf {
+        level_path.as_ref().with_extension("replay")
+    }
+
+    /// The path of the best run's replay, kept next to the given level
+    /// file. Hard mode runs are kept apart from the normal ones.
+    pub fn best_path_for_level<P: AsRef<Path>>(level_path: P, hard_mode: bool) -> PathBuf {
+        let extension = if hard_mode { "hard.best" } else { "best" };
+        level_path.as_ref().with_extension(extension)
+    }
+
+    /// The name of the level which was played.
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    pub fn is_hard_mode(&self) -> bool {
+        self.hard_mode
+    }
+
+    /// The total number of simulation steps recorded.
+    pub fn len(&self) -> u32 {
+        self.steps.iter().map(|&(n, _)| n).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Record the thrust of one more simulation step, along with the ball's
+    /// position at the start of that step.
+    pub fn record(&mut self, thrust: Thrust, position: Vector2<f32>) {
+        if self.len() % TRACK_INTERVAL == 0 {
+            self.track.push([position[0], position[1]]);
+        }
+        let bits = thrust.to_bits();
+        if let Some(&mut (ref mut n, b)) = self.steps.last_mut() {
+            if b == bits {
+                *n += 1;
+                return;
+            }
+        }
+        self.steps.push((1, bits));
+    }
+
+    /// Obtain the recorded position of the ball after the given number of
+    /// steps, or `None` if the run had already ended by then.
+    pub fn position_at(&self, step: u32) -> Option<Vector2<f32>> {
+        let i = (step / TRACK_INTERVAL) as usize;
+        let a = *self.track.get(i)?;
+        let b = self.track.get(i + 1).cloned().unwrap_or(a);
+        let t = (step % TRACK_INTERVAL) as f32 / TRACK_INTERVAL as f32;
+        Some([a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t].into())
+    }
+
+    /// Start playing back the replay from the beginning.
+    pub fn play(self) -> ReplayPlayer {
+        ReplayPlayer {
+            replay: self,
+            run: 0,
+            step: 0,
+        }
+    }
+}
+
+/// The state of a replay being played back.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    /// the index of the current run of steps
+    run: usize,
+    /// the number of steps already played in the current run
+    step: u32,
+}
+
+impl ReplayPlayer {
+    /// Whether all recorded steps were played.
+    pub fn is_finished(&self) -> bool {
+        self.run >= self.replay.steps.len()
+    }
+
+    /// Recover the replay being played.
+    pub fn into_replay(self) -> Replay {
+        self.replay
+    }
+}
+
+impl Iterator for ReplayPlayer {
+    type Item = Thrust;
+
+    /// Obtain the thrust of the next simulation step.
+    fn next(&mut self) -> Option<Thrust> {
+        let (n, bits) = *self.replay.steps.get(self.run)?;
+        self.step += 1;
+        if self.step >= n {
+            self.run += 1;
+            self.step = 0;
+        }
+        Some(Thrust::from_bits(bits))
+    }
+}
+
+/// Draw a translucent ghost ball of the given size, which stands for the
+/// ball of a recorded run.
+pub fn draw_ghost<G: Graphics>(ctx: Context, pos: Vector2<f32>, size: f32, g: &mut G) {
+    let size = f64::from(size);
+    let r = [
+        f64::from(pos[0]) - size / 2.,
+        f64::from(pos[1]) - size / 2.,
+        size,
+        size,
+    ];
+    ellipse(GHOST_COLOR, r, ctx.transform, g);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    /// Thrust in a different combination of directions every few steps.
+    fn script() -> Vec<Thrust> {
+        (0..50u8).map(|i| Thrust::from_bits(i / 3 % 16)).collect()
+    }
+
+    #[test]
+    fn replay_plays_back_what_was_recorded() {
+        let mut replay = Replay::new("Level", false);
+        for (i, &thrust) in script().iter().enumerate() {
+            replay.record(thrust, Vector2::new(i as f32, 0.));
+        }
+        assert_eq!(replay.len(), 50);
+        assert_eq!(replay.position_at(4), Some(Vector2::new(4., 0.)));
+        assert_eq!(replay.position_at(6), Some(Vector2::new(6., 0.)));
+        assert_eq!(replay.position_at(60), None);
+        let mut player = replay.play();
+        let played: Vec<_> = player.by_ref().collect();
+        assert_eq!(played, script());
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn replay_survives_saving_and_loading() {
+        let path = env::temp_dir().join(format!("propan-replay-{}.replay", process::id()));
+        let mut replay = Replay::new("Level", false);
+        for (i, &thrust) in script().iter().enumerate() {
+            replay.record(thrust, Vector2::new(i as f32 * 1.5, -(i as f32)));
+        }
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, replay);
+        assert_eq!(loaded.level(), "Level");
+        assert_eq!(loaded.play().collect::<Vec<_>>(), script());
+    }
+}
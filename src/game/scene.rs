@@ -3,8 +3,9 @@
 //! handling point, thus enabling certain optimizations.
 
 use na::Vector2;
-use physics::Positioned;
+use physics::{Bounded, Positioned};
 use std::collections::HashMap;
+use util::rect_intersects;
 
 pub type Scene<P> = FlatScene<P>;
 pub type CellId = (i32, i32);
@@ -26,6 +27,20 @@ where
         FlatScene { props: iter.into_iter().collect() }
     }
 
+    /// the number of objects in the scene
+    pub fn len(&self) -> usize {
+        self.props.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.props.is_empty()
+    }
+
+    /// add an object to the scene
+    pub fn push(&mut self, prop: P) {
+        self.props.push(prop);
+    }
+
     /// obtain an iterator to all objects, regardless of the given position
     #[inline]
     pub fn at(&self, _: Vector2<f32>) -> impl Iterator<Item = &P> {
@@ -40,6 +55,17 @@ where
     }
 }
 
+impl<P> FlatScene<P>
+where
+    P: Bounded,
+{
+    /// obtain an iterator to all objects whose bounding box overlaps the
+    /// given rectangle (`[x, y, width, height]`)
+    pub fn in_rect(&self, rect: [f32; 4]) -> impl Iterator<Item = &P> {
+        self.props.iter().filter(move |p| rect_intersects(p.bounding_box(), rect))
+    }
+}
+
 impl<'a, P> IntoIterator for &'a FlatScene<P>
 {
     type IntoIter = ::std::slice::Iter<'a, P>;
@@ -0,0 +1,173 @@
+//! Module for the `SectorScene` type, a collection of props which are only
+//! created when needed.
+//!
+//! The map is split into square sectors. Every prop belongs to the sector
+//! of its position, and is kept as plain information until its sector is
+//! activated, for being seen by the camera or being near a ball. A few
+//! sectors which are no longer needed are kept around, least recently
+//! needed first out, so that moving back and forth along a sector's edge
+//! does not create and drop the same props over and over.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use physics::Bounded;
+use util::rect_intersects;
+use super::scene::CellId;
+
+/// The size of each sector, in map units.
+pub const SECTOR_SIZE: f32 = 512.;
+/// How many sectors no longer needed are kept active.
+pub const SPARE_SECTORS: usize = 8;
+
+/// A scene of props which are created from their information `I` as their
+/// sectors become active.
+#[derive(Debug)]
+pub struct SectorScene<I, P> {
+    sector_size: f32,
+    spare: usize,
+    /// the information of every prop, by sector
+    infos: HashMap<CellId, Vec<I>>,
+    /// the sectors of the props which reach into each sector from another
+    reaching: HashMap<CellId, Vec<CellId>>,
+    /// the props of the active sectors
+    active: BTreeMap<CellId, Vec<P>>,
+    /// the active sectors, from the least to the most recently needed
+    recent: VecDeque<CellId>,
+    len: usize,
+}
+
+impl<I, P> SectorScene<I, P>
+where
+    P: Bounded,
+{
+    /// Group the props into sectors by the bounding boxes which `bounds`
+    /// gives for their information. No sector is active at first.
+    pub fn new<T, F>(infos: T, bounds: F) -> Self
+    where
+        T: IntoIterator<Item = I>,
+        F: Fn(&I) -> [f32; 4],
+    {
+        let sector_size = SECTOR_SIZE;
+        let mut by_sector: HashMap<CellId, Vec<I>> = HashMap::new();
+        let mut reaching: HashMap<CellId, HashSet<CellId>> = HashMap::new();
+        let mut len = 0;
+        for info in infos {
+            let r = bounds(&info);
+            let home = to_sector(r[0], r[1], sector_size);
+            let last = to_sector(r[0] + r[2], r[1] + r[3], sector_size);
+            for y in home.1..=last.1 {
+                for x in home.0..=last.0 {
+                    if (x, y) != home {
+                        reaching.entry((x, y)).or_default().insert(home);
+                    }
+                }
+            }
+            by_sector.entry(home).or_default().push(info);
+            len += 1;
+        }
+        SectorScene {
+            sector_size,
+            spare: SPARE_SECTORS,
+            infos: by_sector,
+            reaching: reaching
+                .into_iter()
+                .map(|(sector, homes)| (sector, homes.into_iter().collect()))
+                .collect(),
+            active: BTreeMap::new(),
+            recent: VecDeque::new(),
+            len,
+        }
+    }
+
+    /// the number of props in the scene, active or not
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the number of props which currently exist
+    pub fn active_len(&self) -> usize {
+        self.active.values().map(Vec::len).sum()
+    }
+
+    /// the number of active sectors
+    pub fn active_sectors(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Make sure that every prop overlapping the given rectangles
+    /// (`[x, y, width, height]`) exists, creating them with `make`, and
+    /// drop the props of sectors which have not been needed for a while.
+    pub fn activate<F, E>(&mut self, rects: &[[f32; 4]], mut make: F) -> Result<(), E>
+    where
+        F: FnMut(&I) -> Result<P, E>,
+    {
+        let mut needed = Vec::new();
+        for r in rects {
+            let first = to_sector(r[0], r[1], self.sector_size);
+            let last = to_sector(r[0] + r[2], r[1] + r[3], self.sector_size);
+            for y in first.1..=last.1 {
+                for x in first.0..=last.0 {
+                    needed.push((x, y));
+                    needed.extend(self.reaching.get(&(x, y)).into_iter().flatten());
+                }
+            }
+        }
+        needed.retain(|sector| self.infos.contains_key(sector));
+        needed.sort();
+        needed.dedup();
+
+        for &sector in &needed {
+            if !self.active.contains_key(&sector) {
+                let props = self.infos[&sector].iter().map(&mut make).collect::<Result<Vec<_>, E>>()?;
+                self.active.insert(sector, props);
+            }
+            // mark the sector as the most recently needed
+            self.recent.retain(|&s| s != sector);
+            self.recent.push_back(sector);
+        }
+
+        // the needed sectors are at the back, so the front is the oldest
+        while self.recent.len() > needed.len() + self.spare {
+            if let Some(sector) = self.recent.pop_front() {
+                self.active.remove(&sector);
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop all props, so that they are created again when needed.
+    pub fn deactivate_all(&mut self) {
+        self.active.clear();
+        self.recent.clear();
+    }
+
+    /// obtain an iterator to all existing props
+    pub fn iter(&self) -> impl Iterator<Item = &P> {
+        self.active.values().flatten()
+    }
+
+    /// obtain a mutable iterator to all existing props
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut P> {
+        self.active.values_mut().flatten()
+    }
+
+    /// obtain an iterator to all existing props whose bounding box overlaps
+    /// the given rectangle (`[x, y, width, height]`)
+    pub fn in_rect(&self, rect: [f32; 4]) -> impl Iterator<Item = &P> {
+        self.iter().filter(move |p| rect_intersects(p.bounding_box(), rect))
+    }
+
+    /// obtain a mutable iterator to all existing props whose bounding box
+    /// overlaps the given rectangle
+    pub fn in_rect_mut(&mut self, rect: [f32; 4]) -> impl Iterator<Item = &mut P> {
+        self.iter_mut().filter(move |p| rect_intersects(p.bounding_box(), rect))
+    }
+}
+
+#[inline]
+fn to_sector(x: f32, y: f32, sector_size: f32) -> CellId {
+    ((x / sector_size).floor() as i32, (y / sector_size).floor() as i32)
+}
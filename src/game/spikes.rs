@@ -0,0 +1,135 @@
+//! Module for spike strips: hazards along one face of a wall.
+
+use graphics::{line, polygon, rectangle, Context, Graphics, Rectangle};
+use na::{dot, norm_squared, Vector2};
+use level::info::{Direction, SpikeInfo};
+use physics::{rect_circle_collision, AnimatedObject, Bounded, Collidable, CollisionInfo, Positioned};
+use util::vec_f32;
+use super::palette::{Palette, STRIPE_SPACING, STRIPE_WIDTH};
+
+/// The damage taken by the ball when hitting the spiked face.
+pub const SPIKE_DAMAGE: f32 = 3.0;
+/// The width of each spike, in pixels.
+const SPIKE_WIDTH: f32 = 6.;
+/// The fraction of the strip's depth taken by its base.
+const BASE_FRACTION: f32 = 0.3;
+
+pub struct SpikeStrip {
+    pos: Vector2<f32>,
+    dim: Vector2<f32>,
+    facing: Direction,
+}
+
+impl SpikeStrip {
+    pub fn new(info: SpikeInfo) -> Self {
+        SpikeStrip {
+            pos: vec_f32(info.pos),
+            dim: vec_f32(info.dim),
+            facing: info.facing,
+        }
+    }
+
+    pub fn facing(&self) -> Direction {
+        self.facing
+    }
+
+    /// Check whether the given collision overlap hits the spiked face,
+    /// rather than one of the other faces or a corner.
+    fn hits_spikes(&self, overlap: Vector2<f32>) -> bool {
+        let along = dot(&overlap, &self.facing.normal());
+        along > 0. && along * along * 2. >= norm_squared(&overlap)
+    }
+
+    /// Convert a point in the strip's own frame, where `u` runs along the
+    /// spiked face and `v` grows towards it, to map coordinates.
+    fn to_map(&self, u: f32, v: f32) -> [f64; 2] {
+        let (x0, y0) = (self.pos[0], self.pos[1]);
+        let (x1, y1) = (x0 + self.dim[0], y0 + self.dim[1]);
+        let (x, y) = match self.facing {
+            Direction::Up => (x0 + u, y1 - v),
+            Direction::Down => (x0 + u, y0 + v),
+            Direction::Left => (x1 - v, y0 + u),
+            Direction::Right => (x0 + v, y0 + u),
+        };
+        [f64::from(x), f64::from(y)]
+    }
+
+    pub fn draw<G: Graphics>(&self, ctx: Context, palette: &Palette, g: &mut G) {
+        let (length, depth) = if self.facing.is_horizontal() {
+            (self.dim[1], self.dim[0])
+        } else {
+            (self.dim[0], self.dim[1])
+        };
+        let base = depth * BASE_FRACTION;
+
+        let a = self.to_map(0., 0.);
+        let b = self.to_map(length, base);
+        let r = [
+            f64::min(a[0], b[0]),
+            f64::min(a[1], b[1]),
+            (a[0] - b[0]).abs(),
+            (a[1] - b[1]).abs(),
+        ];
+        rectangle(palette.spike_base, r, ctx.transform, g);
+        if let Some(color) = palette.hazard_stripes {
+            // diagonal stripes across the base
+            let mut u = 0.;
+            while u + base <= length {
+                let a = self.to_map(u, 0.);
+                let b = self.to_map(u + base, base);
+                line(color, STRIPE_WIDTH, [a[0], a[1], b[0], b[1]], ctx.transform, g);
+                u += STRIPE_SPACING;
+            }
+        }
+
+        let n = usize::max(1, (length / SPIKE_WIDTH).round() as usize);
+        let w = length / n as f32;
+        for i in 0..n {
+            let u = i as f32 * w;
+            let tooth = [
+                self.to_map(u, base),
+                self.to_map(u + w / 2., depth),
+                self.to_map(u + w, base),
+            ];
+            polygon(palette.spike, &tooth, ctx.transform, g);
+        }
+
+        if let Some(color) = palette.hazard_outline {
+            let r = [
+                f64::from(self.pos[0]),
+                f64::from(self.pos[1]),
+                f64::from(self.dim[0]),
+                f64::from(self.dim[1]),
+            ];
+            Rectangle::new_border(color, 0.5).draw(r, &ctx.draw_state, ctx.transform, g);
+        }
+    }
+}
+
+impl Positioned for SpikeStrip {
+    fn position(&self) -> Vector2<f32> {
+        self.pos
+    }
+}
+
+impl Bounded for SpikeStrip {
+    fn bounding_box(&self) -> [f32; 4] {
+        [self.pos[0], self.pos[1], self.dim[0], self.dim[1]]
+    }
+}
+
+impl Collidable for SpikeStrip {
+    fn test_circle_collision(&self, position: Vector2<f32>, radius: f32) -> CollisionInfo {
+        rect_circle_collision(self.pos, self.dim, position, radius)
+    }
+
+    fn on_collision<A>(&mut self, ball: &mut A, overlap: Vector2<f32>)
+    where
+        A: AnimatedObject,
+    {
+        ball.issue_bounce(overlap, [0., 0.].into());
+        if self.hits_spikes(overlap) {
+            ball.damage(SPIKE_DAMAGE);
+        }
+    }
+}
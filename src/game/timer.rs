@@ -0,0 +1,136 @@
+//! Module for the speedrun timer overlay.
+
+use graphics::{Context, DrawState, Ellipse, Graphics, Text, Transformed};
+use graphics::character::CharacterCache;
+use graphics::math::Matrix2d;
+use level::info::Medal;
+use progress::LevelProgress;
+
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const AHEAD_COLOR: [f32; 4] = [0.4, 1.0, 0.4, 1.0];
+const BEHIND_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+/// The most recent splits shown on the screen.
+const MAX_SPLITS_SHOWN: usize = 6;
+
+/// Format a time in seconds as minutes, seconds and hundredths.
+pub fn format_time(seconds: f32) -> String {
+    let seconds = f32::max(0., seconds);
+    format!("{}:{:05.2}", (seconds / 60.) as u32, seconds % 60.)
+}
+
+/// Draw a medal's icon, a disc of its color, centered on the given point.
+pub fn draw_medal<G: Graphics>(medal: Medal, center: [f64; 2], radius: f64, transform: Matrix2d, g: &mut G) {
+    let r = [center[0] - radius, center[1] - radius, radius * 2., radius * 2.];
+    Ellipse::new(medal.color()).draw(r, &DrawState::default(), transform, g);
+    Ellipse::new_border([0., 0., 0., 0.6], 0.5).draw(r, &DrawState::default(), transform, g);
+}
+
+/// A clock which starts on the player's first move, recording split times
+/// along the way, to be compared with the best run on the level.
+#[derive(Debug, Clone, Default)]
+pub struct SpeedrunTimer {
+    /// the time at which the clock started
+    start: Option<f32>,
+    /// the time at which the clock stopped
+    end: Option<f32>,
+    /// the split times, relative to the start
+    splits: Vec<f32>,
+    best: LevelProgress,
+    visible: bool,
+}
+
+impl SpeedrunTimer {
+    pub fn new(best: Option<&LevelProgress>) -> Self {
+        SpeedrunTimer {
+            best: best.cloned().unwrap_or_default(),
+            ..SpeedrunTimer::default()
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Start the clock at the given time, unless it already started.
+    pub fn start(&mut self, now: f32) {
+        if self.start.is_none() {
+            self.start = Some(now);
+        }
+    }
+
+    /// The time on the clock.
+    pub fn elapsed(&self, now: f32) -> f32 {
+        match self.start {
+            Some(start) => self.end.unwrap_or(now) - start,
+            None => 0.,
+        }
+    }
+
+    pub fn splits(&self) -> &[f32] {
+        &self.splits
+    }
+
+    /// Record a split at the given time.
+    pub fn split(&mut self, now: f32) {
+        if self.end.is_none() {
+            let t = self.elapsed(now);
+            self.splits.push(t);
+        }
+    }
+
+    /// Stop the clock with a final split, returning the final time.
+    pub fn finish(&mut self, now: f32) -> f32 {
+        self.split(now);
+        self.end = Some(now);
+        self.elapsed(now)
+    }
+
+    /// Draw the clock and the latest splits at the top-left corner of the
+    /// screen, if visible.
+    pub fn draw<C, G>(&self, ctx: Context, now: f32, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache,
+        G: Graphics<Texture = C::Texture>,
+    {
+        if !self.visible {
+            return;
+        }
+        let _ = Text::new_color(TEXT_COLOR, 16).draw(
+            &format_time(self.elapsed(now)),
+            cache,
+            &DrawState::default(),
+            ctx.transform.trans(12., 24.),
+            g,
+        );
+        let first = self.splits.len().saturating_sub(MAX_SPLITS_SHOWN);
+        for (row, (i, t)) in self.splits.iter().enumerate().skip(first).enumerate() {
+            let y = 44. + 14. * row as f64;
+            let _ = Text::new_color(TEXT_COLOR, 10).draw(
+                &format_time(*t),
+                cache,
+                &DrawState::default(),
+                ctx.transform.trans(12., y),
+                g,
+            );
+            if let Some(best) = self.best.best_splits.get(i) {
+                let delta = t - best;
+                let color = if delta <= 0. { AHEAD_COLOR } else { BEHIND_COLOR };
+                let _ = Text::new_color(color, 10).draw(
+                    &format!("{:+.2}", delta),
+                    cache,
+                    &DrawState::default(),
+                    ctx.transform.trans(80., y),
+                    g,
+                );
+            }
+        }
+    }
+}
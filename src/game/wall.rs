@@ -1,9 +1,34 @@
-use graphics::{Context, DrawState, Graphics, Image, Transformed, ImageSize};
-use physics::{AnimatedObject, Collidable, CollisionInfo, Positioned};
-use na::{norm_squared, Vector2};
-use resource::{GameTexture, ResourceManage, Result};
+use graphics::{Context, Graphics, Transformed, ImageSize};
+use physics::{rect_circle_collision, AnimatedObject, Bounded, Collidable, CollisionInfo, Positioned};
+use na::Vector2;
+use resource::{GameSprite, GameTexture, ResourceManage, Result};
 use resource::sprite::{AssetId, SpriteManage};
 use level::info::WallInfo;
+use util::{rect_contains, vec_f32};
+
+/// The most tiles drawn for a single tiled wall. Longer walls are drawn with
+/// larger tiles instead.
+pub const MAX_WALL_TILES: f64 = 256.;
+
+/// Obtain the scale at which a texture of the given size is tiled across
+/// the given extent, doubling it until no more than `MAX_WALL_TILES` tiles
+/// are needed.
+pub fn tile_scale(texture: (f64, f64), extent: (f64, f64)) -> f64 {
+    let mut scale = 1.;
+    while (extent.0 / (texture.0 * scale)).ceil() * (extent.1 / (texture.1 * scale)).ceil() > MAX_WALL_TILES {
+        scale *= 2.;
+    }
+    scale
+}
+
+/// Obtain the indices of the given walls ordered by texture, so that walls
+/// sharing a texture are drawn one after the other. Walls with the same
+/// texture keep their relative order.
+pub fn draw_order(walls: &[WallInfo]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..walls.len()).collect();
+    order.sort_by_key(|&i| walls[i].texture_id);
+    order
+}
 
 pub struct Wall<R>
 where
@@ -11,7 +36,11 @@ where
 {
     pos: Vector2<f32>,
     dim: Vector2<f32>,
-    gfx_tex: GameTexture<R>,
+    /// quarter turns clockwise, from 0 to 3
+    rotation: u8,
+    flip: bool,
+    tile: bool,
+    gfx_tex: GameSprite<R>,
 }
 
 impl<R> Wall<R>
@@ -19,10 +48,13 @@ where
     R: ResourceManage,
 {
     pub fn new(info: WallInfo, res: R) -> Result<Self> {
-        let gfx_tex = res.sprite().get_sprite(AssetId::Other(info.texture_id))?;
+        let gfx_tex = res.sprite().get_sprite_region(AssetId::Other(info.texture_id))?;
         Ok(Wall {
-            pos: Vector2::new(info.pos[0] as f32, info.pos[1] as f32),
-            dim: Vector2::new(info.dim[0] as f32, info.dim[1] as f32),
+            pos: vec_f32(info.pos),
+            dim: vec_f32(info.dim),
+            rotation: info.rotation % 4,
+            flip: info.flip,
+            tile: info.tile,
             gfx_tex,
         })
     }
@@ -35,15 +67,40 @@ where
     where
         G: Graphics<Texture=GameTexture<R>>,
     {
-        let (x, y) = (self.pos[0] as f64, self.pos[1] as f64);
+        let center = self.pos + self.dim / 2.;
         let (w, h) = self.gfx_tex.get_size();
-        let (w, h) = (w as f32, h as f32);
-        let w_scale = self.dim[0] / w;
-        let h_scale = self.dim[1] / h;
+        let (w, h) = (w as f64, h as f64);
+        // a quarter turn lays the texture's width along the wall's height
+        let (dim_w, dim_h) = if self.rotation % 2 == 1 {
+            (self.dim[1] as f64, self.dim[0] as f64)
+        } else {
+            (self.dim[0] as f64, self.dim[1] as f64)
+        };
         let ctx = ctx
-            .trans(x as f64, y as f64)
-            .scale(w_scale.into(), h_scale.into());
-        Image::new().draw(&self.gfx_tex, &DrawState::default(), ctx.transform, g);
+            .trans(center[0] as f64, center[1] as f64)
+            .rot_deg(90. * f64::from(self.rotation))
+            .scale(if self.flip { -1. } else { 1. }, 1.)
+            .trans(-dim_w / 2., -dim_h / 2.);
+        if self.tile && w > 0. && h > 0. {
+            let scale = tile_scale((w, h), (dim_w, dim_h));
+            let (tile_w, tile_h) = (w * scale, h * scale);
+            let mut y = 0.;
+            while y < dim_h {
+                let mut x = 0.;
+                while x < dim_w {
+                    // the last tile of each row and column is cut short
+                    let part_w = f64::min(w, (dim_w - x) / scale);
+                    let part_h = f64::min(h, (dim_h - y) / scale);
+                    let tile_ctx = ctx.trans(x, y).scale(scale, scale);
+                    self.gfx_tex.draw_part(part_w, part_h, tile_ctx.transform, g);
+                    x += tile_w;
+                }
+                y += tile_h;
+            }
+        } else {
+            let ctx = ctx.scale(dim_w / w, dim_h / h);
+            self.gfx_tex.draw(ctx.transform, g);
+        }
     }
 }
 
@@ -56,33 +113,25 @@ where
     }
 }
 
+impl<R> Bounded for Wall<R>
+where
+    R: ResourceManage,
+{
+    fn bounding_box(&self) -> [f32; 4] {
+        [self.pos[0], self.pos[1], self.dim[0], self.dim[1]]
+    }
+}
+
 impl<R> Collidable for Wall<R>
 where
     R: ResourceManage,
 {
     fn test_circle_collision(&self, position: Vector2<f32>, radius: f32) -> CollisionInfo {
-        let br = self.pos + self.dim;
-        let nearest_x = f32::max(self.pos[0], f32::min(position[0], br[0]));
-        let nearest_y = f32::max(self.pos[1], f32::min(position[1], br[1]));
-
-        let nearest_point: Vector2<_> = [nearest_x, nearest_y].into();
-        let delta_vector = position - nearest_point;
-
-        let dist_sqr = norm_squared(&delta_vector);
-        if dist_sqr <= radius * radius {
-            // adjust delta vector to have overlap magnitude
-            let dist = f32::sqrt(dist_sqr);
-            let newdistance_inv = (radius - dist) / dist;
-            CollisionInfo::Yes(delta_vector * newdistance_inv)
-        } else {
-            CollisionInfo::No
-        }
+        rect_circle_collision(self.pos, self.dim, position, radius)
     }
     
     fn test_point_collision_simple(&self, position: Vector2<f32>) -> bool {
-        let tl = self.pos;
-        let br = self.pos + self.dim;
-        position >= tl && position <= br
+        rect_contains(self.bounding_box(), position)
     }
 
     #[inline]
@@ -90,6 +139,6 @@ where
     where
         A: AnimatedObject,
     {
-        ball.issue_bounce(overlap)
+        ball.issue_bounce(overlap, [0., 0.].into())
     }
 }
@@ -0,0 +1,105 @@
+//! Module for water: regions which slow the ball down and push it upwards.
+
+use graphics::{line, rectangle, Context, Graphics};
+use na::Vector2;
+use level::info::WaterInfo;
+use physics::{Bounded, Positioned};
+use util::vec_f32;
+
+/// The fraction of the ball's velocity lost in each tick while fully
+/// submerged.
+pub const WATER_DRAG: f32 = 0.03;
+/// The upward acceleration of a fully submerged ball, per tick.
+pub const WATER_BUOYANCY: f32 = 0.012;
+/// The fraction of the ball's thrust which remains effective while fully
+/// submerged.
+pub const WATER_THRUST_FACTOR: f32 = 0.6;
+const WATER_COLOR: [f32; 4] = [0.2, 0.45, 0.9, 0.35];
+const SURFACE_COLOR: [f32; 4] = [0.7, 0.85, 1.0, 0.7];
+/// The horizontal distance between the points of the surface line.
+const SURFACE_STEP: f32 = 4.;
+const WAVE_HEIGHT: f32 = 1.;
+const WAVE_LENGTH: f32 = 24.;
+/// How fast the surface waves move, in radians per tick.
+const WAVE_SPEED: f32 = 0.08;
+
+pub struct Water {
+    pos: Vector2<f32>,
+    dim: Vector2<f32>,
+    /// the phase of the surface animation
+    phase: f32,
+}
+
+impl Water {
+    pub fn new(info: WaterInfo) -> Self {
+        Water {
+            pos: vec_f32(info.pos),
+            dim: vec_f32(info.dim),
+            phase: 0.,
+        }
+    }
+
+    pub fn dimensions(&self) -> Vector2<f32> {
+        self.dim
+    }
+
+    /// Check whether a point lies inside the water.
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        let br = self.pos + self.dim;
+        point >= self.pos && point <= br
+    }
+
+    /// Obtain how submerged a ball is, from 0 when its center is out of the
+    /// water or at the surface, to 1 when it is at least one ball size
+    /// below the surface.
+    pub fn submersion(&self, position: Vector2<f32>, size: f32) -> f32 {
+        if !self.contains(position) || size <= 0. {
+            return 0.;
+        }
+        let depth = position[1] - self.pos[1];
+        f32::min(1., depth / size)
+    }
+
+    pub fn update(&mut self, ticks: f32) {
+        self.phase += WAVE_SPEED * ticks;
+    }
+
+    /// Draw the water. This should be done after drawing the ball, so that
+    /// it appears to be submerged.
+    pub fn draw<G: Graphics>(&self, ctx: Context, g: &mut G) {
+        let r = [
+            f64::from(self.pos[0]),
+            f64::from(self.pos[1]),
+            f64::from(self.dim[0]),
+            f64::from(self.dim[1]),
+        ];
+        rectangle(WATER_COLOR, r, ctx.transform, g);
+
+        // a wavy line along the surface
+        let wave_y = |x: f32| {
+            let y = (x / WAVE_LENGTH * 2. * ::std::f32::consts::PI + self.phase).sin() * WAVE_HEIGHT;
+            f64::from(self.pos[1] + y)
+        };
+        let right = self.pos[0] + self.dim[0];
+        let mut x = self.pos[0];
+        while x < right {
+            let next = f32::min(right, x + SURFACE_STEP);
+            let l = [f64::from(x), wave_y(x), f64::from(next), wave_y(next)];
+            line(SURFACE_COLOR, 0.5, l, ctx.transform, g);
+            x = next;
+        }
+    }
+}
+
+impl Positioned for Water {
+    fn position(&self) -> Vector2<f32> {
+        self.pos
+    }
+}
+
+impl Bounded for Water {
+    fn bounding_box(&self) -> [f32; 4] {
+        // include the waves above the surface
+        [self.pos[0], self.pos[1] - WAVE_HEIGHT, self.dim[0], self.dim[1] + WAVE_HEIGHT]
+    }
+}
@@ -0,0 +1,151 @@
+//! Module for translating the text shown on screen.
+//!
+//! Each language is a JSON file in the `lang` directory, named after its
+//! code (such as `lang/pt.json`), which maps keys to strings. Strings may
+//! contain `{}` placeholders, which `tr_fmt` fills in order. Keys missing
+//! from a language fall back to English, which is built into the game.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::{read_dir, File};
+use std::path::{Path, PathBuf};
+use serde_json::{from_reader, from_str};
+use util::DynResult;
+
+/// The directory in which language files are kept.
+pub const LANG_DIR: &str = "lang";
+/// The code of the language which every other language falls back to.
+pub const DEFAULT_LANGUAGE: &str = "en";
+/// A copy of the English strings built into the game.
+static ENGLISH: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/lang/en.json"));
+
+/// The strings of a language.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Language {
+    /// the language's name, in the language itself
+    pub name: String,
+    /// a font file for the language's script, instead of the default font
+    #[serde(default)] pub font: Option<String>,
+    #[serde(default)] pub strings: HashMap<String, String>,
+}
+
+impl Language {
+    pub fn path_of(code: &str) -> PathBuf {
+        Path::new(LANG_DIR).join(code).with_extension("json")
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+        let file = File::open(path)?;
+        from_reader(file).map_err(From::from)
+    }
+
+    fn english() -> Self {
+        from_str(ENGLISH).expect("the built-in English strings should be valid")
+    }
+}
+
+/// The language in use, along with the English fallback.
+struct Translator {
+    code: String,
+    language: Language,
+    english: Language,
+}
+
+impl Default for Translator {
+    fn default() -> Self {
+        let english = Language::english();
+        Translator {
+            code: DEFAULT_LANGUAGE.to_string(),
+            language: english.clone(),
+            english,
+        }
+    }
+}
+
+thread_local! {
+    static TRANSLATOR: RefCell<Translator> = RefCell::new(Translator::default());
+}
+
+/// List the codes of the available languages, in alphabetical order.
+/// English is always available.
+pub fn available_languages() -> Vec<String> {
+    let mut codes: Vec<String> = read_dir(LANG_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+                .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    if !codes.iter().any(|c| c == DEFAULT_LANGUAGE) {
+        codes.push(DEFAULT_LANGUAGE.to_string());
+    }
+    codes.sort();
+    codes
+}
+
+/// Switch to the language with the given code. English is built in, so
+/// it is used even if its file is missing.
+pub fn set_language(code: &str) -> DynResult<()> {
+    let path = Language::path_of(code);
+    let language = if code == DEFAULT_LANGUAGE && !path.is_file() {
+        Language::english()
+    } else {
+        Language::load(&path)?
+    };
+    TRANSLATOR.with(|t| {
+        let mut t = t.borrow_mut();
+        t.code = code.to_string();
+        t.language = language;
+    });
+    Ok(())
+}
+
+/// The code of the language in use.
+pub fn current_language() -> String {
+    TRANSLATOR.with(|t| t.borrow().code.clone())
+}
+
+/// The name of the language in use, in the language itself.
+pub fn language_name() -> String {
+    TRANSLATOR.with(|t| t.borrow().language.name.clone())
+}
+
+/// The font which the language in use asks for, if any.
+pub fn language_font() -> Option<String> {
+    TRANSLATOR.with(|t| t.borrow().language.font.clone())
+}
+
+/// Translate the string with the given key. Keys which are not even in
+/// English are returned as they are, so that they stand out.
+pub fn tr(key: &str) -> String {
+    TRANSLATOR.with(|t| {
+        let t = t.borrow();
+        t.language
+            .strings
+            .get(key)
+            .or_else(|| t.english.strings.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    })
+}
+
+/// Translate the string with the given key, replacing each `{}` with the
+/// next argument.
+pub fn tr_fmt(key: &str, args: &[&Display]) -> String {
+    let text = tr(key);
+    let mut parts = text.split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        match args.next() {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str("{}"),
+        }
+        out.push_str(part);
+    }
+    out
+}
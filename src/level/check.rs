@@ -0,0 +1,336 @@
+//! Module for checking the integrity of game levels.
+
+use na::Vector2;
+use game::ball::BALL_DEFAULT_SIZE;
+use util::vec_f32;
+use super::GameLevel;
+use super::info::Goal;
+use super::occupancy::{Cell, OccupancyGrid};
+
+/// Largest coordinate or dimension deemed sane for a wall.
+pub const MAX_WALL_EXTENT: i32 = 1 << 16;
+/// How many cells of the reachability grid fit across the ball.
+const CELLS_PER_BALL: f32 = 4.;
+
+/// A problem found in a game level.
+#[derive(Debug, Clone, PartialEq, Fail)]
+pub enum LevelIssue {
+    #[fail(display = "Level has no finish flag")]
+    NoFinishFlag,
+    #[fail(display = "Level requires collecting all gems, but has none")]
+    NoGemsToCollect,
+    #[fail(display = "Finish flag requires {} gems, but the level only has {}", required, available)]
+    NotEnoughGems {
+        required: u32,
+        available: u32,
+    },
+    #[fail(display = "Ball position ({}, {}) is outside of the map", x, y)]
+    BallOutOfMap {
+        x: i32,
+        y: i32,
+    },
+    #[fail(display = "Wall #{} has an invalid dimension ({}, {})", index, w, h)]
+    BadWallDimensions {
+        index: usize,
+        w: i32,
+        h: i32,
+    },
+    #[fail(display = "Wall #{} at ({}, {}) is out of bounds", index, x, y)]
+    WallOutOfBounds {
+        index: usize,
+        x: i32,
+        y: i32,
+    },
+    #[fail(display = "Gem #{} at ({}, {}) cannot be reached by the ball", index, x, y)]
+    UnreachableGem {
+        index: usize,
+        x: i32,
+        y: i32,
+    },
+    #[fail(display = "Finish flag cannot be reached by the ball")]
+    UnreachableFinish,
+    #[fail(display = "Finish flag requires {} gems, but the ball can only reach {}", required, reachable)]
+    NotEnoughReachableGems {
+        required: u32,
+        reachable: u32,
+    },
+}
+
+/// Which of the objects the ball must touch it can actually get to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReachabilityReport {
+    /// whether each gem can be reached, in the order of the level's gems
+    pub gems: Vec<bool>,
+    /// whether the finish flag can be reached, if there is one
+    pub finish: Option<bool>,
+    /// the number of gems required by the finish flag, if there is one
+    pub gems_required: Option<u32>,
+}
+
+impl ReachabilityReport {
+    pub fn reachable_gems(&self) -> u32 {
+        self.gems.iter().filter(|&&r| r).count() as u32
+    }
+
+    /// Whether the ball can reach as many gems as the finish flag requires.
+    pub fn enough_gems(&self) -> bool {
+        self.gems_required
+            .map(|required| self.reachable_gems() >= required)
+            .unwrap_or(true)
+    }
+
+    /// List the objects which cannot be reached as issues.
+    pub fn issues(&self, level: &GameLevel) -> Vec<LevelIssue> {
+        let mut issues: Vec<LevelIssue> = self.gems
+            .iter()
+            .zip(level.gems())
+            .enumerate()
+            .filter(|&(_, (&reachable, _))| !reachable)
+            .map(|(index, (_, gem))| LevelIssue::UnreachableGem {
+                index,
+                x: gem.pos[0],
+                y: gem.pos[1],
+            })
+            .collect();
+        if self.finish == Some(false) {
+            issues.push(LevelIssue::UnreachableFinish);
+        }
+        if !self.enough_gems() {
+            issues.push(LevelIssue::NotEnoughReachableGems {
+                required: self.gems_required.unwrap_or_default(),
+                reachable: self.reachable_gems(),
+            });
+        }
+        issues
+    }
+}
+
+/// Find which gems and whether the finish flag can be reached from the
+/// ball's starting position.
+///
+/// The walls are rasterized into a grid of cells a fraction of the ball's
+/// size, and the ball's center may only move through cells far enough
+/// from the walls and the map's borders for the ball to fit. An object
+/// counts as reached if the ball can get close enough to touch it. Moving
+/// walls are not considered, nor are hazards in the way.
+pub fn check_reachability(level: &GameLevel) -> ReachabilityReport {
+    let ball_size = level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+    let cell_size = u32::max(1, (ball_size / CELLS_PER_BALL) as u32);
+    let radius = ball_size / 2. / cell_size as f32;
+    let extent = (radius + 0.5).ceil() as u32;
+
+    let grid = OccupancyGrid::from_level(level, cell_size).inflated(extent);
+    let reached = grid
+        .cell_at(level.ball_position())
+        .map(|start| grid.flood_fill(start))
+        .unwrap_or_default();
+    let is_reached = |(x, y): Cell| reached.get(y * grid.cols() + x).map(|p| p.is_some()).unwrap_or(false);
+    let can_touch = |pos: Vector2<i32>| {
+        let cell = match grid.cell_at(vec_f32(pos)) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        let e = extent as usize;
+        let (x0, y0) = (cell.0.saturating_sub(e), cell.1.saturating_sub(e));
+        let (x1, y1) = (usize::min(cell.0 + e, grid.cols() - 1), usize::min(cell.1 + e, grid.rows() - 1));
+        (y0..=y1).any(|y| (x0..=x1).any(|x| is_reached((x, y))))
+    };
+
+    ReachabilityReport {
+        gems: level.gems().iter().map(|gem| can_touch(gem.pos)).collect(),
+        finish: level.finish_flag().map(|f| can_touch(f.pos)),
+        gems_required: level.finish_flag().map(|f| f.gems_required),
+    }
+}
+
+/// Check the given level for invariant violations, returning all issues
+/// found.
+pub fn check_level(level: &GameLevel) -> Vec<LevelIssue> {
+    let mut issues = Vec::new();
+
+    match (level.goal(), level.finish_flag()) {
+        (Goal::ReachFinish, None) => issues.push(LevelIssue::NoFinishFlag),
+        (Goal::CollectAllGems, _) if level.gems().is_empty() => {
+            issues.push(LevelIssue::NoGemsToCollect)
+        }
+        (_, None) => {}
+        (_, Some(f)) => {
+            let available = level.gems().len() as u32;
+            if f.gems_required > available {
+                issues.push(LevelIssue::NotEnoughGems {
+                    required: f.gems_required,
+                    available,
+                });
+            }
+        }
+    }
+
+    let map_dim = level.map().dimensions_f32();
+    let ball_pos = level.ball_position();
+    if ball_pos[0] < 0. || ball_pos[1] < 0. || ball_pos[0] > map_dim[0] || ball_pos[1] > map_dim[1] {
+        issues.push(LevelIssue::BallOutOfMap {
+            x: ball_pos[0] as i32,
+            y: ball_pos[1] as i32,
+        });
+    }
+
+    for (index, wall) in level.walls().iter().enumerate() {
+        let (w, h) = (wall.dim[0], wall.dim[1]);
+        if w <= 0 || h <= 0 || w > MAX_WALL_EXTENT || h > MAX_WALL_EXTENT {
+            issues.push(LevelIssue::BadWallDimensions { index, w, h });
+        }
+        let (x, y) = (wall.pos[0], wall.pos[1]);
+        if x.abs() > MAX_WALL_EXTENT || y.abs() > MAX_WALL_EXTENT {
+            issues.push(LevelIssue::WallOutOfBounds { index, x, y });
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::info::{FinishInfo, GemInfo, WallInfo};
+    use na::Vector2;
+
+    fn wall(pos: [i32; 2], dim: [i32; 2]) -> WallInfo {
+        WallInfo {
+            pos: pos.into(),
+            dim: dim.into(),
+            texture_id: 0,
+            rotation: 0,
+            flip: false,
+            tile: false,
+        }
+    }
+
+    /// A 320x200 level with the ball at (36, 36) and the finish flag at
+    /// the other end.
+    fn valid_level() -> GameLevel {
+        let mut level = GameLevel::default();
+        level.set_finish_flag(FinishInfo {
+            pos: [280, 160].into(),
+            gems_required: 0,
+        });
+        level
+    }
+
+    #[test]
+    fn valid_level_has_no_issues() {
+        assert_eq!(valid_level().validate(), vec![]);
+    }
+
+    #[test]
+    fn walls_without_area_are_rejected() {
+        let mut level = valid_level();
+        level.walls_mut().push(wall([0, 180], [320, 20]));
+        level.walls_mut().push(wall([100, 100], [0, 20]));
+        level.walls_mut().push(wall([100, 100], [20, -4]));
+        assert_eq!(
+            level.validate(),
+            vec![
+                LevelIssue::BadWallDimensions { index: 1, w: 0, h: 20 },
+                LevelIssue::BadWallDimensions { index: 2, w: 20, h: -4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn ball_outside_the_map_is_rejected() {
+        let mut level = valid_level();
+        // the edges of the map are still inside
+        level.set_ball_position(Vector2::new(320., 0.));
+        assert_eq!(level.validate(), vec![]);
+
+        level.set_ball_position(Vector2::new(-1., 100.));
+        assert_eq!(level.validate(), vec![LevelIssue::BallOutOfMap { x: -1, y: 100 }]);
+        level.set_ball_position(Vector2::new(100., 201.));
+        assert_eq!(level.validate(), vec![LevelIssue::BallOutOfMap { x: 100, y: 201 }]);
+    }
+
+    #[test]
+    fn level_without_a_goal_is_rejected() {
+        let mut level = valid_level();
+        level.clear_finish_flag();
+        assert_eq!(level.validate(), vec![LevelIssue::NoFinishFlag]);
+
+        // collecting all gems needs no flag, but it does need gems
+        level.set_goal(Goal::CollectAllGems);
+        assert_eq!(level.validate(), vec![LevelIssue::NoGemsToCollect]);
+        level.gems_mut().push(GemInfo { pos: [100, 100].into() });
+        assert_eq!(level.validate(), vec![]);
+    }
+
+    #[test]
+    fn finish_requiring_missing_gems_is_rejected() {
+        let mut level = valid_level();
+        level.gems_mut().push(GemInfo { pos: [100, 100].into() });
+        level.finish_flag_mut().unwrap().gems_required = 2;
+        assert_eq!(
+            level.validate(),
+            vec![LevelIssue::NotEnoughGems {
+                required: 2,
+                available: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn everything_is_reachable_in_an_open_map() {
+        let mut level = valid_level();
+        level.gems_mut().push(GemInfo { pos: [160, 100].into() });
+        level.gems_mut().push(GemInfo { pos: [300, 20].into() });
+        level.finish_flag_mut().unwrap().gems_required = 2;
+        let report = level.check_reachability();
+        assert_eq!(report.gems, vec![true, true]);
+        assert_eq!(report.finish, Some(true));
+        assert!(report.enough_gems());
+        assert_eq!(report.issues(&level), vec![]);
+    }
+
+    #[test]
+    fn sealed_room_is_unreachable() {
+        let mut level = valid_level();
+        // a room in the bottom right corner, closed by the map's borders
+        level.walls_mut().push(wall([200, 100], [10, 100]));
+        level.walls_mut().push(wall([200, 100], [120, 10]));
+        level.gems_mut().push(GemInfo { pos: [100, 100].into() });
+        level.gems_mut().push(GemInfo { pos: [250, 150].into() });
+        level.finish_flag_mut().unwrap().gems_required = 2;
+        let report = level.check_reachability();
+        assert_eq!(report.gems, vec![true, false]);
+        assert_eq!(report.finish, Some(false));
+        assert_eq!(
+            report.issues(&level),
+            vec![
+                LevelIssue::UnreachableGem { index: 1, x: 250, y: 150 },
+                LevelIssue::UnreachableFinish,
+                LevelIssue::NotEnoughReachableGems {
+                    required: 2,
+                    reachable: 1,
+                },
+            ]
+        );
+    }
+
+    /// A level split by a wall across the map, with a gap of the given
+    /// height in the middle.
+    fn split_level(gap: i32) -> GameLevel {
+        let mut level = valid_level();
+        let top = 100 - gap / 2;
+        level.walls_mut().push(wall([150, 0], [20, top]));
+        level.walls_mut().push(wall([150, top + gap], [20, 200 - top - gap]));
+        level
+    }
+
+    #[test]
+    fn corridor_narrower_than_the_ball_is_impassable() {
+        // the ball is 28 units across
+        let level = split_level(20);
+        assert_eq!(level.check_reachability().finish, Some(false));
+
+        let level = split_level(60);
+        assert_eq!(level.check_reachability().finish, Some(true));
+    }
+}
@@ -0,0 +1,90 @@
+//! Module for the errors which occur while loading and saving levels.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use failure::{Error, Fail};
+use controller::LevelId;
+use super::check::LevelIssue;
+
+pub type LevelResult<T> = ::std::result::Result<T, LevelError>;
+
+/// A reason why a level could not be loaded or saved.
+#[derive(Debug)]
+pub enum LevelError {
+    /// the level file or directory could not be read or written
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// the level file is not a well-formed level
+    Parse {
+        path: PathBuf,
+        source: Error,
+    },
+    /// the level is in a version which this game does not know
+    UnsupportedVersion {
+        found: String,
+    },
+    /// the level directory has no level at the given index
+    NotFound {
+        index: LevelId,
+    },
+    /// the level is well-formed, but has problems which prevent playing it
+    Validation {
+        issues: Vec<LevelIssue>,
+    },
+}
+
+impl LevelError {
+    pub fn io<P: Into<PathBuf>>(path: P, source: io::Error) -> Self {
+        LevelError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn parse<P: Into<PathBuf>, E: Into<Error>>(path: P, source: E) -> Self {
+        LevelError::Parse {
+            path: path.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Describe the error without the path of the file, for where the file
+    /// is already named.
+    pub fn reason(&self) -> String {
+        match *self {
+            LevelError::Io { ref source, .. } => source.to_string(),
+            LevelError::Parse { ref source, .. } => source.to_string(),
+            ref e => e.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LevelError::Io { ref path, ref source } => write!(f, "{}: {}", path.display(), source),
+            LevelError::Parse { ref path, ref source } => {
+                write!(f, "{}: invalid level file: {}", path.display(), source)
+            }
+            LevelError::UnsupportedVersion { ref found } => write!(f, "Unsupported level version {}", found),
+            LevelError::NotFound { index } => write!(f, "No such level #{}", index),
+            LevelError::Validation { ref issues } => {
+                let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+                write!(f, "Invalid level: {}", issues.join("; "))
+            }
+        }
+    }
+}
+
+impl Fail for LevelError {
+    fn cause(&self) -> Option<&Fail> {
+        match *self {
+            LevelError::Io { ref source, .. } => Some(source),
+            LevelError::Parse { ref source, .. } => Some(source.as_fail()),
+            _ => None,
+        }
+    }
+}
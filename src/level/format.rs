@@ -0,0 +1,141 @@
+//! Module for the encodings in which level files are stored.
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::{from_slice, to_writer_pretty as to_writer, Value};
+use util::DynResult;
+
+/// A level file encoding, chosen by the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelFormat {
+    /// plain JSON (`.json`)
+    Json,
+    /// Rusty Object Notation, for hand editing (`.ron`)
+    Ron,
+    /// gzip-compressed JSON, for compact storage (`.json.gz`)
+    JsonGz,
+}
+
+impl LevelFormat {
+    /// Identify the encoding of a level file from its extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(OsStr::to_str) {
+            Some("json") => Some(LevelFormat::Json),
+            Some("ron") => Some(LevelFormat::Ron),
+            Some("gz") => {
+                let stem = Path::new(path.file_stem().unwrap_or_default());
+                if stem.extension() == Some(OsStr::new("json")) {
+                    Some(LevelFormat::JsonGz)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Identify the encoding of a level file, falling back to plain JSON
+    /// for unknown extensions.
+    pub fn from_path_or_json<P: AsRef<Path>>(path: P) -> Self {
+        LevelFormat::from_path(path).unwrap_or(LevelFormat::Json)
+    }
+
+    /// Read the whole contents of a file in this encoding, decompressing
+    /// them if needed.
+    pub fn read<P: AsRef<Path>>(self, path: P) -> io::Result<Vec<u8>> {
+        let mut file = File::open(path)?;
+        let mut data = vec![];
+        match self {
+            LevelFormat::Json | LevelFormat::Ron => {
+                file.read_to_end(&mut data)?;
+            }
+            LevelFormat::JsonGz => {
+                GzDecoder::new(file).read_to_end(&mut data)?;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Deserialize a value from data read with `read`.
+    pub fn deserialize<T: DeserializeOwned>(self, data: &[u8]) -> DynResult<T> {
+        match self {
+            LevelFormat::Json | LevelFormat::JsonGz => from_slice(data).map_err(From::from),
+            LevelFormat::Ron => ::ron::de::from_bytes(data).map_err(From::from),
+        }
+    }
+
+    /// Deserialize data read with `read` into a JSON value, keeping every
+    /// field whether the level format knows it or not.
+    pub fn deserialize_value(self, data: &[u8]) -> DynResult<Value> {
+        match self {
+            LevelFormat::Json | LevelFormat::JsonGz => from_slice(data).map_err(From::from),
+            LevelFormat::Ron => {
+                // RON cannot read maps straight into a JSON value, but it
+                // can read them into its own
+                let value: ::ron::Value = ::ron::de::from_bytes(data)?;
+                Value::deserialize(value).map_err(From::from)
+            }
+        }
+    }
+
+    /// Serialize a value into a file in this encoding.
+    pub fn write<T: Serialize, P: AsRef<Path>>(self, value: &T, path: P) -> DynResult<()> {
+        let mut file = File::create(path)?;
+        match self {
+            LevelFormat::Json => to_writer(file, value)?,
+            LevelFormat::Ron => {
+                let text = to_string_pretty(value, PrettyConfig::default())?;
+                file.write_all(text.as_bytes())?;
+            }
+            LevelFormat::JsonGz => {
+                let mut encoder = GzEncoder::new(file, Compression::default());
+                to_writer(&mut encoder, value)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// List the fields of `input` which are missing from `known`, as paths such
+/// as `finish.gems_requied` or `walls[3].texure`. Since every field is
+/// serialized, `known` can be the serialization of the value which was
+/// deserialized from `input`, revealing the fields that were ignored.
+pub fn unknown_fields(input: &Value, known: &Value) -> Vec<String> {
+    let mut fields = vec![];
+    collect_unknown_fields(input, known, "", &mut fields);
+    fields
+}
+
+fn collect_unknown_fields(input: &Value, known: &Value, path: &str, fields: &mut Vec<String>) {
+    match (input, known) {
+        (&Value::Object(ref input), &Value::Object(ref known)) => {
+            for (key, value) in input {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match known.get(key) {
+                    Some(known) => collect_unknown_fields(value, known, &path, fields),
+                    None => fields.push(path),
+                }
+            }
+        }
+        (&Value::Array(ref input), &Value::Array(ref known)) => {
+            for (i, (value, known)) in input.iter().zip(known).enumerate() {
+                collect_unknown_fields(value, known, &format!("{}[{}]", path, i), fields);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,174 @@
+//! Module for generating random levels.
+//!
+//! Levels are laid out on a grid of cells, each wider than the ball, so
+//! that any path of free cells can be followed by the ball. Walls cover
+//! whole cells, gems are spread along the path from the ball to the finish
+//! flag, and mines are kept away from that path.
+
+use na::Vector2;
+use util::{vec_f32, DynResult, XorShiftRng};
+use super::{GameLevel, MAX_DIFFICULTY};
+use super::info::*;
+use super::occupancy::{Cell, OccupancyGrid};
+
+/// The size of each cell of the layout, in map units. This must be larger
+/// than the ball, so that the ball fits in any free cell.
+const CELL_SIZE: u32 = 40;
+/// The smallest map, in cells.
+const MIN_CELLS: u32 = 6;
+/// How many layouts are tried before giving up.
+pub const MAX_ATTEMPTS: u32 = 100;
+const PUMP_COUNT: usize = 2;
+
+/// The parameters of a generated level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorParams {
+    pub width: u32,
+    pub height: u32,
+    /// from 1 to `MAX_DIFFICULTY`
+    pub difficulty: u8,
+    pub seed: u32,
+}
+
+fn pick<T: Copy>(rng: &mut XorShiftRng, items: &[T]) -> Option<T> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(items[rng.next_u32() as usize % items.len()])
+    }
+}
+
+/// Generate a playable level, retrying with new layouts until one can be
+/// completed and passes validation.
+pub fn generate_level(params: &GeneratorParams) -> DynResult<GameLevel> {
+    let (cols, rows) = (params.width / CELL_SIZE, params.height / CELL_SIZE);
+    if cols < MIN_CELLS || rows < MIN_CELLS {
+        return Err(format_err!(
+            "The map must be at least {}x{} to generate a level",
+            MIN_CELLS * CELL_SIZE,
+            MIN_CELLS * CELL_SIZE
+        ));
+    }
+    let difficulty = u8::min(u8::max(params.difficulty, 1), MAX_DIFFICULTY);
+    let mut rng = XorShiftRng::new(params.seed);
+    for _ in 0..MAX_ATTEMPTS {
+        if let Some(level) = try_generate(params, difficulty, cols as usize, rows as usize, &mut rng) {
+            if level.validate().is_empty() {
+                return Ok(level);
+            }
+        }
+    }
+    Err(format_err!("Could not generate a playable level in {} attempts", MAX_ATTEMPTS))
+}
+
+fn try_generate(
+    params: &GeneratorParams,
+    difficulty: u8,
+    cols: usize,
+    rows: usize,
+    rng: &mut XorShiftRng,
+) -> Option<GameLevel> {
+    let mut layout = OccupancyGrid::new(cols, rows, CELL_SIZE);
+    let start = (1, 1 + rng.next_u32() as usize % (rows - 2));
+    let finish = (cols - 2, 1 + rng.next_u32() as usize % (rows - 2));
+
+    // walls, as horizontal or vertical bars of cells
+    let density = 0.15 + 0.04 * f32::from(difficulty);
+    let target = (density * (cols * rows) as f32) as usize;
+    let mut covered = 0;
+    let mut walls = vec![];
+    for _ in 0..target * 4 {
+        if covered >= target {
+            break;
+        }
+        let length = 2 + rng.next_u32() as usize % 5;
+        let (w, h) = if rng.next_f32() < 0.5 { (length, 1) } else { (1, length) };
+        if w > cols || h > rows {
+            continue;
+        }
+        let x = rng.next_u32() as usize % (cols - w + 1);
+        let y = rng.next_u32() as usize % (rows - h + 1);
+        let cells: Vec<Cell> = (y..y + h)
+            .flat_map(|cy| (x..x + w).map(move |cx| (cx, cy)))
+            .collect();
+        if cells.iter().any(|&c| !layout.is_free(c) || c == start || c == finish) {
+            continue;
+        }
+        for &c in &cells {
+            layout.block(c);
+        }
+        covered += cells.len();
+        walls.push(WallInfo {
+            pos: Vector2::new((x as u32 * CELL_SIZE) as i32, (y as u32 * CELL_SIZE) as i32),
+            dim: Vector2::new((w as u32 * CELL_SIZE) as i32, (h as u32 * CELL_SIZE) as i32),
+            texture_id: 0,
+            rotation: 0,
+            flip: false,
+            tile: false,
+        });
+    }
+
+    // the layout must be solvable
+    let parents = layout.flood_fill(start);
+    let path = layout.path_to(&parents, finish)?;
+    let reachable: Vec<Cell> = (0..rows)
+        .flat_map(|y| (0..cols).map(move |x| (x, y)))
+        .filter(|&(x, y)| parents[y * cols + x].is_some())
+        .collect();
+    let near_path = |c: Cell| {
+        path.iter()
+            .any(|p| (p.0 as i32 - c.0 as i32).abs() + (p.1 as i32 - c.1 as i32).abs() <= 1)
+    };
+
+    // gems, spread along the path
+    let gem_count = usize::min(3 + difficulty as usize, path.len().saturating_sub(2));
+    let gems: Vec<GemInfo> = (1..=gem_count)
+        .map(|i| path[i * (path.len() - 1) / (gem_count + 1)])
+        .map(|c| GemInfo { pos: layout.cell_center(c) })
+        .collect();
+
+    // mines, away from the path
+    let away: Vec<Cell> = reachable.iter().cloned().filter(|&c| !near_path(c)).collect();
+    let mine_count = usize::min(away.len(), difficulty as usize * cols * rows / 40);
+    let mut mines: Vec<MineInfo> = vec![];
+    while mines.len() < mine_count {
+        let pos = layout.cell_center(pick(rng, &away)?);
+        if mines.iter().all(|m| m.pos != pos) {
+            mines.push(MineInfo { pos });
+        }
+    }
+
+    // pumps, next to the path
+    let beside: Vec<Cell> = reachable
+        .iter()
+        .cloned()
+        .filter(|&c| near_path(c) && !path.contains(&c))
+        .collect();
+    let pumps: Vec<PumpInfo> = (0..PUMP_COUNT)
+        .filter_map(|_| pick(rng, &beside).or_else(|| pick(rng, &path)))
+        .map(|c| PumpInfo {
+            pos: layout.cell_center(c),
+            charges: None,
+        })
+        .collect();
+
+    let mut level = GameLevel::default();
+    level.set_name(format!("Generated #{}", params.seed));
+    level.set_difficulty(Some(difficulty));
+    level.set_description(Some(format!(
+        "Generated with seed {} ({}x{}, difficulty {})",
+        params.seed, params.width, params.height, difficulty
+    )));
+    level.map_mut().resize(cols as u32 * CELL_SIZE, rows as u32 * CELL_SIZE);
+    let ball_pos = layout.cell_center(start);
+    level.set_ball_position(vec_f32(ball_pos));
+    level.set_finish_flag(FinishInfo {
+        pos: layout.cell_center(finish),
+        gems_required: gems.len() as u32,
+    });
+    *level.walls_mut() = walls;
+    *level.gems_mut() = gems;
+    *level.mines_mut() = mines;
+    *level.pumps_mut() = pumps;
+    Some(level)
+}
@@ -1,4 +1,7 @@
 use na::Vector2;
+use serde::de::{Deserialize, Deserializer, Error};
+use game::ball::BALL_CAPACITY;
+use lang::{tr, tr_fmt};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GemInfo {
@@ -8,6 +11,39 @@ pub struct GemInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PumpInfo {
     pub pos: Vector2<i32>,
+    /// the number of times the pump can heal the ball, unlimited if `None`
+    #[serde(default)] pub charges: Option<u32>,
+}
+
+/// A pad which flings the ball in its direction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoostInfo {
+    pub pos: Vector2<i32>,
+    #[serde(default = "BoostInfo::default_direction")] pub direction: Vector2<f32>,
+    #[serde(default = "BoostInfo::default_strength")] pub strength: f32,
+}
+
+impl BoostInfo {
+    pub fn default_direction() -> Vector2<f32> {
+        [1., 0.].into()
+    }
+
+    pub fn default_strength() -> f32 {
+        2.
+    }
+}
+
+/// An area which slowly deflates the ball.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeakInfo {
+    pub pos: Vector2<i32>,
+    #[serde(default = "LeakInfo::default_radius")] pub radius: f32,
+}
+
+impl LeakInfo {
+    pub fn default_radius() -> f32 {
+        24.
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,10 +56,320 @@ pub struct WallInfo {
     pub pos: Vector2<i32>,
     pub dim: Vector2<i32>,
     #[serde(default)] pub texture_id: u32,
+    /// how many quarter turns clockwise the texture is rotated, from 0 to 3
+    #[serde(default)] pub rotation: u8,
+    /// whether the texture is mirrored horizontally, before rotating it
+    #[serde(default)] pub flip: bool,
+    /// whether the texture is repeated at its own size across the wall,
+    /// rather than stretched to cover it
+    #[serde(default)] pub tile: bool,
+}
+
+impl WallInfo {
+    /// Update the texture's orientation after mirroring the wall along the
+    /// given axis (0 for horizontally, 1 for vertically).
+    pub fn mirror_texture(&mut self, axis: usize) {
+        let turns = if axis == 0 { 4 } else { 6 };
+        self.rotation = (turns - self.rotation % 4) % 4;
+        self.flip = !self.flip;
+    }
+}
+
+/// A region of water, which slows the ball down and pushes it upwards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaterInfo {
+    pub pos: Vector2<i32>,
+    pub dim: Vector2<i32>,
+}
+
+/// The direction which a spike strip faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Default for Direction {
+    fn default() -> Self {
+        Direction::Up
+    }
+}
+
+impl Direction {
+    /// The next direction, clockwise.
+    pub fn rotated(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// The direction reflected along the given axis (0 for horizontal, 1
+    /// for vertical).
+    pub fn mirrored(self, axis: usize) -> Self {
+        match (self, axis) {
+            (Direction::Left, 0) => Direction::Right,
+            (Direction::Right, 0) => Direction::Left,
+            (Direction::Up, 1) => Direction::Down,
+            (Direction::Down, 1) => Direction::Up,
+            (d, _) => d,
+        }
+    }
+
+    /// Whether the direction is left or right.
+    pub fn is_horizontal(self) -> bool {
+        self == Direction::Left || self == Direction::Right
+    }
+
+    /// The unit vector pointing in this direction.
+    pub fn normal(self) -> Vector2<f32> {
+        match self {
+            Direction::Up => [0., -1.].into(),
+            Direction::Right => [1., 0.].into(),
+            Direction::Down => [0., 1.].into(),
+            Direction::Left => [-1., 0.].into(),
+        }
+    }
+}
+
+/// A strip of spikes along one face of a wall. The ball bounces off the
+/// whole strip, but only the spiked face hurts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpikeInfo {
+    pub pos: Vector2<i32>,
+    pub dim: Vector2<i32>,
+    #[serde(default)] pub facing: Direction,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FinishInfo {
     pub pos: Vector2<i32>,
     #[serde(default)] pub gems_required: u32,
-}
\ No newline at end of file
+}
+/// The condition for completing a level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(into = "GoalFields")]
+pub enum Goal {
+    /// Touch the finish flag while holding the gems it requires.
+    ReachFinish,
+    /// Pick up every gem in the level. No finish flag is needed.
+    CollectAllGems,
+    /// Stay alive for the given number of seconds.
+    Survive { seconds: f32 },
+}
+
+impl Default for Goal {
+    fn default() -> Self {
+        Goal::ReachFinish
+    }
+}
+
+/// How a goal is written in level files, such as
+/// `{"type": "survive", "seconds": 60}`. RON cannot read internally tagged
+/// enums back, so the goal goes through this plain struct instead.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct GoalFields {
+    #[serde(rename = "type")]
+    kind: GoalKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seconds: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GoalKind {
+    ReachFinish,
+    CollectAllGems,
+    Survive,
+}
+
+impl From<Goal> for GoalFields {
+    fn from(goal: Goal) -> Self {
+        let (kind, seconds) = match goal {
+            Goal::ReachFinish => (GoalKind::ReachFinish, None),
+            Goal::CollectAllGems => (GoalKind::CollectAllGems, None),
+            Goal::Survive { seconds } => (GoalKind::Survive, Some(seconds)),
+        };
+        GoalFields { kind, seconds }
+    }
+}
+
+impl<'de> Deserialize<'de> for Goal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let fields = GoalFields::deserialize(deserializer)?;
+        match (fields.kind, fields.seconds) {
+            (GoalKind::ReachFinish, _) => Ok(Goal::ReachFinish),
+            (GoalKind::CollectAllGems, _) => Ok(Goal::CollectAllGems),
+            (GoalKind::Survive, Some(seconds)) => Ok(Goal::Survive { seconds }),
+            (GoalKind::Survive, None) => Err(D::Error::missing_field("seconds")),
+        }
+    }
+}
+
+impl Goal {
+    /// A short description of the goal, to show to the player.
+    pub fn description(&self) -> String {
+        match *self {
+            Goal::ReachFinish => tr("goal.reach_finish"),
+            Goal::CollectAllGems => tr("goal.collect_gems"),
+            Goal::Survive { seconds } => tr_fmt("goal.survive", &[&seconds]),
+        }
+    }
+}
+
+/// A medal earned by finishing a level within one of its par times, from
+/// the lowest to the highest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl Medal {
+    pub fn name(&self) -> String {
+        match *self {
+            Medal::Bronze => tr("medal.bronze"),
+            Medal::Silver => tr("medal.silver"),
+            Medal::Gold => tr("medal.gold"),
+        }
+    }
+
+    /// The color of the medal's icon.
+    pub fn color(&self) -> [f32; 4] {
+        match *self {
+            Medal::Bronze => [0.8, 0.5, 0.25, 1.0],
+            Medal::Silver => [0.75, 0.78, 0.82, 1.0],
+            Medal::Gold => [1.0, 0.8, 0.2, 1.0],
+        }
+    }
+}
+
+/// The times within which a level must be finished to earn each medal, in
+/// seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParTimes {
+    pub gold: f32,
+    pub silver: f32,
+    pub bronze: f32,
+}
+
+impl ParTimes {
+    /// Whether the times are positive and each medal asks for no more time
+    /// than the one below it.
+    pub fn is_valid(&self) -> bool {
+        0. < self.gold && self.gold <= self.silver && self.silver <= self.bronze
+    }
+
+    /// Obtain the medal earned by finishing the level in the given time.
+    pub fn grade(&self, time: f32) -> Option<Medal> {
+        if time <= self.gold {
+            Some(Medal::Gold)
+        } else if time <= self.silver {
+            Some(Medal::Silver)
+        } else if time <= self.bronze {
+            Some(Medal::Bronze)
+        } else {
+            None
+        }
+    }
+}
+
+/// The layer in which a decoration is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecorationLayer {
+    /// Drawn behind the walls.
+    Back,
+    /// Drawn in front of everything else.
+    Front,
+}
+
+impl Default for DecorationLayer {
+    fn default() -> Self {
+        DecorationLayer::Back
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecorationInfo {
+    pub pos: Vector2<i32>,
+    #[serde(default)] pub texture_id: u32,
+    #[serde(default)] pub layer: DecorationLayer,
+    #[serde(default = "DecorationInfo::default_parallax")] pub parallax: f32,
+}
+
+impl DecorationInfo {
+    pub fn default_parallax() -> f32 {
+        1.0
+    }
+}
+
+/// The physical properties of the ball, which levels may tune to make the
+/// ball feel heavier or more slippery.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BallParams {
+    /// the largest size the ball can have without bursting
+    pub capacity: f32,
+    /// the acceleration applied by each thrusting direction, per tick
+    pub thrust: f32,
+    /// the fraction of velocity lost per tick
+    pub drag: f32,
+    /// how much the ball shrinks for each thrusting direction, per tick
+    pub deflate_rate: f32,
+    /// the speed above which the ball starts being slowed down
+    pub max_speed: f32,
+}
+
+impl Default for BallParams {
+    fn default() -> Self {
+        BallParams {
+            capacity: BALL_CAPACITY,
+            thrust: 0.04,
+            drag: 0.,
+            deflate_rate: 0.011,
+            max_speed: f32::sqrt(22.),
+        }
+    }
+}
+
+impl BallParams {
+    /// A ball which is slow to accelerate and quick to slow down.
+    pub fn heavy() -> Self {
+        BallParams {
+            thrust: 0.025,
+            drag: 0.004,
+            max_speed: 3.5,
+            ..BallParams::default()
+        }
+    }
+
+    /// A ball which keeps its momentum and reaches higher speeds.
+    pub fn slippery() -> Self {
+        BallParams {
+            thrust: 0.03,
+            deflate_rate: 0.008,
+            max_speed: 6.,
+            ..BallParams::default()
+        }
+    }
+
+    /// The named presets available to level designers.
+    pub fn presets() -> [(&'static str, BallParams); 3] {
+        [
+            ("default", BallParams::default()),
+            ("heavy", BallParams::heavy()),
+            ("slippery", BallParams::slippery()),
+        ]
+    }
+}
@@ -0,0 +1,110 @@
+//! Module for the level manifest, which defines the order in which the
+//! levels of a directory are played.
+
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use serde_json::{from_reader, to_writer_pretty as to_writer};
+use util::DynResult;
+
+/// The name of the manifest file inside a level directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The intended order of the levels in a directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelManifest {
+    /// the level file names, in the order in which they are played
+    #[serde(default)] pub levels: Vec<String>,
+    /// optional groupings of consecutive levels
+    #[serde(default)] pub chapters: Vec<Chapter>,
+}
+
+/// A group of consecutive levels in the manifest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub name: String,
+    /// the file name of the chapter's first level
+    pub first: String,
+}
+
+impl LevelManifest {
+    pub fn path_in<P: AsRef<Path>>(dir: P) -> PathBuf {
+        dir.as_ref().join(MANIFEST_FILE)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+        let file = File::open(path)?;
+        from_reader(file).map_err(From::from)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> DynResult<()> {
+        let file = File::create(path)?;
+        to_writer(file, self).map_err(From::from)
+    }
+
+    /// Load the manifest of the given level directory, if it has one. A
+    /// manifest which cannot be read is reported and ignored.
+    pub fn load_in<P: AsRef<Path>>(dir: P) -> Option<Self> {
+        let path = LevelManifest::path_in(dir);
+        if !path.is_file() {
+            return None;
+        }
+        LevelManifest::load(&path)
+            .map_err(|e| eprintln!("Warning: could not read {}: {}", path.display(), e))
+            .ok()
+    }
+
+    /// Put the given level files in the manifest's order. Files which are
+    /// not listed come last, in their original order, while listed files
+    /// which do not exist are skipped with a warning.
+    pub fn sort_paths(&self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let (mut listed, rest): (Vec<_>, Vec<_>) = paths
+            .into_iter()
+            .map(|p| {
+                let pos = p.file_name()
+                    .and_then(|f| f.to_str())
+                    .and_then(|f| self.levels.iter().position(|l| l == f));
+                (pos, p)
+            })
+            .partition(|&(pos, _)| pos.is_some());
+        for (i, name) in self.levels.iter().enumerate() {
+            if !listed.iter().any(|&(pos, _)| pos == Some(i)) {
+                eprintln!("Warning: level {} in the manifest does not exist", name);
+            }
+        }
+        listed.sort_by_key(|&(pos, _)| pos);
+        listed
+            .into_iter()
+            .chain(rest)
+            .map(|(_, p)| p)
+            .collect()
+    }
+
+    /// Split the ordered level files into chapters, as ranges of positions
+    /// in the list. Levels before the first chapter are grouped into an
+    /// unnamed chapter. There is always at least one chapter.
+    pub fn chapter_ranges(&self, files: &[&str]) -> Vec<(Option<String>, Range<usize>)> {
+        let mut starts: Vec<(usize, &str)> = self.chapters
+            .iter()
+            .filter_map(|c| {
+                files
+                    .iter()
+                    .position(|f| *f == c.first)
+                    .map(|start| (start, c.name.as_str()))
+            })
+            .collect();
+        starts.sort_by_key(|&(start, _)| start);
+        starts.dedup_by_key(|&mut (start, _)| start);
+
+        let mut ranges = vec![];
+        let first = starts.first().map(|&(start, _)| start).unwrap_or(files.len());
+        if first > 0 || starts.is_empty() {
+            ranges.push((None, 0..first));
+        }
+        for (i, &(start, name)) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).map(|&(s, _)| s).unwrap_or(files.len());
+            ranges.push((Some(name.to_string()), start..end));
+        }
+        ranges
+    }
+}
@@ -9,6 +9,17 @@ const DEFAULT_HEIGHT: u32 = 200;
 pub struct Map {
     width: u32,
     height: u32,
+    /// the edges which the ball can fall out through
+    #[serde(default)] open: OpenEdges,
+}
+
+/// Which edges of the map are open, with no border to keep the ball in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OpenEdges {
+    #[serde(default)] pub left: bool,
+    #[serde(default)] pub right: bool,
+    #[serde(default)] pub up: bool,
+    #[serde(default)] pub down: bool,
 }
 
 impl Default for Map {
@@ -16,6 +27,7 @@ impl Default for Map {
         Map {
             width: DEFAULT_WIDTH,
             height: DEFAULT_HEIGHT,
+            open: OpenEdges::default(),
         }
     }
 }
@@ -24,13 +36,33 @@ impl Map {
     pub fn new(width: u32, height: u32) -> Self {
         assert!(width > 0);
         assert!(height > 0);
-        Map { width, height }
+        Map {
+            width,
+            height,
+            open: OpenEdges::default(),
+        }
     }
 
     pub fn dimensions_f32(&self) -> Vector2<f32> {
         Vector2::new(self.width as f32, self.height as f32)
     }
 
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Set the map's dimensions.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        assert!(width > 0);
+        assert!(height > 0);
+        self.width = width;
+        self.height = height;
+    }
+
     pub fn expand_to_fit(&mut self, dim: Vector2<i32>) {
         if dim[0] > 0 {
             self.width = u32::max(self.width, dim[0] as u32);
@@ -40,6 +72,23 @@ impl Map {
         }
     }
 
+    pub fn open_edges(&self) -> OpenEdges {
+        self.open
+    }
+
+    pub fn open_edges_mut(&mut self) -> &mut OpenEdges {
+        &mut self.open
+    }
+
+    /// Whether a circle at the given position is entirely outside of the
+    /// map, which it can only be after leaving through an open edge.
+    pub fn is_outside(&self, pos: Vector2<f32>, radius: f32) -> bool {
+        pos[0] + radius < 0.
+            || pos[1] + radius < 0.
+            || pos[0] - radius > self.width as f32
+            || pos[1] - radius > self.height as f32
+    }
+
     pub fn up_border(&self) -> UpBorder {
         UpBorder(0.)
     }
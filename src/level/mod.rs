@@ -1,47 +1,95 @@
 use std::ffi::OsStr;
-use std::fs::{File, read_dir};
+use std::fs::read_dir;
+use std::io;
 use std::path::{Path, PathBuf};
 use controller::LevelId;
 use itertools::process_results;
 use na::Vector2;
-use serde_json::{from_reader, to_writer_pretty as to_writer};
-use util::DynResult;
+use serde_json::to_value;
+use util::{vec_f32, vec_i32};
 
 mod v0;
+pub mod check;
+pub mod error;
+pub mod format;
+pub mod generate;
 pub mod info;
+pub mod manifest;
 pub mod map;
+pub mod occupancy;
+pub mod snapshot;
+pub mod stats;
+pub mod tiled;
+pub use self::error::{LevelError, LevelResult};
+pub use self::format::LevelFormat;
+pub use self::manifest::LevelManifest;
 pub use self::map::Map;
 
 use self::info::*;
 
-pub const CURRENT_VERSION: &str = "1.0";
+pub const CURRENT_VERSION: &str = "1.1";
 
-pub fn load_all_level_paths<P: AsRef<Path>>(dir: P) -> DynResult<Vec<PathBuf>> {
-    let entries = read_dir(dir)?;
+/// The directory in which the game's levels are kept.
+pub const LEVEL_DIR: &str = "levels";
+
+/// The name given to levels which were not named yet.
+pub const DEFAULT_NAME: &str = "No Name";
+
+/// The background color of levels which do not specify one.
+pub const DEFAULT_BACKGROUND_COLOR: [f32; 4] = [0.4, 0.6, 0.7, 1.0];
+
+/// The highest level difficulty rating.
+pub const MAX_DIFFICULTY: u8 = 5;
+
+/// List the level files in the given directory, in the order defined by
+/// the directory's manifest, or sorted by path if there is none.
+pub fn load_all_level_paths<P: AsRef<Path>>(dir: P) -> LevelResult<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    let dir_error = |e| LevelError::io(dir, e);
+    let entries = read_dir(dir).map_err(dir_error)?;
 
     let mut x: Vec<PathBuf> = process_results(entries, |iter| {
         iter.map(|e| e.path())
             .filter(|p| p.is_file())
-            .filter(|p| p.extension() == Some(OsStr::new("json")))
+            .filter(|p| LevelFormat::from_path(p).is_some())
+            .filter(|p| p.file_name() != Some(OsStr::new(manifest::MANIFEST_FILE)))
             .map(|p| p.to_path_buf())
             .collect()
-    })?;
+    }).map_err(dir_error)?;
     x.sort();
-    Ok(x)
+    match LevelManifest::load_in(dir) {
+        Some(manifest) => Ok(manifest.sort_paths(x)),
+        None => Ok(x),
+    }
 }
 
-pub fn load_all_levels<P: AsRef<Path>>(dir: P) -> DynResult<Vec<GameLevel>> {
+pub fn load_all_levels<P: AsRef<Path>>(dir: P) -> LevelResult<Vec<GameLevel>> {
     load_all_level_paths(dir)?.into_iter()
         .map(GameLevel::load)
         .collect()
 }
 
-pub fn load_all_level_headers<P: AsRef<Path>>(dir: P) -> DynResult<Vec<GameLevelHeader>> {
+pub fn load_all_level_headers<P: AsRef<Path>>(dir: P) -> LevelResult<Vec<GameLevelHeader>> {
     load_all_level_paths(dir)?.into_iter()
         .map(GameLevelHeader::from_file)
         .collect()
 }
 
+/// Load the headers of all levels in the given directory. Unlike
+/// `load_all_level_headers`, a level which fails to load does not fail the
+/// whole operation, and is kept in the list along with its error.
+pub fn try_load_all_level_headers<P>(dir: P) -> LevelResult<Vec<(PathBuf, LevelResult<GameLevelHeader>)>>
+where
+    P: AsRef<Path>,
+{
+    Ok(load_all_level_paths(dir)?.into_iter()
+        .map(|p| {
+            let header = GameLevelHeader::from_file(&p);
+            (p, header)
+        })
+        .collect())
+}
+
 /// Serializable data type with the bare minimum compatible subset of game
 /// level versions. This is used to check which level version should be
 /// loaded.
@@ -50,13 +98,19 @@ pub struct GameLevelHeader {
     name: String,
     #[serde(default = "GameLevelHeader::default_version")]
     version: String,
+    #[serde(default)] author: Option<String>,
+    #[serde(default)] difficulty: Option<u8>,
+    #[serde(default)] description: Option<String>,
 }
 
 impl Default for GameLevelHeader {
     fn default() -> Self {
         GameLevelHeader {
-            name: "No Name".to_string(),
+            name: DEFAULT_NAME.to_string(),
             version: CURRENT_VERSION.to_string(),
+            author: None,
+            difficulty: None,
+            description: None,
         }
     }
 }
@@ -66,10 +120,11 @@ impl GameLevelHeader {
         "0.1".to_string()
     }
 
-    pub fn from_file<P: AsRef<Path>>(path: P) -> DynResult<Self> {
-        let file = File::open(path)?;
-        let game = from_reader(file)?;
-        Ok(game)
+    pub fn from_file<P: AsRef<Path>>(path: P) -> LevelResult<Self> {
+        let path = path.as_ref();
+        let format = LevelFormat::from_path_or_json(path);
+        let data = format.read(path).map_err(|e| LevelError::io(path, e))?;
+        format.deserialize(&data).map_err(|e| LevelError::parse(path, e))
     }
 
     pub fn name(&self) -> &str {
@@ -79,6 +134,19 @@ impl GameLevelHeader {
     pub fn version(&self) -> &str {
         &self.version
     }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_ref().map(String::as_str)
+    }
+
+    /// Obtain the level's difficulty rating, from 1 to 5.
+    pub fn difficulty(&self) -> Option<u8> {
+        self.difficulty.map(|d| u8::min(u8::max(d, 1), MAX_DIFFICULTY))
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(String::as_str)
+    }
 }
 
 /// Game level.
@@ -86,13 +154,62 @@ impl GameLevelHeader {
 pub struct GameLevel {
     name: String,
     version: String,
+    #[serde(default)]
+    #[builder(default)]
+    author: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    difficulty: Option<u8>,
+    #[serde(default)]
+    #[builder(default)]
+    description: Option<String>,
+    /// the times within which the level must be finished to earn each medal
+    #[serde(default)]
+    #[builder(default)]
+    par_times: Option<ParTimes>,
+    #[serde(default)]
+    #[builder(default)]
+    background_color: Option<[f32; 4]>,
+    #[serde(default)]
+    #[builder(default)]
+    background_texture: Option<String>,
     map: Map,
     ball_pos: Vector2<i32>,
+    #[serde(default)]
+    #[builder(default)]
+    ball_size: Option<f32>,
+    #[serde(default)]
+    #[builder(default)]
+    ball_params: Option<BallParams>,
+    #[serde(default)]
+    #[builder(default)]
+    ball2_pos: Option<Vector2<i32>>,
     #[serde(default)] walls: Vec<WallInfo>,
     #[serde(default)] pumps: Vec<PumpInfo>,
     #[serde(default)] mines: Vec<MineInfo>,
+    #[serde(default)]
+    #[builder(default)]
+    boosts: Vec<BoostInfo>,
+    #[serde(default)]
+    #[builder(default)]
+    leaks: Vec<LeakInfo>,
     #[serde(default)] gems: Vec<GemInfo>,
     #[serde(default)] finish: Option<FinishInfo>,
+    #[serde(default)]
+    #[builder(default)]
+    goal: Goal,
+    #[serde(default)]
+    #[builder(default)]
+    spikes: Vec<SpikeInfo>,
+    #[serde(default)]
+    #[builder(default)]
+    water: Vec<WaterInfo>,
+    #[serde(default)]
+    #[builder(default)]
+    decorations: Vec<DecorationInfo>,
+    #[serde(default)]
+    #[builder(default)]
+    textures: Vec<String>,
 }
 
 impl Default for GameLevel {
@@ -101,50 +218,106 @@ impl Default for GameLevel {
         GameLevel {
             name: header.name,
             version: header.version,
+            author: header.author,
+            difficulty: header.difficulty,
+            description: header.description,
+            par_times: None,
+            background_color: None,
+            background_texture: None,
             ball_pos: [36, 36].into(),
+            ball_size: None,
+            ball_params: None,
+            ball2_pos: None,
             map: Map::default(),
             walls: Vec::new(),
             pumps: Vec::new(),
             mines: Vec::new(),
+            boosts: Vec::new(),
+            leaks: Vec::new(),
             gems: Vec::new(),
             finish: None,
+            goal: Goal::default(),
+            spikes: Vec::new(),
+            water: Vec::new(),
+            decorations: Vec::new(),
+            textures: Vec::new(),
         }
     }
 }
 
 impl GameLevel {
 
-    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+    pub fn load<P: AsRef<Path>>(path: P) -> LevelResult<Self> {
+        let path = path.as_ref();
+        let format = LevelFormat::from_path_or_json(path);
+        let data = format.read(path).map_err(|e| LevelError::io(path, e))?;
+        let parse_error = |e| LevelError::parse(path, e);
         // read as a header first
-        let file = File::open(&path)?;
-        let header: GameLevelHeader = from_reader(file)?;
+        let header: GameLevelHeader = format.deserialize(&data).map_err(parse_error)?;
         match header.version() {
             "0.1" => {
                 // read in legacy format, then convert to new format
-                let file = File::open(path)?;
-                let game: v0::GameLevel = from_reader(file)?;
-                game.upgrade()
+                let game: v0::GameLevel = format.deserialize(&data).map_err(parse_error)?;
+                game.upgrade().map_err(parse_error)
             }
-            "1.0" => {
-                let file = File::open(path)?;
-                let game: GameLevel = from_reader(file)?;
-                Ok(game)
+            "1.0" => format.deserialize(&data).map_err(parse_error),
+            "1.1" => {
+                let level: GameLevel = format.deserialize(&data).map_err(parse_error)?;
+                // fields which were not recognized would otherwise be
+                // silently ignored
+                let input = format.deserialize_value(&data).map_err(parse_error)?;
+                let known = to_value(&level).map_err(|e| LevelError::parse(path, e))?;
+                let unknown = format::unknown_fields(&input, &known);
+                if !unknown.is_empty() {
+                    let e = format_err!("Unknown level field(s): {}", unknown.join(", "));
+                    return Err(LevelError::parse(path, e));
+                }
+                let issues = level.validate();
+                if !issues.is_empty() {
+                    return Err(LevelError::Validation { issues });
+                }
+                Ok(level)
             }
-            v => Err(format_err!("Unsupported level version {}", v))
+            v => Err(LevelError::UnsupportedVersion { found: v.to_string() }),
         }
     }
 
-    pub fn load_by_index<P: AsRef<Path>>(dir: P, id: LevelId) -> DynResult<Self> {
-        let paths = load_all_level_paths(dir)?;
-        match paths.get(id as usize) {
-            Some(p) => GameLevel::load(p),
-            None => Err(format_err!("No such Level")),
+    pub fn load_by_index<P: AsRef<Path>>(dir: P, id: LevelId) -> LevelResult<Self> {
+        GameLevel::path_by_index(dir, id).and_then(GameLevel::load)
+    }
+
+    /// Obtain the path of the level file with the given index in the
+    /// directory.
+    pub fn path_by_index<P: AsRef<Path>>(dir: P, id: LevelId) -> LevelResult<PathBuf> {
+        let mut paths = load_all_level_paths(dir)?;
+        if (id as usize) < paths.len() {
+            Ok(paths.swap_remove(id as usize))
+        } else {
+            Err(LevelError::NotFound { index: id })
         }
     }
 
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> DynResult<()> {
-        let file = File::create(path)?;
-        to_writer(file, self).map_err(From::from)
+    /// Check the level for problems, such as a ball outside of the map or a
+    /// wall with no area. Levels in version 1.1 must have none of them.
+    pub fn validate(&self) -> Vec<check::LevelIssue> {
+        check::check_level(self)
+    }
+
+    /// Find which of the objects the ball must touch it can get to from
+    /// its starting position.
+    pub fn check_reachability(&self) -> check::ReachabilityReport {
+        check::check_reachability(self)
+    }
+
+    /// Save the level, in the encoding given by the file's extension.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> LevelResult<()> {
+        let path = path.as_ref();
+        LevelFormat::from_path_or_json(path)
+            .write(self, path)
+            .map_err(|e| match e.downcast::<io::Error>() {
+                Ok(e) => LevelError::io(path, e),
+                Err(e) => LevelError::parse(path, e),
+            })
     }
 
     pub fn name(&self) -> &str {
@@ -163,6 +336,63 @@ impl GameLevel {
         self.version = v.into();
     }
 
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_ref().map(String::as_str)
+    }
+
+    pub fn set_author(&mut self, author: Option<String>) {
+        self.author = author;
+    }
+
+    /// Obtain the level's difficulty rating, from 1 to 5.
+    pub fn difficulty(&self) -> Option<u8> {
+        self.difficulty.map(|d| u8::min(u8::max(d, 1), MAX_DIFFICULTY))
+    }
+
+    /// Define the level's difficulty rating. The value is clamped to the
+    /// range between 1 and 5.
+    pub fn set_difficulty(&mut self, difficulty: Option<u8>) {
+        self.difficulty = difficulty.map(|d| u8::min(u8::max(d, 1), MAX_DIFFICULTY));
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(String::as_str)
+    }
+
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+
+    /// Obtain the times within which the level must be finished to earn
+    /// each medal.
+    pub fn par_times(&self) -> Option<ParTimes> {
+        self.par_times
+    }
+
+    /// Define the level's par times. Invalid times, such as a gold time
+    /// longer than the silver time, clear them.
+    pub fn set_par_times(&mut self, par_times: Option<ParTimes>) {
+        self.par_times = par_times.filter(ParTimes::is_valid);
+    }
+
+    /// Obtain the color to clear the screen with while playing this level.
+    pub fn background_color(&self) -> [f32; 4] {
+        self.background_color.unwrap_or(DEFAULT_BACKGROUND_COLOR)
+    }
+
+    pub fn set_background_color(&mut self, color: Option<[f32; 4]>) {
+        self.background_color = color;
+    }
+
+    /// Obtain the path to the level's background image, if any.
+    pub fn background_texture(&self) -> Option<&str> {
+        self.background_texture.as_ref().map(String::as_str)
+    }
+
+    pub fn set_background_texture(&mut self, path: Option<String>) {
+        self.background_texture = path;
+    }
+
     pub fn map(&self) -> &Map {
         &self.map
     }
@@ -172,7 +402,7 @@ impl GameLevel {
     }
 
     pub fn ball_position(&self) -> Vector2<f32> {
-        Vector2::new(self.ball_pos[0] as f32, self.ball_pos[1] as f32)
+        vec_f32(self.ball_pos)
     }
 
     pub fn set_ball_position(&mut self, pos: Vector2<f32>) {
@@ -180,6 +410,35 @@ impl GameLevel {
         self.ball_pos[1] = pos[1] as i32;
     }
 
+    /// The starting position of the second player's ball, if the level
+    /// defines one.
+    pub fn second_ball_position(&self) -> Option<Vector2<f32>> {
+        self.ball2_pos.map(|pos| vec_f32(pos))
+    }
+
+    pub fn set_second_ball_position(&mut self, pos: Option<Vector2<f32>>) {
+        self.ball2_pos = pos.map(|pos| vec_i32(pos));
+    }
+
+    /// The ball's starting size, if the level defines one.
+    pub fn ball_size(&self) -> Option<f32> {
+        self.ball_size
+    }
+
+    pub fn set_ball_size(&mut self, size: Option<f32>) {
+        self.ball_size = size;
+    }
+
+    /// The ball's physical parameters, which are the defaults unless the
+    /// level overrides them.
+    pub fn ball_params(&self) -> BallParams {
+        self.ball_params.unwrap_or_default()
+    }
+
+    pub fn set_ball_params(&mut self, params: Option<BallParams>) {
+        self.ball_params = params;
+    }
+
     pub fn walls(&self) -> &[WallInfo] {
         &self.walls
     }
@@ -204,6 +463,22 @@ impl GameLevel {
         &mut self.mines
     }
 
+    pub fn boosts(&self) -> &[BoostInfo] {
+        &self.boosts
+    }
+
+    pub fn boosts_mut(&mut self) -> &mut Vec<BoostInfo> {
+        &mut self.boosts
+    }
+
+    pub fn leaks(&self) -> &[LeakInfo] {
+        &self.leaks
+    }
+
+    pub fn leaks_mut(&mut self) -> &mut Vec<LeakInfo> {
+        &mut self.leaks
+    }
+
     pub fn gems(&self) -> &[GemInfo] {
         &self.gems
     }
@@ -227,4 +502,281 @@ impl GameLevel {
     pub fn clear_finish_flag(&mut self) {
         self.finish = None;
     }
+
+    /// Obtain the list of wall texture files, indexed by texture ID. If the
+    /// list is empty, the default numbered textures are used.
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    pub fn set_goal(&mut self, goal: Goal) {
+        self.goal = goal;
+    }
+
+    pub fn textures(&self) -> &[String] {
+        &self.textures
+    }
+
+    pub fn textures_mut(&mut self) -> &mut Vec<String> {
+        &mut self.textures
+    }
+
+    pub fn spikes(&self) -> &[SpikeInfo] {
+        &self.spikes
+    }
+
+    pub fn spikes_mut(&mut self) -> &mut Vec<SpikeInfo> {
+        &mut self.spikes
+    }
+
+    pub fn water(&self) -> &[WaterInfo] {
+        &self.water
+    }
+
+    pub fn water_mut(&mut self) -> &mut Vec<WaterInfo> {
+        &mut self.water
+    }
+
+    pub fn decorations(&self) -> &[DecorationInfo] {
+        &self.decorations
+    }
+
+    pub fn decorations_mut(&mut self) -> &mut Vec<DecorationInfo> {
+        &mut self.decorations
+    }
+
+    /// Move all objects of the level, including the ball, by the given
+    /// offset. The map grows by the offset when moving towards the right
+    /// or the bottom.
+    pub fn translate(&mut self, offset: Vector2<i32>) {
+        for info in &mut self.walls {
+            info.pos += offset;
+        }
+        for info in &mut self.pumps {
+            info.pos += offset;
+        }
+        for info in &mut self.mines {
+            info.pos += offset;
+        }
+        for info in &mut self.boosts {
+            info.pos += offset;
+        }
+        for info in &mut self.leaks {
+            info.pos += offset;
+        }
+        for info in &mut self.gems {
+            info.pos += offset;
+        }
+        if let Some(info) = self.finish.as_mut() {
+            info.pos += offset;
+        }
+        for info in &mut self.spikes {
+            info.pos += offset;
+        }
+        for info in &mut self.water {
+            info.pos += offset;
+        }
+        for info in &mut self.decorations {
+            info.pos += offset;
+        }
+        self.ball_pos += offset;
+        if let Some(pos) = self.ball2_pos.as_mut() {
+            *pos += offset;
+        }
+
+        let width = self.map.width() as i32 + i32::max(0, offset[0]);
+        let height = self.map.height() as i32 + i32::max(0, offset[1]);
+        self.map.resize(width as u32, height as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{to_value, Value};
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    /// An empty directory of its own for each test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("propan-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sample_level() -> GameLevel {
+        let mut level = GameLevel::default();
+        level.set_name("Round Trip");
+        level.set_author(Some("Someone".to_string()));
+        level.set_difficulty(Some(3));
+        level.set_description(Some("Roll to the flag.".to_string()));
+        level.walls_mut().push(WallInfo {
+            pos: [0, 180].into(),
+            dim: [320, 20].into(),
+            texture_id: 1,
+            rotation: 3,
+            flip: true,
+            tile: true,
+        });
+        level.gems_mut().push(GemInfo { pos: [100, 100].into() });
+        level.set_finish_flag(FinishInfo {
+            pos: [280, 160].into(),
+            gems_required: 1,
+        });
+        level
+    }
+
+    #[test]
+    fn level_survives_saving_and_loading() {
+        let dir = temp_dir("level-round-trip");
+        let level = sample_level();
+        for file in &["level.json", "level.ron", "level.json.gz"] {
+            let path = dir.join(file);
+            level.save(&path).unwrap();
+            let loaded = GameLevel::load(&path).unwrap();
+            assert_eq!(to_value(&loaded).unwrap(), to_value(&level).unwrap(), "{}", file);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn levels_without_metadata_still_load() {
+        let dir = temp_dir("level-no-metadata");
+        // written before levels had an author, a difficulty or a description
+        let path = dir.join("old.json");
+        fs::write(
+            &path,
+            r#"{"name":"Old","version":"1.0","map":{"width":320,"height":200},"ball_pos":[36,36]}"#,
+        ).unwrap();
+        let header = GameLevelHeader::from_file(&path).unwrap();
+        assert_eq!(header.name(), "Old");
+        assert_eq!(header.author(), None);
+        assert_eq!(header.difficulty(), None);
+        assert_eq!(header.description(), None);
+        let level = GameLevel::load(&path).unwrap();
+        assert_eq!(level.author(), None);
+        assert_eq!(level.difficulty(), None);
+        assert_eq!(level.description(), None);
+
+        // and absent fields stay absent through saving and loading
+        let path = dir.join("new.json");
+        level.save(&path).unwrap();
+        let loaded = GameLevel::load(&path).unwrap();
+        assert_eq!(loaded.author(), None);
+        assert_eq!(loaded.difficulty(), None);
+        assert_eq!(loaded.description(), None);
+        assert_eq!(to_value(&loaded).unwrap(), to_value(&level).unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn level_metadata_survives_saving_and_loading() {
+        let dir = temp_dir("level-metadata");
+        let path = dir.join("level.json");
+        sample_level().save(&path).unwrap();
+        let header = GameLevelHeader::from_file(&path).unwrap();
+        assert_eq!(header.author(), Some("Someone"));
+        assert_eq!(header.difficulty(), Some(3));
+        assert_eq!(header.description(), Some("Roll to the flag."));
+        let level = GameLevel::load(&path).unwrap();
+        assert_eq!(level.author(), Some("Someone"));
+        assert_eq!(level.difficulty(), Some(3));
+        assert_eq!(level.description(), Some("Roll to the flag."));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_level_is_an_io_error() {
+        let dir = temp_dir("level-missing");
+        let path = dir.join("missing.json");
+        match GameLevel::load(&path) {
+            Err(LevelError::Io { path: ref p, ref source }) => {
+                assert_eq!(p, &path);
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_level_is_a_parse_error() {
+        let dir = temp_dir("level-malformed");
+        let path = dir.join("malformed.json");
+        fs::write(&path, "{ \"name\": \"Broken\", \"walls\": [ }").unwrap();
+        match GameLevel::load(&path) {
+            Err(LevelError::Parse { path: ref p, .. }) => assert_eq!(p, &path),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_version_is_unsupported() {
+        let dir = temp_dir("level-version");
+        let path = dir.join("future.json");
+        let mut level = sample_level();
+        level.set_version("9.0");
+        level.save(&path).unwrap();
+        match GameLevel::load(&path) {
+            Err(LevelError::UnsupportedVersion { ref found }) => assert_eq!(found, "9.0"),
+            other => panic!("expected an unsupported version, got {:?}", other),
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let dir = temp_dir("level-unknown-fields");
+        let mut value = to_value(&sample_level()).unwrap();
+        value["colour"] = Value::from(vec![1, 0, 0]);
+        value["finish"]["gems_requied"] = Value::from(2);
+        let json = dir.join("level.json");
+        LevelFormat::Json.write(&value, &json).unwrap();
+        // RON writes maps rather than structs from a JSON value
+        let ron = dir.join("level.ron");
+        sample_level().save(&ron).unwrap();
+        let text = fs::read_to_string(&ron).unwrap();
+        let text = text.replacen("(", "(colour: (1, 0, 0),", 1);
+        let text = text.replacen("gems_required:", "gems_requied: 2, gems_required:", 1);
+        fs::write(&ron, text).unwrap();
+
+        for path in &[json, ron] {
+            match GameLevel::load(path) {
+                Err(LevelError::Parse { source, .. }) => {
+                    let msg = source.to_string();
+                    assert!(msg.contains("colour") && msg.contains("finish.gems_requied"), "{}", msg);
+                }
+                other => panic!("expected a parse error in {}, got {:?}", path.display(), other),
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn broken_levels_do_not_hide_the_others() {
+        let dir = temp_dir("level-headers");
+        sample_level().save(dir.join("good.json")).unwrap();
+        fs::write(dir.join("broken.json"), "{ \"name\": ").unwrap();
+        // files which are not levels are left out
+        fs::write(dir.join("notes.txt"), "not a level").unwrap();
+
+        let headers = try_load_all_level_headers(&dir).unwrap();
+        assert_eq!(headers.len(), 2);
+        let (ref path, ref header) = headers[0];
+        assert_eq!(path, &dir.join("broken.json"));
+        match *header {
+            Err(LevelError::Parse { ref path, .. }) => assert_eq!(path, &dir.join("broken.json")),
+            ref other => panic!("expected a parse error, got {:?}", other),
+        }
+        let (ref path, ref header) = headers[1];
+        assert_eq!(path, &dir.join("good.json"));
+        assert_eq!(header.as_ref().unwrap().name(), "Round Trip");
+
+        // unlike the above, a single broken level fails the whole directory
+        assert!(load_all_level_headers(&dir).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,213 @@
+//! Module for occupancy grids, coarse rasters of the walls of a level on
+//! which paths through the level can be looked for.
+
+use std::collections::VecDeque;
+use na::Vector2;
+use super::GameLevel;
+
+/// The column and row of a cell in the grid.
+pub type Cell = (usize, usize);
+
+/// A grid of square cells over the map, each either free or blocked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccupancyGrid {
+    cell_size: u32,
+    cols: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+}
+
+impl OccupancyGrid {
+    /// Create a grid with all cells free.
+    pub fn new(cols: usize, rows: usize, cell_size: u32) -> Self {
+        assert!(cell_size > 0);
+        OccupancyGrid {
+            cell_size,
+            cols,
+            rows,
+            blocked: vec![false; cols * rows],
+        }
+    }
+
+    /// Rasterize the walls of the level. A cell is blocked if any wall
+    /// overlaps it, however slightly.
+    pub fn from_level(level: &GameLevel, cell_size: u32) -> Self {
+        let map = level.map();
+        let cols = ((map.width() + cell_size - 1) / cell_size) as usize;
+        let rows = ((map.height() + cell_size - 1) / cell_size) as usize;
+        let mut grid = OccupancyGrid::new(cols, rows, cell_size);
+        for wall in level.walls() {
+            grid.block_rect(wall.pos, wall.dim);
+        }
+        grid
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    fn index(&self, (x, y): Cell) -> usize {
+        y * self.cols + x
+    }
+
+    pub fn is_free(&self, cell: Cell) -> bool {
+        !self.blocked[self.index(cell)]
+    }
+
+    pub fn block(&mut self, cell: Cell) {
+        let i = self.index(cell);
+        self.blocked[i] = true;
+    }
+
+    /// Block every cell overlapped by the given rectangle, in map units.
+    pub fn block_rect(&mut self, pos: Vector2<i32>, dim: Vector2<i32>) {
+        if dim[0] <= 0 || dim[1] <= 0 {
+            return;
+        }
+        let size = self.cell_size as i32;
+        let first = |p: i32| (i32::max(p, 0) / size) as usize;
+        let last = |p: i32, n: usize| usize::min(n, ((i32::max(p, 0) + size - 1) / size) as usize);
+        let (x0, x1) = (first(pos[0]), last(pos[0] + dim[0], self.cols));
+        let (y0, y1) = (first(pos[1]), last(pos[1] + dim[1], self.rows));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.block((x, y));
+            }
+        }
+    }
+
+    /// The cell containing the given position, if it is inside the grid.
+    pub fn cell_at(&self, pos: Vector2<f32>) -> Option<Cell> {
+        if pos[0] < 0. || pos[1] < 0. {
+            return None;
+        }
+        let x = (pos[0] / self.cell_size as f32) as usize;
+        let y = (pos[1] / self.cell_size as f32) as usize;
+        if x < self.cols && y < self.rows {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// The center of a cell, in map units.
+    pub fn cell_center(&self, (x, y): Cell) -> Vector2<i32> {
+        let size = self.cell_size as i32;
+        Vector2::new(x as i32 * size + size / 2, y as i32 * size + size / 2)
+    }
+
+    /// The cells next to the given one, horizontally or vertically.
+    pub fn neighbours(&self, (x, y): Cell) -> impl Iterator<Item = Cell> {
+        let (cols, rows) = (self.cols, self.rows);
+        let candidates = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        candidates
+            .to_vec()
+            .into_iter()
+            .filter(move |&(x, y)| x < cols && y < rows)
+    }
+
+    /// Flood-fill the free cells from `start`, returning the parent of each
+    /// reachable cell (the start is its own parent).
+    pub fn flood_fill(&self, start: Cell) -> Vec<Option<Cell>> {
+        let mut parents = vec![None; self.blocked.len()];
+        parents[self.index(start)] = Some(start);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(cell) = queue.pop_front() {
+            for next in self.neighbours(cell) {
+                let i = self.index(next);
+                if self.is_free(next) && parents[i].is_none() {
+                    parents[i] = Some(cell);
+                    queue.push_back(next);
+                }
+            }
+        }
+        parents
+    }
+
+    /// Obtain the path of cells from the start of a flood-fill to `end`, if
+    /// it was reached.
+    pub fn path_to(&self, parents: &[Option<Cell>], end: Cell) -> Option<Vec<Cell>> {
+        let mut path = vec![end];
+        let mut cell = end;
+        loop {
+            let parent = parents[self.index(cell)]?;
+            if parent == cell {
+                break;
+            }
+            path.push(parent);
+            cell = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// The number of cells from each cell to the nearest blocked cell or
+    /// the edge of the grid, counting diagonal steps as one. Blocked cells
+    /// have a clearance of zero, and free cells at least one.
+    pub fn clearance(&self) -> Vec<u32> {
+        let mut clearance = vec![u32::max_value(); self.blocked.len()];
+        let mut queue = VecDeque::new();
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                let i = self.index((x, y));
+                if self.blocked[i] {
+                    clearance[i] = 0;
+                    queue.push_back((x, y));
+                } else if x == 0 || y == 0 || x + 1 == self.cols || y + 1 == self.rows {
+                    clearance[i] = 1;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            let next = clearance[self.index((x, y))] + 1;
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    let (nx, ny) = ((x + dx).wrapping_sub(1), (y + dy).wrapping_sub(1));
+                    if nx >= self.cols || ny >= self.rows {
+                        continue;
+                    }
+                    let i = self.index((nx, ny));
+                    if clearance[i] > next {
+                        clearance[i] = next;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+        clearance
+    }
+
+    /// The clearance of a single cell, from the result of `clearance`.
+    pub fn clearance_at(&self, clearance: &[u32], cell: Cell) -> u32 {
+        clearance[self.index(cell)]
+    }
+
+    /// Obtain a grid in which every cell closer than `cells` to a blocked
+    /// cell or the edge of the grid is blocked as well. Where an object
+    /// spans that many cells from its center, it can only be centered on
+    /// the free cells of the inflated grid.
+    pub fn inflated(&self, cells: u32) -> OccupancyGrid {
+        let clearance = self.clearance();
+        OccupancyGrid {
+            cell_size: self.cell_size,
+            cols: self.cols,
+            rows: self.rows,
+            blocked: clearance.iter().map(|&c| c < cells).collect(),
+        }
+    }
+}
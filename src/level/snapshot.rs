@@ -0,0 +1,135 @@
+//! Module for rendering a schematic picture of a level, without a graphics
+//! context.
+
+use image::{Rgba, RgbaImage};
+use na::Vector2;
+use game::ball::BALL_DEFAULT_SIZE;
+use game::entities::{BOOST_SIZE, FINISH_SIZE, GEM_SIZE_W, MINE_SIZE, PUMP_SIZE};
+use game::minimap::{FINISH_COLOR, GEM_COLOR, MINE_COLOR, PUMP_COLOR};
+use util::vec_f32;
+use super::GameLevel;
+
+/// The largest width or height of a snapshot, in pixels. Bigger maps are
+/// scaled down to fit.
+pub const MAX_SNAPSHOT_SIZE: u32 = 2048;
+const WATER_COLOR: [f32; 4] = [0.2, 0.45, 0.9, 0.5];
+const SPIKE_COLOR: [f32; 4] = [0.75, 0.75, 0.8, 1.0];
+const BOOST_COLOR: [f32; 4] = [0.3, 0.9, 1.0, 1.0];
+const LEAK_COLOR: [f32; 4] = [1.0, 0.2, 0.15, 0.35];
+const BALL_COLOR: [f32; 4] = [0.5, 0.86, 1.0, 1.0];
+
+/// A canvas in map coordinates, backed by an image.
+struct Canvas {
+    image: RgbaImage,
+    scale: f32,
+}
+
+impl Canvas {
+    /// Blend a color into a pixel, ignoring pixels outside of the image.
+    fn blend(&mut self, x: i64, y: i64, color: [f32; 4]) {
+        if x < 0 || y < 0 || x >= i64::from(self.image.width()) || y >= i64::from(self.image.height()) {
+            return;
+        }
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        let a = color[3];
+        for c in 0..3 {
+            let old = f32::from(pixel.data[c]) / 255.;
+            pixel.data[c] = ((old * (1. - a) + color[c] * a) * 255.).round() as u8;
+        }
+    }
+
+    fn fill_rect(&mut self, pos: Vector2<f32>, dim: Vector2<f32>, color: [f32; 4]) {
+        let x0 = (pos[0] * self.scale).floor() as i64;
+        let y0 = (pos[1] * self.scale).floor() as i64;
+        // always cover at least one pixel
+        let x1 = i64::max(x0 + 1, ((pos[0] + dim[0]) * self.scale).ceil() as i64);
+        let y1 = i64::max(y0 + 1, ((pos[1] + dim[1]) * self.scale).ceil() as i64);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    fn fill_circle(&mut self, center: Vector2<f32>, radius: f32, color: [f32; 4]) {
+        let (cx, cy) = (center[0] * self.scale, center[1] * self.scale);
+        // always cover at least one pixel
+        let r = f32::max(1., radius * self.scale);
+        for y in (cy - r).floor() as i64..(cy + r).ceil() as i64 {
+            for x in (cx - r).floor() as i64..(cx + r).ceil() as i64 {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                if dx * dx + dy * dy <= r * r {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+/// Choose a wall color for a texture, so that walls of the same texture
+/// look alike.
+fn wall_color(texture_id: u32) -> [f32; 4] {
+    let h = texture_id.wrapping_mul(2_654_435_761);
+    let channel = |shift: u32| 0.25 + f32::from(((h >> shift) & 0xFF) as u8) / 255. * 0.4;
+    [channel(0), channel(8), channel(16), 1.0]
+}
+
+impl GameLevel {
+    /// Draw a top-down schematic of the level, with `scale` pixels for each
+    /// map unit. Large maps are scaled down so that the picture is at most
+    /// `MAX_SNAPSHOT_SIZE` pixels wide or high.
+    pub fn render_to_image(&self, scale: u32) -> RgbaImage {
+        let (w, h) = (self.map().width(), self.map().height());
+        let mut scale = u32::max(1, scale) as f32;
+        let largest = u32::max(w, h) as f32 * scale;
+        if largest > MAX_SNAPSHOT_SIZE as f32 {
+            scale *= MAX_SNAPSHOT_SIZE as f32 / largest;
+        }
+        let width = u32::max(1, (w as f32 * scale).round() as u32);
+        let height = u32::max(1, (h as f32 * scale).round() as u32);
+
+        let bg = self.background_color();
+        let bg = Rgba([
+            (bg[0] * 255.) as u8,
+            (bg[1] * 255.) as u8,
+            (bg[2] * 255.) as u8,
+            255,
+        ]);
+        let mut canvas = Canvas {
+            image: RgbaImage::from_pixel(width, height, bg),
+            scale,
+        };
+
+        for wall in self.walls() {
+            canvas.fill_rect(vec_f32(wall.pos), vec_f32(wall.dim), wall_color(wall.texture_id));
+        }
+        for spikes in self.spikes() {
+            canvas.fill_rect(vec_f32(spikes.pos), vec_f32(spikes.dim), SPIKE_COLOR);
+        }
+        for water in self.water() {
+            canvas.fill_rect(vec_f32(water.pos), vec_f32(water.dim), WATER_COLOR);
+        }
+        for leak in self.leaks() {
+            canvas.fill_circle(vec_f32(leak.pos), leak.radius, LEAK_COLOR);
+        }
+        for boost in self.boosts() {
+            canvas.fill_circle(vec_f32(boost.pos), BOOST_SIZE / 4., BOOST_COLOR);
+        }
+        for pump in self.pumps() {
+            canvas.fill_circle(vec_f32(pump.pos), PUMP_SIZE / 4., PUMP_COLOR);
+        }
+        for mine in self.mines() {
+            canvas.fill_circle(vec_f32(mine.pos), MINE_SIZE, MINE_COLOR);
+        }
+        for gem in self.gems() {
+            canvas.fill_circle(vec_f32(gem.pos), GEM_SIZE_W / 4., GEM_COLOR);
+        }
+        if let Some(finish) = self.finish_flag() {
+            canvas.fill_circle(vec_f32(finish.pos), FINISH_SIZE / 4., FINISH_COLOR);
+        }
+        let ball_size = self.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+        canvas.fill_circle(self.ball_position(), ball_size / 2., BALL_COLOR);
+
+        canvas.image
+    }
+}
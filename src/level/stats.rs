@@ -0,0 +1,163 @@
+//! Module for summarizing game levels, including a crude estimate of their
+//! difficulty.
+//!
+//! The estimate follows the shortest path of free cells from the ball to
+//! the finish flag on an occupancy grid, counting the mines close to it and
+//! measuring the narrowest passage it goes through.
+
+use na::{norm, Vector2};
+use game::ball::BALL_DEFAULT_SIZE;
+use lang::{tr, tr_fmt};
+use util::vec_f32;
+use super::{GameLevel, MAX_DIFFICULTY};
+use super::occupancy::OccupancyGrid;
+
+/// The size of the cells of the occupancy grid used for the estimate, in
+/// map units.
+pub const STATS_CELL_SIZE: u32 = 8;
+/// How close a mine must be to the path to be counted as a danger.
+const MINE_DANGER_RADIUS: f32 = 32.;
+
+/// What was found along the path from the ball to the finish flag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStats {
+    /// the length of the path, in map units
+    pub length: f32,
+    /// the number of mines close to the path, per 1000 units of path
+    pub mine_density: f32,
+    /// the width of the narrowest passage along the path, in ball diameters
+    pub narrowest: f32,
+}
+
+/// A summary of a level's contents.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelStats {
+    pub map_size: (u32, u32),
+    pub walls: usize,
+    pub spikes: usize,
+    pub water: usize,
+    pub decorations: usize,
+    pub gems: usize,
+    pub mines: usize,
+    pub pumps: usize,
+    pub boosts: usize,
+    pub leaks: usize,
+    /// the gems required by the finish flag, if there is one
+    pub gems_required: Option<u32>,
+    /// the straight-line distance from the ball to the finish flag
+    pub finish_distance: Option<f32>,
+    /// the path to the finish flag, if the flag can be reached
+    pub path: Option<PathStats>,
+}
+
+impl LevelStats {
+    pub fn of(level: &GameLevel) -> Self {
+        let ball_pos = level.ball_position();
+        let finish_pos = level
+            .finish_flag()
+            .map(|f| vec_f32(f.pos));
+        LevelStats {
+            map_size: (level.map().width(), level.map().height()),
+            walls: level.walls().len(),
+            spikes: level.spikes().len(),
+            water: level.water().len(),
+            decorations: level.decorations().len(),
+            gems: level.gems().len(),
+            mines: level.mines().len(),
+            pumps: level.pumps().len(),
+            boosts: level.boosts().len(),
+            leaks: level.leaks().len(),
+            gems_required: level.finish_flag().map(|f| f.gems_required),
+            finish_distance: finish_pos.map(|pos| norm(&(pos - ball_pos))),
+            path: finish_pos.and_then(|pos| path_stats(level, ball_pos, pos)),
+        }
+    }
+
+    /// A crude estimate of the level's difficulty, from 1 to
+    /// `MAX_DIFFICULTY`, if the finish flag can be reached. Mines along the
+    /// way and passages barely wider than the ball make it harder.
+    pub fn estimated_difficulty(&self) -> Option<u8> {
+        self.path.map(|path| {
+            let mines = f32::min(2., path.mine_density / 2.);
+            let narrow = if path.narrowest < 1.5 {
+                2.
+            } else if path.narrowest < 2.5 {
+                1.
+            } else {
+                0.
+            };
+            let score = (1. + mines + narrow).round() as u8;
+            u8::min(u8::max(score, 1), MAX_DIFFICULTY)
+        })
+    }
+
+    /// Describe the statistics in a few lines of text.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            tr_fmt("stats.map", &[&self.map_size.0, &self.map_size.1]),
+            tr_fmt("stats.structures", &[&self.walls, &self.spikes, &self.water, &self.decorations]),
+            tr_fmt("stats.items", &[&self.gems, &self.mines, &self.pumps, &self.boosts, &self.leaks]),
+        ];
+        match (self.gems_required, self.finish_distance) {
+            (Some(required), Some(distance)) => {
+                lines.push(tr_fmt("stats.gems_required", &[&required, &self.gems]));
+                lines.push(tr_fmt("stats.finish_distance", &[&format!("{:.0}", distance)]));
+            }
+            _ => lines.push(tr("stats.no_finish")),
+        }
+        match self.path {
+            Some(path) => {
+                lines.push(tr_fmt("stats.mine_density", &[&format!("{:.1}", path.mine_density)]));
+                lines.push(tr_fmt("stats.narrowest", &[&format!("{:.1}", path.narrowest)]));
+            }
+            None if self.gems_required.is_some() => lines.push(tr("stats.no_path")),
+            None => {}
+        }
+        if let Some(difficulty) = self.estimated_difficulty() {
+            lines.push(tr_fmt("stats.difficulty", &[&difficulty, &MAX_DIFFICULTY]));
+        }
+        lines
+    }
+}
+
+/// Follow the shortest path between the two positions on the level's
+/// occupancy grid, if there is one.
+fn path_stats(level: &GameLevel, from: Vector2<f32>, to: Vector2<f32>) -> Option<PathStats> {
+    let grid = OccupancyGrid::from_level(level, STATS_CELL_SIZE);
+    let (start, end) = (grid.cell_at(from)?, grid.cell_at(to)?);
+    if !grid.is_free(start) {
+        return None;
+    }
+    let parents = grid.flood_fill(start);
+    let path = grid.path_to(&parents, end)?;
+
+    let cell_size = STATS_CELL_SIZE as f32;
+    let length = path.len() as f32 * cell_size;
+    let near_path = |pos: Vector2<f32>| {
+        path.iter().any(|&c| {
+            let center = grid.cell_center(c);
+            norm(&(vec_f32(center) - pos)) <= MINE_DANGER_RADIUS
+        })
+    };
+    let mines = level
+        .mines()
+        .iter()
+        .filter(|m| near_path(vec_f32(m.pos)))
+        .count();
+
+    // a cell with a clearance of n lies in a passage about 2n - 1 cells wide
+    let clearance = grid.clearance();
+    let narrowest = path
+        .iter()
+        .map(|&c| grid.clearance_at(&clearance, c))
+        .min()
+        .unwrap_or(1);
+    let width = (2 * narrowest).saturating_sub(1) as f32 * cell_size;
+    let ball_size = level.ball_size().unwrap_or(BALL_DEFAULT_SIZE);
+
+    Some(PathStats {
+        length,
+        mine_density: mines as f32 * 1000. / f32::max(length, cell_size),
+        narrowest: width / ball_size,
+    })
+}
@@ -0,0 +1,203 @@
+//! Module for importing maps made with the Tiled map editor, in its JSON
+//! format (`.tmj`).
+//!
+//! Objects are mapped by their type (or class, in newer versions of
+//! Tiled): rectangles of type `wall` become walls, with the texture given
+//! by an optional `texture_id` property, while objects of type `mine`,
+//! `gem`, `pump`, `finish` and `ball` are placed at their position (or at
+//! their center, if they are not points). The map's size is its number of
+//! tiles times the size of each tile. Anything else is skipped with a
+//! warning.
+
+use std::fs::File;
+use std::path::Path;
+use na::Vector2;
+use serde_json::{from_reader, Value};
+use util::{vec_f32, DynResult};
+use super::GameLevel;
+use super::info::*;
+
+/// A Tiled map, only with the parts which can be imported.
+#[derive(Debug, Clone, Deserialize)]
+struct TiledMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)] infinite: bool,
+    #[serde(default)] layers: Vec<TiledLayer>,
+    #[serde(default)] properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TiledLayer {
+    #[serde(default)] name: String,
+    #[serde(rename = "type")] kind: String,
+    #[serde(default)] objects: Vec<TiledObject>,
+    #[serde(default)] layers: Vec<TiledLayer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TiledObject {
+    #[serde(default)] id: u32,
+    #[serde(default, rename = "type")] kind: String,
+    /// the object's type, as named by Tiled 1.9 and later
+    #[serde(default)] class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)] width: f64,
+    #[serde(default)] height: f64,
+    #[serde(default)] rotation: f64,
+    #[serde(default)] point: bool,
+    #[serde(default)] ellipse: bool,
+    #[serde(default)] polygon: Option<Value>,
+    #[serde(default)] polyline: Option<Value>,
+    #[serde(default)] text: Option<Value>,
+    #[serde(default)] properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: Value,
+}
+
+impl TiledObject {
+    fn kind(&self) -> &str {
+        if self.kind.is_empty() {
+            &self.class
+        } else {
+            &self.kind
+        }
+    }
+
+    /// The object's position, or its center if it has an area.
+    fn center(&self) -> Vector2<i32> {
+        Vector2::new(
+            (self.x + self.width / 2.).round() as i32,
+            (self.y + self.height / 2.).round() as i32,
+        )
+    }
+
+    fn property_u32(&self, name: &str) -> Option<u32> {
+        self.properties
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.value.as_u64())
+            .map(|v| v as u32)
+    }
+
+    /// Describe the shape of the object, if it is not a rectangle or a
+    /// point.
+    fn unsupported_shape(&self) -> Option<&'static str> {
+        if self.ellipse {
+            Some("ellipse")
+        } else if self.polygon.is_some() {
+            Some("polygon")
+        } else if self.polyline.is_some() {
+            Some("polyline")
+        } else if self.text.is_some() {
+            Some("text")
+        } else {
+            None
+        }
+    }
+}
+
+impl GameLevel {
+    /// Convert a map made with Tiled into a game level. Features of the map
+    /// which cannot be imported are listed in the returned warnings.
+    pub fn from_tiled<P: AsRef<Path>>(path: P) -> DynResult<(GameLevel, Vec<String>)> {
+        let path = path.as_ref();
+        if path.extension().map(|e| e == "tmx").unwrap_or(false) {
+            return Err(format_err!("TMX maps are not supported, export the map as JSON (.tmj) from Tiled"));
+        }
+        let file = File::open(path)?;
+        let map: TiledMap = from_reader(file)?;
+
+        let mut warnings = vec![];
+        let mut level = GameLevel::default();
+        if map.infinite {
+            warnings.push("infinite maps are not supported, only the initial area is imported".to_string());
+        }
+        let name = map.properties
+            .iter()
+            .find(|p| p.name == "name")
+            .and_then(|p| p.value.as_str().map(String::from))
+            .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()));
+        if let Some(name) = name {
+            level.set_name(name);
+        }
+        let (width, height) = (map.width * map.tilewidth, map.height * map.tileheight);
+        if width == 0 || height == 0 {
+            return Err(format_err!("Map has no area ({}x{})", width, height));
+        }
+        level.map_mut().resize(width, height);
+
+        let mut finish_required = None;
+        // layers are visited in order, including those inside groups
+        let mut layers: Vec<&TiledLayer> = map.layers.iter().rev().collect();
+        while let Some(layer) = layers.pop() {
+            match layer.kind.as_str() {
+                "objectgroup" => {}
+                "group" => {
+                    layers.extend(layer.layers.iter().rev());
+                    continue;
+                }
+                kind => {
+                    warnings.push(format!("skipped {} layer \"{}\"", kind, layer.name));
+                    continue;
+                }
+            }
+            for object in &layer.objects {
+                if let Some(shape) = object.unsupported_shape() {
+                    warnings.push(format!("skipped {} object #{}", shape, object.id));
+                    continue;
+                }
+                if object.rotation != 0. {
+                    warnings.push(format!("ignored the rotation of object #{}", object.id));
+                }
+                let pos = object.center();
+                match object.kind() {
+                    "wall" => {
+                        if object.point {
+                            warnings.push(format!("skipped wall #{}, which has no area", object.id));
+                            continue;
+                        }
+                        level.walls_mut().push(WallInfo {
+                            pos: Vector2::new(object.x.round() as i32, object.y.round() as i32),
+                            dim: Vector2::new(object.width.round() as i32, object.height.round() as i32),
+                            texture_id: object.property_u32("texture_id").unwrap_or(0),
+                            rotation: 0,
+                            flip: false,
+                            tile: false,
+                        });
+                    }
+                    "mine" => level.mines_mut().push(MineInfo { pos }),
+                    "gem" => level.gems_mut().push(GemInfo { pos }),
+                    "pump" => level.pumps_mut().push(PumpInfo {
+                        pos,
+                        charges: object.property_u32("charges"),
+                    }),
+                    "finish" => {
+                        finish_required = object.property_u32("gems_required");
+                        level.set_finish_flag(FinishInfo {
+                            pos,
+                            gems_required: 0,
+                        });
+                    }
+                    "ball" => level.set_ball_position(vec_f32(pos)),
+                    "" => warnings.push(format!("skipped object #{} without a type", object.id)),
+                    kind => warnings.push(format!("skipped object #{} of unknown type \"{}\"", object.id, kind)),
+                }
+            }
+        }
+
+        // unless told otherwise, the flag requires all gems
+        let gems = level.gems().len() as u32;
+        if let Some(finish) = level.finish_flag_mut() {
+            finish.gems_required = finish_required.unwrap_or(gems);
+        }
+        Ok((level, warnings))
+    }
+}
@@ -1,9 +1,5 @@
 use na::Vector2;
-
-#[inline]
-pub fn vector_to_i32(v: Vector2<f32>) -> Vector2<i32> {
-    Vector2::from([v[0] as i32, v[1] as i32])
-}
+use util::vec_i32;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GemInfo {
@@ -13,7 +9,7 @@ pub struct GemInfo {
 impl GemInfo {
     pub fn upgrade(self) -> ::level::info::GemInfo {
         ::level::info::GemInfo {
-            pos: vector_to_i32(self.pos),
+            pos: vec_i32(self.pos),
         }
     }
 }
@@ -26,7 +22,8 @@ pub struct PumpInfo {
 impl PumpInfo {
     pub fn upgrade(self) -> ::level::info::PumpInfo {
         ::level::info::PumpInfo {
-            pos: vector_to_i32(self.pos),
+            pos: vec_i32(self.pos),
+            charges: None,
         }
     }
 }
@@ -39,7 +36,7 @@ pub struct MineInfo {
 impl MineInfo {
     pub fn upgrade(self) -> ::level::info::MineInfo {
         ::level::info::MineInfo {
-            pos: vector_to_i32(self.pos),
+            pos: vec_i32(self.pos),
         }
     }
 }
@@ -55,9 +52,12 @@ pub struct WallInfo {
 impl WallInfo {
     pub fn upgrade(self) -> ::level::info::WallInfo {
         ::level::info::WallInfo {
-            pos: vector_to_i32(self.pos),
-            dim: vector_to_i32(self.dim),
+            pos: vec_i32(self.pos),
+            dim: vec_i32(self.dim),
             texture_id: self.texture_id,
+            rotation: 0,
+            flip: false,
+            tile: false,
         }
     }
 }
@@ -72,7 +72,7 @@ pub struct FinishInfo {
 impl FinishInfo {
     pub fn upgrade(self) -> ::level::info::FinishInfo {
         ::level::info::FinishInfo {
-            pos: vector_to_i32(self.pos),
+            pos: vec_i32(self.pos),
             gems_required: self.gems_required,
         }
     }
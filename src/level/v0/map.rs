@@ -20,6 +20,10 @@ impl Default for Map {
 }
 
 impl Map {
+    pub fn is_finite(&self) -> bool {
+        self.width.is_finite() && self.height.is_finite()
+    }
+
     pub fn upgrade(self) -> CurrentMap {
         CurrentMap::new(self.width as u32, self.height as u32)
     }
@@ -44,6 +44,10 @@ impl Default for GameLevel {
 
 impl GameLevel {
     pub fn upgrade(self) -> DynResult<CurrentGameLevel> {
+        if !self.is_finite() {
+            return Err(format_err!("Level contains non-finite coordinates"));
+        }
+
         let mut lvl = CurrentGameLevelBuilder::default();
         lvl.name(self.name);
         lvl.version(CURRENT_VERSION.to_string());
@@ -61,4 +65,17 @@ impl GameLevel {
         let lvl = lvl.build().map_err(::failure::err_msg)?;
         Ok(lvl)
     }
+
+    /// Check whether all coordinates and dimensions in the level are finite
+    /// numbers.
+    fn is_finite(&self) -> bool {
+        let finite = |v: &Vector2<f32>| v[0].is_finite() && v[1].is_finite();
+        self.map.is_finite()
+            && finite(&self.ball_pos)
+            && self.walls.iter().all(|w| finite(&w.pos) && finite(&w.dim))
+            && self.pumps.iter().all(|x| finite(&x.pos))
+            && self.mines.iter().all(|x| finite(&x.pos))
+            && self.gems.iter().all(|x| finite(&x.pos))
+            && self.finish.iter().all(|x| finite(&x.pos))
+    }
 }
@@ -0,0 +1,46 @@
+//! Propan game library. The game logic, levels, physics and resource
+//! management live here, whereas the binary only sets up the window and the
+//! graphics pipeline.
+extern crate clap;
+#[macro_use]
+extern crate derive_builder;
+#[macro_use]
+extern crate failure;
+#[macro_use]
+extern crate failure_derive;
+extern crate flate2;
+extern crate gif;
+extern crate graphics;
+extern crate image;
+extern crate itertools;
+extern crate nalgebra as na;
+extern crate piston;
+extern crate ron;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate texture;
+
+pub mod achievements;
+pub mod camera;
+pub mod capture;
+#[cfg(feature = "dev-console")]
+pub mod console;
+pub mod consts;
+pub mod controller;
+pub mod editor;
+pub mod error;
+pub mod game;
+pub mod lang;
+pub mod level;
+pub mod loading;
+pub mod pause;
+pub mod physics;
+pub mod profile;
+pub mod progress;
+pub mod resource;
+pub mod settings;
+pub mod title;
+pub mod tool;
+pub mod util;
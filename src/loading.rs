@@ -0,0 +1,293 @@
+//! Module for the loading screen, which is shown while a level is read and
+//! its entities are created.
+
+use std::mem;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::Instant;
+use graphics::character::CharacterCache;
+use graphics::{clear, rectangle, Context, DrawState, Graphics, Text, Transformed};
+use piston::input::{GenericEvent, UpdateArgs};
+use controller::{Controller, ControllerAction, GameState, LevelId};
+use game::{GameController, GameOptions};
+use game::level_entities::LevelEntities;
+use game::replay::Replay;
+use lang::{tr, tr_fmt};
+use level::{GameLevel, LevelError, LEVEL_DIR};
+use profile::duration_ms;
+use resource::{GameTexture, ResourceManage, SpriteAssetId, SpriteManage};
+use resource::sprite::free_level_textures;
+use util::DynResult;
+
+/// The longest time spent creating entities in a single update, so that
+/// the loading screen keeps being redrawn, in milliseconds.
+const LOAD_STEP_MS: f64 = 4.;
+/// The position of the loading message, in hires pixels.
+const MESSAGE_POS: [f64; 2] = [48., 260.];
+/// The size of the progress bar, in hires pixels.
+const BAR_SIZE: [f64; 2] = [400., 12.];
+const BAR_COLOR: [f32; 4] = [0.4, 0.8, 1.0, 1.0];
+const BAR_BACK_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 1.0];
+
+/// What the worker thread reads from the disk.
+struct LevelFiles {
+    level: GameLevel,
+    path: PathBuf,
+    /// the level which follows this one, if any
+    next: Option<LevelId>,
+    /// the run to play back, if any
+    replay: Option<Replay>,
+}
+
+/// How far the loading has come.
+enum Stage<R>
+where
+    R: ResourceManage,
+{
+    /// the level is being read on a worker thread
+    Reading(Receiver<DynResult<LevelFiles>>),
+    /// the level's assets are loaded, and its entities are being created
+    Creating(LevelFiles, LevelEntities<R>),
+    /// the game is ready to take over
+    Ready(GameController<R>),
+    /// loading failed, and the error is shown over the loading screen
+    Failed,
+}
+
+/// A controller which loads a level without holding up the window, and is
+/// replaced by the game once done.
+pub struct LoadingController<R>
+where
+    R: ResourceManage + Copy,
+{
+    res: R,
+    id: LevelId,
+    options: GameOptions,
+    /// whether a replay is to be played back on the level
+    replay: bool,
+    stage: Stage<R>,
+    /// whether the level's textures were loaded, which must be freed if the
+    /// game never takes over
+    assets_loaded: bool,
+}
+
+impl<R> LoadingController<R>
+where
+    R: ResourceManage + Copy,
+{
+    /// Start loading the level with the given index, to be played with the
+    /// given options.
+    pub fn game(id: LevelId, res: R, options: GameOptions) -> Self {
+        LoadingController::start(id, None, res, options)
+    }
+
+    /// Start loading the level with the given index, to play back the
+    /// replay file on it under the replay's own modifiers.
+    pub fn replay(id: LevelId, replay_path: String, res: R) -> Self {
+        LoadingController::start(id, Some(replay_path), res, GameOptions::default())
+    }
+
+    fn start(id: LevelId, replay_path: Option<String>, res: R, options: GameOptions) -> Self {
+        let (sender, receiver) = channel();
+        let replay = replay_path.is_some();
+        thread::spawn(move || {
+            // the receiver is gone if the loading was cancelled
+            let _ = sender.send(read_level(id, replay_path));
+        });
+        LoadingController {
+            res,
+            id,
+            options,
+            replay,
+            stage: Stage::Reading(receiver),
+            assets_loaded: false,
+        }
+    }
+
+    /// Take the game which was loaded, if it is ready.
+    pub fn into_game(self) -> Option<GameController<R>> {
+        match self.stage {
+            Stage::Ready(game) => Some(game),
+            _ => None,
+        }
+    }
+
+    /// Carry on loading for a little while, returning whether the game is
+    /// ready.
+    fn advance(&mut self) -> DynResult<bool> {
+        let start = Instant::now();
+        loop {
+            self.stage = match mem::replace(&mut self.stage, Stage::Failed) {
+                Stage::Reading(receiver) => match receiver.try_recv() {
+                    Ok(files) => {
+                        let files = files?;
+                        // textures are loaded in one go, ahead of the entities
+                        LevelEntities::load_assets(&files.level, self.res)?;
+                        self.assets_loaded = true;
+                        Stage::Creating(files, LevelEntities::empty())
+                    }
+                    Err(TryRecvError::Empty) => {
+                        self.stage = Stage::Reading(receiver);
+                        return Ok(false);
+                    }
+                    Err(TryRecvError::Disconnected) => {
+                        return Err(format_err!("The level could not be read"));
+                    }
+                },
+                Stage::Creating(files, mut entities) => {
+                    while entities.create_next(&files.level, self.res)? {
+                        if duration_ms(start.elapsed()) >= LOAD_STEP_MS {
+                            self.stage = Stage::Creating(files, entities);
+                            return Ok(false);
+                        }
+                    }
+                    Stage::Ready(self.start_game(files, entities)?)
+                }
+                Stage::Ready(game) => {
+                    self.stage = Stage::Ready(game);
+                    return Ok(true);
+                }
+                Stage::Failed => return Ok(false),
+            };
+        }
+    }
+
+    fn start_game(&self, files: LevelFiles, entities: LevelEntities<R>) -> DynResult<GameController<R>> {
+        let game = GameController::with_entities(files.level, entities, self.res, self.options)?;
+        let mut game = game.with_path(files.path).with_next_level(files.next);
+        if let Some(replay) = files.replay {
+            game.play_replay(replay)?;
+        }
+        Ok(game)
+    }
+
+    /// The fraction of the loading which is done.
+    fn progress(&self) -> f64 {
+        match self.stage {
+            Stage::Reading(_) | Stage::Failed => 0.,
+            // loading the assets counts as one entity
+            Stage::Creating(ref files, ref entities) => {
+                (entities.created() + 1) as f64 / (LevelEntities::<R>::count(&files.level) + 1) as f64
+            }
+            Stage::Ready(_) => 1.,
+        }
+    }
+
+    /// Describe what went wrong while loading.
+    fn error_message(&self, e: &::failure::Error) -> String {
+        let id = self.id;
+        let key = if self.replay { "error.replay" } else { "error.level" };
+        e.downcast_ref()
+            .and_then(|e| level_error_message(id, e))
+            .unwrap_or_else(|| tr_fmt(key, &[&id, e]))
+    }
+}
+
+impl<R> Controller for LoadingController<R>
+where
+    R: ResourceManage + Copy,
+{
+    type Res = R;
+    const NEEDS_HI_RES: bool = true;
+    const NAME: &'static str = "loading";
+
+    fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
+        use piston::input::Button::Keyboard;
+        use piston::input::{ButtonState, Key};
+        if let Some(b) = e.button_args() {
+            if let (Keyboard(Key::Escape), ButtonState::Press) = (b.button, b.state) {
+                return Some(ControllerAction::LoadTitleScreen);
+            }
+        }
+        None
+    }
+
+    fn update(&mut self, _u: UpdateArgs) -> Option<ControllerAction> {
+        match self.advance() {
+            Ok(true) => Some(ControllerAction::Loaded),
+            Ok(false) => None,
+            Err(e) => Some(ControllerAction::Push(GameState::Error {
+                message: self.error_message(&e),
+                fatal: false,
+            })),
+        }
+    }
+
+    fn render<C, G>(&self, _c: Context, _cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        clear([0., 0., 0., 1.], g);
+    }
+
+    fn render_hires<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let message = match self.stage {
+            Stage::Creating(ref files, _) => tr_fmt("loading.level", &[&files.level.name()]),
+            _ => tr_fmt("loading.level_number", &[&self.id]),
+        };
+        let c = c.trans(MESSAGE_POS[0], MESSAGE_POS[1]);
+        let _ = Text::new_color([1.; 4], 16).draw(&message, cache, &DrawState::default(), c.transform, g);
+        let bar = [0., 16., BAR_SIZE[0], BAR_SIZE[1]];
+        rectangle(BAR_BACK_COLOR, bar, c.transform, g);
+        rectangle(BAR_COLOR, [0., 16., BAR_SIZE[0] * self.progress(), BAR_SIZE[1]], c.transform, g);
+        let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 12).draw(
+            &tr("loading.cancel"),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(0., 64.),
+            g,
+        );
+    }
+
+    fn exit(&mut self) {
+        // the loading was cancelled or failed, so the game will not free
+        // the level's textures
+        if self.assets_loaded {
+            let mut sprite = self.res.sprite();
+            let _ = sprite.free_sprite(SpriteAssetId::LevelBackground);
+            free_level_textures(&mut sprite);
+        }
+    }
+}
+
+/// Read the level with the given index from the level directory, along
+/// with the replay file to play back on it.
+fn read_level(id: LevelId, replay_path: Option<String>) -> DynResult<LevelFiles> {
+    let path = GameLevel::path_by_index(LEVEL_DIR, id)?;
+    let level = GameLevel::load(&path)?;
+    let replay = match replay_path {
+        Some(replay_path) => Some(Replay::load(replay_path)?),
+        None => None,
+    };
+    // levels are ordered by chapter, so the next one either follows in the
+    // same chapter or starts the next chapter
+    let next = id
+        .checked_add(1)
+        .filter(|&next| replay.is_none() && GameLevel::path_by_index(LEVEL_DIR, next).is_ok());
+    Ok(LevelFiles {
+        level,
+        path,
+        next,
+        replay,
+    })
+}
+
+/// Describe the level errors which the player can do something about,
+/// leaving the others to the generic message.
+fn level_error_message(id: LevelId, e: &LevelError) -> Option<String> {
+    match *e {
+        LevelError::NotFound { .. } => Some(tr_fmt("error.no_level", &[&id])),
+        LevelError::UnsupportedVersion { ref found } => Some(tr_fmt("error.level_version", &[&id, found])),
+        LevelError::Validation { ref issues } => {
+            let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+            Some(tr_fmt("error.level_invalid", &[&id, &issues.join("; ")]))
+        }
+        _ => None,
+    }
+}
@@ -1,66 +1,81 @@
 extern crate clap;
-#[macro_use]
-extern crate derive_builder;
-#[macro_use]
-extern crate failure;
-#[macro_use]
-extern crate failure_derive;
+#[cfg(all(feature = "backend-gfx", not(feature = "backend-glium")))]
 #[macro_use]
 extern crate gfx;
+#[cfg(all(feature = "backend-gfx", not(feature = "backend-glium")))]
 extern crate gfx_device_gl;
+#[cfg(all(feature = "backend-gfx", not(feature = "backend-glium")))]
 extern crate gfx_graphics;
+#[cfg(feature = "backend-glium")]
+#[macro_use]
+extern crate glium;
+#[cfg(feature = "backend-glium")]
+extern crate glium_graphics;
+#[cfg(feature = "glutin_window")]
+extern crate glutin;
 #[cfg(feature = "glutin_window")]
 extern crate glutin_window;
 #[cfg(feature = "sdl2_window")]
+extern crate sdl2;
+#[cfg(feature = "sdl2_window")]
 extern crate sdl2_window;
 extern crate graphics;
-extern crate itertools;
-extern crate nalgebra as na;
+extern crate image;
 extern crate piston;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde_json;
-
-mod camera;
-mod controller;
-mod editor;
-mod game;
-mod level;
-mod physics;
-mod resource;
-mod title;
-mod util;
+extern crate propan;
+extern crate texture;
+
+mod render;
 
 use clap::{App, Arg, SubCommand};
-use gfx::format::{DepthStencil, Formatted, Srgba8};
-use gfx::handle::{DepthStencilView, RenderTargetView};
-use gfx::memory::Typed;
-use gfx::pso::{PipelineData, PipelineState};
-use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
-use gfx::traits::*;
-use gfx::{CommandBuffer, Device, Resources, Slice};
-use gfx_graphics::{Filter, Gfx2d, GlyphCache, TextureSettings};
 #[cfg(feature = "glutin_window")]
 use glutin_window::{GlutinWindow, OpenGL};
 use graphics::character::CharacterCache;
-use graphics::Viewport;
+use graphics::{rectangle, Context, Graphics, Transformed, Viewport};
 use piston::event_loop::*;
 use piston::input::*;
-use piston::window::{OpenGLWindow, Window, WindowSettings};
+use piston::window::{AdvancedWindow, Window, WindowSettings};
 #[cfg(feature = "sdl2_window")]
 use sdl2_window::{OpenGL, Sdl2Window};
-use std::path::Path;
+use std::time::Instant;
+
+use propan::capture::Recorder;
+use propan::consts::{DEFAULT_PHYSICAL_HEIGHT, DEFAULT_PHYSICAL_WIDTH, HEIGHT, WIDTH};
+use propan::controller::{Controller, ControllerAction, GameState};
+use propan::editor::LevelEditorController;
+use propan::error::ErrorController;
+use propan::game::{GameController, GameOptions};
+use propan::lang;
+use propan::loading::LoadingController;
+use propan::pause::PauseController;
+use propan::profile::{duration_ms, FrameProfiler, FrameStats, PROFILE_FILE};
+use propan::resource::{
+    AudioManager, GameTexture, ResourceManage, ResourceManager, SpriteManage, SpriteManager,
+};
+use propan::settings::Settings;
+use propan::title::TitleController;
+use propan::tool;
 
-use controller::{Controller, ControllerAction, LevelId};
-use editor::LevelEditorController;
-use game::GameController;
-use level::GameLevel;
-use resource::{AudioManager, ResourceManage, ResourceManager, SpriteManage, SpriteManager};
-use title::TitleController;
+use render::{DrawPass, Renderer};
+#[cfg(all(feature = "backend-gfx", not(feature = "backend-glium")))]
+use render::gfx_backend::GfxRenderer as ActiveRenderer;
+#[cfg(feature = "backend-glium")]
+use render::glium_backend::GliumRenderer as ActiveRenderer;
 
-type ColorFormat = Srgba8;
-type DepthFormat = gfx::format::DepthStencil;
+/// The window title, which the title of the controller shown is added to.
+const WINDOW_TITLE: &str = "propan";
+/// The window icon, built into the game.
+static ICON: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/icon.png"));
+
+/// The most frames drawn per second while the window is out of focus, just
+/// enough to redraw the window now and then.
+const UNFOCUSED_MAX_FPS: u64 = 2;
+/// The number of updates per second while the window is out of focus, in
+/// which the game is paused.
+const UNFOCUSED_UPS: u64 = 10;
+/// How long it takes to fade to or from black between controllers, in
+/// milliseconds.
+const FADE_DURATION_MS: f64 = 300.;
 
 #[cfg(feature = "glutin_window")]
 type WindowBackend = GlutinWindow;
@@ -68,72 +83,235 @@ type WindowBackend = GlutinWindow;
 #[cfg(feature = "sdl2_window")]
 type WindowBackend = Sdl2Window;
 
-pub const WIDTH: u16 = 320;
-pub const HEIGHT: u16 = 200;
-pub const DEFAULT_PHYSICAL_WIDTH: u16 = WIDTH * 3;
-pub const DEFAULT_PHYSICAL_HEIGHT: u16 = HEIGHT * 3;
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum GameState {
-    Title,
-    Game(LevelId),
-    Editor(Option<String>),
-    Exit,
+/// The resource manager over the textures of the rendering backend.
+type GameResources =
+    ResourceManager<<ActiveRenderer as Renderer>::Factory, <ActiveRenderer as Renderer>::Texture>;
+
+/// Decode the window icon into RGBA pixels, along with its width and height.
+fn load_icon() -> Option<(Vec<u8>, u32, u32)> {
+    match image::load_from_memory(ICON) {
+        Ok(icon) => {
+            let icon = icon.to_rgba();
+            let (width, height) = icon.dimensions();
+            Some((icon.into_raw(), width, height))
+        }
+        Err(e) => {
+            eprintln!("Warning: could not load the window icon: {}", e);
+            None
+        }
+    }
 }
 
-fn create_main_targets(
-    dim: gfx::texture::Dimensions,
-) -> (
-    gfx::handle::RenderTargetView<gfx_device_gl::Resources, gfx::format::Srgba8>,
-    gfx::handle::DepthStencilView<gfx_device_gl::Resources, gfx::format::DepthStencil>,
-) {
-    let color_format = <Srgba8 as Formatted>::get_format();
-    let depth_format = <DepthFormat as Formatted>::get_format();
-    let (output_color, output_stencil) =
-        gfx_device_gl::create_main_targets_raw(dim, color_format.0, depth_format.0);
-    let output_color: RenderTargetView<_, _> = Typed::new(output_color);
-    let output_stencil: DepthStencilView<_, _> = Typed::new(output_stencil);
-    (output_color, output_stencil)
+#[cfg(feature = "glutin_window")]
+fn set_window_icon(window: &mut GlutinWindow) {
+    if let Some((pixels, width, height)) = load_icon() {
+        match glutin::Icon::from_rgba(pixels, width, height) {
+            Ok(icon) => window.window.set_window_icon(Some(icon)),
+            Err(e) => eprintln!("Warning: could not set the window icon: {:?}", e),
+        }
+    }
 }
 
-fn create_physical_viewport<W>(window: &W) -> Viewport
-where
-    W: Window,
-{
-    let piston::window::Size { width, height } = window.size();
-    // physical viewport
-    Viewport {
-        rect: [0, 0, width as i32, height as i32],
-        draw_size: [width as u32, height as u32],
-        window_size: [width as u32, height as u32],
+#[cfg(feature = "sdl2_window")]
+fn set_window_icon(window: &mut Sdl2Window) {
+    use sdl2::pixels::PixelFormatEnum;
+    use sdl2::surface::Surface;
+    // this SDL2 version has no byte order independent RGBA format
+    let format = if cfg!(target_endian = "little") {
+        PixelFormatEnum::ABGR8888
+    } else {
+        PixelFormatEnum::RGBA8888
+    };
+    if let Some((mut pixels, width, height)) = load_icon() {
+        match Surface::from_data(&mut pixels, width, height, width * 4, format) {
+            Ok(icon) => window.window.set_icon(icon),
+            Err(e) => eprintln!("Warning: could not set the window icon: {}", e),
+        }
     }
 }
 
-fn create_viewports<W>(window: &W) -> (Viewport, Viewport)
+/// The names of the monitors which the game can go fullscreen on, in the
+/// window backend's order.
+#[cfg(feature = "glutin_window")]
+fn monitor_names(window: &GlutinWindow) -> Vec<String> {
+    window
+        .window
+        .get_available_monitors()
+        .enumerate()
+        .map(|(i, monitor)| monitor.get_name().unwrap_or_else(|| format!("Monitor {}", i + 1)))
+        .collect()
+}
+
+/// Cover the monitor with the given index, or the primary monitor, with
+/// the window, leaving the monitor's resolution as it is.
+#[cfg(feature = "glutin_window")]
+fn enter_fullscreen(window: &mut GlutinWindow, monitor: Option<usize>) {
+    let monitor = match monitor.and_then(|i| window.window.get_available_monitors().nth(i)) {
+        Some(monitor) => monitor,
+        None => window.window.get_primary_monitor(),
+    };
+    window.window.set_fullscreen(Some(monitor));
+}
+
+#[cfg(feature = "sdl2_window")]
+fn monitor_names(window: &Sdl2Window) -> Vec<String> {
+    let video = window.window.subsystem();
+    let count = video.num_video_displays().unwrap_or(0);
+    (0..count)
+        .map(|i| video.display_name(i).unwrap_or_else(|_| format!("Monitor {}", i + 1)))
+        .collect()
+}
+
+#[cfg(feature = "sdl2_window")]
+fn enter_fullscreen(window: &mut Sdl2Window, monitor: Option<usize>) {
+    use sdl2::video::{FullscreenType, WindowPos};
+    // SDL goes fullscreen on the display which the window is on, and the
+    // first display is the primary one
+    let display = monitor.unwrap_or(0) as i32;
+    match window.window.subsystem().display_bounds(display) {
+        Ok(bounds) => window
+            .window
+            .set_position(WindowPos::Positioned(bounds.x()), WindowPos::Positioned(bounds.y())),
+        Err(e) => eprintln!("Warning: could not find monitor {}: {}", display + 1, e),
+    }
+    if let Err(e) = window.window.set_fullscreen(FullscreenType::Desktop) {
+        eprintln!("Warning: could not go fullscreen: {}", e);
+    }
+}
+
+/// The index of the monitor to go fullscreen on, as chosen in the settings,
+/// or none for the primary monitor.
+fn chosen_monitor(settings: &Settings, monitors: &[String]) -> Option<usize> {
+    let name = settings.monitor.as_ref()?;
+    let monitor = monitors.iter().position(|m| m == name);
+    if monitor.is_none() {
+        eprintln!("Note: monitor {} was not found, going fullscreen on the primary monitor", name);
+    }
+    monitor
+}
+
+/// Whether the window has nothing to draw on, as happens when it is
+/// minimized.
+fn is_minimized<W>(window: &W) -> bool
 where
     W: Window,
 {
-    (
-        // logical viewport
-        Viewport {
-            rect: [0, 0, ::WIDTH as i32, ::HEIGHT as i32],
-            draw_size: [WIDTH as u32, HEIGHT as u32],
-            window_size: [WIDTH as u32, HEIGHT as u32],
-        },
-        create_physical_viewport(window),
-    )
+    let (size, draw_size) = (window.size(), window.draw_size());
+    size.width == 0 || size.height == 0 || draw_size.width == 0 || draw_size.height == 0
 }
 
-fn create_gfx_device<W>(window: &mut W) -> (gfx_device_gl::Device, gfx_device_gl::Factory)
+/// The viewport of the hires pass, which covers the display within the
+/// window. The hires pass is laid out in window coordinates, like the mouse
+/// cursor, so that it keeps its size on HiDPI displays, where the
+/// framebuffer has more pixels than the window has coordinates.
+fn create_physical_viewport<W>(window: &W, letterbox: &Letterbox) -> Viewport
 where
-    W: OpenGLWindow,
+    W: Window,
 {
-    gfx_device_gl::create(|s| window.get_proc_address(s) as *const std::os::raw::c_void)
+    let draw_size = window.draw_size();
+    // a minimized window may have no area, which would make for divisions
+    // by zero in the controllers
+    let (draw_width, draw_height) = (u32::max(1, draw_size.width), u32::max(1, draw_size.height));
+    // the framebuffer pixels per window coordinate
+    let dpi = f64::from(draw_width) / letterbox.window_size[0];
+    Viewport {
+        rect: [0, 0, draw_width as i32, draw_height as i32],
+        draw_size: [
+            (letterbox.size[0] * dpi).round() as u32,
+            (letterbox.size[1] * dpi).round() as u32,
+        ],
+        window_size: [letterbox.size[0].round() as u32, letterbox.size[1].round() as u32],
+    }
+}
+
+fn create_logical_viewport() -> Viewport {
+    Viewport {
+        rect: [0, 0, WIDTH as i32, HEIGHT as i32],
+        draw_size: [WIDTH as u32, HEIGHT as u32],
+        window_size: [WIDTH as u32, HEIGHT as u32],
+    }
+}
+
+/// Where the display sits in the window, keeping its aspect ratio with
+/// black bars on the sides or at the top and bottom, as on a fullscreen
+/// monitor of another shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Letterbox {
+    /// the top left corner of the display, in window coordinates
+    offset: [f64; 2],
+    /// the size of the display, in window coordinates
+    size: [f64; 2],
+    /// the size of the whole window, in window coordinates
+    window_size: [f64; 2],
+}
+
+impl Letterbox {
+    fn new<W>(window: &W) -> Self
+    where
+        W: Window,
+    {
+        let size = window.size();
+        let (width, height) = (f64::from(u32::max(1, size.width)), f64::from(u32::max(1, size.height)));
+        let scale = f64::min(width / f64::from(WIDTH), height / f64::from(HEIGHT));
+        let display = [f64::from(WIDTH) * scale, f64::from(HEIGHT) * scale];
+        Letterbox {
+            offset: [(width - display[0]) / 2., (height - display[1]) / 2.],
+            size: display,
+            window_size: [width, height],
+        }
+    }
+
+    /// The fraction of the window's width and height taken by the display.
+    fn fill(&self) -> [f32; 2] {
+        [
+            (self.size[0] / self.window_size[0]) as f32,
+            (self.size[1] / self.window_size[1]) as f32,
+        ]
+    }
+
+    /// Move the mouse cursor of an event to the display's own coordinates.
+    fn map_event(&self, e: Event) -> Event {
+        match e.mouse_cursor_args() {
+            Some(pos) => Event::Input(Input::Move(Motion::MouseCursor(
+                pos[0] - self.offset[0],
+                pos[1] - self.offset[1],
+            ))),
+            None => e,
+        }
+    }
 }
 
 fn main() {
     let args = App::new("Propan")
-        .subcommand(
+        .arg(
+            Arg::with_name("sequential-unlock")
+                .long("sequential-unlock")
+                .help("Only allow playing a level after finishing the ones before it"),
+        ).arg(
+            Arg::with_name("practice")
+                .long("practice")
+                .help("Play without keeping records, saving and restoring the state of a run with F1 to F4"),
+        ).arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .help("Save the time taken by each frame to profile.csv on exit"),
+        ).arg(
+            Arg::with_name("fps")
+                .long("fps")
+                .takes_value(true)
+                .validator(validate_rate)
+                .help("The most frames drawn per second (overrides the settings)"),
+        ).arg(
+            Arg::with_name("ups")
+                .long("ups")
+                .takes_value(true)
+                .validator(validate_rate)
+                .help("The number of updates per second (overrides the settings)"),
+        ).arg(
+            Arg::with_name("no-vsync")
+                .long("no-vsync")
+                .help("Do not wait for the display's refresh (overrides the settings)"),
+        ).subcommand(
             SubCommand::with_name("editor")
                 .help("Run the level editor")
                 .arg(
@@ -142,11 +320,149 @@ fn main() {
                         .help("The level file to load")
                         .required(false),
                 ),
+        ).subcommand(
+            SubCommand::with_name("play")
+                .about("Play a level")
+                .arg(
+                    Arg::with_name("LEVEL")
+                        .index(1)
+                        .help("The number of the level to play")
+                        .required(true),
+                ).arg(
+                    Arg::with_name("replay")
+                        .long("replay")
+                        .takes_value(true)
+                        .help("A replay file to play back"),
+                ),
+        ).subcommand(
+            SubCommand::with_name("level")
+                .about("Inspect and convert level files")
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Validate a level file")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .index(1)
+                                .help("The level file to check")
+                                .required(true),
+                        ),
+                ).subcommand(
+                    SubCommand::with_name("import")
+                        .about("Convert a map made with Tiled (.tmj) into a level")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .index(1)
+                                .help("The Tiled map to import")
+                                .required(true),
+                        ).arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .help("The output file (defaults to the input with a .json extension)"),
+                        ),
+                ).subcommand(
+                    SubCommand::with_name("generate")
+                        .about("Generate a random level")
+                        .arg(
+                            Arg::with_name("width")
+                                .long("width")
+                                .takes_value(true)
+                                .default_value("1200")
+                                .help("The width of the map"),
+                        ).arg(
+                            Arg::with_name("height")
+                                .long("height")
+                                .takes_value(true)
+                                .default_value("800")
+                                .help("The height of the map"),
+                        ).arg(
+                            Arg::with_name("difficulty")
+                                .long("difficulty")
+                                .takes_value(true)
+                                .default_value("3")
+                                .help("The difficulty, from 1 to 5"),
+                        ).arg(
+                            Arg::with_name("seed")
+                                .long("seed")
+                                .takes_value(true)
+                                .help("The seed of the random generator (defaults to a random one)"),
+                        ).arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .default_value("levels/generated.json")
+                                .help("The output file"),
+                        ),
+                ).subcommand(
+                    SubCommand::with_name("snapshot")
+                        .about("Draw a picture of a level")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .index(1)
+                                .help("The level file to draw")
+                                .required(true),
+                        ).arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .help("The image file (defaults to the input with a .png extension)"),
+                        ).arg(
+                            Arg::with_name("scale")
+                                .long("scale")
+                                .takes_value(true)
+                                .help("The number of pixels for each map unit (defaults to 1)"),
+                        ),
+                ).subcommand(
+                    SubCommand::with_name("upgrade")
+                        .about("Convert a level file to the current format version")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .index(1)
+                                .help("The level file to upgrade")
+                                .required(true),
+                        ).arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .help("The output file (defaults to overwriting the input)"),
+                        ),
+                ),
         ).get_matches();
+
+    let sequential_unlock = args.is_present("sequential-unlock");
+    let practice = args.is_present("practice");
+    let profile = args.is_present("profile");
+    let fps_arg = args.value_of("fps").and_then(|fps| fps.parse().ok());
+    let ups_arg = args.value_of("ups").and_then(|ups| ups.parse().ok());
+
+    let mut settings = Settings::load_or_default();
+    if let Err(e) = lang::set_language(&settings.language) {
+        eprintln!("Warning: could not load language {}: {}", settings.language, e);
+    }
+
+    // level tools run without a window
+    if let Some(args) = args.subcommand_matches("level") {
+        std::process::exit(tool::run_level_tool(args));
+    }
+
     let boot = if let Some(args) = args.subcommand_matches("editor") {
-        ControllerAction::OpenEditor(args.value_of("FILE").map(String::from))
+        GameState::Editor(args.value_of("FILE").map(String::from))
+    } else if let Some(args) = args.subcommand_matches("play") {
+        let level = args.value_of("LEVEL").unwrap_or_default();
+        match (level.parse(), args.value_of("replay")) {
+            (Ok(id), Some(replay)) => GameState::Replay(id, replay.to_string()),
+            (Ok(id), None) => GameState::Game(id),
+            (Err(_), _) => GameState::Error {
+                message: lang::tr_fmt("error.level_number", &[&level]),
+                fatal: true,
+            },
+        }
     } else {
-        ControllerAction::LoadTitleScreen
+        GameState::Title
     };
 
     // configure window
@@ -155,15 +471,21 @@ fn main() {
     let phys_width: u32 = DEFAULT_PHYSICAL_WIDTH as u32;
     let phys_height: u32 = DEFAULT_PHYSICAL_HEIGHT as u32;
     let samples = 0;
-    let mut window: WindowBackend = WindowSettings::new("propan", [phys_width, phys_height])
-        .srgb(false)
-        .vsync(true)
+    let mut window: WindowBackend = WindowSettings::new(WINDOW_TITLE, [phys_width, phys_height])
+        .srgb(ActiveRenderer::SRGB)
+        .vsync(settings.vsync && !args.is_present("no-vsync"))
         .resizable(false)
         .opengl(opengl)
         .samples(samples)
         .exit_on_esc(false)
         .build()
         .expect("Failed to create game window");
+    set_window_icon(&mut window);
+    let monitors = monitor_names(&window);
+    if settings.fullscreen {
+        let monitor = chosen_monitor(&settings, &monitors);
+        enter_fullscreen(&mut window, monitor);
+    }
 
     #[cfg(feature = "sdl2_window")]
     {
@@ -175,315 +497,594 @@ fn main() {
         }
     }
 
-    let (mut device, mut factory) = create_gfx_device(&mut window);
-
-    // configure graphics
-    let mut g2d = Gfx2d::new(opengl, &mut factory);
-
-    // Create the main color/depth targets.
-    let draw_size = window.draw_size();
-    let aa = samples as gfx::texture::NumSamples;
-    let dim = (
-        draw_size.width as u16,
-        draw_size.height as u16,
-        1,
-        aa.into(),
-    );
-    let (output_color, output_stencil) = create_main_targets(dim);
-
-    let (_lowres_texture, lowres_resource_view, lowres_color) =
-        factory.create_render_target(WIDTH, HEIGHT).unwrap();
-    let lowres_stencil = factory
-        .create_depth_stencil_view_only(WIDTH, HEIGHT)
-        .unwrap();
-    let mut encoder = factory.create_command_buffer().into();
-    let pso = factory
-        .create_pipeline_simple(
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/main.glslv")),
-            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/main.glslf")),
-            pipe::new(),
-        ).unwrap();
-    let (video_rect, indices) = get_video_rect();
-    let (vertex_buffer, slice) = factory.create_vertex_buffer_with_slice(&video_rect, indices);
-    let sampler = factory.create_sampler(SamplerInfo {
-        filter: FilterMethod::Scale, // yay, pixelated!
-        wrap_mode: (WrapMode::Border, WrapMode::Border, WrapMode::Border),
-        lod_bias: (0.0).into(),
-        lod_range: ((0.0).into(), (0.0).into()),
-        comparison: None,
-        border: [0.0, 0.0, 0.0, 1.0].into(), // black border
-    });
-    let data = pipe::Data {
-        vbuf: vertex_buffer,
-        orig: (lowres_resource_view, sampler),
-        out: output_color.clone(),
-    };
-
-    // create initial viewports: logical viewport never changes,
-    // but the physical viewport may change on a window resize
-    let (logical_viewport, physical_viewport) = create_viewports(&window);
+    // the physical viewport changes along with the letterbox, but the
+    // logical viewport never changes
+    let mut letterbox = Letterbox::new(&window);
+    let logical_viewport = create_logical_viewport();
+    let (mut window, mut renderer) = ActiveRenderer::new(window, opengl, samples, letterbox.fill());
 
-    // character cache
-    let mut cache = GlyphCache::new(
-        Path::new("assets/fonts/Monospace.ttf"),
-        factory.clone(),
-        TextureSettings::new().filter(Filter::Nearest),
-    ).unwrap();
+    let mut recorder = Recorder::default();
 
     // game resources stuff
-    let resource_manager = ResourceManager::new(
-        SpriteManager::new(factory).unwrap(),
+    let resource_manager: GameResources = ResourceManager::new(
+        SpriteManager::new(renderer.factory()).unwrap(),
         AudioManager::new(()).unwrap(),
     );
 
     // event loop
-    let mut events = Events::new(EventSettings::new().swap_buffers(true).max_fps(60).ups(120));
-    let mut state = match boot {
-        ControllerAction::OpenEditor(p) => GameState::Editor(p),
-        _ => GameState::Title,
+    let rates = FrameRates::new(&settings, fps_arg, ups_arg);
+    let mut events = Events::new(
+        EventSettings::new()
+            // the renderer shows each frame once it is drawn
+            .swap_buffers(false)
+            .max_fps(rates.max_fps)
+            .ups(rates.ups),
+    );
+    let mut profiler = if profile {
+        FrameProfiler::with_log()
+    } else {
+        FrameProfiler::default()
     };
-    // The root loop dispatches a particular controller and runs the game loop in each one.
+    // The root loop keeps a stack of live controllers, and runs the game
+    // loop in the topmost one until it asks for another.
+    let mut stack: Vec<AnyController<_>> = Vec::new();
+    let mut action = ControllerAction::Push(boot);
     loop {
-        match state {
-            GameState::Title => {
-                // initialize title logic stuff
-                let mut title = TitleController::new(&resource_manager).unwrap();
-                // title loop
-                state = run_controller(
-                    &mut title,
-                    &resource_manager,
-                    &mut events,
-                    &mut window,
-                    &mut device,
-                    &mut encoder,
-                    &slice,
-                    &pso,
-                    &data,
-                    &lowres_color,
-                    &lowres_stencil,
-                    &output_color,
-                    &output_stencil,
-                    logical_viewport,
-                    physical_viewport,
-                    &mut cache,
-                    &mut g2d,
-                );
-                title.exit();
+        // fade in when a controller replaces all others, but not when going
+        // back and forth over another
+        let mut fade_in = false;
+        let state = match action {
+            ControllerAction::Push(state) => {
+                // the controller beneath will not see the release of any
+                // held keys
+                if let Some(top) = stack.last_mut() {
+                    top.focus_lost();
+                }
+                Some(state)
             }
-            GameState::Game(id) => {
-                // game logic stuff
-                let level = GameLevel::load_by_index("levels/", id).unwrap();
-                let mut game = GameController::new(level, &resource_manager).unwrap();
-
-                state = run_controller(
-                    &mut game,
-                    &resource_manager,
-                    &mut events,
-                    &mut window,
-                    &mut device,
-                    &mut encoder,
-                    &slice,
-                    &pso,
-                    &data,
-                    &lowres_color,
-                    &lowres_stencil,
-                    &output_color,
-                    &output_stencil,
-                    logical_viewport,
-                    physical_viewport,
-                    &mut cache,
-                    &mut g2d,
-                );
-                game.exit();
+            ControllerAction::Pop => {
+                if let Some(mut top) = stack.pop() {
+                    top.exit();
+                }
+                // with nothing to go back to, go back to the title screen
+                if stack.is_empty() {
+                    Some(GameState::Title)
+                } else {
+                    None
+                }
+            }
+            ControllerAction::Exit => {
+                close_all(&mut stack);
+                Some(GameState::Exit)
+            }
+            ControllerAction::LoadTitleScreen => {
+                close_all(&mut stack);
+                Some(GameState::Title)
+            }
+            ControllerAction::LoadGame(id) => {
+                close_all(&mut stack);
+                Some(GameState::Game(id))
             }
-            GameState::Editor(path) => {
-                // level editor stuff
-                let mut editor = if let Some(path) = path {
-                    LevelEditorController::load(path, &resource_manager).unwrap()
+            ControllerAction::OpenEditor(path) => {
+                close_all(&mut stack);
+                Some(GameState::Editor(path))
+            }
+            ControllerAction::Loaded => {
+                // the game takes the place of its loading screen
+                if let Some(AnyController::Loading(loading)) = stack.pop() {
+                    stack.extend(loading.into_game().map(AnyController::Game));
+                }
+                fade_in = stack.len() == 1;
+                if stack.is_empty() {
+                    Some(GameState::Title)
                 } else {
-                    LevelEditorController::new(&resource_manager).unwrap()
-                };
-                state = run_controller(
-                    &mut editor,
-                    &resource_manager,
-                    &mut events,
-                    &mut window,
-                    &mut device,
-                    &mut encoder,
-                    &slice,
-                    &pso,
-                    &data,
-                    &lowres_color,
-                    &lowres_stencil,
-                    &output_color,
-                    &output_stencil,
-                    logical_viewport,
-                    physical_viewport,
-                    &mut cache,
-                    &mut g2d,
-                );
-                editor.exit();
+                    None
+                }
             }
-            GameState::Exit => {
-                return;
+        };
+        if let Some(state) = state {
+            // the loading screen shows up at once, and the game fades in
+            // after it instead
+            fade_in = stack.is_empty() && match state {
+                GameState::Game(_) | GameState::Replay(..) => false,
+                _ => true,
+            };
+            // the closed controllers may have changed the settings
+            settings = Settings::load_or_default();
+            let controller =
+                open_controller(state, &resource_manager, &settings, &monitors, sequential_unlock, practice);
+            match controller {
+                Some(controller) => stack.push(controller),
+                None => {
+                    if profile {
+                        save_profile(&profiler);
+                    }
+                    recorder.finish();
+                    return;
+                }
             }
         }
+        let fade_in = settings.transitions && fade_in;
+        let rates = FrameRates::new(&settings, fps_arg, ups_arg);
+        action = run_controller(
+            &mut stack,
+            &mut events,
+            &mut window,
+            &mut renderer,
+            &mut letterbox,
+            logical_viewport,
+            &mut profiler,
+            &mut recorder,
+            rates,
+            !settings.background_simulation,
+            settings.transitions,
+            fade_in,
+        );
     }
 }
 
+/// A live controller of any kind, so that controllers of different kinds
+/// can be kept in the same stack.
+enum AnyController<R>
+where
+    R: ResourceManage + Copy,
+{
+    Title(TitleController<R>),
+    Game(GameController<R>),
+    Editor(LevelEditorController<R>),
+    Loading(LoadingController<R>),
+    Pause(PauseController<R>),
+    Error(ErrorController<R>),
+}
+
+/// Evaluate the expression with `$c` bound to the controller held by the
+/// given `AnyController`.
+macro_rules! dispatch {
+    ($controller:expr, $c:ident => $call:expr) => {
+        match $controller {
+            AnyController::Title($c) => $call,
+            AnyController::Game($c) => $call,
+            AnyController::Editor($c) => $call,
+            AnyController::Loading($c) => $call,
+            AnyController::Pause($c) => $call,
+            AnyController::Error($c) => $call,
+        }
+    };
+}
+
+impl<R> AnyController<R>
+where
+    R: ResourceManage + Copy,
+{
+    fn name(&self) -> &'static str {
+        fn name<C: Controller>(_: &C) -> &'static str {
+            C::NAME
+        }
+        dispatch!(self, c => name(c))
+    }
+
+    fn needs_hi_res(&self) -> bool {
+        fn needs_hi_res<C: Controller>(_: &C) -> bool {
+            C::NEEDS_HI_RES
+        }
+        dispatch!(self, c => needs_hi_res(c))
+    }
+
+    fn is_translucent(&self) -> bool {
+        fn is_translucent<C: Controller>(_: &C) -> bool {
+            C::TRANSLUCENT
+        }
+        dispatch!(self, c => is_translucent(c))
+    }
+
+    fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
+        dispatch!(self, c => c.event(e))
+    }
+
+    fn update(&mut self, args: UpdateArgs) -> Option<ControllerAction> {
+        dispatch!(self, c => c.update(args))
+    }
+
+    fn render<C, G>(&self, ctx: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        dispatch!(self, c => c.render(ctx, cache, g))
+    }
+
+    fn render_hires<C, G>(&self, ctx: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        dispatch!(self, c => c.render_hires(ctx, cache, g))
+    }
+
+    fn title(&self) -> Option<String> {
+        dispatch!(self, c => c.title())
+    }
+
+    fn focus_lost(&mut self) {
+        dispatch!(self, c => c.focus_lost())
+    }
+
+    fn show_frame_stats(&mut self, stats: &FrameStats) {
+        dispatch!(self, c => c.show_frame_stats(stats))
+    }
+
+    fn exit(&mut self) {
+        dispatch!(self, c => c.exit())
+    }
+}
+
+/// The window title for the stack of controllers, after the topmost one
+/// which has a title of its own, such as the game under the pause menu.
+fn window_title<R>(stack: &[AnyController<R>]) -> String
+where
+    R: ResourceManage + Copy,
+{
+    match stack.iter().rev().filter_map(|c| c.title()).next() {
+        Some(title) => format!("{} — {}", WINDOW_TITLE, title),
+        None => WINDOW_TITLE.to_string(),
+    }
+}
+
+/// How often the game loop draws and updates while the window is in focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameRates {
+    max_fps: u64,
+    ups: u64,
+}
+
+impl FrameRates {
+    /// Take the rates from the settings, unless given on the command line.
+    fn new(settings: &Settings, fps_arg: Option<u64>, ups_arg: Option<u64>) -> Self {
+        // a zero rate in the settings file would stop the loop altogether
+        FrameRates {
+            max_fps: fps_arg.unwrap_or(settings.max_fps).max(1),
+            ups: ups_arg.unwrap_or(settings.ups).max(1),
+        }
+    }
+}
+
+fn validate_rate(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(rate) if rate > 0 => Ok(()),
+        _ => Err(format!("{} is not a positive whole number", value)),
+    }
+}
+
+/// Exit and drop all of the live controllers, from the top down.
+fn close_all<R>(stack: &mut Vec<AnyController<R>>)
+where
+    R: ResourceManage + Copy,
+{
+    while let Some(mut controller) = stack.pop() {
+        controller.exit();
+    }
+}
+
+/// Create the controller for the given state, or the error screen if it
+/// fails to load. There is no controller for leaving the game.
+fn open_controller<'a>(
+    state: GameState,
+    resource_manager: &'a GameResources,
+    settings: &Settings,
+    monitors: &[String],
+    sequential_unlock: bool,
+    practice: bool,
+) -> Option<AnyController<&'a GameResources>> {
+    let controller = match state {
+        GameState::Title => TitleController::new(resource_manager, sequential_unlock)
+            .map(|title| AnyController::Title(title.with_monitors(monitors.to_vec())))
+            .map_err(|e| GameState::Error {
+                message: lang::tr_fmt("error.title_screen", &[&e]),
+                fatal: true,
+            }),
+        GameState::Game(id) => {
+            let options = GameOptions {
+                hard_mode: settings.hard_mode,
+                practice,
+            };
+            Ok(AnyController::Loading(LoadingController::game(id, resource_manager, options)))
+        }
+        GameState::Replay(id, replay_path) => {
+            // play back a recorded run, which applies its own modifiers
+            Ok(AnyController::Loading(LoadingController::replay(id, replay_path, resource_manager)))
+        }
+        GameState::Editor(path) => {
+            let editor = if let Some(path) = path {
+                LevelEditorController::load(path, resource_manager)
+            } else {
+                LevelEditorController::new(resource_manager).map_err(From::from)
+            };
+            editor.map(AnyController::Editor).map_err(|e| GameState::Error {
+                message: lang::tr_fmt("error.editor", &[&e]),
+                fatal: false,
+            })
+        }
+        GameState::Pause => Ok(AnyController::Pause(PauseController::new(resource_manager))),
+        GameState::Error { message, fatal } => {
+            // show what went wrong, then either go back to the title
+            // screen or leave the game
+            let next = if fatal {
+                ControllerAction::Exit
+            } else {
+                ControllerAction::LoadTitleScreen
+            };
+            Ok(AnyController::Error(ErrorController::new(message, next, resource_manager)))
+        }
+        GameState::Exit => return None,
+    };
+    match controller {
+        Ok(controller) => Some(controller),
+        Err(error) => {
+            open_controller(error, resource_manager, settings, monitors, sequential_unlock, practice)
+        }
+    }
+}
+
+/// Whether the action replaces all of the live controllers, so that the
+/// screen fades out before it is taken.
+fn fades_out(action: &ControllerAction) -> bool {
+    match *action {
+        ControllerAction::LoadTitleScreen
+        | ControllerAction::LoadGame(_)
+        | ControllerAction::OpenEditor(_) => true,
+        _ => false,
+    }
+}
+
+/// Run the game loop in the topmost controller of the stack until it asks
+/// for another, drawing the controllers beneath it which show through.
+/// With transitions on, the screen fades to black before an action which
+/// replaces the controllers is taken, and stays black until the next
+/// controller fades in.
 #[inline]
-fn run_controller<C, M, W, D, R, PD, CB, CC, PM>(
-    game: &mut C,
-    _resource_manager: M,
+fn run_controller<M, R>(
+    stack: &mut [AnyController<M>],
     events: &mut Events,
-    window: &mut W,
-    device: &mut D,
-    encoder: &mut gfx::Encoder<R, CB>,
-    slice: &Slice<R>,
-    lowres_pso: &PipelineState<R, PM>,
-    lowres_data: &PD,
-    lowres_color: &RenderTargetView<R, Srgba8>,
-    lowres_stencil: &DepthStencilView<R, DepthStencil>,
-    output_color: &RenderTargetView<R, Srgba8>,
-    output_stencil: &DepthStencilView<R, DepthStencil>,
+    window: &mut R::Window,
+    renderer: &mut R,
+    letterbox: &mut Letterbox,
     logical_viewport: Viewport,
-    mut physical_viewport: Viewport,
-    cache: &mut CC,
-    g2d: &mut Gfx2d<R>,
-) -> GameState
+    profiler: &mut FrameProfiler,
+    recorder: &mut Recorder,
+    rates: FrameRates,
+    pause_unfocused: bool,
+    transitions: bool,
+    fade_in: bool,
+) -> ControllerAction
 where
-    C: Controller<Res = M>,
-    D: Device<CommandBuffer = CB, Resources = R>,
-    W: Window,
-    M: ResourceManage,
-    <M as ResourceManage>::Sprite: SpriteManage<Texture = gfx_graphics::Texture<R>>,
-    R: Resources,
-    PD: PipelineData<R, Meta = PM>,
-    CB: CommandBuffer<R>,
-    CC: CharacterCache<Texture = gfx_graphics::Texture<R>>,
+    R: Renderer,
+    M: ResourceManage + Copy,
+    <M as ResourceManage>::Sprite: SpriteManage<Texture = R::Texture>,
 {
-    let mut pixel_scale_w = physical_viewport.window_size[0] as f64 / f64::from(WIDTH);
-    let mut pixel_scale_h = physical_viewport.window_size[1] as f64 / f64::from(HEIGHT);
+    let top = stack.len() - 1;
+    // the lowest controller to draw is the topmost one which hides the rest
+    let bottom = stack.iter().rposition(|c| !c.is_translucent()).unwrap_or(0);
+    let mut minimized = is_minimized(&*window);
+    profiler.start(stack[top].name());
+    // the fade in starts with the first frame, after any loading hitch
+    let mut fade_in_start: Option<Instant> = None;
+    // the action to take once faded out, and when the fade started
+    let mut leaving: Option<(ControllerAction, Instant)> = None;
+    // whether a black frame was drawn, so that the screen stays black
+    let mut faded_out = false;
+    // each controller starts out in focus, at full speed, which may have
+    // been changed in the settings since the last one
+    let mut focused = true;
+    events.set_ups(rates.ups);
+    events.set_max_fps(rates.max_fps);
+    // the title follows the controllers, and is checked again on updates
+    let mut title = window_title(stack);
+    window.set_title(title.clone());
 
     // game loop
     while let Some(e) = events.next(window) {
         // handle window closure
         if e.close_args().is_some() {
-            return GameState::Exit;
+            return ControllerAction::Exit;
         }
 
-        // handle window resize
+        // the black frame has been shown by now
+        if faded_out {
+            if let Some((action, _)) = leaving {
+                return action;
+            }
+        }
+
+        // handle window resize, including minimizing and restoring the
+        // window and going in and out of fullscreen. The renderer is left
+        // alone while minimized, as the framebuffer has no size.
         if e.resize_args().is_some() {
-            // reset physical viewport
-            physical_viewport = create_physical_viewport(&*window);
-            pixel_scale_w = physical_viewport.window_size[0] as f64 / f64::from(WIDTH);
-            pixel_scale_h = physical_viewport.window_size[1] as f64 / f64::from(HEIGHT);
+            minimized = is_minimized(&*window);
+            if !minimized {
+                *letterbox = Letterbox::new(&*window);
+                renderer.resize(window.draw_size(), letterbox.fill());
+            }
         }
 
-        let a = game.event(&e);
-        match a {
-            Some(ControllerAction::Exit) => {
-                return GameState::Exit;
+        // the controllers see the cursor over the display, past any black
+        // bars around it
+        let e = letterbox.map_event(e);
+
+        // pause while out of focus, slowing the loop down to save power
+        if let Some(focus) = e.focus_args() {
+            focused = focus;
+            if !focus {
+                stack[top].focus_lost();
             }
-            Some(ControllerAction::LoadTitleScreen) => {
-                return GameState::Title;
+            if pause_unfocused {
+                let (ups, max_fps) = if focus {
+                    (rates.ups, rates.max_fps)
+                } else {
+                    (UNFOCUSED_UPS, UNFOCUSED_MAX_FPS)
+                };
+                events.set_ups(ups);
+                events.set_max_fps(max_fps);
             }
-            Some(ControllerAction::LoadGame(id)) => {
-                return GameState::Game(id);
+        }
+        let paused = pause_unfocused && !focused;
+
+        // the controller is left alone while fading out
+        let action = if leaving.is_some() {
+            None
+        } else if e.press_args() == Some(Button::Keyboard(Key::F9)) {
+            recorder.toggle();
+            None
+        } else if let Some(a) = stack[top].event(&e) {
+            Some(a)
+        } else if let Some(u) = e.update_args().filter(|_| !paused) {
+            let start = Instant::now();
+            let a = stack[top].update(u);
+            profiler.record_update(start.elapsed());
+            a
+        } else {
+            None
+        };
+        if e.update_args().is_some() {
+            let new_title = window_title(stack);
+            if new_title != title {
+                window.set_title(new_title.clone());
+                title = new_title;
             }
-            Some(ControllerAction::OpenEditor(p)) => {
-                return GameState::Editor(p);
+        }
+        if let Some(a) = action {
+            if transitions && fades_out(&a) {
+                leaving = Some((a, Instant::now()));
+            } else {
+                return a;
             }
-            _ => {}
         }
-
-        if let Some(u) = e.update_args() {
-            let a = game.update(u);
-            match a {
-                Some(ControllerAction::Exit) => {
-                    return GameState::Exit;
+        // there is nothing to draw on while minimized
+        if let Some(_r) = e.render_args().filter(|_| !minimized) {
+            let visible = &stack[bottom..];
+            // how much of the screen is covered in black
+            let fade = match leaving {
+                Some((_, start)) => (duration_ms(start.elapsed()) / FADE_DURATION_MS).min(1.),
+                None if fade_in => {
+                    let start = *fade_in_start.get_or_insert_with(Instant::now);
+                    (1. - duration_ms(start.elapsed()) / FADE_DURATION_MS).max(0.)
                 }
-                Some(ControllerAction::LoadTitleScreen) => {
-                    return GameState::Title;
-                }
-                Some(ControllerAction::LoadGame(id)) => {
-                    return GameState::Game(id);
-                }
-                Some(ControllerAction::OpenEditor(p)) => {
-                    return GameState::Editor(p);
+                None => 0.,
+            };
+            let start = Instant::now();
+            renderer.draw_lowres(logical_viewport, &mut LowresPass { visible });
+            renderer.draw_upscaled();
+            let capture = recorder.wants_frame();
+            if capture {
+                renderer.copy_lowres();
+            }
+            profiler.record_render(start.elapsed());
+            let start = Instant::now();
+            renderer.flush();
+            profiler.record_flush(start.elapsed());
+            if capture {
+                match renderer.read_lowres() {
+                    Ok(pixels) => recorder.push_frame(&pixels),
+                    Err(e) => {
+                        eprintln!("Warning: could not capture the frame: {}", e);
+                        recorder.stop();
+                    }
                 }
-                _ => {}
             }
-        }
-        if let Some(_r) = e.render_args() {
-            g2d.draw(
-                encoder,
-                lowres_color,
-                lowres_stencil,
-                logical_viewport,
-                |c, g| game.render(c, cache, g),
-            );
-            encoder.draw(&slice, lowres_pso, lowres_data);
-            encoder.flush(device);
-            if C::NEEDS_HI_RES {
-                g2d.draw(
-                    encoder,
-                    output_color,
-                    output_stencil,
-                    physical_viewport,
-                    |c, g| game.render_hires(c, cache, g),
+            let start = Instant::now();
+            if fade > 0. || recorder.is_recording() || visible.iter().any(|c| c.needs_hi_res()) {
+                let viewport = create_physical_viewport(&*window, letterbox);
+                renderer.draw_hires(
+                    viewport,
+                    &mut HiresPass {
+                        visible,
+                        letterbox: *letterbox,
+                        fade,
+                        recorder: &*recorder,
+                    },
                 );
             }
-            encoder.flush(device);
-        }
-
-        if let Some(_) = e.after_render_args() {
-            device.cleanup();
+            profiler.record_render(start.elapsed());
+            let start = Instant::now();
+            renderer.flush();
+            profiler.record_flush(start.elapsed());
+            profiler.end_frame();
+            stack[top].show_frame_stats(&profiler.stats());
+            faded_out = leaving.is_some() && fade >= 1.;
+            renderer.end_frame(window);
         }
     }
     unreachable!()
 }
 
-gfx_defines! {
-    vertex Vertex {
-        pos: [f32; 2] = "a_Pos",
-        uv: [f32; 2] = "a_Uv",
-        color: [f32; 3] = "a_Color",
+/// The controllers which show through, drawn onto the low-res target.
+struct LowresPass<'a, M>
+where
+    M: ResourceManage + Copy + 'a,
+{
+    visible: &'a [AnyController<M>],
+}
+
+impl<'a, M> DrawPass<GameTexture<M>> for LowresPass<'a, M>
+where
+    M: ResourceManage + Copy,
+{
+    fn draw<C, G>(&mut self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<M>>,
+        G: Graphics<Texture = GameTexture<M>>,
+    {
+        for controller in self.visible {
+            controller.render(c, cache, g);
+        }
     }
+}
 
-    pipeline pipe {
-        vbuf: gfx::VertexBuffer<Vertex> = (),
-        orig: gfx::TextureSampler<[f32; 4]> = "t_Video",
-        out: gfx::RenderTarget<ColorFormat> = "Target0",
+/// The hires text of the controllers which show through, the fade and the
+/// recording indicator, drawn over the whole window.
+struct HiresPass<'a, M>
+where
+    M: ResourceManage + Copy + 'a,
+{
+    visible: &'a [AnyController<M>],
+    letterbox: Letterbox,
+    /// how much of the screen is covered in black
+    fade: f64,
+    recorder: &'a Recorder,
+}
+
+impl<'a, M> DrawPass<GameTexture<M>> for HiresPass<'a, M>
+where
+    M: ResourceManage + Copy,
+{
+    fn draw<C, G>(&mut self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<M>>,
+        G: Graphics<Texture = GameTexture<M>>,
+    {
+        let letterbox = self.letterbox;
+        let display = c.trans(letterbox.offset[0], letterbox.offset[1]);
+        for controller in self.visible {
+            if controller.needs_hi_res() {
+                controller.render_hires(display, cache, g);
+            }
+        }
+        // the fade covers the hires text as well
+        if self.fade > 0. {
+            let screen = [0., 0., letterbox.window_size[0], letterbox.window_size[1]];
+            rectangle([0., 0., 0., self.fade as f32], screen, c.transform, g);
+        }
+        self.recorder.draw_indicator(display, cache, g);
     }
 }
 
-fn get_video_rect() -> ([Vertex; 4], &'static [u16]) {
-    const INDICES: &[u16] = &[0, 1, 2, 2, 3, 0];
-    // it's just a square lulz
-    (
-        [
-            Vertex {
-                pos: [1., -1.],
-                uv: [1., 0.0],
-                color: [1.0; 3],
-            },
-            Vertex {
-                pos: [-1., -1.],
-                uv: [0.0, 0.0],
-                color: [1.0; 3],
-            },
-            Vertex {
-                pos: [-1., 1.],
-                uv: [0.0, 1.],
-                color: [1.0; 3],
-            },
-            Vertex {
-                pos: [1., 1.],
-                uv: [1., 1.],
-                color: [1.0; 3],
-            },
-        ],
-        INDICES,
-    )
+/// Save the frame times to the profile file, and print how long the
+/// frames of each controller took.
+fn save_profile(profiler: &FrameProfiler) {
+    match profiler.save_csv(PROFILE_FILE) {
+        Ok(()) => println!("Frame times saved to {}", PROFILE_FILE),
+        Err(e) => eprintln!("Failed to save the frame times: {}", e),
+    }
+    for (controller, stats) in profiler.summary() {
+        println!("{}: {}", controller, stats.lines().join(", "));
+    }
 }
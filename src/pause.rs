@@ -0,0 +1,153 @@
+//! Module for the pause menu, which is shown over the game.
+
+use std::marker::PhantomData;
+use graphics::character::CharacterCache;
+use graphics::{rectangle, Context, DrawState, Graphics, Text, Transformed};
+use piston::input::{GenericEvent, UpdateArgs};
+use controller::{Controller, ControllerAction};
+use lang::tr;
+use resource::{GameTexture, ResourceManage};
+
+/// The position of the menu's title, in hires pixels.
+const MENU_POS: [f64; 2] = [48., 140.];
+/// The vertical distance between the entries of the menu.
+const ENTRY_HEIGHT: f64 = 32.;
+const DIM_COLOR: [f32; 4] = [0., 0., 0., 0.6];
+const SELECTED_COLOR: [f32; 4] = [1.0, 1.0, 0.25, 1.0];
+const ENTRY_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+
+/// An entry of the pause menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    Resume,
+    Quit,
+}
+
+const ENTRIES: [Entry; 2] = [Entry::Resume, Entry::Quit];
+
+impl Entry {
+    fn label(self) -> String {
+        match self {
+            Entry::Resume => tr("pause.resume"),
+            Entry::Quit => tr("pause.quit"),
+        }
+    }
+
+    fn action(self) -> ControllerAction {
+        match self {
+            Entry::Resume => ControllerAction::Pop,
+            Entry::Quit => ControllerAction::LoadTitleScreen,
+        }
+    }
+}
+
+pub struct PauseController<R>
+where
+    R: ResourceManage,
+{
+    /// the index of the selected entry
+    selected: usize,
+    phantom: PhantomData<R>,
+}
+
+impl<R> PauseController<R>
+where
+    R: ResourceManage,
+{
+    pub fn new(_res: R) -> Self {
+        PauseController {
+            selected: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let len = ENTRIES.len() as isize;
+        self.selected = ((self.selected as isize + offset + len) % len) as usize;
+    }
+}
+
+impl<R> Controller for PauseController<R>
+where
+    R: ResourceManage,
+{
+    type Res = R;
+    const NEEDS_HI_RES: bool = true;
+    const NAME: &'static str = "pause";
+    const TRANSLUCENT: bool = true;
+
+    fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
+        use piston::input::Button::{Controller, Keyboard};
+        use piston::input::{ButtonState, ControllerButton, Key};
+        if let Some(b) = e.button_args() {
+            match (b.button, b.state) {
+                (Keyboard(Key::Escape), ButtonState::Press)
+                | (Controller(ControllerButton { id: 0, button: 1 }), ButtonState::Press) => {
+                    return Some(ControllerAction::Pop);
+                }
+                (Keyboard(Key::Up), ButtonState::Press) | (Keyboard(Key::NumPad8), ButtonState::Press) => {
+                    self.move_selection(-1);
+                }
+                (Keyboard(Key::Down), ButtonState::Press) | (Keyboard(Key::NumPad2), ButtonState::Press) => {
+                    self.move_selection(1);
+                }
+                (Keyboard(Key::Return), ButtonState::Press)
+                | (Keyboard(Key::Space), ButtonState::Press)
+                | (Controller(ControllerButton { id: 0, button: 0 }), ButtonState::Press) => {
+                    return Some(ENTRIES[self.selected].action());
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn update(&mut self, _u: UpdateArgs) -> Option<ControllerAction> {
+        None
+    }
+
+    fn render<C, G>(&self, _c: Context, _cache: &mut C, _g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        // the game shows through, dimmed in the hires pass
+    }
+
+    fn render_hires<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let window_size = c.viewport.unwrap().window_size;
+        rectangle(
+            DIM_COLOR,
+            [0., 0., f64::from(window_size[0]), f64::from(window_size[1])],
+            c.transform,
+            g,
+        );
+        let c = c.trans(MENU_POS[0], MENU_POS[1]);
+        let _ = Text::new_color([1.; 4], 24).draw(&tr("pause.title"), cache, &DrawState::default(), c.transform, g);
+        for (i, entry) in ENTRIES.iter().enumerate() {
+            let (color, label) = if i == self.selected {
+                (SELECTED_COLOR, format!("> {}", entry.label()))
+            } else {
+                (ENTRY_COLOR, format!("  {}", entry.label()))
+            };
+            let _ = Text::new_color(color, 16).draw(
+                &label,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(0., ENTRY_HEIGHT * (i + 1) as f64 + 16.),
+                g,
+            );
+        }
+        let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 12).draw(
+            &tr("pause.hint"),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(0., ENTRY_HEIGHT * (ENTRIES.len() + 1) as f64 + 24.),
+            g,
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use na::{dot, norm_squared, Vector2};
+use game::events::EventQueue;
 
 /// Trait for things that have a position in game space.
 pub trait Positioned {
@@ -23,10 +24,25 @@ where
     }
 }
 
+/// Trait for things which occupy a rectangular region of game space.
+pub trait Bounded {
+    /// Obtain the object's bounding box, as `[x, y, width, height]`.
+    fn bounding_box(&self) -> [f32; 4];
+}
+
+/// Obtain the bounding box of a square of the given size centered at `pos`.
+pub fn centered_box(pos: Vector2<f32>, size: f32) -> [f32; 4] {
+    [pos[0] - size / 2., pos[1] - size / 2., size, size]
+}
+
 /// A trait for things that move in the level.
 pub trait AnimatedObject {
-    /// Request the object to bounce.
-    fn issue_bounce(&mut self, overlap: Vector2<f32>);
+    /// Obtain the object's velocity.
+    fn velocity(&self) -> Vector2<f32>;
+
+    /// Request the object to bounce off a surface moving at the given
+    /// velocity, which is zero for static props.
+    fn issue_bounce(&mut self, overlap: Vector2<f32>, surface_velocity: Vector2<f32>);
 
     /// Adjust the object's position and bounce on a vertical wall.
     fn correct_and_flip_x(&mut self, overlap: f32);
@@ -92,6 +108,33 @@ pub enum CollisionInfo {
 
 use self::CollisionInfo::{No, Yes};
 
+/// How hard an object hit something.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impact {
+    /// the unit normal of the collision, pointing away from the surface
+    pub normal: Vector2<f32>,
+    /// the speed at which the object approached the surface along the
+    /// normal, which is zero if it was moving away
+    pub speed: f32,
+}
+
+impl Impact {
+    /// Describe the impact of an object moving at `relative_velocity`
+    /// towards a surface, given the overlap vector of the collision. There
+    /// is no impact if the overlap has no direction.
+    pub fn new(overlap: Vector2<f32>, relative_velocity: Vector2<f32>) -> Option<Impact> {
+        let len_sqr = norm_squared(&overlap);
+        if len_sqr == 0. {
+            return None;
+        }
+        let normal = overlap / len_sqr.sqrt();
+        Some(Impact {
+            normal,
+            speed: f32::max(0., -dot(&relative_velocity, &normal)),
+        })
+    }
+}
+
 impl CollisionInfo {
     pub fn is_yes(&self) -> bool {
         match *self {
@@ -101,6 +144,32 @@ impl CollisionInfo {
     }
 }
 
+/// Test for a collision between a circle and an axis-aligned rectangle,
+/// given by its top-left corner and dimensions.
+pub fn rect_circle_collision(
+    pos: Vector2<f32>,
+    dim: Vector2<f32>,
+    position: Vector2<f32>,
+    radius: f32,
+) -> CollisionInfo {
+    let br = pos + dim;
+    let nearest_x = f32::max(pos[0], f32::min(position[0], br[0]));
+    let nearest_y = f32::max(pos[1], f32::min(position[1], br[1]));
+
+    let nearest_point: Vector2<_> = [nearest_x, nearest_y].into();
+    let delta_vector = position - nearest_point;
+
+    let dist_sqr = norm_squared(&delta_vector);
+    if dist_sqr <= radius * radius {
+        // adjust delta vector to have overlap magnitude
+        let dist = f32::sqrt(dist_sqr);
+        let newdistance_inv = (radius - dist) / dist;
+        Yes(delta_vector * newdistance_inv)
+    } else {
+        No
+    }
+}
+
 /// Trait for things that the ball may collide with, but which require no
 /// advanced information about the collision.
 pub trait SimpleCollidable {
@@ -113,7 +182,8 @@ pub trait SimpleCollidable {
     }
 
     /// A function that is called when the ball collides with this object.
-    fn on_collision_simple<A>(&mut self, ball: &mut A) where A: AnimatedObject;
+    /// Anything noteworthy which happens is reported to `events`.
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue) where A: AnimatedObject;
 }
 
 impl<'a, T: ?Sized> SimpleCollidable for &'a mut T
@@ -128,8 +198,8 @@ where
         (**self).test_point_collision_simple(position)
     }
 
-    fn on_collision_simple<A>(&mut self, ball: &mut A) where A: AnimatedObject {
-        (**self).on_collision_simple(ball)
+    fn on_collision_simple<A>(&mut self, ball: &mut A, events: &mut EventQueue) where A: AnimatedObject {
+        (**self).on_collision_simple(ball, events)
     }
 }
 
@@ -255,3 +325,203 @@ impl Collidable for DownBorder {
         ball.correct_and_flip_y(overlap[1]);
     }
 }
+
+/// A circle, for collisions with round objects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub pos: Vector2<f32>,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(pos: Vector2<f32>, radius: f32) -> Self {
+        Circle { pos, radius }
+    }
+
+    /// Obtain the overlap of another circle with this one, along the line
+    /// between their centers and with the length of their penetration
+    /// depth, or `None` if they do not overlap. Concentric circles are
+    /// separated along the x axis.
+    pub fn overlap(&self, other: &Circle) -> Option<Vector2<f32>> {
+        let d = other.pos - self.pos;
+        let min_dist = self.radius + other.radius;
+        let dist_sqr = norm_squared(&d);
+        if dist_sqr >= min_dist * min_dist {
+            return None;
+        }
+        if dist_sqr == 0. {
+            return Some([min_dist, 0.].into());
+        }
+        let dist = dist_sqr.sqrt();
+        Some(d * ((min_dist - dist) / dist))
+    }
+}
+
+impl Collidable for Circle {
+    fn test_circle_collision(&self, position: Vector2<f32>, radius: f32) -> CollisionInfo {
+        match self.overlap(&Circle::new(position, radius)) {
+            Some(overlap) => Yes(overlap),
+            None => No,
+        }
+    }
+
+    #[inline]
+    fn on_collision<A>(&mut self, ball: &mut A, overlap: Vector2<f32>)
+    where
+        A: AnimatedObject,
+    {
+        ball.issue_bounce(overlap, [0., 0.].into());
+    }
+}
+
+/// Make two movable circular objects bounce off each other if they overlap,
+/// returning whether they did. The correction is split between both objects
+/// with a mass proportional to their size, so that the smaller one is
+/// pushed further.
+pub fn resolve_circle_circle<A, B>(a: &mut A, a_circle: Circle, b: &mut B, b_circle: Circle) -> bool
+where
+    A: AnimatedObject,
+    B: AnimatedObject,
+{
+    match a_circle.overlap(&b_circle) {
+        Some(overlap) => {
+            let total = a_circle.radius + b_circle.radius;
+            let (a_share, b_share) = if total > 0. {
+                (b_circle.radius / total, a_circle.radius / total)
+            } else {
+                (0.5, 0.5)
+            };
+            let (a_vel, b_vel) = (a.velocity(), b.velocity());
+            a.issue_bounce(-overlap * a_share, b_vel);
+            b.issue_bounce(overlap * b_share, a_vel);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A moving object which records the bounces issued to it.
+    #[derive(Debug)]
+    struct Body {
+        vel: Vector2<f32>,
+        bounces: Vec<(Vector2<f32>, Vector2<f32>)>,
+    }
+
+    impl Body {
+        fn moving(vel: Vector2<f32>) -> Self {
+            Body {
+                vel,
+                bounces: vec![],
+            }
+        }
+    }
+
+    impl AnimatedObject for Body {
+        fn velocity(&self) -> Vector2<f32> {
+            self.vel
+        }
+        fn issue_bounce(&mut self, overlap: Vector2<f32>, surface_velocity: Vector2<f32>) {
+            self.bounces.push((overlap, surface_velocity));
+        }
+        fn correct_and_flip_x(&mut self, _: f32) {}
+        fn correct_and_flip_y(&mut self, _: f32) {}
+        fn add_velocity(&mut self, _: Vector2<f32>) {}
+        fn add_position(&mut self, _: Vector2<f32>) {}
+        fn damage(&mut self, _: f32) {}
+        fn heal(&mut self, _: f32) {}
+        fn pick_up(&mut self, _: ()) {}
+        fn items(&self) -> u32 {
+            0
+        }
+    }
+
+    fn assert_close(a: Vector2<f32>, b: Vector2<f32>) {
+        assert!((a - b).norm() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn rigid_bounce_without_overlap_keeps_velocity() {
+        let vel = Vector2::new(1.5, -2.);
+        assert_eq!(rigid_bounce(vel, Vector2::new(0., 0.)), vel);
+    }
+
+    #[test]
+    fn rigid_bounce_flips_the_axis_of_the_overlap() {
+        let vel = Vector2::new(1.5, -2.);
+        assert_eq!(rigid_bounce(vel, Vector2::new(-3., 0.)), Vector2::new(-1.5, -2.));
+        assert_eq!(rigid_bounce(vel, Vector2::new(0., 0.5)), Vector2::new(1.5, 2.));
+    }
+
+    #[test]
+    fn rigid_bounce_reflects_off_a_diagonal() {
+        let vel = rigid_bounce(Vector2::new(1., 0.), Vector2::new(-1., -1.));
+        assert!((vel - Vector2::new(0., -1.)).norm() < 1e-6, "{:?}", vel);
+        // the ball keeps its speed
+        let vel = rigid_bounce(Vector2::new(3., 4.), Vector2::new(-1., -2.));
+        assert!((vel.norm() - 5.).abs() < 1e-5, "{:?}", vel);
+    }
+
+    #[test]
+    fn grazing_circles_do_not_overlap() {
+        let a = Circle::new(Vector2::new(0., 0.), 10.);
+        assert_eq!(a.overlap(&Circle::new(Vector2::new(15., 0.), 5.)), None);
+        assert_eq!(a.overlap(&Circle::new(Vector2::new(0., -30.), 5.)), None);
+        // barely touching makes for a tiny push
+        let overlap = a.overlap(&Circle::new(Vector2::new(14.99, 0.), 5.)).unwrap();
+        assert_close(overlap, Vector2::new(0.01, 0.));
+    }
+
+    #[test]
+    fn deep_overlap_pushes_along_the_centers() {
+        let a = Circle::new(Vector2::new(10., 10.), 10.);
+        let b = Circle::new(Vector2::new(13., 14.), 5.);
+        // the centers are 5 apart, 10 short of touching
+        assert_close(a.overlap(&b).unwrap(), Vector2::new(6., 8.));
+        assert_close(b.overlap(&a).unwrap(), Vector2::new(-6., -8.));
+    }
+
+    #[test]
+    fn concentric_circles_are_pushed_apart_along_x() {
+        let pos = Vector2::new(4., -7.);
+        let overlap = Circle::new(pos, 3.).overlap(&Circle::new(pos, 2.));
+        assert_eq!(overlap, Some(Vector2::new(5., 0.)));
+    }
+
+    #[test]
+    fn circles_bounce_off_each_other_by_size() {
+        let (mut a, mut b) = (Body::moving(Vector2::new(1., 0.)), Body::moving(Vector2::new(-2., 0.)));
+        let a_circle = Circle::new(Vector2::new(0., 0.), 30.);
+        let b_circle = Circle::new(Vector2::new(30., 0.), 10.);
+        assert!(resolve_circle_circle(&mut a, a_circle, &mut b, b_circle));
+        // the smaller circle takes three quarters of the correction, and
+        // each one bounces off the other's velocity
+        assert_eq!(a.bounces.len(), 1);
+        assert_close(a.bounces[0].0, Vector2::new(-2.5, 0.));
+        assert_close(a.bounces[0].1, Vector2::new(-2., 0.));
+        assert_eq!(b.bounces.len(), 1);
+        assert_close(b.bounces[0].0, Vector2::new(7.5, 0.));
+        assert_close(b.bounces[0].1, Vector2::new(1., 0.));
+    }
+
+    #[test]
+    fn circles_apart_do_not_bounce() {
+        let (mut a, mut b) = (Body::moving(Vector2::zeros()), Body::moving(Vector2::zeros()));
+        let a_circle = Circle::new(Vector2::new(0., 0.), 5.);
+        let b_circle = Circle::new(Vector2::new(10., 0.), 5.);
+        assert!(!resolve_circle_circle(&mut a, a_circle, &mut b, b_circle));
+        assert!(a.bounces.is_empty() && b.bounces.is_empty());
+    }
+
+    #[test]
+    fn concentric_circles_bounce_apart() {
+        let (mut a, mut b) = (Body::moving(Vector2::zeros()), Body::moving(Vector2::zeros()));
+        let pos = Vector2::new(50., 50.);
+        assert!(resolve_circle_circle(&mut a, Circle::new(pos, 5.), &mut b, Circle::new(pos, 5.)));
+        assert_close(a.bounces[0].0, Vector2::new(-5., 0.));
+        assert_close(b.bounces[0].0, Vector2::new(5., 0.));
+    }
+}
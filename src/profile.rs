@@ -0,0 +1,163 @@
+//! Module for measuring how long the game loop spends updating, rendering
+//! and flushing each frame.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use util::DynResult;
+
+/// The number of recent frames which the statistics are taken from.
+pub const PROFILE_WINDOW: usize = 120;
+/// The file which the frame times are written to, when profiling.
+pub const PROFILE_FILE: &str = "profile.csv";
+
+/// Convert a duration into milliseconds.
+pub fn duration_ms(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1000. + f64::from(d.subsec_nanos()) / 1e6
+}
+
+/// The time spent on each part of a single frame, in milliseconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameSample {
+    /// the time spent in all updates since the previous frame
+    pub update: f64,
+    pub render: f64,
+    pub flush: f64,
+}
+
+/// The average and worst time of a part of the frame, in milliseconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub average: f64,
+    pub worst: f64,
+}
+
+impl Timing {
+    fn of<I: Iterator<Item = f64>>(times: I) -> Self {
+        let (mut total, mut worst, mut count) = (0., 0., 0);
+        for t in times {
+            total += t;
+            worst = f64::max(worst, t);
+            count += 1;
+        }
+        Timing {
+            average: if count > 0 { total / f64::from(count) } else { 0. },
+            worst,
+        }
+    }
+}
+
+/// The frame times of the recent frames of a controller.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    pub update: Timing,
+    pub render: Timing,
+    pub flush: Timing,
+}
+
+impl FrameStats {
+    /// Describe the statistics in a few lines of text.
+    pub fn lines(&self) -> Vec<String> {
+        [("update", self.update), ("render", self.render), ("flush", self.flush)]
+            .iter()
+            .map(|&(name, t)| format!("{} {:.2}ms (max {:.2})", name, t.average, t.worst))
+            .collect()
+    }
+}
+
+/// Measures the frames of each controller, keeping every frame if the
+/// frame times are to be saved.
+#[derive(Debug, Default)]
+pub struct FrameProfiler {
+    /// the name of the controller being measured
+    controller: &'static str,
+    current: FrameSample,
+    recent: VecDeque<FrameSample>,
+    /// every frame measured so far, with the controller it belongs to, if
+    /// they are being kept
+    log: Option<Vec<(&'static str, FrameSample)>>,
+}
+
+impl FrameProfiler {
+    /// Create a profiler which keeps every frame, so that they can be
+    /// saved with `save_csv`.
+    pub fn with_log() -> Self {
+        FrameProfiler {
+            log: Some(Vec::new()),
+            ..FrameProfiler::default()
+        }
+    }
+
+    /// Start measuring the frames of another controller.
+    pub fn start(&mut self, controller: &'static str) {
+        self.controller = controller;
+        self.current = FrameSample::default();
+        self.recent.clear();
+    }
+
+    pub fn record_update(&mut self, d: Duration) {
+        self.current.update += duration_ms(d);
+    }
+
+    pub fn record_render(&mut self, d: Duration) {
+        self.current.render += duration_ms(d);
+    }
+
+    pub fn record_flush(&mut self, d: Duration) {
+        self.current.flush += duration_ms(d);
+    }
+
+    /// Finish measuring the current frame.
+    pub fn end_frame(&mut self) {
+        let sample = self.current;
+        self.current = FrameSample::default();
+        if self.recent.len() >= PROFILE_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(sample);
+        if let Some(log) = self.log.as_mut() {
+            log.push((self.controller, sample));
+        }
+    }
+
+    /// The statistics of the recent frames.
+    pub fn stats(&self) -> FrameStats {
+        FrameStats {
+            update: Timing::of(self.recent.iter().map(|s| s.update)),
+            render: Timing::of(self.recent.iter().map(|s| s.render)),
+            flush: Timing::of(self.recent.iter().map(|s| s.flush)),
+        }
+    }
+
+    /// Summarize the kept frames of each controller.
+    pub fn summary(&self) -> BTreeMap<&'static str, FrameStats> {
+        let mut controllers: BTreeMap<&'static str, Vec<FrameSample>> = BTreeMap::new();
+        for &(controller, sample) in self.log.iter().flatten() {
+            controllers.entry(controller).or_default().push(sample);
+        }
+        controllers
+            .into_iter()
+            .map(|(controller, samples)| {
+                let stats = FrameStats {
+                    update: Timing::of(samples.iter().map(|s| s.update)),
+                    render: Timing::of(samples.iter().map(|s| s.render)),
+                    flush: Timing::of(samples.iter().map(|s| s.flush)),
+                };
+                (controller, stats)
+            })
+            .collect()
+    }
+
+    /// Write the kept frames into a CSV file, one frame per row.
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> DynResult<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "controller,frame,update_ms,render_ms,flush_ms")?;
+        for (i, &(controller, s)) in self.log.iter().flatten().enumerate() {
+            writeln!(out, "{},{},{:.4},{:.4},{:.4}", controller, i, s.update, s.render, s.flush)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Module for the player's progress, which is kept between sessions.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::path::Path;
+use serde_json::{from_reader, to_writer_pretty as to_writer};
+use level::info::Medal;
+use util::DynResult;
+
+/// The file in which the player's progress is kept.
+pub const PROGRESS_FILE: &str = "progress.json";
+
+/// The player's progress over all levels, keyed by level name. Hard mode
+/// records are kept apart from the normal ones.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    #[serde(default)] levels: HashMap<String, LevelProgress>,
+    /// the progress on each level in hard mode
+    #[serde(default)] hard_levels: HashMap<String, LevelProgress>,
+    /// the identifiers of the unlocked achievements
+    #[serde(default)] achievements: BTreeSet<String>,
+}
+
+/// The player's progress on a single level.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelProgress {
+    /// the best time to finish the level, in seconds
+    #[serde(default)] pub best_time: Option<f32>,
+    /// the split times of the best run, in seconds
+    #[serde(default)] pub best_splits: Vec<f32>,
+    /// whether every gem of the level was collected in a finished run
+    #[serde(default)] pub all_gems: bool,
+    /// the best medal earned on the level
+    #[serde(default)] pub medal: Option<Medal>,
+}
+
+impl Progress {
+    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+        let file = File::open(path)?;
+        from_reader(file).map_err(From::from)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> DynResult<()> {
+        let file = File::create(path)?;
+        to_writer(file, self).map_err(From::from)
+    }
+
+    /// Load the progress file, starting from scratch if there is none. A
+    /// progress file which cannot be read is reported and ignored.
+    pub fn load_or_default() -> Self {
+        if !Path::new(PROGRESS_FILE).is_file() {
+            return Progress::default();
+        }
+        Progress::load(PROGRESS_FILE).unwrap_or_else(|e| {
+            eprintln!("Warning: could not read {}: {}", PROGRESS_FILE, e);
+            Progress::default()
+        })
+    }
+
+    /// Save to the progress file, reporting any failure.
+    pub fn save_or_warn(&self) {
+        if let Err(e) = self.save(PROGRESS_FILE) {
+            eprintln!("Warning: could not save {}: {}", PROGRESS_FILE, e);
+        }
+    }
+
+    pub fn level(&self, name: &str, hard_mode: bool) -> Option<&LevelProgress> {
+        if hard_mode {
+            self.hard_levels.get(name)
+        } else {
+            self.levels.get(name)
+        }
+    }
+
+    pub fn level_mut(&mut self, name: &str, hard_mode: bool) -> &mut LevelProgress {
+        let levels = if hard_mode { &mut self.hard_levels } else { &mut self.levels };
+        levels.entry(name.to_string()).or_default()
+    }
+
+    pub fn is_unlocked(&self, achievement: &str) -> bool {
+        self.achievements.contains(achievement)
+    }
+
+    /// Unlock an achievement, returning whether it was locked before.
+    pub fn unlock(&mut self, achievement: &str) -> bool {
+        self.achievements.insert(achievement.to_string())
+    }
+
+    /// Record the time and splits of a finished run, returning whether it
+    /// is the new best time of the level.
+    pub fn record_time(&mut self, name: &str, hard_mode: bool, time: f32, splits: &[f32]) -> bool {
+        let level = self.level_mut(name, hard_mode);
+        if level.best_time.map(|best| time < best).unwrap_or(true) {
+            level.best_time = Some(time);
+            level.best_splits = splits.to_vec();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record a medal earned on a level, returning whether it is better
+    /// than the one the player had.
+    pub fn record_medal(&mut self, name: &str, hard_mode: bool, medal: Medal) -> bool {
+        let level = self.level_mut(name, hard_mode);
+        if level.medal.map(|best| medal > best).unwrap_or(true) {
+            level.medal = Some(medal);
+            true
+        } else {
+            false
+        }
+    }
+}
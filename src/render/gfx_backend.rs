@@ -0,0 +1,295 @@
+//! The gfx rendering backend, over OpenGL.
+
+use gfx;
+use gfx::buffer::Role;
+use gfx::format::{DepthStencil, Formatted, Srgba8};
+use gfx::handle::{Buffer, DepthStencilView, RenderTargetView, Texture};
+use gfx::memory::{Bind, Typed, Usage};
+use gfx::pso::PipelineState;
+use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
+use gfx::traits::*;
+use gfx::{Device, IntoIndexBuffer, Slice};
+use gfx_device_gl::{self, CommandBuffer, Resources};
+use gfx_graphics::{self, Gfx2d};
+use graphics::glyph_cache::rusttype::GlyphCache;
+use graphics::Viewport;
+use piston::window::{OpenGLWindow, Size, Window};
+
+use propan::consts::{HEIGHT, WIDTH};
+
+use super::{create_glyph_cache, get_video_rect, DrawPass, Renderer, FRAGMENT_SHADER, VERTEX_SHADER};
+use {OpenGL, WindowBackend};
+
+type ColorFormat = Srgba8;
+type DepthFormat = gfx::format::DepthStencil;
+
+fn create_main_targets(
+    dim: gfx::texture::Dimensions,
+) -> (
+    RenderTargetView<Resources, Srgba8>,
+    DepthStencilView<Resources, DepthStencil>,
+) {
+    let color_format = <Srgba8 as Formatted>::get_format();
+    let depth_format = <DepthFormat as Formatted>::get_format();
+    let (output_color, output_stencil) =
+        gfx_device_gl::create_main_targets_raw(dim, color_format.0, depth_format.0);
+    let output_color: RenderTargetView<_, _> = Typed::new(output_color);
+    let output_stencil: DepthStencilView<_, _> = Typed::new(output_stencil);
+    (output_color, output_stencil)
+}
+
+fn create_gfx_device<W>(window: &mut W) -> (gfx_device_gl::Device, gfx_device_gl::Factory)
+where
+    W: OpenGLWindow,
+{
+    gfx_device_gl::create(|s| window.get_proc_address(s) as *const ::std::os::raw::c_void)
+}
+
+/// Draws with gfx, onto the window's own color and depth targets, which
+/// are created anew when the window changes size.
+pub struct GfxRenderer {
+    device: gfx_device_gl::Device,
+    factory: gfx_device_gl::Factory,
+    encoder: gfx::Encoder<Resources, CommandBuffer>,
+    g2d: Gfx2d<Resources>,
+    cache: GlyphCache<'static, gfx_device_gl::Factory, gfx_graphics::Texture<Resources>>,
+    color: RenderTargetView<Resources, Srgba8>,
+    stencil: DepthStencilView<Resources, DepthStencil>,
+    lowres_color: RenderTargetView<Resources, Srgba8>,
+    lowres_stencil: DepthStencilView<Resources, DepthStencil>,
+    /// the pipeline which scales the low-res target onto the window
+    lowres_pso: PipelineState<Resources, pipe::Meta>,
+    lowres_data: pipe::Data<Resources>,
+    slice: Slice<Resources>,
+    /// the number of samples per pixel
+    samples: gfx::texture::NumSamples,
+    readback: LowresReadback<Resources, gfx_device_gl::Factory>,
+}
+
+impl GfxRenderer {
+    pub fn new(
+        mut window: WindowBackend,
+        opengl: OpenGL,
+        samples: u8,
+        fill: [f32; 2],
+    ) -> (WindowBackend, Self) {
+        let (device, mut factory) = create_gfx_device(&mut window);
+
+        // configure graphics
+        let g2d = Gfx2d::new(opengl, &mut factory);
+
+        // Create the main color/depth targets.
+        let draw_size = window.draw_size();
+        let aa = samples as gfx::texture::NumSamples;
+        let dim = (draw_size.width as u16, draw_size.height as u16, 1, aa.into());
+        let (output_color, output_stencil) = create_main_targets(dim);
+
+        let (lowres_texture, lowres_resource_view, lowres_color) =
+            factory.create_render_target(WIDTH, HEIGHT).unwrap();
+        let lowres_stencil = factory.create_depth_stencil_view_only(WIDTH, HEIGHT).unwrap();
+        let mut encoder: gfx::Encoder<_, _> = factory.create_command_buffer().into();
+        let pso = factory
+            .create_pipeline_simple(VERTEX_SHADER, FRAGMENT_SHADER, pipe::new())
+            .unwrap();
+        // the video rectangle changes along with the letterbox
+        let (video_rect, indices) = get_video_rect(fill);
+        let video_rect = video_rect.iter().map(Vertex::from).collect::<Vec<_>>();
+        let vertex_buffer = factory
+            .create_buffer(video_rect.len(), Role::Vertex, Usage::Dynamic, Bind::empty())
+            .unwrap();
+        encoder.update_buffer(&vertex_buffer, &video_rect, 0).unwrap();
+        let slice = Slice {
+            start: 0,
+            end: indices.len() as u32,
+            base_vertex: 0,
+            instances: None,
+            buffer: indices.into_index_buffer(&mut factory),
+        };
+        let sampler = factory.create_sampler(SamplerInfo {
+            filter: FilterMethod::Scale, // yay, pixelated!
+            wrap_mode: (WrapMode::Border, WrapMode::Border, WrapMode::Border),
+            lod_bias: (0.0).into(),
+            lod_range: ((0.0).into(), (0.0).into()),
+            comparison: None,
+            border: [0.0, 0.0, 0.0, 1.0].into(), // black border
+        });
+        let data = pipe::Data {
+            vbuf: vertex_buffer,
+            orig: (lowres_resource_view, sampler),
+            out: output_color.clone(),
+        };
+
+        // gameplay capture reads the low-res target back from the GPU
+        let readback = LowresReadback::new(factory.clone(), lowres_texture);
+        let cache = create_glyph_cache(factory.clone());
+
+        let renderer = GfxRenderer {
+            device,
+            factory,
+            encoder,
+            g2d,
+            cache,
+            color: output_color,
+            stencil: output_stencil,
+            lowres_color,
+            lowres_stencil,
+            lowres_pso: pso,
+            lowres_data: data,
+            slice,
+            samples: aa,
+            readback,
+        };
+        (window, renderer)
+    }
+}
+
+impl Renderer for GfxRenderer {
+    type Texture = gfx_graphics::Texture<Resources>;
+    type Factory = gfx_device_gl::Factory;
+    type Window = WindowBackend;
+    const SRGB: bool = false;
+
+    fn factory(&self) -> Self::Factory {
+        self.factory.clone()
+    }
+
+    fn resize(&mut self, draw_size: Size, fill: [f32; 2]) {
+        let (width, height) = (draw_size.width as u16, draw_size.height as u16);
+        let (old_width, old_height, _, _) = self.color.get_dimensions();
+        if width > 0 && height > 0 && (width, height) != (old_width, old_height) {
+            let (color, stencil) = create_main_targets((width, height, 1, self.samples.into()));
+            self.lowres_data.out = color.clone();
+            self.color = color;
+            self.stencil = stencil;
+        }
+        let (video_rect, _) = get_video_rect(fill);
+        let video_rect = video_rect.iter().map(Vertex::from).collect::<Vec<_>>();
+        if let Err(e) = self.encoder.update_buffer(&self.lowres_data.vbuf, &video_rect, 0) {
+            eprintln!("Warning: could not fit the display to the window: {:?}", e);
+        }
+    }
+
+    fn draw_lowres<P>(&mut self, viewport: Viewport, pass: &mut P)
+    where
+        P: DrawPass<Self::Texture>,
+    {
+        let cache = &mut self.cache;
+        self.g2d.draw(
+            &mut self.encoder,
+            &self.lowres_color,
+            &self.lowres_stencil,
+            viewport,
+            |c, g| pass.draw(c, cache, g),
+        );
+    }
+
+    fn draw_upscaled(&mut self) {
+        // the display may not fill the window, which is black around it
+        self.encoder.clear(&self.color, [0., 0., 0., 1.]);
+        self.encoder.draw(&self.slice, &self.lowres_pso, &self.lowres_data);
+    }
+
+    fn copy_lowres(&mut self) {
+        self.readback.copy(&mut self.encoder);
+    }
+
+    fn flush(&mut self) {
+        self.encoder.flush(&mut self.device);
+    }
+
+    fn read_lowres(&mut self) -> Result<Vec<u8>, String> {
+        self.readback.read()
+    }
+
+    fn draw_hires<P>(&mut self, viewport: Viewport, pass: &mut P)
+    where
+        P: DrawPass<Self::Texture>,
+    {
+        let cache = &mut self.cache;
+        self.g2d.draw(&mut self.encoder, &self.color, &self.stencil, viewport, |c, g| {
+            pass.draw(c, cache, g)
+        });
+    }
+
+    fn end_frame(&mut self, window: &mut Self::Window) {
+        window.swap_buffers();
+        self.device.cleanup();
+    }
+}
+
+/// What it takes to read the low-res target back from the GPU, to capture
+/// gameplay.
+struct LowresReadback<R, F>
+where
+    R: gfx::Resources,
+{
+    factory: F,
+    texture: Texture<R, <ColorFormat as Formatted>::Surface>,
+    buffer: Option<Buffer<R, [u8; 4]>>,
+}
+
+impl<R, F> LowresReadback<R, F>
+where
+    R: gfx::Resources,
+    F: Factory<R>,
+{
+    fn new(mut factory: F, texture: Texture<R, <ColorFormat as Formatted>::Surface>) -> Self {
+        // without a buffer to read into, capturing fails on its first frame
+        // instead of keeping the game from starting
+        let buffer = factory
+            .create_download_buffer(usize::from(WIDTH) * usize::from(HEIGHT))
+            .map_err(|e| eprintln!("Warning: could not create the capture buffer: {:?}", e))
+            .ok();
+        LowresReadback {
+            factory,
+            texture,
+            buffer,
+        }
+    }
+
+    /// Copy the low-res target into the download buffer, once it is drawn.
+    fn copy<C>(&self, encoder: &mut gfx::Encoder<R, C>)
+    where
+        C: gfx::CommandBuffer<R>,
+    {
+        if let Some(ref buffer) = self.buffer {
+            let channel = <ColorFormat as Formatted>::get_format().1;
+            let info = self.texture.get_info().to_raw_image_info(channel, 0);
+            if let Err(e) = encoder.copy_texture_to_buffer_raw(self.texture.raw(), None, info, buffer.raw(), 0) {
+                eprintln!("Warning: could not copy the frame: {:?}", e);
+            }
+        }
+    }
+
+    /// Read the copied frame, as RGBA rows from the bottom up, once the
+    /// copy is flushed.
+    fn read(&mut self) -> Result<Vec<u8>, String> {
+        let buffer = self.buffer.as_ref().ok_or("there is no capture buffer")?;
+        let reader = self.factory.read_mapping(buffer).map_err(|e| format!("{:?}", e))?;
+        Ok(reader.iter().flat_map(|pixel| pixel.iter().cloned()).collect())
+    }
+}
+
+gfx_defines! {
+    vertex Vertex {
+        pos: [f32; 2] = "a_Pos",
+        uv: [f32; 2] = "a_Uv",
+        color: [f32; 3] = "a_Color",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        orig: gfx::TextureSampler<[f32; 4]> = "t_Video",
+        out: gfx::RenderTarget<ColorFormat> = "Target0",
+    }
+}
+
+impl<'a> From<&'a super::VideoVertex> for Vertex {
+    fn from(v: &super::VideoVertex) -> Self {
+        Vertex {
+            pos: v.pos,
+            uv: v.uv,
+            color: [1.0; 3],
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! The glium rendering backend, built with the `backend-glium` feature.
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::index::PrimitiveType;
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::{RawImage2d, SrgbTexture2d};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler, SamplerWrapFunction};
+use glium::{Frame, IndexBuffer, Program, Surface, VertexBuffer};
+use glium_graphics::{Glium2d, GliumGraphics, GliumWindow, Texture};
+use graphics::glyph_cache::rusttype::GlyphCache;
+use graphics::{Context, DrawState, Graphics, Viewport};
+use piston::window::Size;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str;
+
+use propan::consts::{HEIGHT, WIDTH};
+
+use super::{
+    create_glyph_cache, get_video_rect, DrawPass, Renderer, VideoVertex, FRAGMENT_SHADER, VERTEX_SHADER,
+};
+use {OpenGL, WindowBackend};
+
+/// A corner of the rectangle which the low-res target is drawn on, named
+/// after the attributes of the shaders.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    a_Pos: [f32; 2],
+    a_Uv: [f32; 2],
+    a_Color: [f32; 3],
+}
+
+implement_vertex!(Vertex, a_Pos, a_Uv, a_Color);
+
+impl<'a> From<&'a VideoVertex> for Vertex {
+    fn from(v: &VideoVertex) -> Self {
+        Vertex {
+            a_Pos: v.pos,
+            a_Uv: v.uv,
+            a_Color: [1.0; 3],
+        }
+    }
+}
+
+/// Draws with glium. The frame on the window begins when the low-res
+/// target is scaled up onto it, and is finished by `end_frame`.
+pub struct GliumRenderer {
+    window: GliumWindow<WindowBackend>,
+    g2d: Glium2d,
+    cache: GlyphCache<'static, GliumWindow<WindowBackend>, Texture>,
+    lowres: SrgbTexture2d,
+    /// the program which scales the low-res target onto the window
+    program: Program,
+    vertices: VertexBuffer<Vertex>,
+    indices: IndexBuffer<u16>,
+    /// a texture drawn with no triangles, which flushes the triangles
+    /// drawn on the low-res target before them
+    blank: Texture,
+    frame: Option<Frame>,
+    /// the low-res target copied for capture
+    copied: Option<PixelBuffer<(u8, u8, u8, u8)>>,
+}
+
+impl GliumRenderer {
+    pub fn new(
+        window: WindowBackend,
+        opengl: OpenGL,
+        _samples: u8,
+        fill: [f32; 2],
+    ) -> (GliumWindow<WindowBackend>, Self) {
+        let mut window = GliumWindow::new(&Rc::new(RefCell::new(window)))
+            .expect("The OpenGL implementation is too old to work with glium");
+
+        // configure graphics
+        let g2d = Glium2d::new(opengl, &window);
+        let lowres = SrgbTexture2d::empty(&window, u32::from(WIDTH), u32::from(HEIGHT))
+            .expect("Failed to create the low-res target");
+        let program = Program::from_source(
+            &window,
+            str::from_utf8(VERTEX_SHADER).unwrap(),
+            str::from_utf8(FRAGMENT_SHADER).unwrap(),
+            None,
+        ).unwrap();
+        // the video rectangle changes along with the letterbox
+        let (video_rect, indices) = get_video_rect(fill);
+        let video_rect = video_rect.iter().map(Vertex::from).collect::<Vec<_>>();
+        let vertices = VertexBuffer::dynamic(&window, &video_rect).unwrap();
+        let indices = IndexBuffer::new(&window, PrimitiveType::TrianglesList, indices).unwrap();
+        let blank = Texture::empty(&mut window).unwrap();
+        let cache = create_glyph_cache(window.clone());
+
+        let renderer = GliumRenderer {
+            window: window.clone(),
+            g2d,
+            cache,
+            lowres,
+            program,
+            vertices,
+            indices,
+            blank,
+            frame: None,
+            copied: None,
+        };
+        (window, renderer)
+    }
+}
+
+impl Renderer for GliumRenderer {
+    type Texture = Texture;
+    type Factory = GliumWindow<WindowBackend>;
+    type Window = GliumWindow<WindowBackend>;
+    const SRGB: bool = true;
+
+    fn factory(&self) -> Self::Factory {
+        self.window.clone()
+    }
+
+    fn resize(&mut self, _draw_size: Size, fill: [f32; 2]) {
+        // each frame already takes the framebuffer's size
+        let (video_rect, _) = get_video_rect(fill);
+        let video_rect = video_rect.iter().map(Vertex::from).collect::<Vec<_>>();
+        self.vertices.write(&video_rect);
+    }
+
+    fn draw_lowres<P>(&mut self, viewport: Viewport, pass: &mut P)
+    where
+        P: DrawPass<Self::Texture>,
+    {
+        let mut target = match SimpleFrameBuffer::new(&self.window, &self.lowres) {
+            Ok(target) => target,
+            Err(e) => {
+                eprintln!("Warning: could not draw on the low-res target: {:?}", e);
+                return;
+            }
+        };
+        let mut g = GliumGraphics::new(&mut self.g2d, &mut target);
+        pass.draw(Context::new_viewport(viewport), &mut self.cache, &mut g);
+        // only `Glium2d::draw` flushes the colored triangles by itself, and
+        // it can only draw on the window
+        g.tri_list_uv(&DrawState::default(), &[1.0; 4], &self.blank, |_| {});
+    }
+
+    fn draw_upscaled(&mut self) {
+        let sampler = Sampler::new(&self.lowres)
+            .magnify_filter(MagnifySamplerFilter::Nearest) // yay, pixelated!
+            .minify_filter(MinifySamplerFilter::Nearest)
+            .wrap_function(SamplerWrapFunction::Clamp);
+        let mut frame = self.window.draw();
+        // the display may not fill the window, which is black around it
+        frame.clear_color(0., 0., 0., 1.);
+        if let Err(e) = frame.draw(
+            &self.vertices,
+            &self.indices,
+            &self.program,
+            &uniform! { t_Video: sampler },
+            &Default::default(),
+        ) {
+            eprintln!("Warning: could not draw the display: {:?}", e);
+        }
+        self.frame = Some(frame);
+    }
+
+    fn copy_lowres(&mut self) {
+        self.copied = Some(self.lowres.read_to_pixel_buffer());
+    }
+
+    fn flush(&mut self) {
+        self.window.flush();
+    }
+
+    fn read_lowres(&mut self) -> Result<Vec<u8>, String> {
+        let buffer = self.copied.take().ok_or("the frame was not copied")?;
+        let image: RawImage2d<u8> = buffer.read_as_texture_2d().map_err(|e| format!("{:?}", e))?;
+        Ok(image.data.into_owned())
+    }
+
+    fn draw_hires<P>(&mut self, viewport: Viewport, pass: &mut P)
+    where
+        P: DrawPass<Self::Texture>,
+    {
+        let cache = &mut self.cache;
+        if let Some(ref mut frame) = self.frame {
+            self.g2d.draw(frame, viewport, |c, g| pass.draw(c, cache, g));
+        }
+    }
+
+    fn end_frame(&mut self, _window: &mut Self::Window) {
+        // finishing the frame swaps the buffers
+        if let Some(frame) = self.frame.take() {
+            if let Err(e) = frame.finish() {
+                eprintln!("Warning: could not show the frame: {:?}", e);
+            }
+        }
+    }
+}
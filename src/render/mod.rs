@@ -0,0 +1,147 @@
+//! The rendering backends of the game. Each one draws the controllers onto
+//! a low-res target, scales it up onto the window, and draws the hires pass
+//! over it. The gfx backend is the default, and the glium backend is built
+//! with the `backend-glium` feature, taking the place of the gfx backend.
+
+use graphics::character::CharacterCache;
+use graphics::glyph_cache::rusttype::GlyphCache;
+use graphics::{Context, Graphics, ImageSize, Viewport};
+use piston::window::{AdvancedWindow, Size, Window};
+use std::path::Path;
+use texture::{CreateTexture, Filter, TextureSettings};
+
+use propan::lang;
+
+#[cfg(all(feature = "backend-gfx", not(feature = "backend-glium")))]
+pub mod gfx_backend;
+#[cfg(feature = "backend-glium")]
+pub mod glium_backend;
+
+#[cfg(not(any(feature = "backend-gfx", feature = "backend-glium")))]
+compile_error!("no rendering backend, enable either the backend-gfx or the backend-glium feature");
+
+/// The font used for all text.
+const FONT_PATH: &str = "assets/fonts/Monospace.ttf";
+/// A copy of the font built into the game, used if the font file is missing.
+static EMBEDDED_FONT: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fonts/Monospace.ttf"));
+
+/// The shaders which scale the low-res target up onto the window.
+pub static VERTEX_SHADER: &[u8] =
+    include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/main.glslv"));
+pub static FRAGMENT_SHADER: &[u8] =
+    include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/main.glslf"));
+
+/// Something drawn in one pass of a frame, with the graphics and glyph
+/// cache of whichever backend is in use.
+pub trait DrawPass<T> {
+    fn draw<C, G>(&mut self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = T>,
+        G: Graphics<Texture = T>;
+}
+
+/// A rendering backend, drawing a frame in the order of its methods, from
+/// `draw_lowres` to `end_frame`.
+pub trait Renderer {
+    /// The textures of the sprites and text.
+    type Texture: ImageSize;
+    /// What creates the textures, for the sprite manager.
+    type Factory: Clone;
+    /// The window drawn on, which the event loop runs in.
+    type Window: Window + AdvancedWindow;
+    /// Whether the window's framebuffer must be sRGB.
+    const SRGB: bool;
+
+    fn factory(&self) -> Self::Factory;
+
+    /// Fit the window's targets to a framebuffer of the given size, with
+    /// the display taking the given fraction of its width and height.
+    fn resize(&mut self, draw_size: Size, fill: [f32; 2]);
+
+    /// Draw onto the low-res target.
+    fn draw_lowres<P>(&mut self, viewport: Viewport, pass: &mut P)
+    where
+        P: DrawPass<Self::Texture>;
+
+    /// Clear the window to black and scale the low-res target up onto it.
+    fn draw_upscaled(&mut self);
+
+    /// Copy the low-res target aside, to read it back once flushed.
+    fn copy_lowres(&mut self);
+
+    /// Send the drawing so far to the GPU.
+    fn flush(&mut self);
+
+    /// Read the copied low-res target, as RGBA rows from the bottom up.
+    fn read_lowres(&mut self) -> Result<Vec<u8>, String>;
+
+    /// Draw over the whole window, after the low-res target is scaled up.
+    fn draw_hires<P>(&mut self, viewport: Viewport, pass: &mut P)
+    where
+        P: DrawPass<Self::Texture>;
+
+    /// Show the frame on the window.
+    fn end_frame(&mut self, window: &mut Self::Window);
+}
+
+/// The glyph cache, with the language's own font if it has one, or else
+/// the built-in font if assets are embedded or the font file is missing.
+pub fn create_glyph_cache<F, T>(factory: F) -> GlyphCache<'static, F, T>
+where
+    F: Clone,
+    T: CreateTexture<F> + ImageSize,
+{
+    let font_path = match lang::language_font() {
+        Some(font) => Some(font),
+        None if cfg!(feature = "embedded-assets") => None,
+        None => Some(FONT_PATH.to_string()),
+    };
+    let font_file = font_path.and_then(|font_path| {
+        GlyphCache::new(
+            Path::new(&font_path),
+            factory.clone(),
+            TextureSettings::new().filter(Filter::Nearest),
+        ).map_err(|e| eprintln!("Warning: could not load {} ({}), using the built-in font", font_path, e))
+            .ok()
+    });
+    match font_file {
+        Some(cache) => cache,
+        None => GlyphCache::from_bytes(EMBEDDED_FONT, factory, TextureSettings::new().filter(Filter::Nearest))
+            .expect("Failed to load the built-in font"),
+    }
+}
+
+/// A corner of the rectangle which the low-res target is drawn on.
+pub struct VideoVertex {
+    pub pos: [f32; 2],
+    pub uv: [f32; 2],
+}
+
+/// The rectangle which the low-res target is drawn on, centered and taking
+/// the given fraction of the window's width and height.
+pub fn get_video_rect(fill: [f32; 2]) -> ([VideoVertex; 4], &'static [u16]) {
+    const INDICES: &[u16] = &[0, 1, 2, 2, 3, 0];
+    let (w, h) = (fill[0], fill[1]);
+    // it's just a square lulz
+    (
+        [
+            VideoVertex {
+                pos: [w, -h],
+                uv: [1., 0.0],
+            },
+            VideoVertex {
+                pos: [-w, -h],
+                uv: [0.0, 0.0],
+            },
+            VideoVertex {
+                pos: [-w, h],
+                uv: [0.0, 1.],
+            },
+            VideoVertex {
+                pos: [w, h],
+                uv: [1., 1.],
+            },
+        ],
+        INDICES,
+    )
+}
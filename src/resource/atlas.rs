@@ -0,0 +1,193 @@
+//! Module for texture atlases (sprite sheets), in which several sprites are
+//! packed into a single texture.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+use graphics::{DrawState, Graphics, Image, ImageSize};
+use graphics::math::Matrix2d;
+use serde_json::{from_reader, from_slice};
+use super::{ResourceError, Result};
+use super::sprite::AssetId;
+
+/// Parse an atlas frame map from JSON data in memory, in the same format as
+/// `read_atlas_frames`.
+pub fn parse_atlas_frames(data: &[u8]) -> Result<AtlasFrames> {
+    from_slice(data).map_err(|e| ResourceError::AtlasFormat { msg: e.to_string() })
+}
+
+/// The location of a single frame in a texture atlas, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AtlasFrame {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// A mapping of frame names to their location in the atlas.
+pub type AtlasFrames = HashMap<String, AtlasFrame>;
+
+/// Read an atlas frame map from a JSON file, in which each frame name maps
+/// to an object with the fields `x`, `y`, `w` and `h`.
+pub fn read_atlas_frames<P: AsRef<Path>>(path: P) -> Result<AtlasFrames> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| ResourceError::AtlasFormat {
+        msg: format!("{}: {}", path.display(), e),
+    })?;
+    from_reader(file).map_err(|e| ResourceError::AtlasFormat {
+        msg: format!("{}: {}", path.display(), e),
+    })
+}
+
+/// A shared texture along with the rectangle of that texture to draw.
+/// Sprites which are not part of an atlas cover the whole texture.
+#[derive(Debug)]
+pub struct SpriteRegion<T> {
+    texture: Rc<T>,
+    src_rect: [f64; 4],
+}
+
+impl<T> Clone for SpriteRegion<T> {
+    fn clone(&self) -> Self {
+        SpriteRegion {
+            texture: Rc::clone(&self.texture),
+            src_rect: self.src_rect,
+        }
+    }
+}
+
+impl<T> SpriteRegion<T>
+where
+    T: ImageSize,
+{
+    /// Create a region covering the given frame of a texture.
+    pub fn new(texture: Rc<T>, frame: AtlasFrame) -> Self {
+        SpriteRegion {
+            texture,
+            src_rect: [frame.x as f64, frame.y as f64, frame.w as f64, frame.h as f64],
+        }
+    }
+
+    /// Create a region covering the whole texture.
+    pub fn whole(texture: Rc<T>) -> Self {
+        let (w, h) = texture.get_size();
+        SpriteRegion {
+            texture,
+            src_rect: [0., 0., w as f64, h as f64],
+        }
+    }
+
+    pub fn texture(&self) -> &T {
+        &self.texture
+    }
+
+    pub fn src_rect(&self) -> [f64; 4] {
+        self.src_rect
+    }
+
+    /// Draw the region with its top-left corner at the origin of the given
+    /// transformation.
+    pub fn draw<G>(&self, transform: Matrix2d, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        Image::new()
+            .src_rect(self.src_rect)
+            .draw(&*self.texture, &DrawState::default(), transform, g);
+    }
+
+    /// Draw only the top-left `w` by `h` part of the region, like `draw`.
+    pub fn draw_part<G>(&self, w: f64, h: f64, transform: Matrix2d, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        Image::new()
+            .src_rect([self.src_rect[0], self.src_rect[1], w, h])
+            .draw(&*self.texture, &DrawState::default(), transform, g);
+    }
+
+    /// Draw the region like `draw`, multiplying its colors by the given
+    /// color.
+    pub fn draw_tinted<G>(&self, color: [f32; 4], transform: Matrix2d, g: &mut G)
+    where
+        G: Graphics<Texture = T>,
+    {
+        Image::new_color(color)
+            .src_rect(self.src_rect)
+            .draw(&*self.texture, &DrawState::default(), transform, g);
+    }
+}
+
+impl<T> ImageSize for SpriteRegion<T> {
+    fn get_size(&self) -> (u32, u32) {
+        (self.src_rect[2] as u32, self.src_rect[3] as u32)
+    }
+}
+
+/// Bookkeeping of loaded atlases and of the sprite IDs which refer to atlas
+/// frames, to be used by sprite managers.
+#[derive(Debug, Default)]
+pub struct AtlasRegistry {
+    atlases: HashMap<AssetId, AtlasFrames>,
+    regions: HashMap<AssetId, (AssetId, AtlasFrame)>,
+    /// the whole frame of each placeholder texture, which stands in for
+    /// every frame of a missing atlas
+    placeholders: HashMap<AssetId, AtlasFrame>,
+}
+
+impl AtlasRegistry {
+    pub fn insert_atlas(&mut self, id: AssetId, frames: AtlasFrames) {
+        self.placeholders.remove(&id);
+        self.atlases.insert(id, frames);
+    }
+
+    /// Make every frame of the atlas `id` refer to the given frame of a
+    /// placeholder texture.
+    pub fn insert_placeholder(&mut self, id: AssetId, frame: AtlasFrame) {
+        self.atlases.remove(&id);
+        self.placeholders.insert(id, frame);
+    }
+
+    /// Obtain a frame of the atlas with the given ID.
+    pub fn frame(&self, id: AssetId, name: &str) -> Result<AtlasFrame> {
+        self.atlases
+            .get(&id)
+            .and_then(|frames| frames.get(name))
+            .or_else(|| self.placeholders.get(&id))
+            .cloned()
+            .ok_or_else(|| ResourceError::NoRegion {
+                id,
+                name: name.to_string(),
+            })
+    }
+
+    /// Make `id` refer to a frame of the atlas `atlas_id`.
+    pub fn insert_region(&mut self, id: AssetId, atlas_id: AssetId, name: &str) -> Result<()> {
+        let frame = self.frame(atlas_id, name)?;
+        self.regions.insert(id, (atlas_id, frame));
+        Ok(())
+    }
+
+    /// Obtain the atlas and frame referred to by the given sprite ID, if it
+    /// is a region of an atlas.
+    pub fn region(&self, id: AssetId) -> Option<(AssetId, AtlasFrame)> {
+        self.regions.get(&id).cloned()
+    }
+
+    /// Forget the atlas or region with the given ID, returning whether
+    /// anything was removed.
+    pub fn remove(&mut self, id: AssetId) -> bool {
+        let atlas = self.atlases.remove(&id).is_some();
+        let region = self.regions.remove(&id).is_some();
+        let placeholder = self.placeholders.remove(&id).is_some();
+        atlas || region || placeholder
+    }
+
+    pub fn clear(&mut self) {
+        self.atlases.clear();
+        self.regions.clear();
+        self.placeholders.clear();
+    }
+}
@@ -0,0 +1,21 @@
+//! Module for the core assets which are compiled into the game with the
+//! `embedded-assets` feature, so that it runs without the `assets`
+//! directory. Level textures are still read from the filesystem.
+
+macro_rules! asset {
+    ($name:expr) => {
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $name))
+    };
+}
+
+pub static PUMP: &[u8] = asset!("pump-wheel.png");
+pub static BOOST: &[u8] = asset!("boost-arrow.png");
+pub static GEM_SHEET: &[u8] = asset!("gem-sheet.png");
+pub static GEM_SHEET_FRAMES: &[u8] = asset!("gem-sheet.json");
+pub static MINE: &[u8] = asset!("mine.png");
+pub static FLAG_SHEET: &[u8] = asset!("flag-sheet.png");
+pub static FLAG_SHEET_FRAMES: &[u8] = asset!("flag-sheet.json");
+pub static CHECK: &[u8] = asset!("check.png");
+pub static TITLE: &[u8] = asset!("title.png");
+pub static LOGO: &[u8] = asset!("logo.png");
+pub static FONT: &[u8] = asset!("fonts/Monospace.ttf");
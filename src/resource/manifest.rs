@@ -0,0 +1,123 @@
+//! Module for the asset manifest, which lists the sprites to load from the
+//! assets directory.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use serde_json::from_reader;
+use util::DynResult;
+use super::{ResourceError, Result, SpriteManage};
+use super::sprite::{AssetId, ASSETS_DIR};
+
+/// The name of the manifest file inside the assets directory.
+pub const ASSET_MANIFEST_FILE: &str = "manifest.json";
+
+/// The sprites needed by every level.
+pub const BASE_SPRITES: [AssetId; 6] = [
+    AssetId::Pump,
+    AssetId::Boost,
+    AssetId::Gem,
+    AssetId::Mine,
+    AssetId::Flag,
+    AssetId::Check,
+];
+
+/// The sprites needed by the title screen.
+pub const TITLE_SPRITES: [AssetId; 2] = [AssetId::Background, AssetId::Logo];
+
+/// The files of the game's sprites, relative to the assets directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetManifest {
+    /// sprite names mapped to their image files
+    #[serde(default)] pub sprites: BTreeMap<String, String>,
+    /// atlas names mapped to their image files, each with a frame map of the
+    /// same name ending in `.json`
+    #[serde(default)] pub atlases: BTreeMap<String, String>,
+    /// the wall textures of levels which do not list their own, in the same
+    /// format as a level's texture list
+    #[serde(default)] pub textures: Vec<String>,
+}
+
+impl Default for AssetManifest {
+    /// The assets shipped with the game. Wall textures are found by probing
+    /// `0.png`, `1.png`, and so on.
+    fn default() -> Self {
+        let entries = |list: &[(&str, &str)]| {
+            list.iter()
+                .map(|&(name, file)| (name.to_string(), file.to_string()))
+                .collect()
+        };
+        AssetManifest {
+            sprites: entries(&[
+                ("pump", "pump-wheel.png"),
+                ("boost", "boost-arrow.png"),
+                ("mine", "mine.png"),
+                ("check", "check.png"),
+                ("title", "title.png"),
+                ("logo", "logo.png"),
+            ]),
+            atlases: entries(&[("gem", "gem-sheet.png"), ("flag", "flag-sheet.png")]),
+            textures: vec![],
+        }
+    }
+}
+
+impl AssetManifest {
+    pub fn path() -> PathBuf {
+        Path::new(ASSETS_DIR).join(ASSET_MANIFEST_FILE)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+        let file = File::open(path)?;
+        from_reader(file).map_err(From::from)
+    }
+
+    /// Load the manifest of the assets directory, or the built-in one if
+    /// there is none. A manifest which cannot be read is reported, and the
+    /// built-in one is used instead.
+    pub fn load_or_default() -> Self {
+        let path = AssetManifest::path();
+        if !path.is_file() {
+            return AssetManifest::default();
+        }
+        AssetManifest::load(&path)
+            .map_err(|e| eprintln!("Warning: could not read {}: {}", path.display(), e))
+            .unwrap_or_default()
+    }
+
+    /// Load the sprites with the given IDs. Files which cannot be loaded, and
+    /// sprites which are not in the manifest, are replaced with placeholders.
+    /// Entries which do not name a known sprite are skipped with a warning.
+    pub fn load_sprites<S: SpriteManage>(&self, sprite: &mut S, ids: &[AssetId]) -> Result<()> {
+        // sprites which are already loaded are kept as they are
+        let mut loaded: Vec<AssetId> = ids.iter().cloned().filter(|&id| sprite.is_loaded(id)).collect();
+        for (name, file) in &self.sprites {
+            match AssetId::from_name(name) {
+                Some(id) if loaded.contains(&id) => {}
+                Some(id) if ids.contains(&id) => {
+                    sprite.new_sprite_or_placeholder(id, Path::new(ASSETS_DIR).join(file))?;
+                    loaded.push(id);
+                }
+                Some(_) => {}
+                None => eprintln!("Warning: unknown sprite \"{}\" in the asset manifest", name),
+            }
+        }
+        for (name, file) in &self.atlases {
+            match AssetId::from_name(name) {
+                Some(id) if loaded.contains(&id) => {}
+                Some(id) if ids.contains(&id) => {
+                    let path = Path::new(ASSETS_DIR).join(file);
+                    sprite.new_atlas_or_placeholder(id, &path, path.with_extension("json"))?;
+                    loaded.push(id);
+                }
+                Some(_) => {}
+                None => eprintln!("Warning: unknown atlas \"{}\" in the asset manifest", name),
+            }
+        }
+        for &id in ids.iter().filter(|id| !loaded.contains(id)) {
+            let name = id.name().unwrap_or_default();
+            sprite.new_placeholder_sprite(id, Path::new(name), &ResourceError::NoSprite { id })?;
+        }
+        Ok(())
+    }
+}
@@ -1,34 +1,57 @@
 use std::cell::{RefCell, RefMut};
-use gfx_device_gl::{Factory, Resources};
 
+pub mod atlas;
 pub mod sprite;
 pub mod audio;
 pub mod config;
+#[cfg(feature = "embedded-assets")]
+pub mod embedded;
+pub mod manifest;
+pub mod null;
 
+pub use self::atlas::SpriteRegion;
 pub use self::sprite::{SpriteManage, SpriteManager, AssetId as SpriteAssetId};
 pub use self::audio::{AudioManage, AudioManager};
+pub use self::null::{NullAudioManager, NullSpriteManager};
 
 pub type Result<T> = ::std::result::Result<T, ResourceError>;
 
 pub type GameTexture<T> = <<T as ResourceManage>::Sprite as SpriteManage>::Texture;
 
+/// A drawable sprite, which may be part of a texture atlas.
+pub type GameSprite<T> = SpriteRegion<GameTexture<T>>;
+
 #[derive(Debug, Fail)]
 pub enum ResourceError {
     #[fail(display = "No such sprite for ID #{:?}", id)]
     NoSprite {
         id: sprite::AssetId,
     },
+    #[fail(display = "No such region \"{}\" in atlas #{:?}", name, id)]
+    NoRegion {
+        id: sprite::AssetId,
+        name: String,
+    },
+    #[fail(display = "Invalid atlas: {}", msg)]
+    AtlasFormat {
+        msg: String,
+    },
     #[fail(display = "No such audio sample for ID #{:?}", id)]
     NoAudioSample {
         id: (),
     },
-    #[fail(display = "gfx error: {}", msg)]
+    #[fail(display = "graphics error: {}", msg)]
     GfxResource {
         msg: String,
     },
 }
 
-pub type ResourceManager = ResourceManagerImpl<SpriteManager<Factory, Resources>, AudioManager>;
+/// A resource manager with textures of type `T`, created with the factory
+/// `F` of a graphics backend.
+pub type ResourceManager<F, T> = ResourceManagerImpl<SpriteManager<F, T>, AudioManager>;
+
+/// A resource manager which requires no graphics context.
+pub type NullResourceManager = ResourceManagerImpl<NullSpriteManager, NullAudioManager>;
 
 pub trait ResourceManage {
     type Sprite: SpriteManage;
@@ -50,6 +73,49 @@ impl<'a, T: ResourceManage> ResourceManage for &'a T {
     }
 }
 
+/// A claim on the sprites which a controller needs. The sprites are
+/// retained while the lease lives, and released when it is dropped.
+pub struct SpriteLease<R>
+where
+    R: ResourceManage,
+{
+    res: R,
+    ids: Vec<SpriteAssetId>,
+}
+
+impl<R> SpriteLease<R>
+where
+    R: ResourceManage,
+{
+    /// Retain the given sprites, which should already be loaded, then free
+    /// the sprites which are no longer needed by anyone else.
+    pub fn new(res: R, ids: &[SpriteAssetId]) -> Self {
+        {
+            let mut sprite = res.sprite();
+            for &id in ids {
+                sprite.retain(id);
+            }
+            sprite.free_unused();
+        }
+        SpriteLease {
+            res,
+            ids: ids.to_vec(),
+        }
+    }
+}
+
+impl<R> Drop for SpriteLease<R>
+where
+    R: ResourceManage,
+{
+    fn drop(&mut self) {
+        let mut sprite = self.res.sprite();
+        for &id in &self.ids {
+            sprite.release(id);
+        }
+    }
+}
+
 pub struct ResourceManagerImpl<S, A> {
     sprite: RefCell<S>,
     audio: RefCell<A>,
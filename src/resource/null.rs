@@ -0,0 +1,213 @@
+//! Module for headless resource managers, which do not require a graphics
+//! context. These are useful for running game logic in automated tests.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use graphics::ImageSize;
+use image::{load_from_memory, open, GenericImageView};
+use super::{AudioManage, ResourceError, Result, SpriteAssetId, SpriteManage};
+use super::atlas::{parse_atlas_frames, read_atlas_frames, AtlasRegistry, SpriteRegion};
+use super::sprite::{format_sprite_report, placeholder_frame, report_missing, SpriteCounts, PLACEHOLDER_SIZE};
+
+/// Dimensions given to sprites whose real dimensions could not be read.
+pub const NULL_TEXTURE_SIZE: (u32, u32) = (48, 48);
+
+/// A dummy texture, which only knows its dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NullTexture {
+    width: u32,
+    height: u32,
+}
+
+impl NullTexture {
+    pub fn new(width: u32, height: u32) -> Self {
+        NullTexture { width, height }
+    }
+}
+
+impl Default for NullTexture {
+    fn default() -> Self {
+        NullTexture::new(NULL_TEXTURE_SIZE.0, NULL_TEXTURE_SIZE.1)
+    }
+}
+
+impl ImageSize for NullTexture {
+    fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+/// A sprite manager which never touches the GPU. Like the real sprite
+/// manager, loading a sprite from a file which does not exist fails (so
+/// that asset probing works the same way), but otherwise only the image
+/// dimensions are read.
+#[derive(Debug, Default)]
+pub struct NullSpriteManager {
+    loaded_sprites: HashMap<SpriteAssetId, Rc<NullTexture>>,
+    atlases: AtlasRegistry,
+    max_id: u32,
+    /// the files which could not be loaded, each reported once
+    missing: HashSet<PathBuf>,
+    counts: SpriteCounts,
+}
+
+impl NullSpriteManager {
+    pub fn new(_: ()) -> Result<Self> {
+        Ok(NullSpriteManager::default())
+    }
+
+    /// Register a sprite with the given dimensions, without reading any file.
+    pub fn insert_sprite(&mut self, id: SpriteAssetId, width: u32, height: u32) {
+        self.loaded_sprites.insert(id, Rc::new(NullTexture::new(width, height)));
+        if let SpriteAssetId::Other(i) = id {
+            self.max_id = u32::max(self.max_id, i + 1);
+        }
+    }
+
+    fn contains(&self, id: SpriteAssetId) -> bool {
+        self.loaded_sprites.contains_key(&id) || self.atlases.region(id).is_some()
+    }
+}
+
+impl SpriteManage for NullSpriteManager {
+    type Texture = NullTexture;
+
+    fn new_sprite_from_path<P: AsRef<Path>>(&mut self, id: SpriteAssetId, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(ResourceError::GfxResource {
+                msg: format!("No such file {}", path.display()),
+            });
+        }
+        let (w, h) = open(path)
+            .map(|image| image.dimensions())
+            .unwrap_or(NULL_TEXTURE_SIZE);
+        self.insert_sprite(id, w, h);
+        Ok(())
+    }
+
+    fn new_atlas_from_path<P, J>(&mut self, id: SpriteAssetId, image_path: P, json_path: J) -> Result<()>
+    where
+        P: AsRef<Path>,
+        J: AsRef<Path>,
+    {
+        let frames = read_atlas_frames(json_path)?;
+        self.new_sprite_from_path(id, image_path)?;
+        self.atlases.insert_atlas(id, frames);
+        Ok(())
+    }
+
+    fn new_sprite_from_memory(&mut self, id: SpriteAssetId, data: &[u8]) -> Result<()> {
+        let (w, h) = load_from_memory(data)
+            .map(|image| image.dimensions())
+            .unwrap_or(NULL_TEXTURE_SIZE);
+        self.insert_sprite(id, w, h);
+        Ok(())
+    }
+
+    fn new_atlas_from_memory(&mut self, id: SpriteAssetId, image: &[u8], frames: &[u8]) -> Result<()> {
+        let frames = parse_atlas_frames(frames)?;
+        self.new_sprite_from_memory(id, image)?;
+        self.atlases.insert_atlas(id, frames);
+        Ok(())
+    }
+
+    fn new_sprite_from_region(&mut self, id: SpriteAssetId, atlas_id: SpriteAssetId, name: &str) -> Result<()> {
+        self.atlases.insert_region(id, atlas_id, name)?;
+        if let SpriteAssetId::Other(i) = id {
+            self.max_id = u32::max(self.max_id, i + 1);
+        }
+        Ok(())
+    }
+
+    fn new_placeholder_sprite(&mut self, id: SpriteAssetId, path: &Path, error: &ResourceError) -> Result<()> {
+        report_missing(&mut self.missing, path, error);
+        self.insert_sprite(id, PLACEHOLDER_SIZE, PLACEHOLDER_SIZE);
+        self.atlases.insert_placeholder(id, placeholder_frame());
+        Ok(())
+    }
+
+    fn get_sprite(&self, id: SpriteAssetId) -> Result<Rc<Self::Texture>> {
+        self.loaded_sprites
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| ResourceError::NoSprite { id })
+    }
+
+    fn get_region(&self, id: SpriteAssetId, name: &str) -> Result<SpriteRegion<Self::Texture>> {
+        let frame = self.atlases.frame(id, name)?;
+        Ok(SpriteRegion::new(self.get_sprite(id)?, frame))
+    }
+
+    fn get_sprite_region(&self, id: SpriteAssetId) -> Result<SpriteRegion<Self::Texture>> {
+        match self.atlases.region(id) {
+            Some((atlas_id, frame)) => Ok(SpriteRegion::new(self.get_sprite(atlas_id)?, frame)),
+            None => Ok(SpriteRegion::whole(self.get_sprite(id)?)),
+        }
+    }
+
+    fn max_texture_id(&self) -> u32 {
+        self.max_id
+    }
+
+    fn free_sprite(&mut self, id: SpriteAssetId) -> Result<()> {
+        let removed_sprite = self.loaded_sprites.remove(&id).is_some();
+        let removed_atlas = self.atlases.remove(id);
+        if removed_sprite || removed_atlas {
+            if let SpriteAssetId::Other(i) = id {
+                if i + 1 < self.max_id { return Ok(()); }
+                let mut i = i;
+                while i > 0 && !self.contains(SpriteAssetId::Other(i - 1)) {
+                    i -= 1;
+                }
+                self.max_id = i;
+            }
+            Ok(())
+        } else {
+            Err(ResourceError::NoSprite { id })
+        }
+    }
+
+    fn free_all(&mut self) -> Result<()> {
+        self.loaded_sprites.clear();
+        self.counts.clear();
+        self.atlases.clear();
+        self.max_id = 0;
+        Ok(())
+    }
+
+    fn is_loaded(&self, id: SpriteAssetId) -> bool {
+        self.contains(id)
+    }
+
+    fn retain(&mut self, id: SpriteAssetId) {
+        self.counts.retain(id);
+    }
+
+    fn release(&mut self, id: SpriteAssetId) {
+        self.counts.release(id);
+    }
+
+    fn free_unused(&mut self) {
+        for id in self.counts.take_unused() {
+            let _ = self.free_sprite(id);
+        }
+    }
+
+    fn sprite_report(&self) -> String {
+        format_sprite_report(&self.loaded_sprites, &self.counts)
+    }
+}
+
+/// An audio manager which plays nothing.
+#[derive(Debug, Default)]
+pub struct NullAudioManager {}
+
+impl NullAudioManager {
+    pub fn new(_: ()) -> Result<Self> {
+        Ok(NullAudioManager {})
+    }
+}
+
+impl AudioManage for NullAudioManager {}
@@ -1,22 +1,63 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
 use std::cell::RefMut;
-use std::path::Path;
+use std::rc::Rc;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use graphics::ImageSize;
-use gfx::{Factory, Resources};
-use gfx_graphics::{Flip, Filter, Texture as GfxTexture, TextureSettings};
+use image::{load_from_memory, open};
 use na::Vector2;
+use texture::{CreateTexture, Filter, Format, TextureSettings};
 use super::{ResourceError, Result};
+use super::manifest::AssetManifest;
+use super::atlas::{parse_atlas_frames, read_atlas_frames, AtlasFrame, AtlasRegistry, SpriteRegion};
+use util::modified_time;
+
+/// The directory containing the game's assets.
+pub const ASSETS_DIR: &str = "assets";
+/// The width and height of the texture standing in for missing images.
+pub const PLACEHOLDER_SIZE: u32 = 32;
+/// The width and height of each square of the placeholder's checker
+/// pattern.
+const PLACEHOLDER_CHECKER: u32 = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum AssetId {
     Background,
+    LevelBackground,
     Logo,
     Pump,
+    Boost,
     Mine,
     Gem,
     Flag,
     Check,
     Other(u32),
+    Atlas(u32),
+}
+
+/// The names of the sprites which can be given in the asset manifest.
+const ASSET_NAMES: [(&str, AssetId); 8] = [
+    ("title", AssetId::Background),
+    ("logo", AssetId::Logo),
+    ("pump", AssetId::Pump),
+    ("boost", AssetId::Boost),
+    ("mine", AssetId::Mine),
+    ("gem", AssetId::Gem),
+    ("flag", AssetId::Flag),
+    ("check", AssetId::Check),
+];
+
+impl AssetId {
+    /// Obtain the sprite with the given name in the asset manifest.
+    pub fn from_name(name: &str) -> Option<AssetId> {
+        ASSET_NAMES.iter().find(|&&(n, _)| n == name).map(|&(_, id)| id)
+    }
+
+    /// Obtain the name of this sprite in the asset manifest, if it has one.
+    pub fn name(self) -> Option<&'static str> {
+        ASSET_NAMES.iter().find(|&&(_, id)| id == self).map(|&(n, _)| n)
+    }
 }
 
 pub trait SpriteManage {
@@ -24,10 +65,67 @@ pub trait SpriteManage {
 
     fn new_sprite_from_path<P: AsRef<Path>>(&mut self, id: AssetId, path: P) -> Result<()>;
 
-    fn get_sprite(&self, id: AssetId) -> Result<Self::Texture>;
+    /// Load a texture atlas from an image file and a JSON file mapping frame
+    /// names to their rectangles in the image.
+    fn new_atlas_from_path<P, J>(&mut self, id: AssetId, image_path: P, json_path: J) -> Result<()>
+    where
+        P: AsRef<Path>,
+        J: AsRef<Path>;
+
+    /// Load a sprite from the contents of an image file.
+    fn new_sprite_from_memory(&mut self, id: AssetId, data: &[u8]) -> Result<()>;
+
+    /// Load a texture atlas from the contents of an image file and of its
+    /// JSON frame map.
+    fn new_atlas_from_memory(&mut self, id: AssetId, image: &[u8], frames: &[u8]) -> Result<()>;
+
+    /// Register a new sprite which refers to a frame of a loaded atlas.
+    fn new_sprite_from_region(&mut self, id: AssetId, atlas_id: AssetId, name: &str) -> Result<()>;
+
+    /// Register a checkered placeholder texture in place of an image which
+    /// could not be loaded from `path`, warning about it the first time.
+    /// When used as an atlas, every frame of the placeholder covers the
+    /// whole texture.
+    fn new_placeholder_sprite(&mut self, id: AssetId, path: &Path, error: &ResourceError) -> Result<()>;
+
+    /// Load a sprite like `new_sprite_from_path`, falling back to a
+    /// placeholder texture if the image cannot be loaded.
+    fn new_sprite_or_placeholder<P: AsRef<Path>>(&mut self, id: AssetId, path: P) -> Result<()> {
+        let path = path.as_ref();
+        match self.new_sprite_from_path(id, path) {
+            Ok(()) => Ok(()),
+            Err(e) => self.new_placeholder_sprite(id, path, &e),
+        }
+    }
+
+    /// Load an atlas like `new_atlas_from_path`, falling back to a
+    /// placeholder texture if the image or its frame map cannot be loaded.
+    fn new_atlas_or_placeholder<P, J>(&mut self, id: AssetId, image_path: P, json_path: J) -> Result<()>
+    where
+        P: AsRef<Path>,
+        J: AsRef<Path>,
+    {
+        let image_path = image_path.as_ref();
+        match self.new_atlas_from_path(id, image_path, json_path) {
+            Ok(()) => Ok(()),
+            Err(e) => self.new_placeholder_sprite(id, image_path, &e),
+        }
+    }
+
+    /// Obtain the texture of the sprite with the given ID. The texture is
+    /// shared with every other user of the same sprite.
+    fn get_sprite(&self, id: AssetId) -> Result<Rc<Self::Texture>>;
+
+    /// Obtain a named frame of the atlas with the given ID.
+    fn get_region(&self, id: AssetId, name: &str) -> Result<SpriteRegion<Self::Texture>>;
+
+    /// Obtain the sprite with the given ID as a region to draw, which
+    /// works both for sprites registered from atlas frames and for whole
+    /// textures.
+    fn get_sprite_region(&self, id: AssetId) -> Result<SpriteRegion<Self::Texture>>;
 
     fn get_sprite_dimensions(&self, id: AssetId) -> Option<Vector2<f32>> {
-        self.get_sprite(id).ok()
+        self.get_sprite_region(id).ok()
             .map(|t| t.get_size())
             .map(|(w, h)| [w as f32, h as f32].into())
     }
@@ -37,6 +135,34 @@ pub trait SpriteManage {
     fn free_sprite(&mut self, id: AssetId) -> Result<()>;
 
     fn free_all(&mut self) -> Result<()>;
+
+    /// Reload the sprites whose image files changed since they were loaded,
+    /// returning their IDs. Sprites which fail to reload keep their old
+    /// texture. Entities keep the textures which they were created with, so
+    /// they must be recreated to show the new ones.
+    fn reload_changed(&mut self) -> Vec<AssetId> {
+        Vec::new()
+    }
+
+    /// Check whether a sprite, atlas or atlas region with the given ID is
+    /// loaded.
+    fn is_loaded(&self, id: AssetId) -> bool;
+
+    /// Declare that the sprite with the given ID is needed, until a matching
+    /// call to `release`.
+    fn retain(&mut self, id: AssetId);
+
+    /// Declare that the sprite with the given ID is no longer needed by
+    /// one of its users. It stays loaded until `free_unused` is called.
+    fn release(&mut self, id: AssetId);
+
+    /// Free the sprites which were retained before, but were released by
+    /// all of their users since.
+    fn free_unused(&mut self);
+
+    /// Describe the loaded textures, with their users and approximate
+    /// memory use.
+    fn sprite_report(&self) -> String;
 }
 impl<'a, 'g, T: SpriteManage> SpriteManage for &'a mut T {
     type Texture = T::Texture;
@@ -45,10 +171,42 @@ impl<'a, 'g, T: SpriteManage> SpriteManage for &'a mut T {
         (**self).new_sprite_from_path(id, path)
     }
 
-    fn get_sprite(&self, id: AssetId) -> Result<Self::Texture> {
+    fn new_atlas_from_path<P, J>(&mut self, id: AssetId, image_path: P, json_path: J) -> Result<()>
+    where
+        P: AsRef<Path>,
+        J: AsRef<Path>,
+    {
+        (**self).new_atlas_from_path(id, image_path, json_path)
+    }
+
+    fn new_sprite_from_memory(&mut self, id: AssetId, data: &[u8]) -> Result<()> {
+        (**self).new_sprite_from_memory(id, data)
+    }
+
+    fn new_atlas_from_memory(&mut self, id: AssetId, image: &[u8], frames: &[u8]) -> Result<()> {
+        (**self).new_atlas_from_memory(id, image, frames)
+    }
+
+    fn new_sprite_from_region(&mut self, id: AssetId, atlas_id: AssetId, name: &str) -> Result<()> {
+        (**self).new_sprite_from_region(id, atlas_id, name)
+    }
+
+    fn new_placeholder_sprite(&mut self, id: AssetId, path: &Path, error: &ResourceError) -> Result<()> {
+        (**self).new_placeholder_sprite(id, path, error)
+    }
+
+    fn get_sprite(&self, id: AssetId) -> Result<Rc<Self::Texture>> {
         (**self).get_sprite(id)
     }
 
+    fn get_region(&self, id: AssetId, name: &str) -> Result<SpriteRegion<Self::Texture>> {
+        (**self).get_region(id, name)
+    }
+
+    fn get_sprite_region(&self, id: AssetId) -> Result<SpriteRegion<Self::Texture>> {
+        (**self).get_sprite_region(id)
+    }
+
     fn max_texture_id(&self) -> u32 {
         (**self).max_texture_id()
     }
@@ -60,6 +218,30 @@ impl<'a, 'g, T: SpriteManage> SpriteManage for &'a mut T {
     fn free_all(&mut self) -> Result<()> {
         (**self).free_all()
     }
+
+    fn reload_changed(&mut self) -> Vec<AssetId> {
+        (**self).reload_changed()
+    }
+
+    fn is_loaded(&self, id: AssetId) -> bool {
+        (**self).is_loaded(id)
+    }
+
+    fn retain(&mut self, id: AssetId) {
+        (**self).retain(id)
+    }
+
+    fn release(&mut self, id: AssetId) {
+        (**self).release(id)
+    }
+
+    fn free_unused(&mut self) {
+        (**self).free_unused()
+    }
+
+    fn sprite_report(&self) -> String {
+        (**self).sprite_report()
+    }
 }
 impl<'a, 'g, T: SpriteManage> SpriteManage for RefMut<'a, T> {
     type Texture = T::Texture;
@@ -68,10 +250,42 @@ impl<'a, 'g, T: SpriteManage> SpriteManage for RefMut<'a, T> {
         (**self).new_sprite_from_path(id, path)
     }
 
-    fn get_sprite(&self, id: AssetId) -> Result<Self::Texture> {
+    fn new_atlas_from_path<P, J>(&mut self, id: AssetId, image_path: P, json_path: J) -> Result<()>
+    where
+        P: AsRef<Path>,
+        J: AsRef<Path>,
+    {
+        (**self).new_atlas_from_path(id, image_path, json_path)
+    }
+
+    fn new_sprite_from_memory(&mut self, id: AssetId, data: &[u8]) -> Result<()> {
+        (**self).new_sprite_from_memory(id, data)
+    }
+
+    fn new_atlas_from_memory(&mut self, id: AssetId, image: &[u8], frames: &[u8]) -> Result<()> {
+        (**self).new_atlas_from_memory(id, image, frames)
+    }
+
+    fn new_sprite_from_region(&mut self, id: AssetId, atlas_id: AssetId, name: &str) -> Result<()> {
+        (**self).new_sprite_from_region(id, atlas_id, name)
+    }
+
+    fn new_placeholder_sprite(&mut self, id: AssetId, path: &Path, error: &ResourceError) -> Result<()> {
+        (**self).new_placeholder_sprite(id, path, error)
+    }
+
+    fn get_sprite(&self, id: AssetId) -> Result<Rc<Self::Texture>> {
         (**self).get_sprite(id)
     }
 
+    fn get_region(&self, id: AssetId, name: &str) -> Result<SpriteRegion<Self::Texture>> {
+        (**self).get_region(id, name)
+    }
+
+    fn get_sprite_region(&self, id: AssetId) -> Result<SpriteRegion<Self::Texture>> {
+        (**self).get_sprite_region(id)
+    }
+
     fn max_texture_id(&self) -> u32 {
         (**self).max_texture_id()
     }
@@ -83,72 +297,421 @@ impl<'a, 'g, T: SpriteManage> SpriteManage for RefMut<'a, T> {
     fn free_all(&mut self) -> Result<()> {
         (**self).free_all()
     }
+
+    fn reload_changed(&mut self) -> Vec<AssetId> {
+        (**self).reload_changed()
+    }
+
+    fn is_loaded(&self, id: AssetId) -> bool {
+        (**self).is_loaded(id)
+    }
+
+    fn retain(&mut self, id: AssetId) {
+        (**self).retain(id)
+    }
+
+    fn release(&mut self, id: AssetId) {
+        (**self).release(id)
+    }
+
+    fn free_unused(&mut self) {
+        (**self).free_unused()
+    }
+
+    fn sprite_report(&self) -> String {
+        (**self).sprite_report()
+    }
 }
 
-#[derive(Debug)]
-pub struct SpriteManager<F, R>
+/// The number of users of each sprite which was ever retained, to be used
+/// by sprite managers.
+#[derive(Debug, Default)]
+pub struct SpriteCounts {
+    counts: HashMap<AssetId, u32>,
+}
+
+impl SpriteCounts {
+    pub fn retain(&mut self, id: AssetId) {
+        *self.counts.entry(id).or_insert(0) += 1;
+    }
+
+    pub fn release(&mut self, id: AssetId) {
+        if let Some(count) = self.counts.get_mut(&id) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    /// Obtain the number of users of a sprite, or `None` if it was never
+    /// retained.
+    pub fn count(&self, id: AssetId) -> Option<u32> {
+        self.counts.get(&id).cloned()
+    }
+
+    /// Forget the sprites which no longer have any users, returning them so
+    /// that they can be freed.
+    pub fn take_unused(&mut self) -> Vec<AssetId> {
+        let unused: Vec<AssetId> = self.counts
+            .iter()
+            .filter(|&(_, &count)| count == 0)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in &unused {
+            self.counts.remove(id);
+        }
+        unused
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+}
+
+/// List the given textures, with their users and their approximate memory
+/// use (assuming 4 bytes per pixel).
+pub fn format_sprite_report<T: ImageSize>(textures: &HashMap<AssetId, Rc<T>>, counts: &SpriteCounts) -> String {
+    let mut ids: Vec<AssetId> = textures.keys().cloned().collect();
+    ids.sort();
+    let mut report = String::from("Loaded textures:\n");
+    let mut total = 0;
+    for id in ids {
+        let (w, h) = textures[&id].get_size();
+        let bytes = u64::from(w) * u64::from(h) * 4;
+        total += bytes;
+        let users = match counts.count(id) {
+            Some(n) => format!("{} user(s)", n),
+            None => "not retained".to_string(),
+        };
+        report += &format!("  {:<16} {:>5}x{:<5} {:>8} KiB  {}\n", format!("{:?}", id), w, h, bytes / 1024, users);
+    }
+    report += &format!("Total: {} texture(s), about {} KiB", textures.len(), total / 1024);
+    report
+}
+
+/// Obtain the pixels of the placeholder texture, a magenta and black checker
+/// pattern, in RGBA order.
+pub fn placeholder_pixels() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((PLACEHOLDER_SIZE * PLACEHOLDER_SIZE * 4) as usize);
+    for y in 0..PLACEHOLDER_SIZE {
+        for x in 0..PLACEHOLDER_SIZE {
+            if (x / PLACEHOLDER_CHECKER + y / PLACEHOLDER_CHECKER) % 2 == 0 {
+                pixels.extend_from_slice(&[255, 0, 255, 255]);
+            } else {
+                pixels.extend_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+    }
+    pixels
+}
+
+/// The frame covering the whole placeholder texture.
+pub fn placeholder_frame() -> AtlasFrame {
+    AtlasFrame {
+        x: 0,
+        y: 0,
+        w: PLACEHOLDER_SIZE,
+        h: PLACEHOLDER_SIZE,
+    }
+}
+
+/// Warn about an asset which could not be loaded, unless it is already in
+/// the given set of missing assets.
+pub fn report_missing(missing: &mut HashSet<PathBuf>, path: &Path, error: &ResourceError) {
+    if missing.insert(path.to_path_buf()) {
+        eprintln!("Warning: could not load {} ({}), using a placeholder", path.display(), error);
+    }
+}
+
+/// Load the sprites of the objects found in every level, compiled into the
+/// game.
+#[cfg(feature = "embedded-assets")]
+pub fn load_base_sprites<S: SpriteManage>(sprite: &mut S) -> Result<()> {
+    use super::embedded::*;
+    for &(id, data) in &[
+        (AssetId::Pump, PUMP),
+        (AssetId::Boost, BOOST),
+        (AssetId::Mine, MINE),
+        (AssetId::Check, CHECK),
+    ] {
+        if !sprite.is_loaded(id) {
+            sprite.new_sprite_from_memory(id, data)?;
+        }
+    }
+    for &(id, image, frames) in &[
+        (AssetId::Gem, GEM_SHEET, GEM_SHEET_FRAMES),
+        (AssetId::Flag, FLAG_SHEET, FLAG_SHEET_FRAMES),
+    ] {
+        if !sprite.is_loaded(id) {
+            sprite.new_atlas_from_memory(id, image, frames)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load the sprites of the objects found in every level from the files
+/// listed in the asset manifest, with placeholders for missing files.
+#[cfg(not(feature = "embedded-assets"))]
+pub fn load_base_sprites<S: SpriteManage>(sprite: &mut S) -> Result<()> {
+    AssetManifest::load_or_default().load_sprites(sprite, &super::manifest::BASE_SPRITES)
+}
+
+/// Load the title screen's background and logo, compiled into the game.
+#[cfg(feature = "embedded-assets")]
+pub fn load_title_sprites<S: SpriteManage>(sprite: &mut S) -> Result<()> {
+    use super::embedded::*;
+    for &(id, data) in &[(AssetId::Background, TITLE), (AssetId::Logo, LOGO)] {
+        if !sprite.is_loaded(id) {
+            sprite.new_sprite_from_memory(id, data)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load the title screen's background and logo from the files listed in
+/// the asset manifest, with placeholders for missing files.
+#[cfg(not(feature = "embedded-assets"))]
+pub fn load_title_sprites<S: SpriteManage>(sprite: &mut S) -> Result<()> {
+    AssetManifest::load_or_default().load_sprites(sprite, &super::manifest::TITLE_SPRITES)
+}
+
+/// Separator between an atlas image and a frame name in a texture entry.
+pub const ATLAS_FRAME_SEPARATOR: char = '#';
+
+/// Load the wall textures of a level. Each texture file in `textures` is
+/// relative to the assets directory, and is assigned the ID of its index in
+/// the list. An entry in the form `tiles.png#brick` refers to the frame
+/// `brick` of the atlas `tiles.png`, whose frame map is read from
+/// `tiles.json`. If the list is empty, the textures listed in the asset
+/// manifest are loaded instead. Without those, textures are loaded by
+/// probing `assets/0.png`, `assets/1.png`, and so on until a file is
+/// missing.
+pub fn load_level_textures<S, T>(sprite: &mut S, textures: &[T]) -> Result<()>
+where
+    S: SpriteManage,
+    T: AsRef<str>,
+{
+    if !textures.is_empty() {
+        return load_texture_list(sprite, textures);
+    }
+    let manifest = AssetManifest::load_or_default();
+    if !manifest.textures.is_empty() {
+        return load_texture_list(sprite, &manifest.textures);
+    }
+
+    for i in 0.. {
+        let id = AssetId::Other(i);
+        let path = Path::new(ASSETS_DIR).join(format!("{}.png", i));
+        if !sprite.is_loaded(id) && sprite.new_sprite_from_path(id, path).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Load the given wall textures, as described in `load_level_textures`.
+fn load_texture_list<S, T>(sprite: &mut S, textures: &[T]) -> Result<()>
 where
-    R: Resources,
+    S: SpriteManage,
+    T: AsRef<str>,
 {
+    let mut atlases: HashMap<&str, AssetId> = HashMap::new();
+    for (i, entry) in textures.iter().enumerate() {
+        let id = AssetId::Other(i as u32);
+        if sprite.is_loaded(id) {
+            // the level is being loaded again
+            continue;
+        }
+        let mut parts = entry.as_ref().splitn(2, ATLAS_FRAME_SEPARATOR);
+        let file = parts.next().unwrap_or_default();
+        let path = Path::new(ASSETS_DIR).join(file);
+        match parts.next() {
+            None => sprite.new_sprite_or_placeholder(id, path)?,
+            Some(frame) => {
+                let atlas_id = match atlases.get(file).cloned() {
+                    Some(atlas_id) => atlas_id,
+                    None => {
+                        let atlas_id = AssetId::Atlas(atlases.len() as u32);
+                        sprite.new_atlas_or_placeholder(atlas_id, &path, path.with_extension("json"))?;
+                        atlases.insert(file, atlas_id);
+                        atlas_id
+                    }
+                };
+                sprite.new_sprite_from_region(id, atlas_id, frame)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Free all wall textures loaded with `load_level_textures`.
+pub fn free_level_textures<S>(sprite: &mut S)
+where
+    S: SpriteManage,
+{
+    let max_id = sprite.max_texture_id();
+    for i in (0..max_id).rev() {
+        // textures may have gaps, which are fine to skip
+        let _ = sprite.free_sprite(AssetId::Other(i));
+        // there are never more atlases than textures
+        let _ = sprite.free_sprite(AssetId::Atlas(i));
+    }
+}
+
+/// A sprite manager which creates textures of type `T` with the factory
+/// `F` of a graphics backend.
+#[derive(Debug)]
+pub struct SpriteManager<F, T> {
     factory: F,
-    loaded_sprites: HashMap<AssetId, GfxTexture<R>>,
+    loaded_sprites: HashMap<AssetId, Rc<T>>,
+    /// the file of each loaded sprite, with its modification time
+    sources: HashMap<AssetId, (PathBuf, Option<SystemTime>)>,
+    /// the files which could not be loaded, each reported once
+    missing: HashSet<PathBuf>,
+    counts: SpriteCounts,
+    atlases: AtlasRegistry,
     max_id: u32,
 }
 
-impl<F, R> SpriteManager<F, R>
+impl<F, T> SpriteManager<F, T>
 where
-    F: Factory<R>,
-    R: Resources,
+    T: CreateTexture<F>,
+    T::Error: Debug,
 {
     pub fn new(params: F) -> Result<Self> {
         Ok(SpriteManager {
             factory: params,
             loaded_sprites: HashMap::new(),
+            sources: HashMap::new(),
+            missing: HashSet::new(),
+            counts: SpriteCounts::default(),
+            atlases: AtlasRegistry::default(),
             max_id: 0,
         })
     }
+
+    fn contains(&self, id: AssetId) -> bool {
+        self.loaded_sprites.contains_key(&id) || self.atlases.region(id).is_some()
+    }
+
+    /// Create a texture from RGBA pixels.
+    fn create_texture(&mut self, pixels: &[u8], width: u32, height: u32) -> Result<T> {
+        let mut tex_settings = TextureSettings::new();
+        tex_settings.set_filter(Filter::Nearest);
+        T::create(&mut self.factory, Format::Rgba8, pixels, [width, height], &tex_settings)
+            .map_err(|e| ResourceError::GfxResource { msg: format!("{:?}", e) })
+    }
+
+    fn load_texture(&mut self, path: &Path) -> Result<T> {
+        let image = open(path)
+            .map_err(|e| ResourceError::GfxResource { msg: e.to_string() })?
+            .to_rgba();
+        self.create_texture(&image, image.width(), image.height())
+    }
 }
 
-impl<F, R> SpriteManage for SpriteManager<F, R>
+impl<F, T> SpriteManage for SpriteManager<F, T>
 where
-    F: Factory<R>,
-    R: Resources,
+    T: CreateTexture<F>,
+    T::Error: Debug,
 {
-    type Texture = GfxTexture<R>;
+    type Texture = T;
 
     fn new_sprite_from_path<P: AsRef<Path>>(&mut self, id: AssetId, path: P) -> Result<()> {
-        let mut tex_settings = TextureSettings::new();
-        tex_settings.set_filter(Filter::Nearest);
-        let tex =
-            GfxTexture::from_path(&mut self.factory, path, Flip::None, &tex_settings)
-                .map_err(|e| ResourceError::GfxResource { msg: e })?;
-        self.loaded_sprites.insert(id, tex);
+        let path = path.as_ref();
+        let tex = self.load_texture(path)?;
+        self.loaded_sprites.insert(id, Rc::new(tex));
+        self.sources.insert(id, (path.to_path_buf(), modified_time(path)));
+        if let AssetId::Other(i) = id {
+            self.max_id = u32::max(self.max_id, i + 1);
+        }
+        Ok(())
+    }
+
+    fn new_atlas_from_path<P, J>(&mut self, id: AssetId, image_path: P, json_path: J) -> Result<()>
+    where
+        P: AsRef<Path>,
+        J: AsRef<Path>,
+    {
+        let frames = read_atlas_frames(json_path)?;
+        self.new_sprite_from_path(id, image_path)?;
+        self.atlases.insert_atlas(id, frames);
+        Ok(())
+    }
+
+    fn new_sprite_from_memory(&mut self, id: AssetId, data: &[u8]) -> Result<()> {
+        let image = load_from_memory(data)
+            .map_err(|e| ResourceError::GfxResource { msg: e.to_string() })?
+            .to_rgba();
+        let tex = self.create_texture(&image, image.width(), image.height())?;
+        self.loaded_sprites.insert(id, Rc::new(tex));
+        self.sources.remove(&id);
+        if let AssetId::Other(i) = id {
+            self.max_id = u32::max(self.max_id, i + 1);
+        }
+        Ok(())
+    }
+
+    fn new_atlas_from_memory(&mut self, id: AssetId, image: &[u8], frames: &[u8]) -> Result<()> {
+        let frames = parse_atlas_frames(frames)?;
+        self.new_sprite_from_memory(id, image)?;
+        self.atlases.insert_atlas(id, frames);
+        Ok(())
+    }
+
+    fn new_sprite_from_region(&mut self, id: AssetId, atlas_id: AssetId, name: &str) -> Result<()> {
+        self.atlases.insert_region(id, atlas_id, name)?;
         if let AssetId::Other(i) = id {
             self.max_id = u32::max(self.max_id, i + 1);
         }
         Ok(())
     }
 
-    fn get_sprite(&self, id: AssetId) -> Result<Self::Texture> {
+    fn new_placeholder_sprite(&mut self, id: AssetId, path: &Path, error: &ResourceError) -> Result<()> {
+        report_missing(&mut self.missing, path, error);
+        let tex = self.create_texture(&placeholder_pixels(), PLACEHOLDER_SIZE, PLACEHOLDER_SIZE)?;
+        self.loaded_sprites.insert(id, Rc::new(tex));
+        self.sources.remove(&id);
+        self.atlases.insert_placeholder(id, placeholder_frame());
+        if let AssetId::Other(i) = id {
+            self.max_id = u32::max(self.max_id, i + 1);
+        }
+        Ok(())
+    }
+
+    fn get_sprite(&self, id: AssetId) -> Result<Rc<Self::Texture>> {
         self.loaded_sprites
             .get(&id)
-            .map(|x| x.clone())
+            .cloned()
             .ok_or_else(|| ResourceError::NoSprite { id })
     }
 
+    fn get_region(&self, id: AssetId, name: &str) -> Result<SpriteRegion<Self::Texture>> {
+        let frame = self.atlases.frame(id, name)?;
+        Ok(SpriteRegion::new(self.get_sprite(id)?, frame))
+    }
+
+    fn get_sprite_region(&self, id: AssetId) -> Result<SpriteRegion<Self::Texture>> {
+        match self.atlases.region(id) {
+            Some((atlas_id, frame)) => Ok(SpriteRegion::new(self.get_sprite(atlas_id)?, frame)),
+            None => Ok(SpriteRegion::whole(self.get_sprite(id)?)),
+        }
+    }
+
     fn max_texture_id(&self) -> u32 {
         self.max_id
     }
 
     fn free_sprite(&mut self, id: AssetId) -> Result<()> {
-        if self.loaded_sprites.remove(&id).is_some() {
+        let removed_sprite = self.loaded_sprites.remove(&id).is_some();
+        self.sources.remove(&id);
+        let removed_atlas = self.atlases.remove(id);
+        if removed_sprite || removed_atlas {
             if let AssetId::Other(i) = id {
-                if i < self.max_id - 1 { return Ok(()); }
+                if i + 1 < self.max_id { return Ok(()); }
                 let mut i = i;
-                while i > 0 && !self.loaded_sprites.contains_key(&AssetId::Other(i - 1)) {
+                while i > 0 && !self.contains(AssetId::Other(i - 1)) {
                     i -= 1;
                 }
-                self.max_id = 1;
+                self.max_id = i;
             }
             Ok(())
         } else {
@@ -158,7 +721,103 @@ where
 
     fn free_all(&mut self) -> Result<()> {
         self.loaded_sprites.clear();
+        self.sources.clear();
+        self.counts.clear();
+        self.atlases.clear();
         self.max_id = 0;
         Ok(())
     }
+
+    fn is_loaded(&self, id: AssetId) -> bool {
+        self.contains(id)
+    }
+
+    fn retain(&mut self, id: AssetId) {
+        self.counts.retain(id);
+    }
+
+    fn release(&mut self, id: AssetId) {
+        self.counts.release(id);
+    }
+
+    fn free_unused(&mut self) {
+        for id in self.counts.take_unused() {
+            let _ = self.free_sprite(id);
+        }
+    }
+
+    fn sprite_report(&self) -> String {
+        format_sprite_report(&self.loaded_sprites, &self.counts)
+    }
+
+    fn reload_changed(&mut self) -> Vec<AssetId> {
+        let changed: Vec<(AssetId, PathBuf, Option<SystemTime>)> = self.sources
+            .iter()
+            .filter_map(|(&id, &(ref path, time))| {
+                let new_time = modified_time(path);
+                if new_time.is_some() && new_time != time {
+                    Some((id, path.clone(), new_time))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let mut reloaded = vec![];
+        for (id, path, time) in changed {
+            // a failed reload is not retried until the file changes again
+            if let Some(source) = self.sources.get_mut(&id) {
+                source.1 = time;
+            }
+            match self.load_texture(&path) {
+                Ok(tex) => {
+                    self.loaded_sprites.insert(id, Rc::new(tex));
+                    reloaded.push(id);
+                }
+                Err(e) => eprintln!("Warning: could not reload {}: {}", path.display(), e),
+            }
+        }
+        reloaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::wall::Wall;
+    use level::info::WallInfo;
+    use level::GameLevel;
+    use resource::{NullAudioManager, NullResourceManager, NullSpriteManager, ResourceManage};
+
+    #[test]
+    fn walls_share_one_texture_per_texture_id() {
+        let sprite = NullSpriteManager::new(()).unwrap();
+        let res = &NullResourceManager::new(sprite, NullAudioManager::new(()).unwrap());
+        let mut level = GameLevel::default();
+        level.textures_mut().extend(vec!["0.png".to_string(), "1.png".to_string()]);
+        for i in 0..1000 {
+            level.walls_mut().push(WallInfo {
+                pos: [(i % 40) * 16, (i / 40) * 16].into(),
+                dim: [16, 16].into(),
+                texture_id: (i % 2) as u32,
+                rotation: 0,
+                flip: false,
+                tile: false,
+            });
+        }
+        load_level_textures(&mut res.sprite(), level.textures()).unwrap();
+        let walls: Vec<_> = level
+            .walls()
+            .iter()
+            .map(|info| Wall::new(info.clone(), res).unwrap())
+            .collect();
+        assert_eq!(walls.len(), 1000);
+
+        // every wall holds the one texture of its texture_id, which the
+        // sprite manager and the handle obtained here also hold
+        let sprite = res.sprite();
+        for &id in &[0, 1] {
+            let texture = sprite.get_sprite(AssetId::Other(id)).unwrap();
+            assert_eq!(Rc::strong_count(&texture), 500 + 2);
+        }
+    }
 }
@@ -0,0 +1,189 @@
+//! Module for the player's settings, which are kept between sessions.
+
+use std::fs::File;
+use std::path::Path;
+use serde_json::{from_reader, to_writer_pretty as to_writer};
+use game::palette::PaletteKind;
+use lang::DEFAULT_LANGUAGE;
+use util::DynResult;
+
+/// The file in which the player's settings are kept.
+pub const SETTINGS_FILE: &str = "settings.json";
+/// The frame rate limits which the main menu cycles through.
+pub const FPS_CHOICES: &[u64] = &[30, 60, 120, 144, 240];
+/// The update rates which the main menu cycles through. The game's physics
+/// runs in fixed steps, so these only change how often input is read.
+pub const UPS_CHOICES: &[u64] = &[60, 120, 240];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// the code of the language of the text on screen
+    #[serde(default = "Settings::default_language")] pub language: String,
+    /// whether the game keeps running while its window is out of focus
+    #[serde(default)] pub background_simulation: bool,
+    /// whether levels are played in hard mode, with a constantly shrinking
+    /// ball
+    #[serde(default)] pub hard_mode: bool,
+    /// whether switching between the title screen, the levels and the
+    /// editor fades through black
+    #[serde(default = "Settings::default_transitions")] pub transitions: bool,
+    /// the colors in which the ball and the level's objects are drawn
+    #[serde(default)] pub palette: PaletteKind,
+    /// how strongly screen shake, flashes and particles are shown
+    #[serde(default)] pub effects: EffectsIntensity,
+    /// the most frames drawn per second
+    #[serde(default = "Settings::default_max_fps")] pub max_fps: u64,
+    /// the number of updates per second
+    #[serde(default = "Settings::default_ups")] pub ups: u64,
+    /// whether frames wait for the display's refresh, which only takes
+    /// effect the next time the game starts
+    #[serde(default = "Settings::default_vsync")] pub vsync: bool,
+    /// whether the window covers a whole monitor without borders, which
+    /// only takes effect the next time the game starts
+    #[serde(default)] pub fullscreen: bool,
+    /// the name of the monitor to go fullscreen on, or none for the primary
+    /// monitor
+    #[serde(default)] pub monitor: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            language: Settings::default_language(),
+            background_simulation: false,
+            hard_mode: false,
+            transitions: Settings::default_transitions(),
+            palette: PaletteKind::default(),
+            effects: EffectsIntensity::default(),
+            max_fps: Settings::default_max_fps(),
+            ups: Settings::default_ups(),
+            vsync: Settings::default_vsync(),
+            fullscreen: false,
+            monitor: None,
+        }
+    }
+}
+
+/// The step by which the intensity of an effect changes in the main menu,
+/// as a percentage.
+pub const INTENSITY_STEP: u8 = 25;
+
+/// How strongly each kind of visual effect is shown, as a percentage from 0
+/// (not at all) to 100 (in full).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectsIntensity {
+    /// the strength of screen shake
+    #[serde(default = "EffectsIntensity::full")] pub shake: u8,
+    /// the strength of flashing and blinking
+    #[serde(default = "EffectsIntensity::full")] pub flash: u8,
+    /// the number of particles emitted
+    #[serde(default = "EffectsIntensity::full")] pub particles: u8,
+}
+
+impl Default for EffectsIntensity {
+    fn default() -> Self {
+        EffectsIntensity {
+            shake: EffectsIntensity::full(),
+            flash: EffectsIntensity::full(),
+            particles: EffectsIntensity::full(),
+        }
+    }
+}
+
+impl EffectsIntensity {
+    fn full() -> u8 {
+        100
+    }
+
+    /// The strength of screen shake, as a factor from 0 to 1.
+    pub fn shake_factor(&self) -> f32 {
+        percent_factor(self.shake)
+    }
+
+    /// The strength of flashing and blinking, as a factor from 0 to 1.
+    pub fn flash_factor(&self) -> f32 {
+        percent_factor(self.flash)
+    }
+
+    /// The fraction of particles which are emitted, from 0 to 1.
+    pub fn particle_factor(&self) -> f32 {
+        percent_factor(self.particles)
+    }
+}
+
+/// Lower an intensity by one step, going back to full intensity after
+/// none.
+pub fn next_intensity(percent: u8) -> u8 {
+    if percent == 0 {
+        EffectsIntensity::full()
+    } else {
+        percent.saturating_sub(INTENSITY_STEP)
+    }
+}
+
+fn percent_factor(percent: u8) -> f32 {
+    f32::from(u8::min(percent, 100)) / 100.
+}
+
+/// The choice which follows the given value, going back to the first one
+/// after the last. A value which is not a choice, as may be set in the
+/// settings file, is followed by the first choice above it.
+pub fn next_choice(value: u64, choices: &[u64]) -> u64 {
+    choices
+        .iter()
+        .cloned()
+        .find(|&choice| choice > value)
+        .unwrap_or(choices[0])
+}
+
+impl Settings {
+    fn default_language() -> String {
+        DEFAULT_LANGUAGE.to_string()
+    }
+
+    fn default_transitions() -> bool {
+        true
+    }
+
+    fn default_max_fps() -> u64 {
+        60
+    }
+
+    fn default_ups() -> u64 {
+        120
+    }
+
+    fn default_vsync() -> bool {
+        true
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> DynResult<Self> {
+        let file = File::open(path)?;
+        from_reader(file).map_err(From::from)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> DynResult<()> {
+        let file = File::create(path)?;
+        to_writer(file, self).map_err(From::from)
+    }
+
+    /// Load the settings file, falling back to the default settings if
+    /// there is none. A settings file which cannot be read is reported and
+    /// ignored.
+    pub fn load_or_default() -> Self {
+        if !Path::new(SETTINGS_FILE).is_file() {
+            return Settings::default();
+        }
+        Settings::load(SETTINGS_FILE).unwrap_or_else(|e| {
+            eprintln!("Warning: could not read {}: {}", SETTINGS_FILE, e);
+            Settings::default()
+        })
+    }
+
+    /// Save to the settings file, reporting any failure.
+    pub fn save_or_warn(&self) {
+        if let Err(e) = self.save(SETTINGS_FILE) {
+            eprintln!("Warning: could not save {}: {}", SETTINGS_FILE, e);
+        }
+    }
+}
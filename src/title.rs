@@ -1,94 +1,490 @@
-use graphics::{Context, DrawState, Graphics, Image, Text, Transformed};
+use graphics::{line, rectangle, Context, DrawState, Graphics, Text, Transformed};
 use graphics::character::CharacterCache;
 use piston::input::{GenericEvent, UpdateArgs};
-use level::load_all_level_headers;
-use resource::{GameTexture, ResourceManage, Result, SpriteAssetId, SpriteManage};
+use std::fs::create_dir_all;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use achievements::ACHIEVEMENTS;
+use level::try_load_all_level_headers;
+use resource::{GameSprite, GameTexture, ResourceManage, Result, SpriteAssetId, SpriteLease, SpriteManage};
+use resource::manifest::TITLE_SPRITES;
+use resource::sprite::load_title_sprites;
 use controller::{Controller, ControllerAction, LevelId};
-use level::GameLevelHeader;
+use game::timer::{draw_medal, format_time};
+use lang::{self, tr, tr_fmt};
+use level::{GameLevelHeader, LevelManifest, LevelResult, LEVEL_DIR, MAX_DIFFICULTY};
+use progress::{LevelProgress, Progress};
+use settings::{next_choice, next_intensity, Settings, FPS_CHOICES, UPS_CHOICES};
+use util::{clamp, wrap_text};
 
 const WINDOW_SIZE: usize = 8;
+/// The position of the first row of the level list, in hires pixels.
+const LIST_POS: [f64; 2] = [24., 108.];
+/// The vertical distance between rows of the level list.
+const ROW_HEIGHT: f64 = 30.;
+/// The width taken by the level details on the right side of the screen.
+const DETAILS_WIDTH: f64 = 340.;
+/// How much of the remaining distance the list scrolls in each tick.
+const SCROLL_SPEED: f64 = 0.25;
+/// How long a locked row shakes after trying to play it, in ticks.
+const SHAKE_DURATION: f64 = 20.;
+/// The longest error message shown next to an unloadable level.
+const MAX_ERROR_LENGTH: usize = 48;
+/// The vertical distance between rows of the achievement list.
+const ACHIEVEMENT_ROW_HEIGHT: f64 = 40.;
+const CHECK_COLOR: [f32; 4] = [0.4, 1.0, 0.4, 1.0];
+const LOCKED_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+const BEST_TIME_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
 
 pub struct TitleController<R>
 where
-    R: ResourceManage,
+    R: ResourceManage + Copy,
 {
     res: R,
-    title_tex: GameTexture<R>,
-    logo_tex: GameTexture<R>,
+    /// keeps the title screen's sprites loaded
+    _sprites: SpriteLease<R>,
+    title_tex: GameSprite<R>,
+    logo_tex: GameSprite<R>,
     logo_pos: f64,
-    level_list: Vec<GameLevelHeader>,
+    level_list: Vec<(PathBuf, LevelResult<GameLevelHeader>)>,
     selected: Option<u32>,
     controller_moved: bool,
+    /// the index of the topmost visible row, eased towards the selection
+    scroll: f64,
+    /// the last known mouse cursor position, in window coordinates
+    cursor: [f64; 2],
+    progress: Progress,
+    settings: Settings,
+    /// whether a level can only be played after finishing the ones before
+    sequential_unlock: bool,
+    /// the locked row which was last attempted, and how long it still shakes
+    shake: Option<(u32, f64)>,
+    /// whether the player is being asked to confirm quitting the game
+    confirm_quit: bool,
+    /// whether the achievements are listed instead of the levels
+    show_achievements: bool,
+    /// the level directory's manifest, if it has one
+    manifest: Option<LevelManifest>,
+    /// whether a Ctrl key is held down
+    ctrl: bool,
+    /// the chapters of the level list, as ranges of level indices
+    chapters: Vec<(Option<String>, Range<usize>)>,
+    /// the reason why the level list could not be read, if it failed
+    list_error: Option<String>,
+    /// the names of the monitors which the game can go fullscreen on
+    monitors: Vec<String>,
 }
 
 impl<R> TitleController<R>
 where
-    R: ResourceManage,
+    R: ResourceManage + Copy,
 {
-    pub fn new(res: R) -> Result<Self> {
-        let mut sprite = res.sprite();
-        sprite.new_sprite_from_path(SpriteAssetId::Background, "assets/title.png")?;
-        sprite.new_sprite_from_path(SpriteAssetId::Logo, "assets/logo.png")?;
-        let title_tex = sprite.get_sprite(SpriteAssetId::Background)?;
-        let logo_tex = sprite.get_sprite(SpriteAssetId::Logo)?;
+    pub fn new(res: R, sequential_unlock: bool) -> Result<Self> {
+        load_title_sprites(&mut res.sprite())?;
+        let sprites = SpriteLease::new(res, &TITLE_SPRITES);
+        let sprite = res.sprite();
+        let title_tex = sprite.get_sprite_region(SpriteAssetId::Background)?;
+        let logo_tex = sprite.get_sprite_region(SpriteAssetId::Logo)?;
 
         Ok(TitleController {
             res,
+            _sprites: sprites,
             title_tex,
             logo_tex,
             logo_pos: -120.0,
             level_list: vec![],
             selected: None,
             controller_moved: false,
+            scroll: 0.,
+            cursor: [0., 0.],
+            progress: Progress::load_or_default(),
+            settings: Settings::load_or_default(),
+            sequential_unlock,
+            shake: None,
+            confirm_quit: false,
+            show_achievements: false,
+            manifest: None,
+            ctrl: false,
+            chapters: vec![],
+            list_error: None,
+            monitors: vec![],
         })
     }
+
+    /// Offer to go fullscreen on any of the given monitors.
+    pub fn with_monitors(mut self, monitors: Vec<String>) -> Self {
+        self.monitors = monitors;
+        self
+    }
+
+    /// Switch to the next monitor to go fullscreen on, going back to the
+    /// primary monitor after the last one.
+    fn next_monitor(&mut self) {
+        let next = match self.settings.monitor {
+            Some(ref name) => self.monitors.iter().position(|m| m == name).map_or(0, |i| i + 1),
+            None => 0,
+        };
+        self.settings.monitor = self.monitors.get(next).cloned();
+        self.settings.save_or_warn();
+    }
+
+    /// Read the level list, creating the level directory if it is missing.
+    /// Failing to do so leaves the list empty, with the error on display.
+    fn load_levels(&mut self) {
+        if !Path::new(LEVEL_DIR).is_dir() {
+            if let Err(e) = create_dir_all(LEVEL_DIR) {
+                eprintln!("Failed to create the {} directory: {}", LEVEL_DIR, e);
+            }
+        }
+        match try_load_all_level_headers(LEVEL_DIR) {
+            Ok(list) => {
+                self.level_list = list;
+                self.list_error = None;
+            }
+            Err(e) => {
+                eprintln!("Failed to list the levels: {}", e);
+                self.level_list = vec![];
+                self.list_error = Some(e.to_string());
+            }
+        }
+        self.manifest = LevelManifest::load_in(LEVEL_DIR);
+        self.refresh_chapters();
+    }
+
+    /// Retrieve the player's progress on the level at the given index, in
+    /// the current mode.
+    fn level_progress(&self, i: usize) -> Option<&LevelProgress> {
+        self.level_progress_in(i, self.settings.hard_mode)
+    }
+
+    fn level_progress_in(&self, i: usize, hard_mode: bool) -> Option<&LevelProgress> {
+        match self.level_list.get(i) {
+            Some(&(_, Ok(ref lvl))) => self.progress.level(lvl.name(), hard_mode),
+            _ => None,
+        }
+    }
+
+    /// Whether the level at the given index was finished, in either mode.
+    fn is_completed(&self, i: usize) -> bool {
+        [false, true].iter().any(|&hard_mode| {
+            self.level_progress_in(i, hard_mode)
+                .map(|p| p.best_time.is_some())
+                .unwrap_or(false)
+        })
+    }
+
+    /// Whether the level at the given index cannot be played yet, because
+    /// of an earlier level which was not completed. Unloadable levels never
+    /// hold back the ones after them.
+    fn is_locked(&self, i: usize) -> bool {
+        self.sequential_unlock && (0..i).any(|j| self.level_list[j].1.is_ok() && !self.is_completed(j))
+    }
+
+    /// The file names of the listed levels, in order.
+    fn level_files(&self) -> Vec<&str> {
+        self.level_list
+            .iter()
+            .map(|&(ref path, _)| path.file_name().and_then(|f| f.to_str()).unwrap_or_default())
+            .collect()
+    }
+
+    /// Group the level list into the chapters of the manifest.
+    fn refresh_chapters(&mut self) {
+        self.chapters = {
+            let files = self.level_files();
+            match self.manifest {
+                Some(ref manifest) => manifest.chapter_ranges(&files),
+                None => vec![(None, 0..files.len())],
+            }
+        };
+    }
+
+    /// The index of the chapter containing the selected level.
+    fn chapter_index(&self) -> usize {
+        let selected = self.selected.unwrap_or(0) as usize;
+        self.chapters
+            .iter()
+            .position(|&(_, ref range)| range.start <= selected && selected < range.end)
+            .unwrap_or(0)
+    }
+
+    /// The range of level indices in the chapter of the selected level.
+    fn chapter_range(&self) -> Range<usize> {
+        self.chapters
+            .get(self.chapter_index())
+            .map(|&(_, ref range)| range.clone())
+            .unwrap_or(0..0)
+    }
+
+    /// Select the first level of an adjacent chapter, returning whether
+    /// there was one.
+    fn switch_chapter(&mut self, delta: i32) -> bool {
+        let i = self.chapter_index() as i32 + delta;
+        if self.chapters.len() < 2 || i < 0 || i as usize >= self.chapters.len() {
+            return false;
+        }
+        self.selected = Some(self.chapters[i as usize].1.start as u32);
+        self.scroll = 0.;
+        true
+    }
+
+    /// Move the selected level up or down its chapter, and write the new
+    /// order to the level directory's manifest.
+    fn move_level(&mut self, delta: i32) {
+        let i = match self.selected {
+            Some(i) => i as i32,
+            None => return,
+        };
+        let range = self.chapter_range();
+        let j = i + delta;
+        if j < range.start as i32 || j >= range.end as i32 {
+            return;
+        }
+        let old_first = self.level_files()[range.start].to_string();
+        self.level_list.swap(i as usize, j as usize);
+        self.selected = Some(j as u32);
+
+        let levels: Vec<String> = self.level_files().into_iter().map(String::from).collect();
+        let manifest = self.manifest.get_or_insert_with(Default::default);
+        // the chapter still starts at the same position
+        for chapter in manifest.chapters.iter_mut().filter(|c| c.first == old_first) {
+            chapter.first = levels[range.start].clone();
+        }
+        manifest.levels = levels;
+        if let Err(e) = manifest.save(LevelManifest::path_in(LEVEL_DIR)) {
+            eprintln!("Failed to save the level order: {}", e);
+        }
+        self.refresh_chapters();
+    }
+
+    /// Switch to the next available language, and remember it in the
+    /// settings.
+    fn next_language(&mut self) {
+        let languages = lang::available_languages();
+        let current = lang::current_language();
+        let i = languages.iter().position(|l| *l == current).map_or(0, |i| i + 1);
+        let code = &languages[i % languages.len()];
+        match lang::set_language(code) {
+            Ok(()) => {
+                self.settings.language = code.clone();
+                self.settings.save_or_warn();
+            }
+            Err(e) => eprintln!("Warning: could not load language {}: {}", code, e),
+        }
+    }
+
+    /// Start the level at the given index, or shake its row if it is locked.
+    fn try_launch(&mut self, i: u32) -> Option<ControllerAction> {
+        if self.is_locked(i as usize) {
+            self.shake = Some((i, SHAKE_DURATION));
+            None
+        } else {
+            Some(ControllerAction::LoadGame(i as LevelId))
+        }
+    }
+
+    /// Move the selection by the given number of rows, staying within the
+    /// current chapter.
+    fn move_selection(&mut self, delta: i32) {
+        let range = self.chapter_range();
+        if range.start == range.end {
+            return;
+        }
+        let (first, last) = (range.start as i32, range.end as i32 - 1);
+        self.selected = self.selected
+            .map(|s| clamp(s as i32 + delta, first, last) as u32);
+    }
+
+    /// The scroll offset, relative to the start of the chapter, which keeps
+    /// the selected row inside the window.
+    fn target_scroll(&self) -> f64 {
+        let range = self.chapter_range();
+        let selected = match self.selected {
+            Some(s) => (s as usize).saturating_sub(range.start) as f64,
+            None => return 0.,
+        };
+        let max_scroll = range.len().saturating_sub(WINDOW_SIZE) as f64;
+        let top = self.scroll.round();
+        let top = if selected < top {
+            selected
+        } else if selected >= top + WINDOW_SIZE as f64 {
+            selected - WINDOW_SIZE as f64 + 1.
+        } else {
+            top
+        };
+        f64::min(top, max_scroll)
+    }
+
+    /// Draw the list of achievements in place of the level list, each
+    /// with a check mark if it was unlocked.
+    fn draw_achievements<C, G>(&self, c: Context, cache: &mut C, g: &mut G)
+    where
+        C: CharacterCache<Texture = GameTexture<R>>,
+        G: Graphics<Texture = GameTexture<R>>,
+    {
+        let unlocked = ACHIEVEMENTS.iter().filter(|a| self.progress.is_unlocked(a.id)).count();
+        let cw = c.trans(LIST_POS[0], LIST_POS[1]);
+        let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 12).draw(
+            &tr_fmt("title.achievements", &[&unlocked, &ACHIEVEMENTS.len()]),
+            cache,
+            &DrawState::default(),
+            cw.transform.trans(0., -ROW_HEIGHT),
+            g,
+        );
+        for (i, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            let c = cw.trans(0., ACHIEVEMENT_ROW_HEIGHT * i as f64);
+            let color = if self.progress.is_unlocked(achievement.id) {
+                line(CHECK_COLOR, 1., [-18., -6., -14., -2.], c.transform, g);
+                line(CHECK_COLOR, 1., [-14., -2., -6., -12.], c.transform, g);
+                [1.; 4]
+            } else {
+                LOCKED_COLOR
+            };
+            let _ = Text::new_color(color, 16).draw(
+                &achievement.name(),
+                cache,
+                &DrawState::default(),
+                c.transform,
+                g,
+            );
+            let _ = Text::new_color(color, 10).draw(
+                &achievement.description(),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(0., 14.),
+                g,
+            );
+        }
+
+        let window_size = c.viewport.unwrap().window_size;
+        let _ = Text::new_color([1.; 4], 10).draw(
+            &tr("title.achievements_back"),
+            cache,
+            &DrawState::default(),
+            c.transform.trans(window_size[0] as f64 - DETAILS_WIDTH, window_size[1] as f64 - 8.),
+            g,
+        );
+    }
+
+    /// Retrieve the index of the level list row under the mouse cursor.
+    fn row_at_cursor(&self) -> Option<u32> {
+        // the hires pass is laid out in window coordinates, as the cursor is
+        let (x, y) = (self.cursor[0], self.cursor[1]);
+        if x < LIST_POS[0] {
+            return None;
+        }
+        // text is drawn above its baseline, so rows extend mostly upwards
+        let offset = ((y - LIST_POS[1]) / ROW_HEIGHT + 0.75).floor() as i32;
+        if offset < 0 || offset >= WINDOW_SIZE as i32 {
+            return None;
+        }
+        let range = self.chapter_range();
+        let i = range.start + (self.scroll.round() as i32 + offset) as usize;
+        if i < range.end {
+            Some(i as u32)
+        } else {
+            None
+        }
+    }
 }
 impl<R> Controller for TitleController<R>
 where
-    R: ResourceManage,
+    R: ResourceManage + Copy,
 {
     type Res = R;
     const NEEDS_HI_RES: bool = true;
+    const NAME: &'static str = "title";
 
     fn event<E: GenericEvent>(&mut self, e: &E) -> Option<ControllerAction> {
-        use piston::input::{ButtonState, ControllerButton, ControllerAxisArgs, Key};
-        use piston::input::Button::{Controller, Keyboard};
+        use piston::input::{ButtonState, ControllerButton, ControllerAxisArgs, Key, MouseButton};
+        use piston::input::Button::{Controller, Keyboard, Mouse};
+        let has_levels = !self.level_list.is_empty();
+        if self.confirm_quit {
+            // swallow everything else until answered
+            if let Some(b) = e.button_args() {
+                match (b.button, b.state) {
+                    (Keyboard(Key::Y), ButtonState::Press) => {
+                        return Some(ControllerAction::Exit);
+                    }
+                    (Keyboard(Key::N), ButtonState::Press) |
+                    (Keyboard(Key::Escape), ButtonState::Press) => {
+                        self.confirm_quit = false;
+                    }
+                    _ => {}
+                }
+            }
+            return None;
+        }
+        if self.show_achievements {
+            if let Some(b) = e.button_args() {
+                match (b.button, b.state) {
+                    (Keyboard(Key::Escape), ButtonState::Press) |
+                    (Controller(ControllerButton {id: 0, button: 1}), ButtonState::Press) => {
+                        self.show_achievements = false;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(k) = e.text_args() {
+                if k == "A" || k == "a" {
+                    self.show_achievements = false;
+                }
+            }
+            return None;
+        }
         if let Some(b) = e.button_args() {
             // Set cell value.
             match (self.selected.is_some(), b.button, b.state) {
                 (_, Keyboard(Key::Escape), ButtonState::Press) => {
-                    return Some(ControllerAction::Exit);
+                    self.confirm_quit = true;
+                }
+                (_, Keyboard(Key::LCtrl), state) | (_, Keyboard(Key::RCtrl), state) => {
+                    self.ctrl = state == ButtonState::Press;
                 }
                 (false, _, ButtonState::Press) => {
-                    // load levels
-                    // TODO do not unwrap this error, treat this better
-                    self.level_list = load_all_level_headers("levels").unwrap();
+                    self.load_levels();
                     self.selected = Some(0);
                 }
                 (true, Keyboard(Key::Return), ButtonState::Press) |
                 (true, Keyboard(Key::Space), ButtonState::Press) |
                 (true, Controller(ControllerButton {id: 0, button: 0}), ButtonState::Press) |
                 (true, Controller(ControllerButton {id: 0, button: 1}), ButtonState::Press) => {
-                    return Some(ControllerAction::LoadGame(self.selected.unwrap() as LevelId));
+                    if has_levels {
+                        let selected = self.selected.unwrap();
+                        return self.try_launch(selected);
+                    }
+                }
+                (true, Mouse(MouseButton::Left), ButtonState::Press) => {
+                    if let Some(i) = self.row_at_cursor() {
+                        self.selected = Some(i);
+                        return self.try_launch(i);
+                    }
                 }
                 (true, Keyboard(Key::Right), ButtonState::Press) |
                 (true, Keyboard(Key::NumPad6), ButtonState::Press) => {
-                    self.selected = self.selected.map(|s| {
-                        u32::min(s + WINDOW_SIZE as u32, self.level_list.len() as u32 - 1)
-                    });
+                    if !self.switch_chapter(1) {
+                        self.move_selection(WINDOW_SIZE as i32);
+                    }
                 }
                 (true, Keyboard(Key::Left), ButtonState::Press) |
                 (true, Keyboard(Key::NumPad4), ButtonState::Press) => {
-                    self.selected = self.selected.map(|s| s.saturating_sub(WINDOW_SIZE as u32));
+                    if !self.switch_chapter(-1) {
+                        self.move_selection(-(WINDOW_SIZE as i32));
+                    }
+                }
+                (true, Keyboard(Key::Up), ButtonState::Press) if self.ctrl => {
+                    self.move_level(-1);
+                }
+                (true, Keyboard(Key::Down), ButtonState::Press) if self.ctrl => {
+                    self.move_level(1);
                 }
                 (true, Keyboard(Key::Up), ButtonState::Press) |
                 (true, Keyboard(Key::NumPad8), ButtonState::Press) => {
-                    self.selected = self.selected.map(|s| s.saturating_sub(1));
+                    self.move_selection(-1);
                 }
                 (true, Keyboard(Key::Down), ButtonState::Press) |
                 (true, Keyboard(Key::NumPad2), ButtonState::Press) => {
-                    self.selected = self.selected
-                        .map(|s| u32::min(s + 1, self.level_list.len() as u32 - 1));
+                    self.move_selection(1);
                 }
                 _ => {}
             }
@@ -98,12 +494,9 @@ where
                     // horizontal axis, move page by page
                     if position.abs() > 0.2 {
                         if !self.controller_moved {
-                            if position > 0. {
-                                self.selected = self.selected.map(|s| {
-                                    u32::min(s + WINDOW_SIZE as u32, self.level_list.len() as u32 - 1)
-                                });
-                            } else {
-                                self.selected = self.selected.map(|s| s.saturating_sub(WINDOW_SIZE as u32));
+                            let delta = if position > 0. { 1 } else { -1 };
+                            if !self.switch_chapter(delta) {
+                                self.move_selection(delta * WINDOW_SIZE as i32);
                             }
                             self.controller_moved = true;
                         }
@@ -116,9 +509,9 @@ where
                     if position.abs() > 0.2 {
                         if !self.controller_moved {
                             if position > 0. {
-                                self.selected = self.selected.map(|s| u32::min(s + 1, self.level_list.len() as u32 - 1));
+                                self.move_selection(1);
                             } else {
-                                self.selected = self.selected.map(|s| s.saturating_sub(1));
+                                self.move_selection(-1);
                             }
                             self.controller_moved = true;
                         }
@@ -130,12 +523,69 @@ where
                     // ignore this one
                 }
             }
+        } else if let Some(m) = e.mouse_cursor_args() {
+            self.cursor = m;
+            if let Some(i) = self.row_at_cursor() {
+                self.selected = Some(i);
+            }
+        } else if let Some(m) = e.mouse_scroll_args() {
+            if m[1] > 0. {
+                self.move_selection(-1);
+            } else if m[1] < 0. {
+                self.move_selection(1);
+            }
         }
 
         if let Some(k) = e.text_args() {
             if k == "E" {
                 return Some(ControllerAction::OpenEditor(None));
             }
+            if k == "L" || k == "l" {
+                self.next_language();
+            }
+            if k == "H" || k == "h" {
+                self.settings.hard_mode = !self.settings.hard_mode;
+                self.settings.save_or_warn();
+            }
+            if k == "C" || k == "c" {
+                self.settings.palette = self.settings.palette.next();
+                self.settings.save_or_warn();
+            }
+            let changed = {
+                let effects = &mut self.settings.effects;
+                let intensity = match k.as_str() {
+                    "S" | "s" => Some(&mut effects.shake),
+                    "F" | "f" => Some(&mut effects.flash),
+                    "P" | "p" => Some(&mut effects.particles),
+                    _ => None,
+                };
+                intensity.map(|intensity| *intensity = next_intensity(*intensity)).is_some()
+            };
+            if changed {
+                self.settings.save_or_warn();
+            }
+            if k == "R" || k == "r" {
+                self.settings.max_fps = next_choice(self.settings.max_fps, FPS_CHOICES);
+                self.settings.save_or_warn();
+            }
+            if k == "U" || k == "u" {
+                self.settings.ups = next_choice(self.settings.ups, UPS_CHOICES);
+                self.settings.save_or_warn();
+            }
+            if k == "V" || k == "v" {
+                self.settings.vsync = !self.settings.vsync;
+                self.settings.save_or_warn();
+            }
+            if k == "W" || k == "w" {
+                self.settings.fullscreen = !self.settings.fullscreen;
+                self.settings.save_or_warn();
+            }
+            if (k == "M" || k == "m") && self.settings.fullscreen {
+                self.next_monitor();
+            }
+            if (k == "A" || k == "a") && self.selected.is_some() {
+                self.show_achievements = true;
+            }
         }
 
         None
@@ -144,21 +594,29 @@ where
     fn update(&mut self, u: UpdateArgs) -> Option<ControllerAction> {
         let ticks = 60. * u.dt as f64;
         self.logo_pos = f64::min(self.logo_pos + 4.0 * ticks, 100.);
+        let target = self.target_scroll();
+        let t = f64::min(1., SCROLL_SPEED * ticks);
+        self.scroll += (target - self.scroll) * t;
+        if (target - self.scroll).abs() < 0.01 {
+            self.scroll = target;
+        }
+        self.shake = self.shake
+            .map(|(i, t)| (i, t - ticks))
+            .filter(|&(_, t)| t > 0.);
         None
     }
 
+    fn focus_lost(&mut self) {
+        self.ctrl = false;
+    }
+
     fn render<C, G>(&self, c: Context, _cache: &mut C, g: &mut G)
     where
         C: CharacterCache<Texture = GameTexture<R>>,
         G: Graphics<Texture = GameTexture<R>>,
     {
-        Image::new().draw(&self.title_tex, &DrawState::default(), c.transform, g);
-        Image::new().draw(
-            &self.logo_tex,
-            &DrawState::default(),
-            c.transform.trans(self.logo_pos, 20.),
-            g,
-        );
+        self.title_tex.draw(c.transform, g);
+        self.logo_tex.draw(c.transform.trans(self.logo_pos, 20.), g);
 
     }
 
@@ -167,27 +625,144 @@ where
         C: CharacterCache<Texture = GameTexture<R>>,
         G: Graphics<Texture = GameTexture<R>>,
     {
+        if self.confirm_quit {
+            let window_size = c.viewport.unwrap().window_size;
+            rectangle([0., 0., 0., 0.75], [0., 0., window_size[0] as f64, 56.], c.transform, g);
+            let _ = Text::new_color([1.; 4], 16).draw(
+                &tr("title.quit"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(12., 24.),
+                g,
+            );
+        }
 
         if let Some(selected) = self.selected {
-            let draw_size = c.viewport.unwrap().draw_size;
-            let window_size = WINDOW_SIZE;
-            let window_n = selected as usize / WINDOW_SIZE;
-            let cw = c.trans(24., 108.);
-            for (window_i, (i, lvl)) in self.level_list
+            let window_size = c.viewport.unwrap().window_size;
+
+            if self.show_achievements {
+                self.draw_achievements(c, cache, g);
+                return;
+            }
+
+            if let Some(e) = self.list_error.as_ref() {
+                let _ = Text::new_color([1.0, 0.4, 0.4, 1.0], 16).draw(
+                    &tr_fmt("title.list_error", &[e]),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(LIST_POS[0], LIST_POS[1]),
+                    g,
+                );
+            } else if self.level_list.is_empty() {
+                let _ = Text::new_color([1.; 4], 16).draw(
+                    &tr("title.no_levels"),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(LIST_POS[0], LIST_POS[1]),
+                    g,
+                );
+            }
+
+            let range = self.chapter_range();
+            let first = range.start + self.scroll.floor() as usize;
+            let cw = c.trans(LIST_POS[0], LIST_POS[1]);
+            let list_right = window_size[0] as f64 - DETAILS_WIDTH - 2. * LIST_POS[0];
+
+            // name the chapter, and whether there are others to each side
+            if self.chapters.len() > 1 {
+                let chapter_i = self.chapter_index();
+                let name = self.chapters[chapter_i]
+                    .0
+                    .clone()
+                    .unwrap_or_else(|| tr("title.other_levels"));
+                let label = format!(
+                    "{} {} ({}/{}) {}",
+                    if chapter_i > 0 { "<" } else { " " },
+                    name,
+                    chapter_i + 1,
+                    self.chapters.len(),
+                    if chapter_i + 1 < self.chapters.len() { ">" } else { " " },
+                );
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 12).draw(
+                    &label,
+                    cache,
+                    &DrawState::default(),
+                    cw.transform.trans(0., -ROW_HEIGHT),
+                    g,
+                );
+            }
+
+            for (i, &(ref path, ref lvl)) in self.level_list
                 .iter()
                 .enumerate()
-                .skip(window_n * window_size)
-                .take(window_size)
-                .enumerate()
+                .take(range.end)
+                .skip(first)
+                .take(WINDOW_SIZE + 1)
             {
-                let c = cw.trans(0., 30. * window_i as f64);
-                let color = if selected == i as u32 {
-                    [1.; 4]
-                } else {
-                    [1.0, 1.0, 0.25, 1.0]
+                let row = (i - range.start) as f64 - self.scroll;
+                // fade out the rows which are partly scrolled out of the window
+                let outside = f64::max(-row, row - (WINDOW_SIZE - 1) as f64);
+                let alpha = f64::max(0., f64::min(1., 1. - outside)) as f32;
+                if alpha <= 0. {
+                    continue;
+                }
+                let shake = match self.shake {
+                    Some((s, t)) if s == i as u32 => {
+                        (t * 1.5).sin() * 4. * t / SHAKE_DURATION
+                    }
+                    _ => 0.,
+                };
+                let c = cw.trans(shake, ROW_HEIGHT * row);
+                let locked = self.is_locked(i);
+                let mut color = match (selected == i as u32, lvl.is_ok()) {
+                    (true, true) => [1.; 4],
+                    (false, true) => [1.0, 1.0, 0.25, 1.0],
+                    (true, false) => [1.0, 0.6, 0.6, 1.0],
+                    (false, false) => [0.8, 0.3, 0.3, 1.0],
+                };
+                if shake != 0. {
+                    color = [1.0, 0.4, 0.4, 1.0];
+                } else if locked {
+                    for v in &mut color[..3] {
+                        *v *= 0.4;
+                    }
+                }
+                color[3] = alpha;
+
+                if let Some(best) = self.level_progress(i).and_then(|p| p.best_time) {
+                    // a check mark to the left of the name
+                    let mut check_color = CHECK_COLOR;
+                    check_color[3] = alpha;
+                    line(check_color, 1., [-18., -6., -14., -2.], c.transform, g);
+                    line(check_color, 1., [-14., -2., -6., -12.], c.transform, g);
+
+                    let time = format_time(best);
+                    let w = cache.width(12, &time).unwrap_or(0.);
+                    if let Some(medal) = self.level_progress(i).and_then(|p| p.medal) {
+                        draw_medal(medal, [list_right - w - 10., -4.], 4., c.transform, g);
+                    }
+                    let mut time_color = BEST_TIME_COLOR;
+                    time_color[3] = alpha;
+                    let _ = Text::new_color(time_color, 12).draw(
+                        &time,
+                        cache,
+                        &DrawState::default(),
+                        c.transform.trans(list_right - w, 0.),
+                        g,
+                    );
+                }
+                let name = match *lvl {
+                    Ok(ref lvl) => lvl.name().to_string(),
+                    Err(ref e) => {
+                        let mut e = e.reason();
+                        if e.chars().count() > MAX_ERROR_LENGTH {
+                            e = e.chars().take(MAX_ERROR_LENGTH).chain("...".chars()).collect();
+                        }
+                        format!("{} ({})", path.file_name().unwrap_or_default().to_string_lossy(), e)
+                    }
                 };
                 let _ = Text::new_color(color, 16).draw(
-                    lvl.name(),
+                    &name,
                     cache,
                     &DrawState::default(),
                     c.transform,
@@ -195,21 +770,131 @@ where
                 );
             }
 
-            let text_pos = (draw_size[0] as f64 - 340., draw_size[1] as f64 - 8.);
+            // tell which page of the list is being shown
+            let pages = (range.len() + WINDOW_SIZE - 1) / WINDOW_SIZE;
+            if pages > 1 {
+                let page = (selected as usize - range.start) / WINDOW_SIZE + 1;
+                let mut label = tr_fmt("title.page", &[&page, &pages]);
+                if self.scroll.round() > 0. {
+                    label.push_str("  ^");
+                }
+                if (self.scroll.round() as usize) + WINDOW_SIZE < range.len() {
+                    label.push_str("  v");
+                }
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 12).draw(
+                    &label,
+                    cache,
+                    &DrawState::default(),
+                    cw.transform.trans(0., ROW_HEIGHT * WINDOW_SIZE as f64),
+                    g,
+                );
+            }
+
+            // show details of the highlighted level
+            if let Some(&(_, Ok(ref lvl))) = self.level_list.get(selected as usize) {
+                let cd = c.trans(window_size[0] as f64 - DETAILS_WIDTH, LIST_POS[1]);
+                let mut lines = vec![];
+                if let Some(d) = lvl.difficulty() {
+                    let stars: String = (0..MAX_DIFFICULTY)
+                        .map(|i| if i < d { '*' } else { '-' })
+                        .collect();
+                    lines.push(tr_fmt("title.difficulty", &[&stars]));
+                }
+                if let Some(author) = lvl.author() {
+                    lines.push(tr_fmt("title.author", &[&author]));
+                }
+                if let Some(description) = lvl.description() {
+                    lines.extend(wrap_text(description, 48));
+                }
+                for (i, line) in lines.iter().enumerate() {
+                    let _ = Text::new_color([1.; 4], 12).draw(
+                        line,
+                        cache,
+                        &DrawState::default(),
+                        cd.transform.trans(0., 20. * i as f64),
+                        g,
+                    );
+                }
+            }
+
+            let text_pos = (window_size[0] as f64 - DETAILS_WIDTH, window_size[1] as f64 - 8.);
             let _ = Text::new_color([1.; 4], 10).draw(
-                "Press Shift+E to enter the level editor",
+                &tr("title.editor_hint"),
                 cache,
                 &DrawState::default(),
                 c.transform.trans(text_pos.0, text_pos.1),
                 g,
             );
+            let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                &tr_fmt("title.language", &[&lang::language_name()]),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(text_pos.0, text_pos.1 - 16.),
+                g,
+            );
+            let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                &tr("title.achievements_hint"),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(text_pos.0, text_pos.1 - 32.),
+                g,
+            );
+            let (hard_mode_text, hard_mode_color) = if self.settings.hard_mode {
+                (tr("title.hard_mode_on"), [1.0, 0.5, 0.4, 1.0])
+            } else {
+                (tr("title.hard_mode_off"), [0.8, 0.8, 0.8, 1.0])
+            };
+            let _ = Text::new_color(hard_mode_color, 10).draw(
+                &hard_mode_text,
+                cache,
+                &DrawState::default(),
+                c.transform.trans(text_pos.0, text_pos.1 - 48.),
+                g,
+            );
+            let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                &tr_fmt("title.palette", &[&self.settings.palette.label()]),
+                cache,
+                &DrawState::default(),
+                c.transform.trans(text_pos.0, text_pos.1 - 64.),
+                g,
+            );
+            let effects = self.settings.effects;
+            let intensities = [
+                ("title.shake", effects.shake),
+                ("title.flash", effects.flash),
+                ("title.particles", effects.particles),
+            ];
+            for (i, &(key, percent)) in intensities.iter().enumerate() {
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                    &tr_fmt(key, &[&percent]),
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(text_pos.0, text_pos.1 - 80. - 16. * i as f64),
+                    g,
+                );
+            }
+            let vsync = if self.settings.vsync { tr("title.vsync_on") } else { tr("title.vsync_off") };
+            let display = if self.settings.fullscreen {
+                let monitor = self.settings.monitor.clone().unwrap_or_else(|| tr("title.primary_monitor"));
+                tr_fmt("title.fullscreen", &[&monitor])
+            } else {
+                tr("title.windowed")
+            };
+            let rates = [
+                tr_fmt("title.max_fps", &[&self.settings.max_fps]),
+                tr_fmt("title.ups", &[&self.settings.ups]),
+                vsync,
+                display,
+            ];
+            for (i, text) in rates.iter().enumerate() {
+                let _ = Text::new_color([0.8, 0.8, 0.8, 1.0], 10).draw(
+                    text,
+                    cache,
+                    &DrawState::default(),
+                    c.transform.trans(text_pos.0, text_pos.1 - 128. - 16. * i as f64),
+                    g,
+                );
+            }
         }
     }
-
-    fn exit(&mut self) {
-        self.res
-            .sprite()
-            .free_sprite(SpriteAssetId::Background)
-            .unwrap();
-    }
 }
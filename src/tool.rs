@@ -0,0 +1,212 @@
+//! Module for the headless command line tools, which run without opening a
+//! game window.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use clap::ArgMatches;
+use level::{GameLevel, CURRENT_VERSION};
+use level::generate::{generate_level, GeneratorParams};
+
+/// Run the `level` subcommand, returning the process exit code.
+pub fn run_level_tool(args: &ArgMatches) -> i32 {
+    match args.subcommand() {
+        ("check", Some(args)) => check(args.value_of("FILE").unwrap()),
+        ("import", Some(args)) => {
+            let input = args.value_of("FILE").unwrap();
+            let output = args
+                .value_of("output")
+                .map(String::from)
+                .unwrap_or_else(|| Path::new(input).with_extension("json").to_string_lossy().into_owned());
+            import(input, &output)
+        }
+        ("generate", Some(args)) => generate(args),
+        ("snapshot", Some(args)) => {
+            let input = args.value_of("FILE").unwrap();
+            let output = args
+                .value_of("output")
+                .map(String::from)
+                .unwrap_or_else(|| Path::new(input).with_extension("png").to_string_lossy().into_owned());
+            match args.value_of("scale").unwrap_or("1").parse() {
+                Ok(scale) => snapshot(input, &output, scale),
+                Err(_) => {
+                    eprintln!("Invalid scale \"{}\"", args.value_of("scale").unwrap_or_default());
+                    2
+                }
+            }
+        }
+        ("upgrade", Some(args)) => {
+            let input = args.value_of("FILE").unwrap();
+            let output = args.value_of("output").unwrap_or(input);
+            upgrade(input, output)
+        }
+        _ => {
+            eprintln!("{}", args.usage());
+            2
+        }
+    }
+}
+
+fn check(path: &str) -> i32 {
+    let level = match GameLevel::load(path) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("{}: failed to load level: {}", path, e.reason());
+            return 1;
+        }
+    };
+
+    let issues = level.validate();
+    if !issues.is_empty() {
+        println!("{}: {} issue(s) found", path, issues.len());
+        for issue in issues {
+            println!("  - {}", issue);
+        }
+        return 1;
+    }
+
+    // the reachability check is an estimate, so it only warns
+    let warnings = level.check_reachability().issues(&level);
+    println!("{}: OK ({:?}, version {})", path, level.name(), level.version());
+    if !warnings.is_empty() {
+        println!("{}: {} warning(s)", path, warnings.len());
+        for warning in warnings {
+            println!("  - {}", warning);
+        }
+    }
+    0
+}
+
+fn upgrade(input: &str, output: &str) -> i32 {
+    let mut level = match GameLevel::load(input) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("{}: failed to load level: {}", input, e.reason());
+            return 1;
+        }
+    };
+
+    // the current version does not allow levels with issues
+    let issues = level.validate();
+    if !issues.is_empty() {
+        eprintln!("{}: {} issue(s) must be fixed before upgrading", input, issues.len());
+        for issue in issues {
+            eprintln!("  - {}", issue);
+        }
+        return 1;
+    }
+    level.set_version(CURRENT_VERSION);
+
+    match level.save(output) {
+        Ok(()) => {
+            println!("Saved level version {} to {}", level.version(), output);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: failed to save level: {}", output, e.reason());
+            1
+        }
+    }
+}
+
+fn import(input: &str, output: &str) -> i32 {
+    let (level, warnings) = match GameLevel::from_tiled(input) {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("{}: failed to import map: {}", input, e);
+            return 1;
+        }
+    };
+    for warning in warnings {
+        eprintln!("{}: warning: {}", input, warning);
+    }
+
+    let issues = level.validate();
+    if !issues.is_empty() {
+        eprintln!("{}: {} issue(s) must be fixed in the map before importing", input, issues.len());
+        for issue in issues {
+            eprintln!("  - {}", issue);
+        }
+        return 1;
+    }
+
+    match level.save(output) {
+        Ok(()) => {
+            println!("Imported {:?} to {}", level.name(), output);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: failed to save level: {}", output, e.reason());
+            1
+        }
+    }
+}
+
+fn snapshot(input: &str, output: &str, scale: u32) -> i32 {
+    let level = match GameLevel::load(input) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("{}: failed to load level: {}", input, e.reason());
+            return 1;
+        }
+    };
+
+    let image = level.render_to_image(scale);
+    match image.save(output) {
+        Ok(()) => {
+            println!("Saved a {}x{} picture to {}", image.width(), image.height(), output);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: failed to save picture: {}", output, e);
+            1
+        }
+    }
+}
+
+fn generate(args: &ArgMatches) -> i32 {
+    fn parse<T: ::std::str::FromStr>(args: &ArgMatches, name: &str) -> Option<T> {
+        let value = args.value_of(name).unwrap_or_default();
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            eprintln!("Invalid {} \"{}\"", name, value);
+        }
+        parsed
+    }
+
+    let seed = if args.is_present("seed") {
+        parse(args, "seed")
+    } else {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.subsec_nanos() ^ t.as_secs() as u32)
+            .ok()
+    };
+    let params = match (parse(args, "width"), parse(args, "height"), parse(args, "difficulty"), seed) {
+        (Some(width), Some(height), Some(difficulty), Some(seed)) => GeneratorParams {
+            width,
+            height,
+            difficulty,
+            seed,
+        },
+        _ => return 2,
+    };
+    let output = args.value_of("output").unwrap();
+
+    let level = match generate_level(&params) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("Failed to generate a level: {}", e);
+            return 1;
+        }
+    };
+    match level.save(output) {
+        Ok(()) => {
+            println!("Generated a level with seed {} into {}", params.seed, output);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}: failed to save level: {}", output, e.reason());
+            1
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use na::Vector2;
 use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
 
 pub type DynResult<T> = Result<T, ::failure::Error>;
 
@@ -10,6 +13,175 @@ where
     [T::default() ; 2].into()
 }
 
-pub fn clamp(v: f32, min: f32, max: f32) -> f32 {
-    f32::max(min, f32::min(max, v))
+/// Restrict a value to the range `[min, max]`.
+pub fn clamp<T: PartialOrd>(v: T, min: T, max: T) -> T {
+    if v < min {
+        min
+    } else if v > max {
+        max
+    } else {
+        v
+    }
+}
+
+/// Convert a vector to integer coordinates, truncating towards zero.
+#[inline]
+pub fn vec_i32(v: Vector2<f32>) -> Vector2<i32> {
+    Vector2::new(v[0] as i32, v[1] as i32)
+}
+
+#[inline]
+pub fn vec_f32(v: Vector2<i32>) -> Vector2<f32> {
+    Vector2::new(v[0] as f32, v[1] as f32)
+}
+
+/// Round a position to the nearest point of a grid with the given spacing.
+pub fn snap_to_grid(v: Vector2<f32>, step: f32) -> Vector2<f32> {
+    Vector2::new((v[0] / step).round() * step, (v[1] / step).round() * step)
+}
+
+/// Check whether a point lies in a rectangle, as `[x, y, width, height]`,
+/// edges included.
+pub fn rect_contains(rect: [f32; 4], point: Vector2<f32>) -> bool {
+    point[0] >= rect[0]
+        && point[0] <= rect[0] + rect[2]
+        && point[1] >= rect[1]
+        && point[1] <= rect[1] + rect[3]
+}
+
+/// Check whether two rectangles, as `[x, y, width, height]`, overlap.
+/// Rectangles which only touch at their edges do not.
+pub fn rect_intersects(a: [f32; 4], b: [f32; 4]) -> bool {
+    a[0] < b[0] + b[2] && b[0] < a[0] + a[2] && a[1] < b[1] + b[3] && b[1] < a[1] + a[3]
+}
+
+/// How often files are checked for changes on disk, in seconds.
+pub const WATCH_INTERVAL: f64 = 1.;
+
+/// Obtain the time at which a file was last modified, if it can be read.
+pub fn modified_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Split the given text into lines of at most `width` characters, breaking
+/// at whitespace whenever possible.
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A small and fast pseudo-random number generator (xorshift), for visual
+/// effects which do not need good statistical properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XorShiftRng(u32);
+
+impl Default for XorShiftRng {
+    fn default() -> Self {
+        XorShiftRng::new(0x2545_F491)
+    }
+}
+
+impl XorShiftRng {
+    pub fn new(seed: u32) -> Self {
+        // the state must never be zero
+        XorShiftRng(if seed == 0 { 0x2545_F491 } else { seed })
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Obtain a number in the range [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Obtain a number in the range [min, max).
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_keeps_values_in_range() {
+        assert_eq!(clamp(5, 0, 10), 5);
+        assert_eq!(clamp(-3, 0, 10), 0);
+        assert_eq!(clamp(12, 0, 10), 10);
+        assert_eq!(clamp(0, 0, 10), 0);
+        assert_eq!(clamp(10, 0, 10), 10);
+        assert_eq!(clamp(0.5, 1., 1.), 1.);
+    }
+
+    #[test]
+    fn vec_i32_truncates_towards_zero() {
+        assert_eq!(vec_i32(Vector2::new(1.9, -1.9)), Vector2::new(1, -1));
+        assert_eq!(vec_i32(Vector2::new(-0.5, 0.5)), Vector2::new(0, 0));
+        assert_eq!(vec_i32(Vector2::new(320., -200.)), Vector2::new(320, -200));
+    }
+
+    #[test]
+    fn vec_f32_is_exact_for_map_coordinates() {
+        assert_eq!(vec_f32(Vector2::new(-36, 65536)), Vector2::new(-36., 65536.));
+        assert_eq!(vec_i32(vec_f32(Vector2::new(-1, 1))), Vector2::new(-1, 1));
+    }
+
+    #[test]
+    fn snap_to_grid_rounds_to_the_nearest_point() {
+        assert_eq!(snap_to_grid(Vector2::new(14., 16.), 10.), Vector2::new(10., 20.));
+        assert_eq!(snap_to_grid(Vector2::new(20., 0.), 10.), Vector2::new(20., 0.));
+        assert_eq!(snap_to_grid(Vector2::new(-14., -16.), 10.), Vector2::new(-10., -20.));
+        // halfway points round away from zero on either side
+        assert_eq!(snap_to_grid(Vector2::new(5., -5.), 10.), Vector2::new(10., -10.));
+        assert_eq!(snap_to_grid(Vector2::new(-2., 3.), 8.), Vector2::new(0., 0.));
+    }
+
+    #[test]
+    fn rect_contains_its_edges() {
+        let rect = [10., 20., 30., 40.];
+        assert!(rect_contains(rect, Vector2::new(25., 40.)));
+        assert!(rect_contains(rect, Vector2::new(10., 20.)));
+        assert!(rect_contains(rect, Vector2::new(40., 60.)));
+        assert!(rect_contains(rect, Vector2::new(10., 60.)));
+        assert!(!rect_contains(rect, Vector2::new(9.9, 40.)));
+        assert!(!rect_contains(rect, Vector2::new(25., 60.1)));
+        // a rectangle with no area still contains its only point
+        assert!(rect_contains([-5., -5., 0., 0.], Vector2::new(-5., -5.)));
+    }
+
+    #[test]
+    fn rect_intersects_only_with_overlap() {
+        let a = [0., 0., 10., 10.];
+        assert!(rect_intersects(a, [5., 5., 10., 10.]));
+        assert!(rect_intersects(a, [2., 2., 2., 2.]));
+        assert!(rect_intersects([2., 2., 2., 2.], a));
+        assert!(rect_intersects(a, [-5., -5., 20., 20.]));
+        // touching edges and corners
+        assert!(!rect_intersects(a, [10., 0., 10., 10.]));
+        assert!(!rect_intersects(a, [0., -10., 10., 10.]));
+        assert!(!rect_intersects(a, [10., 10., 5., 5.]));
+        assert!(!rect_intersects(a, [20., 20., 5., 5.]));
+    }
 }